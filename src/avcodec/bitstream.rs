@@ -104,21 +104,24 @@ impl AVBSFContext {
     /// [`RsmpegError::BitstreamDrainError`] or
     /// [`RsmpegError::BitstreamFlushedError`].
     ///
-    /// Return `Ok(())` on success. Return [`RsmpegError::BitstreamDrainError`]
-    /// if more packets need to be sent to the filter (using
-    /// [`Self::send_packet()`]) to get more output.
+    /// Return the packet on success. Return
+    /// [`RsmpegError::BitstreamDrainError`] if more packets need to be sent to
+    /// the filter (using [`Self::send_packet()`]) to get more output.
     /// [`RsmpegError::BitstreamFlushedError`] if there will be no further
     /// output from the filter. [`RsmpegError::BitstreamReceivePacketError`] if
     /// an error occurs.
-    ///
-    /// # EVILNESS:
-    /// I found that FFmpeg rely on the reusage of packet sending to the
-    /// `AVBSFContext`.  Which means creating a new packet for receiving the
-    /// packet doesn't work, which is really evil...... So the API is designed
-    /// to take an packet for holding the output, and this packet should only be
-    /// the one that changed by [`Self::send_packet()`], or this function will
-    /// returns [`RsmpegError::BitstreamFlushedError`].
-    pub fn receive_packet(&mut self, packet: &mut AVPacket) -> Result<()> {
+    pub fn receive_packet(&mut self) -> Result<AVPacket> {
+        let mut packet = AVPacket::new();
+        self.receive_packet_into(&mut packet)?;
+        Ok(packet)
+    }
+
+    /// Same as [`Self::receive_packet()`], but the filtered packet is moved
+    /// into `packet`, e.g. for reusing the packet just sent. The previous
+    /// content of `packet` is released.
+    pub fn receive_packet_into(&mut self, packet: &mut AVPacket) -> Result<()> {
+        // `av_bsf_receive_packet` requires a blank packet.
+        unsafe { ffi::av_packet_unref(packet.as_mut_ptr()) };
         match unsafe { ffi::av_bsf_receive_packet(self.as_mut_ptr(), packet.as_mut_ptr()) }
             .upgrade()
         {
@@ -134,6 +137,12 @@ impl AVBSFContext {
     pub fn par_out(&self) -> AVCodecParametersRef<'_> {
         unsafe { AVCodecParametersRef::from_raw(NonNull::new(self.par_out).unwrap()) }
     }
+
+    /// Get the time base of the output packets, which is set after
+    /// [`AVBSFContextUninit::init()`].
+    pub fn time_base_out(&self) -> ffi::AVRational {
+        self.time_base_out
+    }
 }
 
 impl std::ops::Deref for AVBSFContext {
@@ -213,7 +222,8 @@ impl Drop for AVBSFContextUninit {
 
 #[cfg(test)]
 mod test {
-    use super::{AVBSFContextUninit, AVBitStreamFilter};
+    use super::{AVBSFContext, AVBSFContextUninit, AVBitStreamFilter};
+    use crate::{avformat::AVFormatContextInput, error::RsmpegError, ffi};
     use cstr::cstr;
 
     #[test]
@@ -236,4 +246,52 @@ mod test {
         for _ in iter.by_ref() {}
         assert!(iter.next().is_none());
     }
+
+    #[test]
+    fn test_mp4toannexb() {
+        let mut input =
+            AVFormatContextInput::open(cstr!("tests/assets/vids/bear.mp4"), None, &mut None)
+                .unwrap();
+        let (video_index, _) = input
+            .find_best_stream(ffi::AVMEDIA_TYPE_VIDEO)
+            .unwrap()
+            .unwrap();
+        let filter = AVBitStreamFilter::find_by_name(cstr!("h264_mp4toannexb")).unwrap();
+        let mut ctx = AVBSFContextUninit::new(&filter);
+        let stream = &input.streams()[video_index];
+        ctx.set_par_in(&stream.codecpar());
+        ctx.set_time_base_in(stream.time_base);
+        let time_base = stream.time_base;
+        let mut ctx = ctx.init().unwrap();
+        assert_eq!(ctx.time_base_out().num, time_base.num);
+        assert_eq!(ctx.time_base_out().den, time_base.den);
+
+        let mut nb_in = 0;
+        let mut nb_out = 0;
+        let mut drain = |ctx: &mut AVBSFContext| loop {
+            match ctx.receive_packet() {
+                Ok(packet) => {
+                    // Annex B start code.
+                    let data = unsafe { std::slice::from_raw_parts(packet.data, 4) };
+                    assert!(data == [0, 0, 0, 1] || data[..3] == [0, 0, 1]);
+                    nb_out += 1;
+                }
+                Err(RsmpegError::BitstreamDrainError) | Err(RsmpegError::BitstreamFlushedError) => {
+                    break
+                }
+                Err(e) => panic!("{}", e),
+            }
+        };
+        while let Some(mut packet) = input.read_packet().unwrap() {
+            if packet.stream_index as usize == video_index {
+                nb_in += 1;
+                ctx.send_packet(Some(&mut packet)).unwrap();
+                drain(&mut ctx);
+            }
+        }
+        ctx.send_packet(None).unwrap();
+        drain(&mut ctx);
+        assert!(nb_in > 0);
+        assert_eq!(nb_out, nb_in);
+    }
 }
@@ -1,15 +1,21 @@
 use std::{
     ffi::{c_void, CStr},
+    iter::FusedIterator,
     mem,
+    os::raw::c_int,
+    panic::{self, AssertUnwindSafe},
     ptr::{self, NonNull},
     slice,
+    sync::Mutex,
 };
 
 use crate::{
     avcodec::{AVCodecID, AVCodecParameters, AVPacket},
+    avformat::AVStream,
     avutil::{
-        AVChannelLayoutRef, AVDictionary, AVFrame, AVHWFramesContext, AVHWFramesContextMut,
-        AVHWFramesContextRef, AVPixelFormat, AVRational,
+        log::log_message, AVBufferRef, AVChannelLayoutRef, AVDictionary, AVFrame,
+        AVHWDeviceContext, AVHWFramesContext, AVHWFramesContextMut, AVHWFramesContextRef,
+        AVPixFmtDescriptorRef, AVPixelFormat, AVRational, PixFmtLoss, SampleFormat,
     },
     error::{Result, RsmpegError},
     ffi,
@@ -57,6 +63,21 @@ impl AVCodec {
         unsafe { CStr::from_ptr(self.long_name) }
     }
 
+    /// Return true if the codec is an encoder.
+    pub fn is_encoder(&self) -> bool {
+        unsafe { ffi::av_codec_is_encoder(self.as_ptr()) != 0 }
+    }
+
+    /// Return true if the codec is a decoder.
+    pub fn is_decoder(&self) -> bool {
+        unsafe { ffi::av_codec_is_decoder(self.as_ptr()) != 0 }
+    }
+
+    /// Get the capabilities of the codec, `AV_CODEC_CAP_*`.
+    pub fn capabilities(&self) -> AVCodecCapabilities {
+        AVCodecCapabilities(self.capabilities)
+    }
+
     /// Iterate over all registered codecs.
     pub fn iterate() -> AVCodecIter {
         AVCodecIter {
@@ -65,6 +86,8 @@ impl AVCodec {
     }
 }
 
+/// Iterator of the registered [`AVCodecRef`]s, created by
+/// [`AVCodec::iterate()`].
 pub struct AVCodecIter {
     opaque: *mut c_void,
 }
@@ -78,6 +101,9 @@ impl Iterator for AVCodecIter {
     }
 }
 
+// `av_codec_iterate` keeps returning null once the end is reached.
+impl FusedIterator for AVCodecIter {}
+
 impl<'codec> AVCodec {
     /// Probing specific memory pattern and return the offset.
     ///
@@ -135,6 +161,25 @@ impl<'codec> AVCodec {
         // terminates with -1
         unsafe { Self::build_array(self.sample_fmts, -1) }
     }
+
+    /// Return supported channel layouts of this [`AVCodec`].
+    pub fn ch_layouts(&'codec self) -> Option<Vec<AVChannelLayoutRef<'codec>>> {
+        let ch_layouts = NonNull::new(self.ch_layouts as *mut ffi::AVChannelLayout)?;
+        // terminates with a zeroed layout
+        let layouts = (0..)
+            .map(|i| unsafe { NonNull::new_unchecked(ch_layouts.as_ptr().add(i)) })
+            .take_while(|x| unsafe { x.as_ref() }.nb_channels != 0)
+            .map(|x| unsafe { AVChannelLayoutRef::from_raw(x) })
+            .collect();
+        Some(layouts)
+    }
+
+    /// Iterate over the hardware configurations supported by this
+    /// [`AVCodec`], e.g. for finding the pixel format of the frames decoded
+    /// with a device type.
+    pub fn hw_configs(&'codec self) -> impl Iterator<Item = &'codec ffi::AVCodecHWConfig> + 'codec {
+        (0..).map_while(move |i| unsafe { ffi::avcodec_get_hw_config(self.as_ptr(), i).as_ref() })
+    }
 }
 
 impl Drop for AVCodec {
@@ -144,24 +189,116 @@ impl Drop for AVCodec {
     }
 }
 
+/// Non exhaustive wrapper of the `capabilities` of [`AVCodec`], a
+/// combination of `AV_CODEC_CAP_*`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AVCodecCapabilities(pub c_int);
+
+impl AVCodecCapabilities {
+    /// Return true if all the bits of `flag` are set.
+    pub fn contains(&self, flag: u32) -> bool {
+        self.0 & flag as c_int == flag as c_int
+    }
+
+    /// The codec calls the `get_buffer2` callback for allocating the frames.
+    pub fn is_dr1(&self) -> bool {
+        self.contains(ffi::AV_CODEC_CAP_DR1)
+    }
+
+    /// The codec has a delay, it needs to be drained with a `None` packet
+    /// or frame at the end.
+    pub fn is_delay(&self) -> bool {
+        self.contains(ffi::AV_CODEC_CAP_DELAY)
+    }
+
+    /// The encoder accepts audio frames of any size, otherwise all the frames
+    /// but the last one must be of `frame_size` samples.
+    pub fn is_variable_frame_size(&self) -> bool {
+        self.contains(ffi::AV_CODEC_CAP_VARIABLE_FRAME_SIZE)
+    }
+
+    /// The codec is experimental, it needs `strict_std_compliance` set to
+    /// [`Compliance::Experimental`].
+    pub fn is_experimental(&self) -> bool {
+        self.contains(ffi::AV_CODEC_CAP_EXPERIMENTAL)
+    }
+
+    /// The codec is backed by hardware.
+    pub fn is_hardware(&self) -> bool {
+        self.contains(ffi::AV_CODEC_CAP_HARDWARE)
+    }
+}
+
+/// Standards compliance levels, mirroring FFmpeg's `FF_COMPLIANCE_*`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(i32)]
+pub enum Compliance {
+    /// Strictly conform to an older more strict version of the spec or reference software.
+    VeryStrict = ffi::FF_COMPLIANCE_VERY_STRICT as i32,
+    /// Strictly conform to all the things in the spec no matter what consequences.
+    Strict = ffi::FF_COMPLIANCE_STRICT as i32,
+    Normal = ffi::FF_COMPLIANCE_NORMAL as i32,
+    /// Allow unofficial extensions.
+    Unofficial = ffi::FF_COMPLIANCE_UNOFFICIAL as i32,
+    /// Allow nonstandardized experimental things.
+    Experimental = ffi::FF_COMPLIANCE_EXPERIMENTAL as i32,
+}
+
+/// Multithreading methods of a codec, see
+/// [`AVCodecContext::set_thread_type()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(i32)]
+pub enum ThreadType {
+    /// Decode several frames at once, adding one frame of delay per thread.
+    Frame = ffi::FF_THREAD_FRAME as i32,
+    /// Decode several slices of a frame at once, for codecs and streams with
+    /// slices.
+    Slice = ffi::FF_THREAD_SLICE as i32,
+    /// Let the codec pick, frame threading first, the default.
+    FrameAndSlice = (ffi::FF_THREAD_FRAME | ffi::FF_THREAD_SLICE) as i32,
+}
+
+/// Which frames a decoder discards, mirroring FFmpeg's `AVDiscard`, from
+/// none to all of them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[repr(i32)]
+pub enum Discard {
+    None = ffi::AVDISCARD_NONE,
+    /// Useless packets, e.g. the 0 size ones of AVI, the default.
+    Default = ffi::AVDISCARD_DEFAULT,
+    /// Frames not used as references.
+    NonRef = ffi::AVDISCARD_NONREF,
+    /// Bidirectional frames.
+    Bidir = ffi::AVDISCARD_BIDIR,
+    /// Frames other than intra ones.
+    NonIntra = ffi::AVDISCARD_NONINTRA,
+    /// Frames other than key ones.
+    NonKey = ffi::AVDISCARD_NONKEY,
+    All = ffi::AVDISCARD_ALL,
+}
+
 wrap_ref!(AVCodecContext: ffi::AVCodecContext);
-settable!(AVCodecContext {
-    framerate: AVRational,
-    ch_layout: ffi::AVChannelLayout,
-    height: i32,
-    width: i32,
-    sample_aspect_ratio: AVRational,
-    pix_fmt: i32,
-    time_base: AVRational,
-    pkt_timebase: AVRational,
-    sample_rate: i32,
-    sample_fmt: i32,
-    flags: i32,
-    bit_rate: i64,
-    strict_std_compliance: i32,
-    gop_size: i32,
-    max_b_frames: i32,
-});
+// Options set after opening are ignored by codecs, so the setters fail then.
+settable!(
+    AVCodecContext,
+    check_unopened {
+        height: i32,
+        width: i32,
+        sample_aspect_ratio: AVRational,
+        pix_fmt: i32,
+        pkt_timebase: AVRational,
+        sample_rate: i32,
+        sample_fmt: i32,
+        flags: i32,
+        flags2: i32,
+        bit_rate: i64,
+        strict_std_compliance: i32,
+        gop_size: i32,
+        max_b_frames: i32,
+        global_quality: i32,
+        thread_count: i32,
+    }
+);
 
 impl AVCodecContext {
     /// Create a new [`AVCodecContext`] instance, allocate private data and
@@ -178,11 +315,28 @@ impl AVCodecContext {
     ///
     /// dict: A [`AVDictionary`] filled with [`AVCodecContext`] and [`AVCodec`]
     /// private options.  Function returns a [`AVDictionary`] filled with
-    /// options that were not found if given dictionary. It can usually be
-    /// ignored.
+    /// options that were not found if given dictionary, e.g. misspelled option
+    /// names, `None` if all of them are consumed.
     ///
     /// Note: Always call this function before using decoding routines, such as [`Self::receive_frame()`].
+    ///
+    /// For audio encoders without a time base set, the time base is set to
+    /// `1/sample_rate` before opening.
+    ///
+    /// Return [`RsmpegError::CodecAlreadyOpenedError`] if the context is
+    /// already opened.
     pub fn open(&mut self, dict: Option<AVDictionary>) -> Result<Option<AVDictionary>> {
+        self.check_unopened()?;
+        if self.codec_type == ffi::AVMEDIA_TYPE_AUDIO
+            && self.time_base.num <= 0
+            && self.sample_rate > 0
+            && unsafe { ffi::av_codec_is_encoder(self.codec) } != 0
+        {
+            self.set_time_base(AVRational {
+                num: 1,
+                den: self.sample_rate,
+            })?;
+        }
         if let Some(mut dict) = dict {
             let dict_ptr = {
                 // Doesn't use into_raw or we will drop the dict when error occurs.
@@ -205,8 +359,155 @@ impl AVCodecContext {
         }
     }
 
+    /// Return true if the context is opened by [`Self::open()`].
+    pub fn is_open(&self) -> bool {
+        unsafe { ffi::avcodec_is_open(self.as_ptr() as *mut _) > 0 }
+    }
+
+    /// Set the multithreading methods allowed, with
+    /// [`Self::try_set_thread_count()`], 0 for as many threads as CPU cores.
+    /// Read `active_thread_type` after opening for the one in use.
+    ///
+    /// Return [`RsmpegError::CodecAlreadyOpenedError`] after opening, the
+    /// threads are created then.
+    pub fn set_thread_type(&mut self, thread_type: ThreadType) -> Result<()> {
+        self.check_unopened()?;
+        unsafe { self.deref_mut() }.thread_type = thread_type as i32;
+        Ok(())
+    }
+
+    /// Set the frames a decoder skips decoding, e.g. [`Discard::NonKey`] for
+    /// scanning the key frames quickly. Decoders read it for each frame, so
+    /// it can be changed while decoding.
+    pub fn set_skip_frame(&mut self, discard: Discard) {
+        unsafe { self.deref_mut() }.skip_frame = discard as ffi::AVDiscard;
+    }
+
+    /// Set the frames a decoder skips the loop filter of, trading quality
+    /// for speed. It can be changed while decoding, like
+    /// [`Self::set_skip_frame()`].
+    pub fn set_skip_loop_filter(&mut self, discard: Discard) {
+        unsafe { self.deref_mut() }.skip_loop_filter = discard as ffi::AVDiscard;
+    }
+
+    /// Decode at `1 << lowres` times less width and height, for the decoders
+    /// supporting it, e.g. 1 for half size with the MJPEG one.
+    ///
+    /// Return `AVERROR(EINVAL)` if `lowres` is more than the `max_lowres` of
+    /// the codec, [`RsmpegError::CodecAlreadyOpenedError`] after opening.
+    pub fn set_lowres(&mut self, lowres: i32) -> Result<()> {
+        self.check_unopened()?;
+        let max_lowres = unsafe { self.codec.as_ref() }.map_or(0, |codec| codec.max_lowres);
+        if !(0..=max_lowres as i32).contains(&lowres) {
+            return Err(RsmpegError::AVError(ffi::AVERROR(ffi::EINVAL)));
+        }
+        unsafe { self.deref_mut() }.lowres = lowres;
+        Ok(())
+    }
+
+    /// Set `AV_CODEC_FLAG2_FAST`, allowing speedups that don't comply with
+    /// the specification, e.g. skipping the loop filter of H.264 chroma.
+    pub fn set_fast(&mut self, fast: bool) -> Result<()> {
+        let flag = ffi::AV_CODEC_FLAG2_FAST as i32;
+        if fast {
+            self.set_flags2(self.flags2 | flag)
+        } else {
+            self.set_flags2(self.flags2 & !flag)
+        }
+    }
+
+    /// Set `AV_CODEC_FLAG_COPY_OPAQUE`, making decoders copy the `opaque_ref`
+    /// of packets to the frames decoded from them, and encoders copy the one
+    /// of frames to the packets encoded from them, see
+    /// [`AVFrame::set_opaque()`] and [`AVPacket::set_opaque()`].
+    pub fn set_copy_opaque(&mut self, copy_opaque: bool) -> Result<()> {
+        let flag = ffi::AV_CODEC_FLAG_COPY_OPAQUE as i32;
+        if copy_opaque {
+            self.set_flags(self.flags | flag)
+        } else {
+            self.set_flags(self.flags & !flag)
+        }
+    }
+
+    /// Let `callback` allocate the buffers of the decoded video frames, e.g.
+    /// in memory shared with the GPU, should be called before
+    /// [`Self::open()`].
+    ///
+    /// The callback fills `data`, `linesize` and `buf` of the given frame,
+    /// whose `width`, `height` and `format` are set, e.g. with
+    /// [`AVFrame::set_buffer()`]. Each plane should hold an image of the
+    /// aligned size in [`BufferRequest`] with linesizes aligned as requested,
+    /// start at an address aligned to [`cpu::max_align()`] and be followed by
+    /// at least 16 + `max_align() - 1` bytes of padding, as
+    /// [`AVBufferRef::new()`] and FFmpeg's own allocator do.
+    ///
+    /// If the callback fails or panics, the buffers are allocated by FFmpeg
+    /// instead, same for audio frames, hardware frames and codecs without
+    /// `AV_CODEC_CAP_DR1`. With frame threading, the callback is called from
+    /// the decoding threads.
+    ///
+    /// The `opaque` field of the context holds the callback.
+    ///
+    /// # Safety
+    /// The decoder writes the image into the buffers set by `callback` without
+    /// checking their sizes, so each plane must hold the requested image with
+    /// the padding described above.
+    ///
+    /// [`AVBufferRef::new()`]: crate::avutil::AVBufferRef::new
+    /// [`cpu::max_align()`]: crate::avutil::cpu::max_align
+    pub unsafe fn set_get_buffer2(
+        &mut self,
+        callback: impl FnMut(&mut AVFrame, BufferRequest) -> Result<()> + Send + 'static,
+    ) -> Result<()> {
+        self.check_unopened()?;
+        let callback: Box<GetBuffer2> = Box::new(Mutex::new(Box::new(callback)));
+        self.free_get_buffer2();
+        unsafe {
+            let context = self.deref_mut();
+            context.opaque = Box::into_raw(callback) as *mut c_void;
+            context.get_buffer2 = Some(get_buffer2_c);
+        }
+        Ok(())
+    }
+
+    /// Return true if a callback is set by [`Self::set_get_buffer2()`].
+    fn has_get_buffer2(&self) -> bool {
+        self.get_buffer2.map(|x| x as usize) == Some(get_buffer2_c as usize)
+    }
+
+    /// Free the callback set by [`Self::set_get_buffer2()`], if any.
+    fn free_get_buffer2(&mut self) {
+        if self.has_get_buffer2() {
+            let _ = unsafe { Box::from_raw(self.opaque as *mut GetBuffer2) };
+            unsafe {
+                let context = self.deref_mut();
+                context.opaque = ptr::null_mut();
+                context.get_buffer2 = Some(ffi::avcodec_default_get_buffer2);
+            }
+        }
+    }
+
+    /// Fail with [`RsmpegError::CodecAlreadyOpenedError`] if the context is
+    /// opened, for configuration only valid before opening.
+    fn check_unopened(&self) -> Result<()> {
+        if self.is_open() {
+            return Err(RsmpegError::CodecAlreadyOpenedError);
+        }
+        Ok(())
+    }
+
+    /// Fail with [`RsmpegError::CodecNotOpenedError`] if the context isn't
+    /// opened, for encoding and decoding.
+    fn check_opened(&self) -> Result<()> {
+        if !self.is_open() {
+            return Err(RsmpegError::CodecNotOpenedError);
+        }
+        Ok(())
+    }
+
     /// Trying to push a packet to current decoding_context([`AVCodecContext`]).
     pub fn send_packet(&mut self, packet: Option<&AVPacket>) -> Result<()> {
+        self.check_opened()?;
         let packet_ptr = match packet {
             Some(packet) => packet.as_ptr(),
             None => ptr::null(),
@@ -221,6 +522,7 @@ impl AVCodecContext {
 
     /// Trying to pull a frame from current decoding_context([`AVCodecContext`]).
     pub fn receive_frame(&mut self) -> Result<AVFrame> {
+        self.check_opened()?;
         let mut frame = AVFrame::new();
         match unsafe { ffi::avcodec_receive_frame(self.as_mut_ptr(), frame.as_mut_ptr()) }.upgrade()
         {
@@ -231,8 +533,67 @@ impl AVCodecContext {
         }
     }
 
+    /// Same as [`Self::receive_frame()`], but decodes into the given `frame`
+    /// instead of allocating a new one. The previous content of `frame` is
+    /// unreferenced first, on error `frame` is left blank.
+    pub fn receive_frame_into(&mut self, frame: &mut AVFrame) -> Result<()> {
+        frame.unref();
+        self.check_opened()?;
+        match unsafe { ffi::avcodec_receive_frame(self.as_mut_ptr(), frame.as_mut_ptr()) }.upgrade()
+        {
+            Ok(_) => Ok(()),
+            Err(AVERROR_EAGAIN) => Err(RsmpegError::DecoderDrainError),
+            Err(ffi::AVERROR_EOF) => Err(RsmpegError::DecoderFlushedError),
+            Err(x) => Err(RsmpegError::ReceiveFrameError(x)),
+        }
+    }
+
+    /// Enter the draining mode of a decoder at the end of the input, and
+    /// receive the frames it still holds. Calling it on a flushed decoder
+    /// returns no frames. Call [`Self::flush_buffers()`] to decode again.
+    pub fn flush_decoder(&mut self) -> Result<Vec<AVFrame>> {
+        match self.send_packet(None) {
+            Ok(()) | Err(RsmpegError::DecoderFlushedError) => {}
+            Err(e) => return Err(e),
+        }
+        let mut frames = vec![];
+        loop {
+            match self.receive_frame() {
+                Ok(frame) => frames.push(frame),
+                Err(RsmpegError::DecoderFlushedError) => return Ok(frames),
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Enter the draining mode of an encoder at the end of the input, and
+    /// receive the packets it still holds, e.g. the delayed ones of encoders
+    /// with B-frames or a lookahead. Calling it on a flushed encoder returns no
+    /// packets.
+    pub fn flush_encoder(&mut self) -> Result<Vec<AVPacket>> {
+        match self.send_frame(None) {
+            Ok(()) | Err(RsmpegError::EncoderFlushedError) => {}
+            Err(e) => return Err(e),
+        }
+        let mut packets = vec![];
+        loop {
+            match self.receive_packet() {
+                Ok(packet) => packets.push(packet),
+                Err(RsmpegError::EncoderFlushedError) => return Ok(packets),
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Reset the internal codec state / flush internal buffers. Should be
+    /// called e.g. when seeking or when switching to a different stream.
+    pub fn flush_buffers(&mut self) {
+        unsafe { ffi::avcodec_flush_buffers(self.as_mut_ptr()) }
+    }
+
     /// Trying to push a frame to current encoding_context([`AVCodecContext`]).
     pub fn send_frame(&mut self, frame: Option<&AVFrame>) -> Result<()> {
+        self.check_opened()?;
         let frame_ptr = match frame {
             Some(frame) => frame.as_ptr(),
             None => ptr::null(),
@@ -247,6 +608,7 @@ impl AVCodecContext {
 
     /// Trying to pull a packet from current encoding_context([`AVCodecContext`]).
     pub fn receive_packet(&mut self) -> Result<AVPacket> {
+        self.check_opened()?;
         let mut packet = AVPacket::new();
         match unsafe { ffi::avcodec_receive_packet(self.as_mut_ptr(), packet.as_mut_ptr()) }
             .upgrade()
@@ -266,6 +628,7 @@ impl AVCodecContext {
     /// decoding to get all the decoded data. Flushing is done by calling this
     /// function with `None`.
     pub fn decode_subtitle(&mut self, packet: Option<&mut AVPacket>) -> Result<Option<AVSubtitle>> {
+        self.check_opened()?;
         let mut subtitle = AVSubtitle::new();
         let mut got_sub = 0;
         let mut local_packet;
@@ -305,6 +668,7 @@ impl AVCodecContext {
 
     /// Encode subtitle to buffer.
     pub fn encode_subtitle(&mut self, subtitle: &AVSubtitle, buf: &mut [u8]) -> Result<()> {
+        self.check_opened()?;
         unsafe {
             ffi::avcodec_encode_subtitle(
                 self.as_mut_ptr(),
@@ -326,20 +690,194 @@ impl AVCodecContext {
     /// of the corresponding field in `codecpar`. Fields in current
     /// `AVCodecContext` that do not have a counterpart in given `codecpar` are
     /// not touched.
+    ///
+    /// Return [`RsmpegError::CodecAlreadyOpenedError`] after opening.
     pub fn apply_codecpar(&mut self, codecpar: &AVCodecParameters) -> Result<()> {
+        self.check_unopened()?;
         unsafe { ffi::avcodec_parameters_to_context(self.as_mut_ptr(), codecpar.as_ptr()) }
             .upgrade()?;
         Ok(())
     }
 
+    /// Set the time base, the unit of frame timestamps. Both the numerator and
+    /// the denominator should be positive.
+    ///
+    /// Encoders may adjust it on [`Self::open()`], read `time_base` afterwards
+    /// for the effective value.
+    pub fn set_time_base(&mut self, time_base: AVRational) -> Result<()> {
+        self.check_unopened()?;
+        validate_rational(time_base)?;
+        unsafe { self.deref_mut() }.time_base = time_base;
+        Ok(())
+    }
+
+    /// Set the frame rate. Both the numerator and the denominator should be
+    /// positive.
+    pub fn set_framerate(&mut self, framerate: AVRational) -> Result<()> {
+        self.check_unopened()?;
+        validate_rational(framerate)?;
+        unsafe { self.deref_mut() }.framerate = framerate;
+        Ok(())
+    }
+
+    /// Set `framerate` to `framerate` and `time_base` to its inverse, which
+    /// makes the timestamps of constant frame rate video count frames.
+    pub fn set_time_base_from_framerate(&mut self, framerate: AVRational) -> Result<()> {
+        self.set_framerate(framerate)?;
+        self.set_time_base(AVRational {
+            num: framerate.den,
+            den: framerate.num,
+        })
+    }
+
+    /// Set the option `key` of the context or of the codec private options,
+    /// e.g. `preset` of libx264, to `value`. The value is parsed as `av_opt_set`
+    /// does, flags take the `+flag-other` syntax.
+    ///
+    /// Options are read by the codec on [`Self::open()`], setting them
+    /// afterwards returns [`RsmpegError::CodecAlreadyOpenedError`].
+    /// [`RsmpegError::SetPropertyError`] carries the error code on failure,
+    /// e.g. `AVERROR_OPTION_NOT_FOUND`.
+    pub fn set_opt_str(&mut self, key: &CStr, value: &CStr) -> Result<()> {
+        self.check_unopened()?;
+        unsafe {
+            ffi::av_opt_set(
+                self.as_mut_ptr().cast(),
+                key.as_ptr(),
+                value.as_ptr(),
+                ffi::AV_OPT_SEARCH_CHILDREN as i32,
+            )
+        }
+        .upgrade()
+        .map_err(RsmpegError::SetPropertyError)?;
+        Ok(())
+    }
+
+    /// Integer version of [`Self::set_opt_str()`].
+    pub fn set_opt_int(&mut self, key: &CStr, value: i64) -> Result<()> {
+        self.check_unopened()?;
+        unsafe {
+            ffi::av_opt_set_int(
+                self.as_mut_ptr().cast(),
+                key.as_ptr(),
+                value,
+                ffi::AV_OPT_SEARCH_CHILDREN as i32,
+            )
+        }
+        .upgrade()
+        .map_err(RsmpegError::SetPropertyError)?;
+        Ok(())
+    }
+
+    /// Replace the opened context with a new one of the same codec, configured
+    /// with `codecpar` and `pkt_timebase`, e.g. when stream parameters change
+    /// mid-stream. Drain the context before calling this, frames still buffered
+    /// in the old context are dropped.
+    ///
+    /// Options set on the old context(threading, etc.) are not carried over.
+    pub fn reconfigure(
+        &mut self,
+        codecpar: &AVCodecParameters,
+        pkt_timebase: AVRational,
+    ) -> Result<()> {
+        let mut context = AVCodecContext::new(&self.codec());
+        context.apply_codecpar(codecpar)?;
+        context.try_set_pkt_timebase(pkt_timebase)?;
+        if self.framerate.num > 0 {
+            context.set_framerate(self.framerate)?;
+        }
+        context.open(None)?;
+        *self = context;
+        Ok(())
+    }
+
+    /// Similar to [`Self::reconfigure()`], with the current codec parameters
+    /// and time base of `stream`.
+    pub fn reconfigure_from_stream(&mut self, stream: &AVStream) -> Result<()> {
+        self.reconfigure(&stream.codecpar(), stream.time_base)
+    }
+
+    /// Pick the pixel format supported by the codec of this context that loses
+    /// the least when converting from `desired`, `desired` itself if it's
+    /// supported or the codec declares no supported list.
+    pub fn pick_best_pix_fmt(&self, desired: AVPixelFormat) -> Result<AVPixelFormat> {
+        self.pick_best_pix_fmt_with_loss(desired)
+            .map(|(pix_fmt, _)| pix_fmt)
+    }
+
+    /// Similar to [`Self::pick_best_pix_fmt()`], while also returning the
+    /// loss of converting `desired` to the picked format.
+    pub fn pick_best_pix_fmt_with_loss(
+        &self,
+        desired: AVPixelFormat,
+    ) -> Result<(AVPixelFormat, PixFmtLoss)> {
+        let codec = self.codec();
+        if codec.pix_fmts().is_none() {
+            return Ok((desired, PixFmtLoss(0)));
+        }
+        let has_alpha = AVPixFmtDescriptorRef::get(desired)
+            .map(|desc| desc.has_alpha())
+            .unwrap_or(false);
+        let mut loss = 0;
+        let pix_fmt = unsafe {
+            ffi::avcodec_find_best_pix_fmt_of_list(
+                codec.pix_fmts,
+                desired,
+                has_alpha as _,
+                &mut loss,
+            )
+        };
+        if pix_fmt == ffi::AV_PIX_FMT_NONE {
+            return Err(RsmpegError::AVError(ffi::AVERROR(ffi::EINVAL)));
+        }
+        Ok((pix_fmt, PixFmtLoss(loss)))
+    }
+
+    /// Pick the sample format supported by the codec of this context closest to
+    /// `desired`: `desired` itself, then its planar/packed alternative, then
+    /// the first one with no less bytes per sample, then the first one.
+    pub fn pick_sample_fmt(&self, desired: ffi::AVSampleFormat) -> Result<ffi::AVSampleFormat> {
+        let codec = self.codec();
+        let sample_fmts = match codec.sample_fmts() {
+            Some(sample_fmts) => sample_fmts,
+            None => return Ok(desired),
+        };
+        let bytes = |sample_fmt| SampleFormat(sample_fmt).bytes();
+        let planar = SampleFormat(desired).planar().map(|x| x.0);
+        let packed = SampleFormat(desired).packed().map(|x| x.0);
+        [Some(desired), planar, packed]
+            .into_iter()
+            .flatten()
+            .find(|x| sample_fmts.contains(x))
+            .or_else(|| {
+                sample_fmts
+                    .iter()
+                    .copied()
+                    .find(|&x| bytes(x) >= bytes(desired))
+            })
+            .or_else(|| sample_fmts.first().copied())
+            .ok_or(RsmpegError::AVError(ffi::AVERROR(ffi::EINVAL)))
+    }
+
+    /// Pick the sample rate supported by the codec of this context nearest to
+    /// `desired`(the higher one on ties), `desired` itself if the codec
+    /// accepts any rate.
+    pub fn pick_sample_rate(&self, desired: i32) -> Result<i32> {
+        let codec = self.codec();
+        let sample_rates = match codec.supported_samplerates() {
+            Some(sample_rates) => sample_rates,
+            None => return Ok(desired),
+        };
+        sample_rates
+            .iter()
+            .copied()
+            .min_by_key(|&x| ((x as i64 - desired as i64).abs(), -x))
+            .ok_or(RsmpegError::AVError(ffi::AVERROR(ffi::EINVAL)))
+    }
+
     /// Get a filled [`AVCodecParameters`] based on the values from current [`AVCodecContext`].
     pub fn extract_codecpar(&self) -> AVCodecParameters {
-        let mut parameters = AVCodecParameters::new();
-        // Only fails on no memory, so unwrap.
-        unsafe { ffi::avcodec_parameters_from_context(parameters.as_mut_ptr(), self.as_ptr()) }
-            .upgrade()
-            .unwrap();
-        parameters
+        AVCodecParameters::from_codec_context(self)
     }
 
     /// Get channel layout
@@ -348,6 +886,39 @@ impl AVCodecContext {
         unsafe { AVChannelLayoutRef::from_raw(inner) }
     }
 
+    /// Set channel layout, the previous layout is uninitialized and the
+    /// context takes the ownership of `ch_layout`, an
+    /// [`AVChannelLayout`](crate::avutil::AVChannelLayout) or a raw one.
+    ///
+    /// Return [`RsmpegError::CodecAlreadyOpenedError`] after opening,
+    /// `ch_layout` is dropped then.
+    pub fn try_set_ch_layout(&mut self, ch_layout: impl Into<ffi::AVChannelLayout>) -> Result<()> {
+        let mut ch_layout = ch_layout.into();
+        if let Err(e) = self.check_unopened() {
+            unsafe { ffi::av_channel_layout_uninit(&mut ch_layout) };
+            return Err(e);
+        }
+        unsafe {
+            ffi::av_channel_layout_uninit(&mut self.deref_mut().ch_layout);
+            self.deref_mut().ch_layout = ch_layout;
+        }
+        Ok(())
+    }
+
+    #[deprecated(note = "use `try_set_ch_layout()`, which returns the error")]
+    pub fn set_ch_layout(&mut self, ch_layout: impl Into<ffi::AVChannelLayout>) {
+        if let Err(e) = self.try_set_ch_layout(ch_layout) {
+            let message = format!("set_ch_layout ignored: {}", e);
+            unsafe {
+                log_message(
+                    self.as_mut_ptr().cast(),
+                    ffi::AV_LOG_WARNING as i32,
+                    &message,
+                )
+            };
+        }
+    }
+
     pub fn hw_frames_ctx(&self) -> Option<AVHWFramesContextRef> {
         let hw_frame_ctx = NonNull::new(self.hw_frames_ctx)?;
         Some(unsafe { AVHWFramesContextRef::from_raw(hw_frame_ctx) })
@@ -358,6 +929,24 @@ impl AVCodecContext {
         Some(unsafe { AVHWFramesContextMut::from_raw(hw_frame_ctx) })
     }
 
+    /// Decode or encode with the hardware device `hw_device_ctx`, before
+    /// [`Self::open()`]. The context takes its own reference to the device,
+    /// so `hw_device_ctx` can be dropped before the context.
+    ///
+    /// Decoded frames are then in the hardware pixel format of the device
+    /// (check [`AVCodec::hw_configs()`]), download them with
+    /// [`AVFrame::hwframe_transfer_data()`].
+    pub fn set_hw_device_ctx(&mut self, hw_device_ctx: &AVHWDeviceContext) -> Result<()> {
+        self.check_unopened()?;
+        let buffer_ref = AVBufferRef::clone(hw_device_ctx).into_raw();
+        unsafe {
+            let context = self.deref_mut();
+            ffi::av_buffer_unref(&mut context.hw_device_ctx);
+            context.hw_device_ctx = buffer_ref.as_ptr();
+        }
+        Ok(())
+    }
+
     pub fn set_hw_frames_ctx(&mut self, hw_frames_ctx: AVHWFramesContext) {
         unsafe { self.deref_mut().hw_frames_ctx = hw_frames_ctx.buffer_ref.into_raw().as_ptr() };
     }
@@ -372,6 +961,13 @@ impl AVCodecContext {
     }
 }
 
+fn validate_rational(value: AVRational) -> Result<()> {
+    if value.num <= 0 || value.den <= 0 {
+        return Err(RsmpegError::AVError(ffi::AVERROR(ffi::EINVAL)));
+    }
+    Ok(())
+}
+
 impl<'ctx> AVCodecContext {
     /// Get a reference to the [`AVCodec`] in current codec context.
     pub fn codec(&'ctx self) -> AVCodecRef<'ctx> {
@@ -381,12 +977,127 @@ impl<'ctx> AVCodecContext {
 
 impl Drop for AVCodecContext {
     fn drop(&mut self) {
+        // Decoding threads may call the callback until they are joined in
+        // freeing, so it's dropped afterwards.
+        let get_buffer2 = self
+            .has_get_buffer2()
+            .then(|| self.opaque as *mut GetBuffer2);
         // A pointer holder
         let mut context = self.as_mut_ptr();
         unsafe {
             ffi::avcodec_free_context(&mut context);
         }
+        if let Some(get_buffer2) = get_buffer2 {
+            let _ = unsafe { Box::from_raw(get_buffer2) };
+        }
+    }
+}
+
+/// Callback set by [`AVCodecContext::set_get_buffer2()`], locked as decoding
+/// threads may call it concurrently.
+type GetBuffer2 = Mutex<Box<dyn FnMut(&mut AVFrame, BufferRequest) -> Result<()> + Send>>;
+
+/// Buffer requirements of a decoded video frame, see
+/// [`AVCodecContext::set_get_buffer2()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BufferRequest {
+    pub width: i32,
+    pub height: i32,
+    pub format: AVPixelFormat,
+    /// Size of the image the planes should hold, `width` and `height` padded
+    /// as the codec requires.
+    pub aligned_width: i32,
+    pub aligned_height: i32,
+    /// The linesize of each plane should be a multiple of the value here.
+    pub linesize_align: [i32; 8],
+    /// Whether the decoder keeps a reference to the frame for predicting other
+    /// frames(`AV_GET_BUFFER_FLAG_REF`).
+    pub is_ref: bool,
+}
+
+impl BufferRequest {
+    /// Return `None` for the frames the callback can't allocate: audio,
+    /// hardware frames, or codecs without `AV_CODEC_CAP_DR1`.
+    fn new(context: &ffi::AVCodecContext, frame: &ffi::AVFrame, flags: i32) -> Option<Self> {
+        let codec = unsafe { context.codec.as_ref() }?;
+        if context.codec_type != ffi::AVMEDIA_TYPE_VIDEO
+            || codec.capabilities & ffi::AV_CODEC_CAP_DR1 as i32 == 0
+        {
+            return None;
+        }
+        let descriptor = AVPixFmtDescriptorRef::get(frame.format)?;
+        if descriptor.is_hwaccel() {
+            return None;
+        }
+        let (mut aligned_width, mut aligned_height) = (frame.width, frame.height);
+        let mut linesize_align = [0; 8];
+        unsafe {
+            ffi::avcodec_align_dimensions2(
+                context as *const _ as *mut _,
+                &mut aligned_width,
+                &mut aligned_height,
+                linesize_align.as_mut_ptr(),
+            )
+        };
+        Some(Self {
+            width: frame.width,
+            height: frame.height,
+            format: frame.format,
+            aligned_width,
+            aligned_height,
+            linesize_align,
+            is_ref: flags & ffi::AV_GET_BUFFER_FLAG_REF as i32 != 0,
+        })
+    }
+}
+
+unsafe extern "C" fn get_buffer2_c(
+    context: *mut ffi::AVCodecContext,
+    frame: *mut ffi::AVFrame,
+    flags: c_int,
+) -> c_int {
+    let request = unsafe { BufferRequest::new(&*context, &*frame, flags) };
+    if let Some(request) = request {
+        // `opaque` holds the callback as long as this function is set.
+        let callback = unsafe { &*((*context).opaque as *const GetBuffer2) };
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            // The frame is owned by the decoder.
+            let mut frame =
+                mem::ManuallyDrop::new(unsafe { AVFrame::from_raw(NonNull::new(frame).unwrap()) });
+            // A panic in the last call poisons the lock, the callback is used
+            // anyway.
+            let mut callback = callback.lock().unwrap_or_else(|e| e.into_inner());
+            (*callback)(&mut *frame, request)
+        }));
+        let frame = unsafe { &mut *frame };
+        let message = match result {
+            Ok(Ok(())) if !frame.data[0].is_null() && !frame.buf[0].is_null() => {
+                if frame.extended_data.is_null() {
+                    frame.extended_data = frame.data.as_mut_ptr();
+                }
+                return 0;
+            }
+            Ok(Ok(())) => "no buffer is set".to_string(),
+            Ok(Err(e)) => e.to_string(),
+            Err(_) => "panicked".to_string(),
+        };
+        // Drop what the callback set before failing.
+        for (buf, (data, linesize)) in frame
+            .buf
+            .iter_mut()
+            .zip(frame.data.iter_mut().zip(frame.linesize.iter_mut()))
+        {
+            unsafe { ffi::av_buffer_unref(buf) };
+            *data = ptr::null_mut();
+            *linesize = 0;
+        }
+        let message = format!(
+            "get_buffer2 callback failed: {}, using the default one",
+            message
+        );
+        unsafe { log_message(context.cast(), ffi::AV_LOG_WARNING as i32, &message) };
     }
+    unsafe { ffi::avcodec_default_get_buffer2(context, frame, flags) }
 }
 
 wrap_ref_mut!(AVSubtitle: ffi::AVSubtitle);
@@ -426,14 +1137,516 @@ impl Drop for AVSubtitle {
     }
 }
 
+impl AVSubtitle {
+    /// Get the rects of current subtitle.
+    pub fn rects(&self) -> Vec<SubtitleRect<'_>> {
+        if self.rects.is_null() {
+            return vec![];
+        }
+        let rects = unsafe { slice::from_raw_parts(self.rects, self.num_rects as usize) };
+        rects
+            .iter()
+            .map(|&rect| SubtitleRect::from_raw(unsafe { &*rect }))
+            .collect()
+    }
+}
+
+/// Content of an [`ffi::AVSubtitleRect`].
+#[derive(Debug, Clone, Copy)]
+pub enum SubtitleRect<'subtitle> {
+    /// Palettized image, e.g. PGS and DVB subtitles.
+    Bitmap(SubtitleBitmap<'subtitle>),
+    /// Plain text.
+    Text(&'subtitle CStr),
+    /// ASS event line.
+    Ass(&'subtitle CStr),
+    /// Rect of `SUBTITLE_NONE` type or with its content missing.
+    None,
+}
+
+impl<'subtitle> SubtitleRect<'subtitle> {
+    fn from_raw(rect: &'subtitle ffi::AVSubtitleRect) -> Self {
+        let text = |ptr: *mut std::os::raw::c_char| {
+            (!ptr.is_null()).then(|| unsafe { CStr::from_ptr(ptr) })
+        };
+        match rect.type_ {
+            ffi::SUBTITLE_BITMAP => SubtitleBitmap::from_raw(rect)
+                .map(Self::Bitmap)
+                .unwrap_or(Self::None),
+            ffi::SUBTITLE_TEXT => text(rect.text).map(Self::Text).unwrap_or(Self::None),
+            ffi::SUBTITLE_ASS => text(rect.ass).map(Self::Ass).unwrap_or(Self::None),
+            _ => Self::None,
+        }
+    }
+
+    /// Get the text of a text rect, or the `Text` field of an ASS event line,
+    /// i.e. the part after the first eight commas with the override tags (e.g.
+    /// `{\i1}`) and line breaks (`\N`) left in. `None` for bitmaps.
+    pub fn text(&self) -> Option<&'subtitle CStr> {
+        match *self {
+            Self::Text(text) => Some(text),
+            Self::Ass(ass) => {
+                // ReadOrder, Layer, Style, Name, MarginL, MarginR, MarginV, Effect, Text
+                let bytes = ass.to_bytes_with_nul();
+                let offset = bytes
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, &x)| x == b',')
+                    .nth(7)
+                    .map(|(i, _)| i + 1)?;
+                CStr::from_bytes_with_nul(&bytes[offset..]).ok()
+            }
+            Self::Bitmap(_) | Self::None => None,
+        }
+    }
+}
+
+/// Bitmap of a [`SubtitleRect`], one palette index per pixel.
+#[derive(Debug, Clone, Copy)]
+pub struct SubtitleBitmap<'subtitle> {
+    /// Top left corner of the bitmap in the video frame.
+    pub x: i32,
+    pub y: i32,
+    pub w: i32,
+    pub h: i32,
+    /// Number of colors in the palette.
+    pub nb_colors: i32,
+    /// Palette indices, `h` lines of `linesize` bytes.
+    pub data: &'subtitle [u8],
+    pub linesize: i32,
+    /// `nb_colors` native endian `0xAARRGGBB` colors, in bytes.
+    pub palette: &'subtitle [u8],
+}
+
+impl<'subtitle> SubtitleBitmap<'subtitle> {
+    fn from_raw(rect: &'subtitle ffi::AVSubtitleRect) -> Option<Self> {
+        let (w, h, linesize, nb_colors) = (rect.w, rect.h, rect.linesize[0], rect.nb_colors);
+        if w <= 0 || h <= 0 || linesize < w || !(0..=256).contains(&nb_colors) {
+            return None;
+        }
+        if rect.data[0].is_null() || (nb_colors > 0 && rect.data[1].is_null()) {
+            return None;
+        }
+        let len = linesize as usize * h as usize;
+        let data = unsafe { slice::from_raw_parts(rect.data[0], len) };
+        let palette = if nb_colors > 0 {
+            unsafe { slice::from_raw_parts(rect.data[1], nb_colors as usize * 4) }
+        } else {
+            &[]
+        };
+        Some(Self {
+            x: rect.x,
+            y: rect.y,
+            w,
+            h,
+            nb_colors,
+            data,
+            linesize,
+            palette,
+        })
+    }
+
+    /// Get the `0xAARRGGBB` color of palette entry `index`.
+    pub fn color(&self, index: u8) -> Option<u32> {
+        let offset = index as usize * 4;
+        let bytes = self.palette.get(offset..offset + 4)?;
+        Some(u32::from_ne_bytes(bytes.try_into().unwrap()))
+    }
+
+    /// Expand the bitmap into an `AV_PIX_FMT_RGBA` frame, ready to be fed to
+    /// an `overlay` filter at `x`/`y` of the returned [`SubtitleFrame`].
+    ///
+    /// The alpha of each palette entry is kept as is(not premultiplied),
+    /// indices out of the palette become fully transparent.
+    pub fn to_rgba_frame(&self) -> Result<SubtitleFrame> {
+        let mut frame = AVFrame::new();
+        frame.set_format(ffi::AV_PIX_FMT_RGBA);
+        frame.set_width(self.w);
+        frame.set_height(self.h);
+        frame.alloc_buffer()?;
+
+        let linesize = frame.linesize[0] as usize;
+        let image = frame.data_mut(0).unwrap();
+        for (src, dst) in self
+            .data
+            .chunks(self.linesize as usize)
+            .zip(image.chunks_mut(linesize))
+        {
+            for (&index, pixel) in src[..self.w as usize].iter().zip(dst.chunks_mut(4)) {
+                let argb = self.color(index).unwrap_or(0);
+                pixel.copy_from_slice(&[
+                    (argb >> 16) as u8,
+                    (argb >> 8) as u8,
+                    argb as u8,
+                    (argb >> 24) as u8,
+                ]);
+            }
+        }
+        Ok(SubtitleFrame {
+            x: self.x,
+            y: self.y,
+            frame,
+        })
+    }
+}
+
+/// RGBA image of a [`SubtitleBitmap`] with its position in the video frame.
+pub struct SubtitleFrame {
+    pub x: i32,
+    pub y: i32,
+    pub frame: AVFrame,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::avutil::{ra, AVChannelLayout};
     use cstr::cstr;
 
+    #[test]
+    fn test_setters() {
+        let encoder = AVCodec::find_encoder(ffi::AV_CODEC_ID_MPEG4).unwrap();
+        let mut context = AVCodecContext::new(&encoder);
+        context.try_set_width(64).unwrap();
+        context.try_set_height(48).unwrap();
+        context.try_set_pix_fmt(ffi::AV_PIX_FMT_YUV420P).unwrap();
+        context.try_set_sample_aspect_ratio(ra(1, 1)).unwrap();
+        context.try_set_pkt_timebase(ra(1, 90000)).unwrap();
+        context.try_set_sample_rate(44100).unwrap();
+        context.try_set_sample_fmt(ffi::AV_SAMPLE_FMT_S16).unwrap();
+        context
+            .try_set_flags(ffi::AV_CODEC_FLAG_GLOBAL_HEADER as i32)
+            .unwrap();
+        context.try_set_bit_rate(400_000).unwrap();
+        context
+            .try_set_strict_std_compliance(ffi::FF_COMPLIANCE_EXPERIMENTAL as i32)
+            .unwrap();
+        context.try_set_gop_size(12).unwrap();
+        context.try_set_max_b_frames(2).unwrap();
+        context.try_set_global_quality(5).unwrap();
+        context.try_set_thread_count(4).unwrap();
+        assert_eq!((context.width, context.height), (64, 48));
+        assert_eq!(context.pix_fmt, ffi::AV_PIX_FMT_YUV420P);
+        assert_eq!(context.sample_aspect_ratio.num, 1);
+        assert_eq!(context.pkt_timebase.den, 90000);
+        assert_eq!(context.sample_rate, 44100);
+        assert_eq!(context.sample_fmt, ffi::AV_SAMPLE_FMT_S16);
+        assert_eq!(context.flags, ffi::AV_CODEC_FLAG_GLOBAL_HEADER as i32);
+        assert_eq!(context.bit_rate, 400_000);
+        assert_eq!(
+            context.strict_std_compliance,
+            ffi::FF_COMPLIANCE_EXPERIMENTAL as i32
+        );
+        assert_eq!((context.gop_size, context.max_b_frames), (12, 2));
+        assert_eq!(context.global_quality, 5);
+        assert_eq!(context.thread_count, 4);
+
+        context
+            .try_set_ch_layout(AVChannelLayout::from_nb_channels(2))
+            .unwrap();
+        context
+            .try_set_ch_layout(AVChannelLayout::from_nb_channels(6))
+            .unwrap();
+        assert_eq!(context.ch_layout.nb_channels, 6);
+    }
+
+    #[test]
+    fn test_open_state() {
+        let encoder = AVCodec::find_encoder(ffi::AV_CODEC_ID_MPEG4).unwrap();
+        let mut context = AVCodecContext::new(&encoder);
+        assert!(!context.is_open());
+        assert_eq!(
+            context.send_frame(None),
+            Err(RsmpegError::CodecNotOpenedError)
+        );
+        assert_eq!(
+            context.receive_packet().unwrap_err(),
+            RsmpegError::CodecNotOpenedError
+        );
+
+        context.try_set_width(64).unwrap();
+        context.try_set_height(48).unwrap();
+        context.try_set_pix_fmt(ffi::AV_PIX_FMT_YUV420P).unwrap();
+        context.set_time_base(ra(1, 25)).unwrap();
+        context.open(None).unwrap();
+        assert!(context.is_open());
+
+        assert_eq!(
+            context.try_set_bit_rate(400_000),
+            Err(RsmpegError::CodecAlreadyOpenedError)
+        );
+        assert_eq!(
+            context.set_time_base(ra(1, 30)),
+            Err(RsmpegError::CodecAlreadyOpenedError)
+        );
+        assert_eq!(
+            context.open(None).unwrap_err(),
+            RsmpegError::CodecAlreadyOpenedError
+        );
+        assert_eq!(context.time_base.den, 25);
+        // The deprecated setters still compile, the value is ignored.
+        let bit_rate = context.bit_rate;
+        #[allow(deprecated)]
+        context.set_bit_rate(bit_rate + 1);
+        assert_eq!(context.bit_rate, bit_rate);
+        context.send_frame(None).unwrap();
+    }
+
+    #[test]
+    fn test_decode_options() {
+        let decoder = AVCodec::find_decoder(ffi::AV_CODEC_ID_MJPEG).unwrap();
+        let mut context = AVCodecContext::new(&decoder);
+        context.try_set_thread_count(2).unwrap();
+        context.set_thread_type(ThreadType::Slice).unwrap();
+        context.set_skip_frame(Discard::NonKey);
+        context.set_skip_loop_filter(Discard::All);
+        context.set_lowres(1).unwrap();
+        assert_eq!(
+            context.set_lowres(decoder.max_lowres as i32 + 1),
+            Err(RsmpegError::AVError(ffi::AVERROR(ffi::EINVAL)))
+        );
+        context.set_fast(true).unwrap();
+        assert_eq!(context.thread_type, ffi::FF_THREAD_SLICE as i32);
+        assert_eq!(context.skip_frame, ffi::AVDISCARD_NONKEY);
+        assert_eq!(context.skip_loop_filter, ffi::AVDISCARD_ALL);
+        assert_eq!(context.lowres, 1);
+        assert_eq!(context.flags2, ffi::AV_CODEC_FLAG2_FAST as i32);
+        context.set_fast(false).unwrap();
+        assert_eq!(context.flags2, 0);
+
+        context.open(None).unwrap();
+        assert_eq!(
+            context.try_set_thread_count(4),
+            Err(RsmpegError::CodecAlreadyOpenedError)
+        );
+        assert_eq!(
+            context.set_thread_type(ThreadType::Frame),
+            Err(RsmpegError::CodecAlreadyOpenedError)
+        );
+        assert_eq!(
+            context.set_lowres(0),
+            Err(RsmpegError::CodecAlreadyOpenedError)
+        );
+        assert_eq!(context.thread_count, 2);
+        // Still allowed while decoding.
+        context.set_skip_frame(Discard::Default);
+        assert_eq!(context.skip_frame, ffi::AVDISCARD_DEFAULT);
+    }
+
+    #[test]
+    fn test_thread_count_frames() {
+        use crate::avformat::AVFormatContextInput;
+
+        /// Decode the whole video, return the pts of the frames.
+        fn decode(thread_count: i32, thread_type: ThreadType) -> Vec<i64> {
+            let mut input =
+                AVFormatContextInput::open(cstr!("tests/assets/vids/centaur.mpg"), None, &mut None)
+                    .unwrap();
+            let (stream_index, decoder) = input
+                .find_best_stream(ffi::AVMEDIA_TYPE_VIDEO)
+                .unwrap()
+                .unwrap();
+            let mut context = AVCodecContext::new(&decoder);
+            context
+                .apply_codecpar(&input.streams()[stream_index].codecpar())
+                .unwrap();
+            context.try_set_thread_count(thread_count).unwrap();
+            context.set_thread_type(thread_type).unwrap();
+            context.open(None).unwrap();
+
+            let mut pts = vec![];
+            let mut receive = |context: &mut AVCodecContext| loop {
+                match context.receive_frame() {
+                    Ok(frame) => pts.push(frame.best_effort_timestamp),
+                    Err(RsmpegError::DecoderDrainError) | Err(RsmpegError::DecoderFlushedError) => {
+                        break
+                    }
+                    Err(e) => panic!("{e}"),
+                }
+            };
+            while let Some(packet) = input.read_packet().unwrap() {
+                if packet.stream_index as usize == stream_index {
+                    context.send_packet(Some(&packet)).unwrap();
+                    receive(&mut context);
+                }
+            }
+            context.send_packet(None).unwrap();
+            receive(&mut context);
+            pts
+        }
+
+        let single = decode(1, ThreadType::FrameAndSlice);
+        assert!(!single.is_empty());
+        // The frames buffered by each thread come out when draining.
+        assert_eq!(decode(4, ThreadType::Frame), single);
+        assert_eq!(decode(4, ThreadType::FrameAndSlice), single);
+    }
+
+    #[test]
+    fn test_open_options() {
+        let encoder = AVCodec::find_encoder(ffi::AV_CODEC_ID_MPEG4).unwrap();
+        let mut context = AVCodecContext::new(&encoder);
+        context.try_set_width(64).unwrap();
+        context.try_set_height(48).unwrap();
+        context.try_set_pix_fmt(ffi::AV_PIX_FMT_YUV420P).unwrap();
+        context.set_time_base(ra(1, 25)).unwrap();
+        // Unknown options are handed back.
+        let options =
+            AVDictionary::new(cstr!("g"), cstr!("10"), 0).set(cstr!("craf"), cstr!("23"), 0);
+        let options = context.open(Some(options)).unwrap().unwrap();
+        let pairs: Vec<_> = options.pairs().collect();
+        assert_eq!(pairs, [(cstr!("craf"), cstr!("23"))]);
+        assert_eq!(context.gop_size, 10);
+
+        let mut context = AVCodecContext::new(&encoder);
+        context.try_set_width(64).unwrap();
+        context.try_set_height(48).unwrap();
+        context.try_set_pix_fmt(ffi::AV_PIX_FMT_YUV420P).unwrap();
+        context.set_time_base(ra(1, 25)).unwrap();
+        let options = AVDictionary::new(cstr!("g"), cstr!("10"), 0);
+        assert!(context.open(Some(options)).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_set_opt() {
+        let encoder = AVCodec::find_encoder(ffi::AV_CODEC_ID_MPEG4).unwrap();
+        let mut context = AVCodecContext::new(&encoder);
+        context.try_set_width(64).unwrap();
+        context.try_set_height(48).unwrap();
+        context.try_set_pix_fmt(ffi::AV_PIX_FMT_YUV420P).unwrap();
+        context.set_time_base_from_framerate(ra(25, 1)).unwrap();
+        context.set_opt_str(cstr!("b"), cstr!("200k")).unwrap();
+        context.set_opt_int(cstr!("g"), 5).unwrap();
+        context
+            .set_opt_str(cstr!("flags"), cstr!("+global_header"))
+            .unwrap();
+        // Private option of the mpeg4 encoder.
+        context.set_opt_int(cstr!("data_partitioning"), 1).unwrap();
+        assert_eq!(context.bit_rate, 200_000);
+        assert_eq!(context.gop_size, 5);
+        assert_ne!(context.flags & ffi::AV_CODEC_FLAG_GLOBAL_HEADER as i32, 0);
+
+        assert_eq!(
+            context.set_opt_str(cstr!("preset"), cstr!("veryfast")),
+            Err(RsmpegError::SetPropertyError(ffi::AVERROR_OPTION_NOT_FOUND))
+        );
+        assert_eq!(
+            context.set_opt_str(cstr!("g"), cstr!("many")),
+            Err(RsmpegError::SetPropertyError(ffi::AVERROR(ffi::EINVAL)))
+        );
+
+        context.open(None).unwrap();
+        assert_eq!(
+            context.set_opt_int(cstr!("g"), 10),
+            Err(RsmpegError::CodecAlreadyOpenedError)
+        );
+
+        let mut nb_packets = 0;
+        for i in 0..10 {
+            let mut frame = AVFrame::new();
+            frame.set_width(64);
+            frame.set_height(48);
+            frame.set_format(ffi::AV_PIX_FMT_YUV420P);
+            frame.set_pts(i);
+            frame.alloc_buffer().unwrap();
+            for plane in 0..3 {
+                frame.data_mut(plane).unwrap().fill((i * 20) as u8);
+            }
+            context.send_frame(Some(&frame)).unwrap();
+            while context.receive_packet().is_ok() {
+                nb_packets += 1;
+            }
+        }
+        context.send_frame(None).unwrap();
+        while context.receive_packet().is_ok() {
+            nb_packets += 1;
+        }
+        assert_eq!(nb_packets, 10);
+    }
+
+    #[test]
+    fn test_time_base_and_framerate() {
+        let encoder = AVCodec::find_encoder(ffi::AV_CODEC_ID_MPEG4).unwrap();
+        let mut context = AVCodecContext::new(&encoder);
+        assert!(context
+            .set_time_base(AVRational { num: 1, den: 0 })
+            .is_err());
+        assert!(context
+            .set_framerate(AVRational { num: -25, den: 1 })
+            .is_err());
+        context
+            .set_time_base_from_framerate(AVRational {
+                num: 30000,
+                den: 1001,
+            })
+            .unwrap();
+        assert_eq!(context.time_base.num, 1001);
+        assert_eq!(context.time_base.den, 30000);
+        assert_eq!(context.framerate.num, 30000);
+        assert_eq!(context.framerate.den, 1001);
+
+        // Audio encoders default to `1/sample_rate`.
+        let encoder = AVCodec::find_encoder(ffi::AV_CODEC_ID_PCM_S16LE).unwrap();
+        let mut context = AVCodecContext::new(&encoder);
+        context.try_set_sample_rate(44100).unwrap();
+        context.try_set_sample_fmt(ffi::AV_SAMPLE_FMT_S16).unwrap();
+        context
+            .try_set_ch_layout(AVChannelLayout::from_nb_channels(2))
+            .unwrap();
+        context.open(None).unwrap();
+        assert_eq!(context.time_base.num, 1);
+        assert_eq!(context.time_base.den, 44100);
+    }
+
+    #[test]
+    fn test_pick_best_pix_fmt() {
+        // mpeg4 encoder only supports YUV420P.
+        let encoder = AVCodec::find_encoder(ffi::AV_CODEC_ID_MPEG4).unwrap();
+        let context = AVCodecContext::new(&encoder);
+        let (pix_fmt, loss) = context
+            .pick_best_pix_fmt_with_loss(ffi::AV_PIX_FMT_RGB24)
+            .unwrap();
+        assert_eq!(pix_fmt, ffi::AV_PIX_FMT_YUV420P);
+        assert!(loss.is_colorspace());
+        assert!(loss.is_resolution());
+        assert_eq!(
+            context.pick_best_pix_fmt_with_loss(ffi::AV_PIX_FMT_YUV420P),
+            Ok((ffi::AV_PIX_FMT_YUV420P, PixFmtLoss(0)))
+        );
+    }
+
+    #[test]
+    fn test_pick_sample_fmt_and_rate() {
+        // pcm_s16le accepts any sample rate.
+        let encoder = AVCodec::find_encoder(ffi::AV_CODEC_ID_PCM_S16LE).unwrap();
+        let context = AVCodecContext::new(&encoder);
+        assert_eq!(context.pick_sample_rate(44100), Ok(44100));
+        assert_eq!(
+            context.pick_sample_fmt(ffi::AV_SAMPLE_FMT_S16P),
+            Ok(ffi::AV_SAMPLE_FMT_S16)
+        );
+        assert_eq!(
+            context.pick_sample_fmt(ffi::AV_SAMPLE_FMT_FLT),
+            Ok(ffi::AV_SAMPLE_FMT_S16)
+        );
+
+        // mp2 supports 44100, 48000, 32000, 22050, 24000 and 16000.
+        let encoder = AVCodec::find_encoder(ffi::AV_CODEC_ID_MP2).unwrap();
+        let context = AVCodecContext::new(&encoder);
+        assert_eq!(context.pick_sample_rate(44000), Ok(44100));
+        assert_eq!(context.pick_sample_rate(8000), Ok(16000));
+        assert_eq!(context.pick_sample_rate(96000), Ok(48000));
+    }
+
     #[test]
     fn test_av_codec_iterator() {
         assert!(AVCodec::iterate().count() > 10);
+        assert!(AVCodec::iterate().any(|x| x.name() == cstr!("mpeg1video")
+            && x.is_decoder()
+            && x.id == ffi::AV_CODEC_ID_MPEG1VIDEO));
+        let mut iter = AVCodec::iterate();
+        for _ in iter.by_ref() {}
+        assert!(iter.next().is_none());
 
         let iter = AVCodec::iterate();
         for codec in iter {
@@ -452,4 +1665,141 @@ mod tests {
             println!("codec: {:?}: {:?}", codec.name(), codec.long_name());
         }
     }
+
+    #[test]
+    fn test_codec_capabilities() {
+        let encoder = AVCodec::find_encoder_by_name(cstr!("aac")).unwrap();
+        assert!(encoder.is_encoder());
+        assert!(!encoder.is_decoder());
+        assert_eq!(encoder.long_name(), cstr!("AAC (Advanced Audio Coding)"));
+        assert!(encoder
+            .sample_fmts()
+            .unwrap()
+            .contains(&ffi::AV_SAMPLE_FMT_FLTP));
+        assert!(encoder.supported_samplerates().unwrap().contains(&48000));
+        assert!(encoder.pix_fmts().is_none());
+        assert!(encoder.capabilities().is_delay());
+        assert!(!encoder.capabilities().is_variable_frame_size());
+
+        let decoder = AVCodec::find_decoder_by_name(cstr!("h264")).unwrap();
+        assert!(decoder.is_decoder());
+        assert!(!decoder.is_encoder());
+        assert!(decoder.capabilities().is_dr1());
+        assert!(decoder.sample_fmts().is_none());
+
+        let encoder = AVCodec::find_encoder(ffi::AV_CODEC_ID_MPEG4).unwrap();
+        assert_eq!(encoder.pix_fmts().unwrap(), [ffi::AV_PIX_FMT_YUV420P]);
+
+        let encoder = AVCodec::find_encoder(ffi::AV_CODEC_ID_AC3).unwrap();
+        let ch_layouts = encoder.ch_layouts().unwrap();
+        assert!(ch_layouts.len() > 2);
+        assert!(ch_layouts
+            .iter()
+            .any(|x| x.nb_channels == 6 && x.describe().unwrap().to_str().unwrap() == "5.1(side)"));
+    }
+
+    #[test]
+    fn test_hw_configs() {
+        let decoder = AVCodec::find_decoder(ffi::AV_CODEC_ID_H264).unwrap();
+        for config in decoder.hw_configs() {
+            assert_ne!(config.device_type, ffi::AV_HWDEVICE_TYPE_NONE);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "hwaccel")]
+    fn test_hw_device_ctx() {
+        let decoder = AVCodec::find_decoder(ffi::AV_CODEC_ID_H264).unwrap();
+        // Any device present on the machine will do.
+        let hw_device_ctx = decoder
+            .hw_configs()
+            .filter(|config| {
+                config.methods & ffi::AV_CODEC_HW_CONFIG_METHOD_HW_DEVICE_CTX as i32 != 0
+            })
+            .find_map(|config| AVHWDeviceContext::create(config.device_type, None, None, 0).ok())
+            .expect("No hardware device available");
+        assert_eq!(hw_device_ctx.get_ref_count(), 1);
+        let mut context = AVCodecContext::new(&decoder);
+        context.set_hw_device_ctx(&hw_device_ctx).unwrap();
+        assert_eq!(hw_device_ctx.get_ref_count(), 2);
+        // Setting it again releases the previous reference.
+        context.set_hw_device_ctx(&hw_device_ctx).unwrap();
+        assert_eq!(hw_device_ctx.get_ref_count(), 2);
+        let cloned = hw_device_ctx.clone();
+        assert_eq!(cloned.get_ref_count(), 3);
+        drop(hw_device_ctx);
+        drop(context);
+        assert_eq!(cloned.get_ref_count(), 1);
+    }
+
+    #[test]
+    fn test_subtitle_rects() {
+        let mut subtitle = AVSubtitle::new();
+        assert!(subtitle.rects().is_empty());
+        unsafe {
+            let alloc_rect = || {
+                ffi::av_mallocz(mem::size_of::<ffi::AVSubtitleRect>()) as *mut ffi::AVSubtitleRect
+            };
+            // 2x2 bitmap: transparent, half transparent red / opaque green,
+            // out of palette.
+            let bitmap = alloc_rect();
+            (*bitmap).type_ = ffi::SUBTITLE_BITMAP;
+            (*bitmap).x = 10;
+            (*bitmap).y = 20;
+            (*bitmap).w = 2;
+            (*bitmap).h = 2;
+            (*bitmap).nb_colors = 3;
+            (*bitmap).linesize[0] = 4;
+            (*bitmap).data[0] = ffi::av_mallocz(8) as *mut u8;
+            ptr::copy_nonoverlapping([0u8, 1, 0, 0, 2, 3, 0, 0].as_ptr(), (*bitmap).data[0], 8);
+            (*bitmap).data[1] = ffi::av_mallocz(ffi::AVPALETTE_SIZE as usize) as *mut u8;
+            let palette = (*bitmap).data[1] as *mut u32;
+            *palette.add(1) = 0x80FF0000;
+            *palette.add(2) = 0xFF00FF00;
+
+            let text = alloc_rect();
+            (*text).type_ = ffi::SUBTITLE_TEXT;
+            (*text).text = ffi::av_strdup(cstr!("hello").as_ptr());
+
+            let rects = ffi::av_mallocz(mem::size_of::<*mut ffi::AVSubtitleRect>() * 3)
+                as *mut *mut ffi::AVSubtitleRect;
+            *rects = bitmap;
+            *rects.add(1) = text;
+            *rects.add(2) = alloc_rect();
+            (*subtitle.as_mut_ptr()).rects = rects;
+            (*subtitle.as_mut_ptr()).num_rects = 3;
+        }
+
+        let rects = subtitle.rects();
+        assert_eq!(rects.len(), 3);
+        assert!(matches!(rects[1], SubtitleRect::Text(x) if x == cstr!("hello")));
+        assert_eq!(rects[1].text(), Some(cstr!("hello")));
+        assert!(matches!(rects[2], SubtitleRect::None));
+        assert_eq!(rects[2].text(), None);
+        assert_eq!(
+            SubtitleRect::Ass(cstr!("0,0,Default,,0,0,0,,Hi, {\\i1}there")).text(),
+            Some(cstr!("Hi, {\\i1}there"))
+        );
+        assert_eq!(SubtitleRect::Ass(cstr!("0,0,Default")).text(), None);
+        let SubtitleRect::Bitmap(bitmap) = rects[0] else {
+            panic!("not a bitmap");
+        };
+        assert_eq!((bitmap.x, bitmap.y, bitmap.w, bitmap.h), (10, 20, 2, 2));
+        assert_eq!(bitmap.nb_colors, 3);
+        assert_eq!(bitmap.color(2), Some(0xFF00FF00));
+        assert_eq!(bitmap.color(3), None);
+
+        let SubtitleFrame { x, y, frame } = bitmap.to_rgba_frame().unwrap();
+        assert_eq!((x, y), (10, 20));
+        assert_eq!(frame.format, ffi::AV_PIX_FMT_RGBA);
+        assert_eq!((frame.width, frame.height), (2, 2));
+        let line = |i: usize| unsafe {
+            slice::from_raw_parts(
+                frame.data[0].offset(frame.linesize[0] as isize * i as isize),
+                8,
+            )
+        };
+        assert_eq!(line(0), [0, 0, 0, 0, 0xFF, 0, 0, 0x80]);
+        assert_eq!(line(1), [0, 0xFF, 0, 0xFF, 0, 0, 0, 0]);
+    }
 }
@@ -1,10 +1,17 @@
 use crate::{
-    avcodec::AVCodecContext,
+    avcodec::{AVCodecContext, AVCodecID},
     avutil::{AVChannelLayoutRef, AVMediaType},
+    error::*,
     ffi,
     shared::*,
 };
-use std::{fmt, ops::Deref, ptr::NonNull};
+use std::{
+    ffi::c_void,
+    fmt,
+    ops::Deref,
+    ptr::{self, NonNull},
+    slice,
+};
 
 wrap_ref_mut!(AVCodecParameters: ffi::AVCodecParameters);
 
@@ -17,19 +24,37 @@ impl AVCodecParameters {
         unsafe { Self::from_raw(ptr) }
     }
 
+    /// Create a codecpar filled with the values of `context`, e.g. of an
+    /// opened encoder for the stream of an output.
+    pub fn from_codec_context(context: &AVCodecContext) -> Self {
+        let mut parameters = Self::new();
+        parameters.copy_from_context(context);
+        parameters
+    }
+
+    /// Fill current codecpar based on the values from the supplied
+    /// [`AVCodecContext`].
+    #[deprecated(
+        note = "renamed to `copy_from_context()`, use `from_codec_context()` for a new codecpar"
+    )]
+    pub fn from_context(&mut self, context: &AVCodecContext) {
+        self.copy_from_context(context)
+    }
+
     /// Fill current codecpar based on the values from the supplied
     /// [`AVCodecContext`]. Any allocated fields in this codecpar are freed and
     /// replaced with duplicates of the corresponding fields in codec.
-    pub fn from_context(&mut self, context: &AVCodecContext) {
+    pub fn copy_from_context(&mut self, context: &AVCodecContext) {
         // only fails when no memory, so wrap.
         unsafe { ffi::avcodec_parameters_from_context(self.as_mut_ptr(), context.as_ptr()) }
             .upgrade()
             .unwrap();
     }
 
-    /// Copy the contents from another [`AVCodecParameters`]. Any allocated fields in dst are freed
-    /// and replaced with newly allocated duplicates of the corresponding fields
-    /// in src.
+    /// Copy the contents from another [`AVCodecParameters`], e.g. the
+    /// `codecpar()` of an input stream for remuxing. Any allocated fields in
+    /// dst are freed and replaced with newly allocated duplicates of the
+    /// corresponding fields in src.
     pub fn copy(&mut self, from: &Self) {
         // `avcodec_parameters_copy()` ensures that destination pointer is
         // dropped, so we can legally set `self.raw` here.
@@ -40,16 +65,86 @@ impl AVCodecParameters {
             .unwrap();
     }
 
+    /// Same as [`Self::copy()`], from borrowed parameters, e.g. the
+    /// `codecpar()` of an input stream.
+    pub fn copy_from(&mut self, from: &AVCodecParametersRef) {
+        self.copy(from)
+    }
+
     /// Get the codec type.
     pub fn codec_type(&self) -> AVMediaType {
         AVMediaType(self.codec_type)
     }
 
+    /// Get the codec id.
+    pub fn codec_id(&self) -> AVCodecID {
+        self.codec_id
+    }
+
+    /// Get the width of video, 0 for other streams.
+    pub fn width(&self) -> i32 {
+        self.width
+    }
+
+    /// Get the height of video, 0 for other streams.
+    pub fn height(&self) -> i32 {
+        self.height
+    }
+
+    /// Get the sample rate of audio, 0 for other streams.
+    pub fn sample_rate(&self) -> i32 {
+        self.sample_rate
+    }
+
+    /// Get the codec specific extra data, e.g. the payload of attachment
+    /// streams.
+    pub fn extradata(&self) -> &[u8] {
+        if self.extradata.is_null() {
+            return &[];
+        }
+        unsafe { slice::from_raw_parts(self.extradata, self.extradata_size as usize) }
+    }
+
+    /// Replace the codec specific extra data with a padded copy of `data`.
+    ///
+    /// Return `AVERROR(EINVAL)` if `data` is too large for the padded size to
+    /// fit in an `i32`, `AVERROR(ENOMEM)` if the allocation fails. The extra
+    /// data is left untouched then.
+    pub fn set_extradata(&mut self, data: &[u8]) -> Result<()> {
+        let padding = ffi::AV_INPUT_BUFFER_PADDING_SIZE as usize;
+        if data.len() > i32::MAX as usize - padding {
+            return Err(RsmpegError::AVError(ffi::AVERROR(ffi::EINVAL)));
+        }
+        let extradata = unsafe { ffi::av_mallocz(data.len() + padding) }
+            .upgrade()
+            .ok_or(RsmpegError::AVError(ffi::AVERROR(ffi::ENOMEM)))?
+            .as_ptr() as *mut u8;
+        unsafe {
+            ptr::copy_nonoverlapping(data.as_ptr(), extradata, data.len());
+            let raw = self.deref_mut();
+            ffi::av_freep(&mut raw.extradata as *mut _ as *mut c_void);
+            raw.extradata = extradata;
+            raw.extradata_size = data.len() as i32;
+        }
+        Ok(())
+    }
+
     /// Get channel layout
     pub fn ch_layout(&self) -> AVChannelLayoutRef {
         let inner = NonNull::new(&self.ch_layout as *const _ as *mut _).unwrap();
         unsafe { AVChannelLayoutRef::from_raw(inner) }
     }
+
+    /// Get the number of priming samples(the encoder delay) at the start of an
+    /// audio stream, which are not part of the content.
+    pub fn initial_padding(&self) -> i32 {
+        self.initial_padding
+    }
+
+    /// Get the number of padding samples at the end of an audio stream.
+    pub fn trailing_padding(&self) -> i32 {
+        self.trailing_padding
+    }
 }
 
 impl fmt::Debug for AVCodecParameters {
@@ -1,6 +1,15 @@
-use std::{fmt, ptr::NonNull};
+use std::{
+    fmt,
+    ptr::{self, NonNull},
+    slice,
+};
 
-use crate::{avutil::AVRational, ffi, shared::*};
+use crate::{
+    avutil::{buffer_value, display_rotation_get, AVBufferRef, AVRational, Timestamp},
+    error::Result,
+    ffi,
+    shared::*,
+};
 
 wrap!(AVPacket: ffi::AVPacket);
 settable!(AVPacket {
@@ -19,6 +28,84 @@ impl AVPacket {
         unsafe { Self::from_raw(NonNull::new(packet).unwrap()) }
     }
 
+    /// Create a packet owning `data` without copying it, e.g. to feed a
+    /// decoder with units received from the network. The
+    /// `AV_INPUT_BUFFER_PADDING_SIZE` zero bytes decoders may read past the
+    /// end are appended to `data`, which reallocates it unless it has the
+    /// capacity.
+    ///
+    /// Empty `data` gives a valid packet of size 0, note that decoders take
+    /// such packets as the end of the stream.
+    ///
+    /// Note: if `data.len()` exceeds [`i32::MAX`], this function returns [`RsmpegError::TryFromIntError`].
+    ///
+    /// [`RsmpegError::TryFromIntError`]: crate::error::RsmpegError::TryFromIntError
+    pub fn from_data(mut data: Vec<u8>) -> Result<Self> {
+        let size: i32 = data.len().try_into()?;
+        data.resize(data.len() + ffi::AV_INPUT_BUFFER_PADDING_SIZE as usize, 0);
+        let buffer = AVBufferRef::from_vec(data);
+        let mut packet = Self::new();
+        unsafe {
+            let packet = packet.deref_mut();
+            packet.data = buffer.data;
+            packet.size = size;
+            packet.buf = buffer.into_raw().as_ptr();
+        }
+        Ok(packet)
+    }
+
+    /// Create a packet holding a copy of `data`, padded like
+    /// [`Self::from_data()`].
+    ///
+    /// Note: if `data.len()` exceeds [`i32::MAX`], this function returns [`RsmpegError::TryFromIntError`].
+    ///
+    /// [`RsmpegError::TryFromIntError`]: crate::error::RsmpegError::TryFromIntError
+    pub fn from_slice(data: &[u8]) -> Result<Self> {
+        let mut packet = Self::new();
+        unsafe { ffi::av_new_packet(packet.as_mut_ptr(), data.len().try_into()?) }.upgrade()?;
+        if !data.is_empty() {
+            unsafe { ptr::copy_nonoverlapping(data.as_ptr(), packet.data, data.len()) };
+        }
+        Ok(packet)
+    }
+
+    /// Return true if current packet contains a keyframe.
+    pub fn is_key(&self) -> bool {
+        self.flags & ffi::AV_PKT_FLAG_KEY as i32 != 0
+    }
+
+    /// Get the presentation timestamp in the stream time base, `None` if
+    /// unknown.
+    pub fn pts(&self) -> Option<i64> {
+        Timestamp(self.pts).get()
+    }
+
+    /// Get the decompression timestamp in the stream time base, `None` if
+    /// unknown.
+    pub fn dts(&self) -> Option<i64> {
+        Timestamp(self.dts).get()
+    }
+
+    /// Get the duration in the stream time base, `None` if unknown.
+    pub fn duration(&self) -> Option<i64> {
+        (self.duration > 0).then_some(self.duration)
+    }
+
+    /// Set the presentation timestamp, `None` for unknown.
+    pub fn set_pts_opt(&mut self, pts: Option<i64>) {
+        self.set_pts(Timestamp::from(pts).0);
+    }
+
+    /// Set the decompression timestamp, `None` for unknown.
+    pub fn set_dts_opt(&mut self, dts: Option<i64>) {
+        self.set_dts(Timestamp::from(dts).0);
+    }
+
+    /// Set the duration, `None` for unknown.
+    pub fn set_duration_opt(&mut self, duration: Option<i64>) {
+        self.set_duration(duration.unwrap_or(0));
+    }
+
     /// Convert valid timing fields (timestamps / durations) in a packet from
     /// one timebase to another. Timestamps with unknown values
     /// (`AV_NOPTS_VALUE`) will be ignored.
@@ -27,6 +114,87 @@ impl AVPacket {
             ffi::av_packet_rescale_ts(self.as_mut_ptr(), from, to);
         }
     }
+
+    /// Get the payload of current packet, `None` if it's empty.
+    pub fn data(&self) -> Option<&[u8]> {
+        let size = usize::try_from(self.size).ok()?;
+        (!self.data.is_null() && size > 0)
+            .then(|| unsafe { slice::from_raw_parts(self.data, size) })
+    }
+
+    /// Attach `value` to current packet as its `opaque_ref`, replacing the
+    /// previous one. Decoders copy it to the frames decoded from the packet
+    /// when [`AVCodecContext::set_copy_opaque()`] is enabled.
+    ///
+    /// [`AVCodecContext::set_copy_opaque()`]: crate::avcodec::AVCodecContext::set_copy_opaque
+    pub fn set_opaque<T: Send + Sync + 'static>(&mut self, value: T) {
+        let buffer = AVBufferRef::from_value(value);
+        unsafe {
+            let packet = self.deref_mut();
+            ffi::av_buffer_unref(&mut packet.opaque_ref);
+            packet.opaque_ref = buffer.into_raw().as_ptr();
+        }
+    }
+
+    /// Get the value attached by [`Self::set_opaque()`]. Return `None` if
+    /// there is no `opaque_ref` or it doesn't hold a `T`.
+    pub fn opaque_ref<T: Send + Sync + 'static>(&self) -> Option<&T> {
+        unsafe { buffer_value(self.opaque_ref) }
+    }
+
+    /// Get the side data of `side_data_type` of current packet.
+    pub fn side_data(&self, side_data_type: ffi::AVPacketSideDataType) -> Option<&[u8]> {
+        let mut size = 0;
+        let data =
+            unsafe { ffi::av_packet_get_side_data(self.as_ptr(), side_data_type, &mut size) };
+        (!data.is_null() && size > 0).then(|| unsafe { slice::from_raw_parts(data, size) })
+    }
+
+    /// Add side data of `side_data_type` to current packet, an existing entry
+    /// of the same type is replaced.
+    pub fn add_side_data(
+        &mut self,
+        side_data_type: ffi::AVPacketSideDataType,
+        data: &[u8],
+    ) -> Result<()> {
+        let buffer =
+            unsafe { ffi::av_packet_new_side_data(self.as_mut_ptr(), side_data_type, data.len()) }
+                .upgrade_or(ffi::AVERROR(ffi::ENOMEM))?;
+        unsafe { ptr::copy_nonoverlapping(data.as_ptr(), buffer.as_ptr(), data.len()) };
+        Ok(())
+    }
+
+    /// Get the rotation of the display matrix side data of current packet in
+    /// degrees counterclockwise, see [`display_rotation_get()`].
+    pub fn rotation(&self) -> Option<f64> {
+        display_rotation_get(self.side_data(ffi::AV_PKT_DATA_DISPLAYMATRIX)?)
+    }
+
+    /// Get the `AV_PKT_DATA_SKIP_SAMPLES` side data of current packet, e.g.
+    /// the encoder delay on the first audio packet.
+    ///
+    /// Decoders drop these samples by themselves unless
+    /// `AV_CODEC_FLAG2_SKIP_MANUAL` is set.
+    pub fn skip_samples(&self) -> Option<SkipSamples> {
+        let data = self.side_data(ffi::AV_PKT_DATA_SKIP_SAMPLES)?;
+        // u32le samples to skip from the start, u32le samples to discard from
+        // the end, then the reasons of both.
+        let data = data.get(..8)?;
+        Some(SkipSamples {
+            start: u32::from_le_bytes(data[..4].try_into().unwrap()),
+            end: u32::from_le_bytes(data[4..].try_into().unwrap()),
+        })
+    }
+}
+
+/// Samples to be dropped from the audio decoded from a packet, see
+/// [`AVPacket::skip_samples()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SkipSamples {
+    /// Number of samples to skip from the start.
+    pub start: u32,
+    /// Number of samples to discard from the end.
+    pub end: u32,
 }
 
 impl fmt::Debug for AVPacket {
@@ -43,6 +211,18 @@ impl fmt::Debug for AVPacket {
     }
 }
 
+impl Clone for AVPacket {
+    /// Create a new reference to the data of current packet, copying its
+    /// properties. The data is shared if the packet is reference counted,
+    /// copied otherwise.
+    fn clone(&self) -> Self {
+        let new_packet = unsafe { ffi::av_packet_clone(self.as_ptr()) }
+            .upgrade()
+            .unwrap();
+        unsafe { Self::from_raw(new_packet) }
+    }
+}
+
 impl Default for AVPacket {
     fn default() -> Self {
         Self::new()
@@ -59,3 +239,164 @@ impl Drop for AVPacket {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::avformat::AVFormatContextInput;
+    use cstr::cstr;
+
+    #[test]
+    fn test_packet_clone() {
+        let mut ifmt_ctx =
+            AVFormatContextInput::open(cstr!("tests/assets/vids/bear.mp4"), None, &mut None)
+                .unwrap();
+        let mut packet = ifmt_ctx.read_packet().unwrap().unwrap();
+        let pts = packet.pts;
+        let cloned = packet.clone();
+        packet.set_pts(pts + 42);
+        assert_eq!(cloned.pts, pts);
+        assert_eq!(packet.pts, pts + 42);
+
+        // The payload is shared.
+        assert!(!packet.buf.is_null());
+        assert_eq!(
+            packet.data().unwrap().as_ptr(),
+            cloned.data().unwrap().as_ptr()
+        );
+        assert_eq!(packet.data().unwrap().len(), packet.size as usize);
+        drop(packet);
+        assert_eq!(cloned.data().unwrap().len(), cloned.size as usize);
+
+        let packet = AVPacket::new();
+        assert!(packet.data().is_none());
+        assert_eq!(
+            (packet.pts(), packet.dts(), packet.duration()),
+            (None, None, None)
+        );
+        assert_eq!(cloned.pts(), Some(pts));
+
+        let mut packet = packet;
+        packet.set_pts_opt(Some(3));
+        packet.set_dts_opt(Some(2));
+        packet.set_duration_opt(Some(1));
+        assert_eq!(
+            (packet.pts(), packet.dts(), packet.duration()),
+            (Some(3), Some(2), Some(1))
+        );
+        packet.set_pts_opt(None);
+        packet.set_dts_opt(None);
+        packet.set_duration_opt(None);
+        assert_eq!(
+            (packet.pts(), packet.dts(), packet.duration()),
+            (None, None, None)
+        );
+        assert_eq!(packet.dts, ffi::AV_NOPTS_VALUE);
+    }
+
+    #[test]
+    fn test_packet_from_data() {
+        let padding = ffi::AV_INPUT_BUFFER_PADDING_SIZE as usize;
+        let packet = AVPacket::from_data(vec![1, 2, 3]).unwrap();
+        assert_eq!(packet.data(), Some(&[1, 2, 3][..]));
+        let buffer = unsafe { &*packet.buf };
+        assert_eq!(buffer.size, 3 + padding);
+        let padded = unsafe { slice::from_raw_parts(packet.data, 3 + padding) };
+        assert!(padded[3..].iter().all(|&x| x == 0));
+        assert!(!packet.is_key());
+
+        let packet = AVPacket::from_slice(&[4, 5]).unwrap();
+        assert_eq!(packet.data(), Some(&[4, 5][..]));
+        let padded = unsafe { slice::from_raw_parts(packet.data, 2 + padding) };
+        assert!(padded[2..].iter().all(|&x| x == 0));
+
+        for packet in [
+            AVPacket::from_data(vec![]).unwrap(),
+            AVPacket::from_slice(&[]).unwrap(),
+        ] {
+            assert_eq!(packet.size, 0);
+            assert!(packet.data().is_none());
+            assert!(!packet.buf.is_null());
+        }
+
+        let mut packet = AVPacket::from_data(vec![0; 16]).unwrap();
+        packet.set_flags(ffi::AV_PKT_FLAG_KEY as i32);
+        assert!(packet.is_key());
+        // Clones share the buffer.
+        assert_eq!(packet.clone().data, packet.data);
+    }
+
+    #[test]
+    fn test_decode_from_data() {
+        use crate::{avcodec::AVCodecContext, avutil::AVFrame};
+
+        fn decode(decode_context: &mut AVCodecContext, packet: Option<&AVPacket>) -> usize {
+            decode_context.send_packet(packet).unwrap();
+            let mut frame = AVFrame::new();
+            let mut nb_frames = 0;
+            while decode_context.receive_frame_into(&mut frame).is_ok() {
+                nb_frames += 1;
+            }
+            nb_frames
+        }
+
+        // Rebuild the packets of the demuxer from their bytes only.
+        let mut ifmt_ctx =
+            AVFormatContextInput::open(cstr!("tests/assets/vids/centaur.mpg"), None, &mut None)
+                .unwrap();
+        let (video_index, decoder) = ifmt_ctx
+            .find_best_stream(ffi::AVMEDIA_TYPE_VIDEO)
+            .unwrap()
+            .unwrap();
+        let mut units = vec![];
+        while let Some(packet) = ifmt_ctx.read_packet().unwrap() {
+            if packet.stream_index as usize == video_index {
+                units.push(packet.data().unwrap().to_vec());
+            }
+        }
+
+        let mut decode_context = AVCodecContext::new(&decoder);
+        decode_context.open(None).unwrap();
+        let mut nb_frames = 0;
+        for (i, unit) in units.iter().enumerate() {
+            let packet = if i % 2 == 0 {
+                AVPacket::from_data(unit.clone()).unwrap()
+            } else {
+                AVPacket::from_slice(unit).unwrap()
+            };
+            nb_frames += decode(&mut decode_context, Some(&packet));
+        }
+        nb_frames += decode(&mut decode_context, None);
+        assert_eq!(nb_frames, units.len());
+    }
+
+    #[test]
+    fn test_packet_side_data() {
+        let mut packet = AVPacket::new();
+        assert_eq!(packet.side_data(ffi::AV_PKT_DATA_DISPLAYMATRIX), None);
+        assert_eq!(packet.rotation(), None);
+
+        let matrix = crate::avutil::display_rotation_matrix(90.);
+        packet
+            .add_side_data(ffi::AV_PKT_DATA_DISPLAYMATRIX, &matrix)
+            .unwrap();
+        assert_eq!(
+            packet.side_data(ffi::AV_PKT_DATA_DISPLAYMATRIX),
+            Some(&matrix[..])
+        );
+        assert_eq!(packet.rotation(), Some(90.));
+
+        // Replaced, not appended.
+        packet
+            .add_side_data(
+                ffi::AV_PKT_DATA_DISPLAYMATRIX,
+                &crate::avutil::display_rotation_matrix(-90.),
+            )
+            .unwrap();
+        assert_eq!(packet.side_data_elems, 1);
+        assert_eq!(packet.rotation(), Some(-90.));
+
+        // Side data is kept by clones.
+        assert_eq!(packet.clone().rotation(), Some(-90.));
+    }
+}
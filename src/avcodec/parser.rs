@@ -4,6 +4,7 @@ use crate::{
     ffi,
     shared::*,
 };
+use std::{ptr, slice};
 
 wrap!(AVCodecParserContext: ffi::AVCodecParserContext);
 
@@ -54,6 +55,53 @@ impl AVCodecParserContext {
         }
         Ok((packet.size != 0, offset as usize))
     }
+
+    /// Parse `data`, return the number of bytes consumed and a packet when
+    /// one is complete. The packet owns a copy of its data, its `pts`, `dts`
+    /// and `pos` are the ones given with the data it starts in.
+    ///
+    /// Call it again with the remaining data until all of it is consumed, and
+    /// once with empty `data` at the end of the input to get the last packet.
+    ///
+    /// Note: if `data.len()` exceeds [`i32::MAX`], this function returns [`RsmpegError::TryFromIntError`].
+    pub fn parse(
+        &mut self,
+        codec_context: &mut AVCodecContext,
+        data: &[u8],
+        pts: i64,
+        dts: i64,
+        pos: i64,
+    ) -> Result<(usize, Option<AVPacket>)> {
+        let mut out_data = ptr::null_mut();
+        let mut out_size = 0;
+        let consumed = unsafe {
+            ffi::av_parser_parse2(
+                self.as_mut_ptr(),
+                codec_context.as_mut_ptr(),
+                &mut out_data,
+                &mut out_size,
+                data.as_ptr(),
+                data.len().try_into()?,
+                pts,
+                dts,
+                pos,
+            )
+        }
+        .upgrade()?;
+        if out_size == 0 {
+            return Ok((consumed as usize, None));
+        }
+        // The output points into the parser, which overwrites it on next call.
+        let out = unsafe { slice::from_raw_parts(out_data, out_size as usize) };
+        let mut packet = AVPacket::from_slice(out)?;
+        packet.set_pts(self.pts);
+        packet.set_dts(self.dts);
+        packet.set_pos(self.pos);
+        if self.key_frame == 1 {
+            packet.set_flags(ffi::AV_PKT_FLAG_KEY as i32);
+        }
+        Ok((consumed as usize, Some(packet)))
+    }
 }
 
 impl Drop for AVCodecParserContext {
@@ -61,3 +109,86 @@ impl Drop for AVCodecParserContext {
         unsafe { ffi::av_parser_close(self.as_mut_ptr()) }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{avformat::AVFormatContextInput, avutil::AVFrame};
+    use cstr::cstr;
+
+    /// Decode `packet`, return the number of frames received.
+    fn decode(decode_context: &mut AVCodecContext, packet: Option<&AVPacket>) -> usize {
+        decode_context.send_packet(packet).unwrap();
+        let mut frame = AVFrame::new();
+        let mut nb_frames = 0;
+        while decode_context.receive_frame_into(&mut frame).is_ok() {
+            nb_frames += 1;
+        }
+        nb_frames
+    }
+
+    #[test]
+    fn test_parse() {
+        // Extract the elementary stream of the video, then split it again.
+        let mut ifmt_ctx =
+            AVFormatContextInput::open(cstr!("tests/assets/vids/centaur.mpg"), None, &mut None)
+                .unwrap();
+        let (video_index, decoder) = ifmt_ctx
+            .find_best_stream(ffi::AVMEDIA_TYPE_VIDEO)
+            .unwrap()
+            .unwrap();
+        let mut elementary_stream = vec![];
+        let mut nb_packets = 0;
+        while let Some(packet) = ifmt_ctx.read_packet().unwrap() {
+            if packet.stream_index as usize == video_index {
+                elementary_stream.extend_from_slice(packet.data().unwrap());
+                nb_packets += 1;
+            }
+        }
+
+        let mut decode_context = AVCodecContext::new(&decoder);
+        decode_context.open(None).unwrap();
+        let mut parser_context = AVCodecParserContext::init(decoder.id).unwrap();
+        let mut nb_parsed = 0;
+        let mut nb_frames = 0;
+        for mut chunk in elementary_stream.chunks(4096) {
+            while !chunk.is_empty() {
+                let (consumed, packet) = parser_context
+                    .parse(
+                        &mut decode_context,
+                        chunk,
+                        ffi::AV_NOPTS_VALUE,
+                        ffi::AV_NOPTS_VALUE,
+                        0,
+                    )
+                    .unwrap();
+                chunk = &chunk[consumed..];
+                if let Some(packet) = packet {
+                    assert!(packet.size > 0);
+                    nb_parsed += 1;
+                    nb_frames += decode(&mut decode_context, Some(&packet));
+                }
+            }
+        }
+        let (consumed, packet) = parser_context
+            .parse(
+                &mut decode_context,
+                &[],
+                ffi::AV_NOPTS_VALUE,
+                ffi::AV_NOPTS_VALUE,
+                0,
+            )
+            .unwrap();
+        assert_eq!(consumed, 0);
+        if let Some(packet) = packet {
+            nb_parsed += 1;
+            nb_frames += decode(&mut decode_context, Some(&packet));
+        }
+        nb_frames += decode(&mut decode_context, None);
+
+        // One packet per picture, like the demuxer splits them.
+        assert!(nb_frames > 0);
+        assert_eq!(nb_parsed, nb_frames);
+        assert_eq!(nb_parsed, nb_packets);
+    }
+}
@@ -0,0 +1,134 @@
+use crate::{
+    avformat::{AVInputFormat, AVInputFormatRef},
+    avutil::AVDictionary,
+    error::*,
+    ffi,
+    shared::*,
+};
+use std::{ffi::CStr, iter::FusedIterator, os::raw::c_char, ptr, slice};
+
+/// Register the capture and playback devices, e.g. `v4l2`, `avfoundation`,
+/// `dshow` or `x11grab`, as input and output formats. Call it once before
+/// looking them up with [`AVInputFormat::find()`].
+pub fn register_all() {
+    unsafe { ffi::avdevice_register_all() }
+}
+
+/// A device found by [`list_input_sources()`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeviceInfo {
+    /// Name to open the device with, e.g. `/dev/video0`.
+    pub name: String,
+    /// Human friendly name of the device.
+    pub description: String,
+    /// Whether it's the default device of the format.
+    pub is_default: bool,
+}
+
+fn to_string(s: *const c_char) -> String {
+    if s.is_null() {
+        return String::new();
+    }
+    unsafe { CStr::from_ptr(s) }.to_string_lossy().into_owned()
+}
+
+/// List the devices of the input device `format`, e.g. the cameras of
+/// `v4l2`. `options` are the private options of the device, as passed to
+/// [`AVFormatContextInput::open()`].
+///
+/// Return [`RsmpegError::DeviceListUnsupportedError`] if the device can't
+/// enumerate its sources, e.g. `lavfi`, `AVERROR(EINVAL)` if `format` is not
+/// a device.
+///
+/// [`AVFormatContextInput::open()`]: crate::avformat::AVFormatContextInput::open
+pub fn list_input_sources(
+    format: &AVInputFormat,
+    options: Option<&AVDictionary>,
+) -> Result<Vec<DeviceInfo>> {
+    let options = options.map_or(ptr::null_mut(), |x| x.as_ptr() as *mut _);
+    let mut list = ptr::null_mut();
+    let ret = unsafe {
+        ffi::avdevice_list_input_sources(format.as_ptr(), ptr::null(), options, &mut list)
+    };
+    // Null on failure, freeing it is a no-op then.
+    let devices = unsafe { device_infos(list) };
+    unsafe { ffi::avdevice_free_list_devices(&mut list) };
+    match ret.upgrade() {
+        Ok(_) => Ok(devices),
+        Err(x) if x == ffi::AVERROR(ffi::ENOSYS) => Err(RsmpegError::DeviceListUnsupportedError),
+        Err(x) => Err(RsmpegError::AVError(x)),
+    }
+}
+
+/// # Safety
+/// `list` should be null or a valid device list.
+unsafe fn device_infos(list: *const ffi::AVDeviceInfoList) -> Vec<DeviceInfo> {
+    let Some(list) = (unsafe { list.as_ref() }) else {
+        return vec![];
+    };
+    if list.devices.is_null() || list.nb_devices <= 0 {
+        return vec![];
+    }
+    let devices = unsafe { slice::from_raw_parts(list.devices, list.nb_devices as usize) };
+    devices
+        .iter()
+        .enumerate()
+        .filter_map(|(i, &device)| {
+            let device = unsafe { device.as_ref() }?;
+            Some(DeviceInfo {
+                name: to_string(device.device_name),
+                description: to_string(device.device_description),
+                is_default: i as i32 == list.default_device,
+            })
+        })
+        .collect()
+}
+
+/// Iterate over the registered input devices capturing video, e.g. `v4l2`.
+pub fn input_video_devices() -> InputDeviceIter {
+    InputDeviceIter::new(ffi::av_input_video_device_next)
+}
+
+/// Iterate over the registered input devices capturing audio, e.g. `alsa`.
+pub fn input_audio_devices() -> InputDeviceIter {
+    InputDeviceIter::new(ffi::av_input_audio_device_next)
+}
+
+type DeviceNext = unsafe extern "C" fn(*const ffi::AVInputFormat) -> *const ffi::AVInputFormat;
+
+/// Iterator of the registered input devices, created by
+/// [`input_video_devices()`] and [`input_audio_devices()`].
+pub struct InputDeviceIter {
+    next: DeviceNext,
+    last: *const ffi::AVInputFormat,
+    done: bool,
+}
+
+impl InputDeviceIter {
+    fn new(next: DeviceNext) -> Self {
+        Self {
+            next,
+            last: ptr::null(),
+            done: false,
+        }
+    }
+}
+
+impl Iterator for InputDeviceIter {
+    type Item = AVInputFormatRef<'static>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        // Passing null again once the end is reached restarts from the first.
+        let Some(format) = unsafe { (self.next)(self.last) }.upgrade() else {
+            self.done = true;
+            return None;
+        };
+        self.last = format.as_ptr();
+        Some(unsafe { AVInputFormatRef::from_raw(format) })
+    }
+}
+
+impl FusedIterator for InputDeviceIter {}
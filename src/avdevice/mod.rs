@@ -0,0 +1,4 @@
+//! Everything related to `libavdevice`, requires the `avdevice` feature.
+mod avdevice;
+
+pub use avdevice::*;
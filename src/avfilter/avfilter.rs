@@ -1,11 +1,16 @@
 use std::{
     ffi::CStr,
+    fmt,
+    iter::FusedIterator,
+    marker::PhantomData,
     mem::{size_of, MaybeUninit},
+    os::raw::{c_char, c_int, c_void},
     ptr::{self, NonNull},
+    slice,
 };
 
 use crate::{
-    avutil::{AVChannelLayout, AVFrame},
+    avutil::{AVChannelLayout, AVFrame, AVHWFramesContext, AVPixelFormat, AVRational},
     error::{Result, RsmpegError},
     ffi,
     shared::*,
@@ -19,15 +24,95 @@ impl AVFilter {
         let filter = unsafe { ffi::avfilter_get_by_name(filter_name.as_ptr()) }.upgrade()?;
         Some(unsafe { AVFilterRef::from_raw(filter) })
     }
+
+    /// Iterate over all registered filters.
+    pub fn iterate() -> AVFilterIter {
+        AVFilterIter {
+            opaque: ptr::null_mut(),
+        }
+    }
+
+    /// Get name of the filter.
+    pub fn name(&self) -> &CStr {
+        unsafe { CStr::from_ptr(self.name) }
+    }
+
+    /// Get description of the filter.
+    pub fn description(&self) -> Option<&CStr> {
+        // Can be null when FFmpeg is built with `--enable-small`.
+        NonNull::new(self.description as *mut _).map(|x| unsafe { CStr::from_ptr(x.as_ptr()) })
+    }
+}
+
+/// Iterator of the registered [`AVFilterRef`]s, created by
+/// [`AVFilter::iterate()`].
+pub struct AVFilterIter {
+    opaque: *mut c_void,
+}
+
+impl Iterator for AVFilterIter {
+    type Item = AVFilterRef<'static>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let ptr = unsafe { ffi::av_filter_iterate(&mut self.opaque) }.upgrade()?;
+        Some(unsafe { AVFilterRef::from_raw(ptr) })
+    }
 }
 
+// `av_filter_iterate` keeps returning null once the end is reached.
+impl FusedIterator for AVFilterIter {}
+
 impl Drop for AVFilter {
     fn drop(&mut self) {
         // Do nothing, filter is always static
     }
 }
 
-wrap_mut!(AVFilterContext: ffi::AVFilterContext);
+/// Get the name of pad `pad` of the pad array `pads`.
+///
+/// # Safety
+/// `pad` must be in range of `pads`, the name lives as long as the filter.
+unsafe fn pad_name<'a>(pads: *const ffi::AVFilterPad, pad: u32) -> &'a CStr {
+    unsafe { CStr::from_ptr(ffi::avfilter_pad_get_name(pads, pad as c_int)) }
+}
+
+/// Flags of [`AVFilterContext::buffersink_get_frame_flags_into()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BufferSinkFlag {
+    /// Return the next frame without removing it from the sink
+    /// (`AV_BUFFERSINK_FLAG_PEEK`).
+    Peek,
+    /// Don't request a frame from the graph when none is buffered, return
+    /// [`RsmpegError::BufferSinkDrainError`] instead
+    /// (`AV_BUFFERSINK_FLAG_NO_REQUEST`).
+    NoRequest,
+}
+
+impl BufferSinkFlag {
+    fn bits(&self) -> i32 {
+        match self {
+            Self::Peek => ffi::AV_BUFFERSINK_FLAG_PEEK as i32,
+            Self::NoRequest => ffi::AV_BUFFERSINK_FLAG_NO_REQUEST as i32,
+        }
+    }
+}
+
+wrap_mut!(
+    !Send
+    /// A filter instance in an [`AVFilterGraph`].
+    ///
+    /// Unlike the graph, it isn't `Send`. Filter contexts are created from a
+    /// shared reference of the graph, so two of them in different threads
+    /// could run the same graph concurrently. To run a graph on another
+    /// thread, move the [`AVFilterGraph`] and look the filters up there with
+    /// [`AVFilterGraph::get_filter()`].
+    ///
+    /// ```compile_fail
+    /// fn assert_send<T: Send>() {}
+    /// assert_send::<rsmpeg::avfilter::AVFilterContextMut<'static>>();
+    /// ```
+    AVFilterContext: ffi::AVFilterContext
+);
 
 impl AVFilterContext {
     /// Set property of a [`AVFilterContext`].
@@ -59,6 +144,87 @@ impl AVFilterContext {
         Ok(())
     }
 
+    /// Get the instance name of the filter, `None` if it's unnamed.
+    pub fn name(&self) -> Option<&CStr> {
+        NonNull::new(self.name).map(|x| unsafe { CStr::from_ptr(x.as_ptr()) })
+    }
+
+    /// Get the number of input pads.
+    pub fn nb_inputs(&self) -> u32 {
+        self.nb_inputs
+    }
+
+    /// Get the number of output pads.
+    pub fn nb_outputs(&self) -> u32 {
+        self.nb_outputs
+    }
+
+    /// Get the name of input pad `pad`, `None` if out of range.
+    pub fn input_pad_name(&self, pad: u32) -> Option<&CStr> {
+        (pad < self.nb_inputs).then(|| unsafe { pad_name(self.input_pads, pad) })
+    }
+
+    /// Get the media type of input pad `pad`, `None` if out of range.
+    pub fn input_pad_type(&self, pad: u32) -> Option<ffi::AVMediaType> {
+        (pad < self.nb_inputs)
+            .then(|| unsafe { ffi::avfilter_pad_get_type(self.input_pads, pad as c_int) })
+    }
+
+    /// Get the name of output pad `pad`, `None` if out of range.
+    pub fn output_pad_name(&self, pad: u32) -> Option<&CStr> {
+        (pad < self.nb_outputs).then(|| unsafe { pad_name(self.output_pads, pad) })
+    }
+
+    /// Get the media type of output pad `pad`, `None` if out of range.
+    pub fn output_pad_type(&self, pad: u32) -> Option<ffi::AVMediaType> {
+        (pad < self.nb_outputs)
+            .then(|| unsafe { ffi::avfilter_pad_get_type(self.output_pads, pad as c_int) })
+    }
+
+    /// Link output pad `src_pad` of the filter to input pad `dst_pad` of
+    /// `dst`, both filters being in the same graph. This is the programmatic
+    /// alternative to [`AVFilterGraph::parse_ptr()`].
+    ///
+    /// Return [`RsmpegError::FilterPadIndexOutOfRangeError`] if a pad doesn't
+    /// exist.
+    pub fn link(&mut self, src_pad: u32, dst: &mut AVFilterContext, dst_pad: u32) -> Result<()> {
+        let out_of_range = |filter: &AVFilterContext, pad| {
+            let name = filter.name().map(|x| x.to_string_lossy().into_owned());
+            RsmpegError::FilterPadIndexOutOfRangeError(name.unwrap_or_default(), pad)
+        };
+        if src_pad >= self.nb_outputs {
+            return Err(out_of_range(self, src_pad));
+        }
+        if dst_pad >= dst.nb_inputs {
+            return Err(out_of_range(dst, dst_pad));
+        }
+        unsafe { ffi::avfilter_link(self.as_mut_ptr(), src_pad, dst.as_mut_ptr(), dst_pad) }
+            .upgrade()?;
+        Ok(())
+    }
+
+    /// Initialize a filter allocated by
+    /// [`AVFilterGraph::alloc_filter_context()`] with the options in `args`,
+    /// after setting the options not given in a string, e.g. with
+    /// [`Self::buffersrc_parameters_set()`].
+    pub fn init(&mut self, args: Option<&CStr>) -> Result<()> {
+        let args_ptr = args.map(|s| s.as_ptr()).unwrap_or(ptr::null());
+        unsafe { ffi::avfilter_init_str(self.as_mut_ptr(), args_ptr) }.upgrade()?;
+        Ok(())
+    }
+
+    /// Set the parameters of a buffer source, instead of an arguments
+    /// string. Call it before [`Self::init()`], or before configuring the
+    /// graph for parameters only used then like the hardware frames context.
+    /// The parameters are copied, the hardware frames context is referenced.
+    pub fn buffersrc_parameters_set(&mut self, parameters: &AVBufferSrcParameters) -> Result<()> {
+        unsafe {
+            ffi::av_buffersrc_parameters_set(self.as_mut_ptr(), parameters.as_ptr() as *mut _)
+        }
+        .upgrade()?;
+        Ok(())
+    }
+
     /// Add a frame to the buffer source.
     pub fn buffersrc_add_frame(
         &mut self,
@@ -95,6 +261,50 @@ impl AVFilterContext {
         }
     }
 
+    /// Same as [`Self::buffersink_get_frame()`] without flags, but writes into
+    /// the given `frame` instead of allocating a new one. The previous content
+    /// of `frame` is unreferenced first, on error `frame` is left blank.
+    pub fn buffersink_get_frame_into(&mut self, frame: &mut AVFrame) -> Result<()> {
+        self.buffersink_get_frame_flags_into(frame, &[])
+    }
+
+    /// Same as [`Self::buffersink_get_frame_into()`] with the given `flags`.
+    ///
+    /// With [`BufferSinkFlag::Peek`] the frame stays in the sink, the next
+    /// get returns it again. This allows looking ahead before pulling a frame.
+    pub fn buffersink_get_frame_flags_into(
+        &mut self,
+        frame: &mut AVFrame,
+        flags: &[BufferSinkFlag],
+    ) -> Result<()> {
+        let flags = flags.iter().fold(0, |flags, flag| flags | flag.bits());
+        // The sink moves its reference into `frame` without unreferencing it.
+        frame.unref();
+        match unsafe {
+            ffi::av_buffersink_get_frame_flags(self.as_mut_ptr(), frame.as_mut_ptr(), flags)
+        }
+        .upgrade()
+        {
+            Ok(_) => Ok(()),
+            Err(AVERROR_EAGAIN) => Err(RsmpegError::BufferSinkDrainError),
+            Err(ffi::AVERROR_EOF) => Err(RsmpegError::BufferSinkEofError),
+            Err(err) => Err(RsmpegError::BufferSinkGetFrameError(err)),
+        }
+    }
+
+    /// Make the audio buffer sink return frames of exactly `frame_size`
+    /// samples, except the last one, e.g. for encoders without
+    /// `AV_CODEC_CAP_VARIABLE_FRAME_SIZE` like AAC. Call it after
+    /// [`AVFilterGraph::config()`].
+    pub fn buffersink_set_frame_size(&mut self, frame_size: u32) {
+        unsafe { ffi::av_buffersink_set_frame_size(self.as_mut_ptr(), frame_size) }
+    }
+
+    // The getters below are only meaningful for buffer sinks of a configured
+    // graph, they return the negotiated output parameters for setting up
+    // encoders.
+
+    /// Media type of the buffer sink output.
     pub fn get_type(&self) -> i32 {
         unsafe { ffi::av_buffersink_get_type(self.as_ptr()) }
     }
@@ -103,6 +313,7 @@ impl AVFilterContext {
         unsafe { ffi::av_buffersink_get_time_base(self.as_ptr()) }
     }
 
+    /// Pixel format for video, sample format for audio.
     pub fn get_format(&self) -> i32 {
         unsafe { ffi::av_buffersink_get_format(self.as_ptr()) }
     }
@@ -141,25 +352,87 @@ impl AVFilterContext {
     }
 }
 
-wrap!(AVFilterInOut: ffi::AVFilterInOut);
+/// A linked list of the unconnected pads of filters in a graph, passed to and
+/// returned by [`AVFilterGraph::parse_ptr()`].
+///
+/// It's bound to the lifetime of the graph it points into, and like
+/// [`AVFilterContextMut`] it isn't `Send`.
+///
+/// ```compile_fail
+/// use rsmpeg::avfilter::{AVFilter, AVFilterGraph, AVFilterInOut};
+/// use std::ffi::CStr;
+///
+/// let name = CStr::from_bytes_with_nul(b"out\0").unwrap();
+/// let buffersink = AVFilter::get_by_name(CStr::from_bytes_with_nul(b"buffersink\0").unwrap())
+///     .unwrap();
+/// let filter_graph = AVFilterGraph::new();
+/// let buffersink_ctx = filter_graph
+///     .create_filter_context(&buffersink, name, None)
+///     .unwrap();
+/// let inputs = AVFilterInOut::new(name, &buffersink_ctx, 0);
+/// drop(buffersink_ctx);
+/// drop(filter_graph);
+/// drop(inputs);
+/// ```
+pub struct AVFilterInOut<'graph> {
+    something_should_not_be_touched_directly: NonNull<ffi::AVFilterInOut>,
+    _marker: PhantomData<&'graph AVFilterGraph>,
+}
 
-impl AVFilterInOut {
-    /// Allocate a single, unlinked [`AVFilterInOut`] entry.
-    pub fn new(name: &CStr, filter_context: &mut AVFilterContext, pad_idx: i32) -> Self {
+impl<'graph> AVFilterInOut<'graph> {
+    /// Allocate a single, unlinked [`AVFilterInOut`] entry of the pad `pad_idx`
+    /// of `filter_context`. Several entries can point to the same filter, e.g.
+    /// one for its input and one for its output.
+    pub fn new(name: &CStr, filter_context: &AVFilterContextMut<'graph>, pad_idx: i32) -> Self {
         let name = unsafe { ffi::av_strdup(name.as_ptr()) }.upgrade().unwrap();
         let mut inout_ptr = unsafe { ffi::avfilter_inout_alloc() }.upgrade().unwrap();
 
         let inout_mut = unsafe { inout_ptr.as_mut() };
         inout_mut.name = name.as_ptr();
-        inout_mut.filter_ctx = filter_context.as_mut_ptr();
+        // ATTENTION: The filter context is only modified by the graph, through
+        // `AVFilterGraph::parse_ptr()`, same as `create_filter_context()`.
+        inout_mut.filter_ctx = filter_context.as_ptr() as *mut _;
         inout_mut.pad_idx = pad_idx;
         inout_mut.next = ptr::null_mut();
 
         unsafe { Self::from_raw(inout_ptr) }
     }
+
+    pub fn as_ptr(&self) -> *const ffi::AVFilterInOut {
+        self.something_should_not_be_touched_directly.as_ptr() as *const _
+    }
+
+    pub fn as_mut_ptr(&mut self) -> *mut ffi::AVFilterInOut {
+        self.something_should_not_be_touched_directly.as_ptr()
+    }
+
+    /// # Safety
+    /// This function should only be called when the pointer is valid, the
+    /// data it's pointing to can be dropped, and the filter contexts it points
+    /// to live for `'graph`.
+    pub unsafe fn from_raw(raw: NonNull<ffi::AVFilterInOut>) -> Self {
+        Self {
+            something_should_not_be_touched_directly: raw,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn into_raw(self) -> NonNull<ffi::AVFilterInOut> {
+        let raw = self.something_should_not_be_touched_directly;
+        std::mem::forget(self);
+        raw
+    }
 }
 
-impl Drop for AVFilterInOut {
+impl<'graph> std::ops::Deref for AVFilterInOut<'graph> {
+    type Target = ffi::AVFilterInOut;
+
+    fn deref(&self) -> &Self::Target {
+        unsafe { self.something_should_not_be_touched_directly.as_ref() }
+    }
+}
+
+impl<'graph> Drop for AVFilterInOut<'graph> {
     /// This frees a linked [`AVFilterInOut`] chain.
     fn drop(&mut self) {
         let mut inout = self.as_mut_ptr();
@@ -169,6 +442,81 @@ impl Drop for AVFilterInOut {
     }
 }
 
+wrap!(
+    /// Parameters of a buffer source (`buffer` or `abuffer` filter), see
+    /// [`AVFilterContext::buffersrc_parameters_set()`]. Fields not set are
+    /// left unchanged in the filter.
+    AVBufferSrcParameters: ffi::AVBufferSrcParameters
+);
+
+settable!(AVBufferSrcParameters {
+    format: i32,
+    time_base: AVRational,
+    width: i32,
+    height: i32,
+    sample_aspect_ratio: AVRational,
+    frame_rate: AVRational,
+    sample_rate: i32,
+});
+
+impl AVBufferSrcParameters {
+    /// Allocate parameters with all the fields unset.
+    pub fn new() -> Self {
+        let parameters = unsafe { ffi::av_buffersrc_parameters_alloc() }
+            .upgrade()
+            .unwrap();
+        unsafe { Self::from_raw(parameters) }
+    }
+
+    /// Set the parameters of a video buffer source: `format` is the pixel
+    /// format and `time_base` the time base of the frames.
+    pub fn video(width: i32, height: i32, format: AVPixelFormat, time_base: AVRational) -> Self {
+        let mut parameters = Self::new();
+        parameters.set_width(width);
+        parameters.set_height(height);
+        parameters.set_format(format);
+        parameters.set_time_base(time_base);
+        parameters
+    }
+
+    /// Set the channel layout of an audio buffer source, the parameters take
+    /// the ownership of `ch_layout`, an
+    /// [`AVChannelLayout`](crate::avutil::AVChannelLayout) or a raw one.
+    pub fn set_ch_layout(&mut self, ch_layout: impl Into<ffi::AVChannelLayout>) {
+        let ch_layout = ch_layout.into();
+        unsafe {
+            ffi::av_channel_layout_uninit(&mut self.deref_mut().ch_layout);
+            self.deref_mut().ch_layout = ch_layout;
+        }
+    }
+
+    /// Set the hardware frames context the frames of a video buffer source
+    /// come from. The parameters keep a reference to it.
+    pub fn set_hw_frames_ctx(&mut self, hw_frames_ctx: &AVHWFramesContext) {
+        let hw_frames_ctx = AVHWFramesContext::clone(hw_frames_ctx).into_inner();
+        unsafe {
+            ffi::av_buffer_unref(&mut self.deref_mut().hw_frames_ctx);
+            self.deref_mut().hw_frames_ctx = hw_frames_ctx.into_raw().as_ptr();
+        }
+    }
+}
+
+impl Default for AVBufferSrcParameters {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for AVBufferSrcParameters {
+    fn drop(&mut self) {
+        unsafe {
+            ffi::av_buffer_unref(&mut self.deref_mut().hw_frames_ctx);
+            ffi::av_channel_layout_uninit(&mut self.deref_mut().ch_layout);
+            ffi::av_free(self.as_mut_ptr().cast());
+        }
+    }
+}
+
 wrap!(AVFilterGraph: ffi::AVFilterGraph);
 
 impl AVFilterGraph {
@@ -179,16 +527,185 @@ impl AVFilterGraph {
         unsafe { Self::from_raw(filter_graph) }
     }
 
+    /// Add a graph fully described by `filter_spec` to current graph, sources
+    /// and sinks included, e.g.
+    /// `buffer=video_size=64x48:pix_fmt=yuv420p:time_base=1/25[in];[in]scale=32:24,buffersink@out`.
+    /// Retrieve the source and sink contexts by name with
+    /// [`Self::get_filter()`] afterwards.
+    ///
+    /// Pads left unconnected by the spec are reported by [`Self::config()`].
+    pub fn parse_full(&self, filter_spec: &CStr) -> Result<()> {
+        // The graph is only appended to, same as `parse_ptr()`.
+        unsafe {
+            ffi::avfilter_graph_parse(
+                self.as_ptr() as _,
+                filter_spec.as_ptr(),
+                ptr::null_mut(),
+                ptr::null_mut(),
+                ptr::null_mut(),
+            )
+        }
+        .upgrade()?;
+        Ok(())
+    }
+
+    /// Check validity and configure all the links and formats in the graph.
+    ///
+    /// Return [`RsmpegError::FilterPadNotConnectedError`] if any pad of the
+    /// filters is not connected.
+    pub fn config(&self) -> Result<()> {
+        self.check_connected()?;
+        // ATTENTION: This takes immutable reference since it doesn't delete any filter.
+        unsafe { ffi::avfilter_graph_config(self.as_ptr() as *mut _, ptr::null_mut()) }
+            .upgrade()?;
+        Ok(())
+    }
+
+    /// Return an error naming the first unconnected pad in the graph.
+    fn check_connected(&self) -> Result<()> {
+        if self.filters.is_null() {
+            return Ok(());
+        }
+        let filters = unsafe { slice::from_raw_parts(self.filters, self.nb_filters as usize) };
+        for filter in filters.iter().map(|&x| unsafe { &*x }) {
+            let pads = [
+                (filter.input_pads, filter.inputs, filter.nb_inputs),
+                (filter.output_pads, filter.outputs, filter.nb_outputs),
+            ];
+            for (pads, links, nb_pads) in pads {
+                for i in 0..nb_pads as usize {
+                    if !unsafe { *links.add(i) }.is_null() {
+                        continue;
+                    }
+                    let name = |x: *const c_char| {
+                        (!x.is_null())
+                            .then(|| unsafe { CStr::from_ptr(x) }.to_string_lossy().into_owned())
+                    };
+                    // Filter instances may be unnamed, fall back to the name
+                    // of the filter then.
+                    let filter_name = name(filter.name)
+                        .or_else(|| name(unsafe { (*filter.filter).name }))
+                        .unwrap_or_default();
+                    let pad_name = name(unsafe { ffi::avfilter_pad_get_name(pads, i as c_int) });
+                    return Err(RsmpegError::FilterPadNotConnectedError(
+                        filter_name,
+                        pad_name.unwrap_or_default(),
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Dump the graph into a human-readable string: a diagram of the filters
+    /// and their links. Once configured, the links show the negotiated
+    /// formats.
+    ///
+    /// Return [`RsmpegError::EmptyFilterGraphError`] if the graph has no
+    /// filters.
+    pub fn dump(&self) -> Result<String> {
+        if self.nb_filters == 0 {
+            return Err(RsmpegError::EmptyFilterGraphError);
+        }
+        let dump = unsafe { ffi::avfilter_graph_dump(self.as_ptr() as *mut _, ptr::null()) }
+            .upgrade()
+            .ok_or(RsmpegError::AVError(ffi::AVERROR(ffi::ENOMEM)))?;
+        let string = unsafe { CStr::from_ptr(dump.as_ptr()) }
+            .to_string_lossy()
+            .into_owned();
+        unsafe { ffi::av_free(dump.as_ptr().cast()) };
+        Ok(string)
+    }
+
+    /// Send the command `cmd` with argument `arg` to the filters matching
+    /// `target`: an instance name, a filter name, or `all`. Return the response
+    /// of the filter, e.g. for changing the gain of a running `volume` filter:
+    /// `graph.send_command(cstr!("volume"), cstr!("volume"), cstr!("0.5"), 0)`.
+    ///
+    /// With `AVFILTER_CMD_FLAG_ONE` in `flags`, the command is only sent to
+    /// the first matching filter. `AVERROR(ENOSYS)` is returned if no filter
+    /// matches or supports the command.
+    pub fn send_command(
+        &self,
+        target: &CStr,
+        cmd: &CStr,
+        arg: &CStr,
+        flags: i32,
+    ) -> Result<String> {
+        const RESPONSE_SIZE: usize = 4096;
+        let mut response = vec![0u8; RESPONSE_SIZE];
+        // ATTENTION: This takes immutable reference since it doesn't add or
+        // delete any filter, same as `config()`.
+        unsafe {
+            ffi::avfilter_graph_send_command(
+                self.as_ptr() as *mut _,
+                target.as_ptr(),
+                cmd.as_ptr(),
+                arg.as_ptr(),
+                response.as_mut_ptr() as *mut c_char,
+                RESPONSE_SIZE as c_int,
+                flags,
+            )
+        }
+        .upgrade()?;
+        let len = response
+            .iter()
+            .position(|&x| x == 0)
+            .unwrap_or(RESPONSE_SIZE);
+        response.truncate(len);
+        Ok(String::from_utf8_lossy(&response).into_owned())
+    }
+
+    /// Queue the command `cmd` for the filters matching `target`, it's sent
+    /// once a frame with a timestamp of at least `ts` seconds reaches them.
+    /// Check [`Self::send_command()`] for the arguments.
+    ///
+    /// Unlike [`Self::send_command()`], it's not checked whether a filter
+    /// matches.
+    pub fn queue_command(
+        &self,
+        target: &CStr,
+        cmd: &CStr,
+        arg: &CStr,
+        flags: i32,
+        ts: f64,
+    ) -> Result<()> {
+        unsafe {
+            ffi::avfilter_graph_queue_command(
+                self.as_ptr() as *mut _,
+                target.as_ptr(),
+                cmd.as_ptr(),
+                arg.as_ptr(),
+                flags,
+                ts,
+            )
+        }
+        .upgrade()?;
+        Ok(())
+    }
+
+    /// Get a filter instance identified by instance name from graph.
+    pub fn get_filter(&mut self, name: &CStr) -> Option<AVFilterContextMut> {
+        unsafe {
+            ffi::avfilter_graph_get_filter(self.as_mut_ptr(), name.as_ptr())
+                .upgrade()
+                .map(|raw| AVFilterContextMut::from_raw(raw))
+        }
+    }
+}
+
+impl<'graph> AVFilterGraph {
     /// Add a graph described by a string to a [`AVFilterGraph`].
     ///
     /// This function returns the inputs and outputs (if any) that are left
     /// unlinked after parsing the graph and the caller then deals with them.
+    /// They point into the graph, so they can't outlive it.
     pub fn parse_ptr(
-        &self,
+        &'graph self,
         filter_spec: &CStr,
-        mut inputs: Option<AVFilterInOut>,
-        mut outputs: Option<AVFilterInOut>,
-    ) -> Result<(Option<AVFilterInOut>, Option<AVFilterInOut>)> {
+        mut inputs: Option<AVFilterInOut<'graph>>,
+        mut outputs: Option<AVFilterInOut<'graph>>,
+    ) -> Result<(Option<AVFilterInOut<'graph>>, Option<AVFilterInOut<'graph>>)> {
         let mut inputs_new = inputs
             .as_mut()
             .map(|x| x.as_mut_ptr())
@@ -223,7 +740,7 @@ impl AVFilterGraph {
         let _ = inputs.map(|x| x.into_raw());
         let _ = outputs.map(|x| x.into_raw());
 
-        // ATTENTION: TODO here we didn't bind the AVFilterInOut to the lifetime of the AVFilterGraph
+        // The new entries point to filters of the graph, hence the `'graph` lifetime.
         let new_inputs = inputs_new
             .upgrade()
             .map(|raw| unsafe { AVFilterInOut::from_raw(raw) });
@@ -233,28 +750,52 @@ impl AVFilterGraph {
         Ok((new_inputs, new_outputs))
     }
 
-    /// Check validity and configure all the links and formats in the graph.
-    pub fn config(&self) -> Result<()> {
-        // ATTENTION: This takes immutable reference since it doesn't delete any filter.
-        unsafe { ffi::avfilter_graph_config(self.as_ptr() as *mut _, ptr::null_mut()) }
-            .upgrade()?;
-        Ok(())
-    }
-
-    /// Get a filter instance identified by instance name from graph.
-    pub fn get_filter(&mut self, name: &CStr) -> Option<AVFilterContextMut> {
-        unsafe {
-            ffi::avfilter_graph_get_filter(self.as_mut_ptr(), name.as_ptr())
-                .upgrade()
-                .map(|raw| AVFilterContextMut::from_raw(raw))
+    /// Add an uninitialized instance of `filter` into the graph, to be
+    /// initialized by [`AVFilterContext::init()`] once its options are set.
+    pub fn alloc_filter_context(
+        &'graph self,
+        filter: &AVFilter,
+        name: &CStr,
+    ) -> Result<AVFilterContextMut<'graph>> {
+        // Same restriction as `create_filter_context()`.
+        let filter_context = unsafe {
+            ffi::avfilter_graph_alloc_filter(
+                self.as_ptr() as *mut _,
+                filter.as_ptr(),
+                name.as_ptr(),
+            )
         }
+        .upgrade()
+        .ok_or(RsmpegError::AVError(ffi::AVERROR(ffi::ENOMEM)))?;
+        Ok(unsafe { AVFilterContextMut::from_raw(filter_context) })
     }
-}
 
-impl<'graph> AVFilterGraph {
     /// Create and add a [`AVFilter`] instance into an existing
     /// [`AVFilterGraph`]. The filter instance is created from the `filter` and
     /// inited with the parameter `args`.
+    ///
+    /// The returned context borrows the graph, which can't be dropped before
+    /// it:
+    ///
+    /// ```compile_fail
+    /// use rsmpeg::avfilter::{AVFilter, AVFilterContextMut, AVFilterGraph};
+    /// use std::ffi::CStr;
+    ///
+    /// struct Sink<'graph> {
+    ///     buffersink_ctx: AVFilterContextMut<'graph>,
+    /// }
+    ///
+    /// let buffersink = AVFilter::get_by_name(CStr::from_bytes_with_nul(b"buffersink\0").unwrap())
+    ///     .unwrap();
+    /// let filter_graph = AVFilterGraph::new();
+    /// let sink = Sink {
+    ///     buffersink_ctx: filter_graph
+    ///         .create_filter_context(&buffersink, CStr::from_bytes_with_nul(b"out\0").unwrap(), None)
+    ///         .unwrap(),
+    /// };
+    /// drop(filter_graph);
+    /// let _ = sink.buffersink_ctx.get_type();
+    /// ```
     pub fn create_filter_context(
         &'graph self,
         filter: &AVFilter,
@@ -284,6 +825,32 @@ impl<'graph> AVFilterGraph {
     }
 }
 
+/// The [`AVFilterGraph::dump()`] of the graph, empty if the graph has no
+/// filters.
+impl fmt::Display for AVFilterGraph {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.dump().unwrap_or_default())
+    }
+}
+
+/// Number of filters and the beginning of the [`AVFilterGraph::dump()`].
+impl fmt::Debug for AVFilterGraph {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        const MAX_LEN: usize = 4096;
+        let mut dump = self.dump().unwrap_or_default();
+        if dump.len() > MAX_LEN {
+            let mut end = MAX_LEN;
+            while !dump.is_char_boundary(end) {
+                end -= 1;
+            }
+            dump.truncate(end);
+            dump.push_str("...\n");
+        }
+        writeln!(f, "AVFilterGraph {{ nb_filters: {} }}", self.nb_filters)?;
+        f.write_str(&dump)
+    }
+}
+
 impl Default for AVFilterGraph {
     fn default() -> Self {
         Self::new()
@@ -298,3 +865,167 @@ impl Drop for AVFilterGraph {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::avutil::{AVDictionary, ColorPrimaries, ColorTransfer};
+    use cstr::cstr;
+
+    #[test]
+    fn test_filter_iterator() {
+        let scale = AVFilter::iterate()
+            .find(|x| x.name() == cstr!("scale"))
+            .unwrap();
+        assert_eq!(
+            scale.as_ptr(),
+            AVFilter::get_by_name(cstr!("scale")).unwrap().as_ptr()
+        );
+        assert!(AVFilter::iterate().count() > 100);
+
+        let mut iter = AVFilter::iterate();
+        for _ in iter.by_ref() {}
+        assert!(iter.next().is_none());
+    }
+
+    #[test]
+    fn test_inout() {
+        let filter_graph = AVFilterGraph::new();
+        let buffer = AVFilter::get_by_name(cstr!("buffer")).unwrap();
+        let buffersink = AVFilter::get_by_name(cstr!("buffersink")).unwrap();
+        let buffer_ctx = filter_graph
+            .create_filter_context(
+                &buffer,
+                cstr!("in"),
+                Some(cstr!("video_size=64x48:pix_fmt=yuv420p:time_base=1/25")),
+            )
+            .unwrap();
+        let buffersink_ctx = filter_graph
+            .create_filter_context(&buffersink, cstr!("out"), None)
+            .unwrap();
+
+        // Only shared references of the contexts are needed.
+        let outputs = AVFilterInOut::new(cstr!("in"), &buffer_ctx, 0);
+        let inputs = AVFilterInOut::new(cstr!("out"), &buffersink_ctx, 0);
+        assert_eq!(outputs.filter_ctx as *const _, buffer_ctx.as_ptr());
+        assert_eq!(inputs.filter_ctx as *const _, buffersink_ctx.as_ptr());
+
+        let (inputs, outputs) = filter_graph
+            .parse_ptr(cstr!("hflip"), Some(inputs), Some(outputs))
+            .unwrap();
+        assert!(inputs.is_none());
+        assert!(outputs.is_none());
+        filter_graph.config().unwrap();
+        assert_eq!(buffersink_ctx.get_w(), 64);
+
+        // Left unlinked by the spec, pointing to the new filters.
+        let (inputs, outputs) = filter_graph
+            .parse_ptr(cstr!("[a]vflip[b]"), None, None)
+            .unwrap();
+        assert_eq!(unsafe { CStr::from_ptr(inputs.unwrap().name) }, cstr!("a"));
+        assert_eq!(unsafe { CStr::from_ptr(outputs.unwrap().name) }, cstr!("b"));
+    }
+
+    #[test]
+    fn test_buffersrc_parameters() {
+        let filter_graph = AVFilterGraph::new();
+        let abuffer = AVFilter::get_by_name(cstr!("abuffer")).unwrap();
+        let mut parameters = AVBufferSrcParameters::new();
+        parameters.set_format(ffi::AV_SAMPLE_FMT_FLTP);
+        parameters.set_sample_rate(48000);
+        parameters.set_time_base(crate::avutil::ra(1, 48000));
+        parameters.set_ch_layout(AVChannelLayout::from_nb_channels(1));
+        parameters.set_ch_layout(AVChannelLayout::from_nb_channels(2));
+        let mut abuffer_ctx = filter_graph
+            .alloc_filter_context(&abuffer, cstr!("in"))
+            .unwrap();
+        abuffer_ctx.buffersrc_parameters_set(&parameters).unwrap();
+        drop(parameters);
+        abuffer_ctx.init(None).unwrap();
+        assert!(abuffer_ctx.init(None).is_err());
+
+        // A video buffer source can't be initialized without its size.
+        let buffer = AVFilter::get_by_name(cstr!("buffer")).unwrap();
+        let mut buffer_ctx = filter_graph
+            .alloc_filter_context(&buffer, cstr!("video_in"))
+            .unwrap();
+        assert!(buffer_ctx.init(None).is_err());
+    }
+
+    #[test]
+    fn test_link() {
+        let mut filter_graph = AVFilterGraph::new();
+        {
+            let buffer = AVFilter::get_by_name(cstr!("buffer")).unwrap();
+            let scale = AVFilter::get_by_name(cstr!("scale")).unwrap();
+            let buffersink = AVFilter::get_by_name(cstr!("buffersink")).unwrap();
+            let mut buffer_ctx = filter_graph
+                .create_filter_context(
+                    &buffer,
+                    cstr!("in"),
+                    Some(cstr!("video_size=64x48:pix_fmt=yuv420p:time_base=1/25")),
+                )
+                .unwrap();
+            let mut scale_ctx = filter_graph
+                .create_filter_context(&scale, cstr!("scale"), Some(cstr!("32:24")))
+                .unwrap();
+            let mut buffersink_ctx = filter_graph
+                .create_filter_context(&buffersink, cstr!("out"), None)
+                .unwrap();
+
+            assert_eq!(buffer_ctx.name(), Some(cstr!("in")));
+            assert_eq!((buffer_ctx.nb_inputs(), buffer_ctx.nb_outputs()), (0, 1));
+            assert_eq!((scale_ctx.nb_inputs(), scale_ctx.nb_outputs()), (1, 1));
+            assert_eq!(scale_ctx.input_pad_name(0), Some(cstr!("default")));
+            assert_eq!(scale_ctx.output_pad_type(0), Some(ffi::AVMEDIA_TYPE_VIDEO));
+            assert_eq!(scale_ctx.input_pad_name(1), None);
+            assert_eq!(buffersink_ctx.output_pad_type(0), None);
+
+            assert_eq!(
+                buffer_ctx.link(1, &mut scale_ctx, 0),
+                Err(RsmpegError::FilterPadIndexOutOfRangeError(
+                    "in".to_string(),
+                    1
+                ))
+            );
+            assert_eq!(
+                buffer_ctx.link(0, &mut scale_ctx, 1),
+                Err(RsmpegError::FilterPadIndexOutOfRangeError(
+                    "scale".to_string(),
+                    1
+                ))
+            );
+            buffer_ctx.link(0, &mut scale_ctx, 0).unwrap();
+            scale_ctx.link(0, &mut buffersink_ctx, 0).unwrap();
+        }
+        filter_graph.config().unwrap();
+
+        let mut frame = AVFrame::new();
+        frame.set_format(ffi::AV_PIX_FMT_YUV420P);
+        frame.set_width(64);
+        frame.set_height(48);
+        frame.set_pts(0);
+        frame.set_color_primaries(ColorPrimaries::Bt2020);
+        frame.set_color_trc(ColorTransfer::AribStdB67);
+        frame.set_metadata(Some(AVDictionary::new(cstr!("key"), cstr!("value"), 0)));
+        frame.alloc_buffer().unwrap();
+        let mut buffer_ctx = filter_graph.get_filter(cstr!("in")).unwrap();
+        buffer_ctx.buffersrc_add_frame(Some(frame), None).unwrap();
+        buffer_ctx.buffersrc_add_frame(None, None).unwrap();
+        let mut buffersink_ctx = filter_graph.get_filter(cstr!("out")).unwrap();
+        let frame = buffersink_ctx.buffersink_get_frame(None).unwrap();
+        assert_eq!((frame.width, frame.height), (32, 24));
+        // Passed along by the scale filter, which doesn't convert them.
+        assert_eq!(frame.color_primaries(), ColorPrimaries::Bt2020);
+        assert_eq!(frame.color_trc(), ColorTransfer::AribStdB67);
+        let metadata = frame.metadata().unwrap();
+        assert_eq!(
+            metadata.get(cstr!("key"), None, 0).unwrap().value(),
+            cstr!("value")
+        );
+        assert_eq!(
+            buffersink_ctx.buffersink_get_frame(None).unwrap_err(),
+            RsmpegError::BufferSinkEofError
+        );
+    }
+}
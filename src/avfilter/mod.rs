@@ -1,4 +1,5 @@
 //! Everything related to `libavfilter`.
 mod avfilter;
+pub mod specs;
 
 pub use avfilter::*;
@@ -0,0 +1,508 @@
+//! Builders of filter spec strings for [`AVFilterGraph::parse_ptr()`] and
+//! [`AVFilterGraph::parse_full()`].
+//!
+//! Option values are escaped twice, once for the option parser of the filter
+//! (`:` separating the options) and once for the graph parser (`,`, `;` and
+//! `[]` separating the filters), so arbitrary text can be passed, e.g.
+//! `Filter::new("drawtext").option("text", "a: b, c")`. Numbers are rendered
+//! with `.` as the decimal point regardless of the locale.
+//!
+//! [`AVFilterGraph::parse_ptr()`]: crate::avfilter::AVFilterGraph::parse_ptr
+//! [`AVFilterGraph::parse_full()`]: crate::avfilter::AVFilterGraph::parse_full
+use crate::avutil::AVRational;
+use std::{
+    ffi::CString,
+    fmt::{self, Display, Write},
+};
+
+/// Whitespaces skipped around a token by `av_get_token()`.
+const WHITESPACES: &[char] = &[' ', '\n', '\t', '\r'];
+
+/// Escape `value` for `av_get_token()` terminated by `terms`: special
+/// characters and leading or trailing whitespaces are prefixed by `\`.
+fn escape(value: &str, terms: &[char]) -> String {
+    let start = value.len() - value.trim_start_matches(WHITESPACES).len();
+    let end = value.trim_end_matches(WHITESPACES).len().max(start);
+    let mut escaped = String::with_capacity(value.len());
+    for (i, c) in value.char_indices() {
+        if c == '\\' || c == '\'' || terms.contains(&c) || i < start || i >= end {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Escape an option value of a filter, it's escaped again with the whole
+/// argument string by [`Filter`].
+pub fn escape_option(value: &str) -> String {
+    escape(value, &[':'])
+}
+
+/// Escape the argument string of a filter for the graph parser.
+pub fn escape_arguments(arguments: &str) -> String {
+    escape(arguments, &['[', ']', ',', ';'])
+}
+
+/// A filter with its options, e.g. `scale=w=1280:h=-2`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Filter {
+    name: String,
+    instance: Option<String>,
+    options: Vec<(String, String)>,
+}
+
+impl Filter {
+    /// Filter of the given name, e.g. `"scale"`, without options.
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            instance: None,
+            options: vec![],
+        }
+    }
+
+    /// Name the filter context `name@instance`, for retrieving it by
+    /// [`AVFilterGraph::get_filter()`](crate::avfilter::AVFilterGraph::get_filter).
+    pub fn instance(mut self, instance: &str) -> Self {
+        self.instance = Some(instance.to_string());
+        self
+    }
+
+    /// Set option `key` to `value`, which is escaped.
+    pub fn option(mut self, key: &str, value: impl Display) -> Self {
+        self.options.push((key.to_string(), value.to_string()));
+        self
+    }
+
+    /// Render the spec fragment.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a name or value contains a nul byte.
+    pub fn build(&self) -> CString {
+        CString::new(self.to_string()).unwrap()
+    }
+}
+
+impl Display for Filter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.name)?;
+        if let Some(instance) = &self.instance {
+            write!(f, "@{}", instance)?;
+        }
+        if !self.options.is_empty() {
+            let mut arguments = String::new();
+            for (i, (key, value)) in self.options.iter().enumerate() {
+                if i > 0 {
+                    arguments.push(':');
+                }
+                write!(arguments, "{}={}", key, escape_option(value))?;
+            }
+            write!(f, "={}", escape_arguments(&arguments))?;
+        }
+        Ok(())
+    }
+}
+
+macro_rules! filter_builder {
+    ($name: ident) => {
+        impl $name {
+            /// Render the spec fragment.
+            pub fn build(&self) -> CString {
+                Filter::from(self.clone()).build()
+            }
+        }
+
+        impl Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                Filter::from(self.clone()).fmt(f)
+            }
+        }
+    };
+}
+
+/// Scaling algorithm of [`Scale`], the `flags` option.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScaleAlgorithm {
+    FastBilinear,
+    Bilinear,
+    Bicubic,
+    Neighbor,
+    Area,
+    Gauss,
+    Lanczos,
+    Spline,
+}
+
+impl ScaleAlgorithm {
+    fn name(self) -> &'static str {
+        match self {
+            Self::FastBilinear => "fast_bilinear",
+            Self::Bilinear => "bilinear",
+            Self::Bicubic => "bicubic",
+            Self::Neighbor => "neighbor",
+            Self::Area => "area",
+            Self::Gauss => "gauss",
+            Self::Lanczos => "lanczos",
+            Self::Spline => "spline",
+        }
+    }
+}
+
+/// The `scale` filter. Width and height are numbers or expressions, e.g.
+/// `-2` keeps the aspect ratio with an even size, `"iw/2"` halves the input.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Scale {
+    width: Option<String>,
+    height: Option<String>,
+    flags: Option<ScaleAlgorithm>,
+}
+
+impl Scale {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn width(mut self, width: impl Display) -> Self {
+        self.width = Some(width.to_string());
+        self
+    }
+
+    pub fn height(mut self, height: impl Display) -> Self {
+        self.height = Some(height.to_string());
+        self
+    }
+
+    pub fn flags(mut self, flags: ScaleAlgorithm) -> Self {
+        self.flags = Some(flags);
+        self
+    }
+}
+
+impl From<Scale> for Filter {
+    fn from(scale: Scale) -> Self {
+        let mut filter = Filter::new("scale");
+        if let Some(width) = scale.width {
+            filter = filter.option("w", width);
+        }
+        if let Some(height) = scale.height {
+            filter = filter.option("h", height);
+        }
+        if let Some(flags) = scale.flags {
+            filter = filter.option("flags", flags.name());
+        }
+        filter
+    }
+}
+
+filter_builder!(Scale);
+
+/// The `crop` filter, centered unless positioned by [`Self::at()`]. Values
+/// are numbers or expressions, e.g. `"min(iw,ih)"`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Crop {
+    width: String,
+    height: String,
+    position: Option<(String, String)>,
+}
+
+impl Crop {
+    pub fn new(width: impl Display, height: impl Display) -> Self {
+        Self {
+            width: width.to_string(),
+            height: height.to_string(),
+            position: None,
+        }
+    }
+
+    /// Position of the top left corner of the cropped area.
+    pub fn at(mut self, x: impl Display, y: impl Display) -> Self {
+        self.position = Some((x.to_string(), y.to_string()));
+        self
+    }
+}
+
+impl From<Crop> for Filter {
+    fn from(crop: Crop) -> Self {
+        let mut filter = Filter::new("crop")
+            .option("w", crop.width)
+            .option("h", crop.height);
+        if let Some((x, y)) = crop.position {
+            filter = filter.option("x", x).option("y", y);
+        }
+        filter
+    }
+}
+
+filter_builder!(Crop);
+
+/// The `fps` filter, converting to a constant frame rate.
+#[derive(Debug, Clone, Copy)]
+pub struct Fps {
+    rate: AVRational,
+}
+
+impl Fps {
+    pub fn new(rate: AVRational) -> Self {
+        Self { rate }
+    }
+}
+
+impl From<Fps> for Filter {
+    fn from(fps: Fps) -> Self {
+        Filter::new("fps").option("fps", format!("{}/{}", fps.rate.num, fps.rate.den))
+    }
+}
+
+filter_builder!(Fps);
+
+/// The `overlay` filter, drawing its second input over the first one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Overlay {
+    x: String,
+    y: String,
+    shortest: bool,
+}
+
+impl Overlay {
+    /// Overlay at position `(x, y)`, numbers or expressions, e.g.
+    /// `"main_w-overlay_w"`.
+    pub fn at(x: impl Display, y: impl Display) -> Self {
+        Self {
+            x: x.to_string(),
+            y: y.to_string(),
+            shortest: false,
+        }
+    }
+
+    /// End the output with the shortest input.
+    pub fn shortest(mut self, shortest: bool) -> Self {
+        self.shortest = shortest;
+        self
+    }
+}
+
+impl From<Overlay> for Filter {
+    fn from(overlay: Overlay) -> Self {
+        let filter = Filter::new("overlay")
+            .option("x", overlay.x)
+            .option("y", overlay.y);
+        if overlay.shortest {
+            filter.option("shortest", 1)
+        } else {
+            filter
+        }
+    }
+}
+
+filter_builder!(Overlay);
+
+/// The `volume` filter.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Volume {
+    /// Linear factor, `1.0` keeps the volume.
+    Factor(f64),
+    /// Gain in decibels, `0.0` keeps the volume.
+    Db(f64),
+}
+
+impl Volume {
+    pub fn factor(factor: f64) -> Self {
+        Self::Factor(factor)
+    }
+
+    pub fn db(db: f64) -> Self {
+        Self::Db(db)
+    }
+}
+
+impl From<Volume> for Filter {
+    fn from(volume: Volume) -> Self {
+        let value = match volume {
+            Volume::Factor(factor) => factor.to_string(),
+            Volume::Db(db) => format!("{}dB", db),
+        };
+        Filter::new("volume").option("volume", value)
+    }
+}
+
+filter_builder!(Volume);
+
+/// The `atempo` filter, changing the audio speed without changing the pitch.
+/// FFmpeg accepts tempos in `[0.5, 100.0]`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Atempo {
+    tempo: f64,
+}
+
+impl Atempo {
+    pub fn new(tempo: f64) -> Self {
+        Self { tempo }
+    }
+}
+
+impl From<Atempo> for Filter {
+    fn from(atempo: Atempo) -> Self {
+        Filter::new("atempo").option("tempo", atempo.tempo)
+    }
+}
+
+filter_builder!(Atempo);
+
+/// Filters linked one after another, with labeled inputs and outputs, e.g.
+/// `[in]scale=w=64:h=48,fps=fps=25/1[out]`.
+///
+/// Labels are written as is, use alphanumeric characters and `_` only.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FilterChain {
+    inputs: Vec<String>,
+    filters: Vec<Filter>,
+    outputs: Vec<String>,
+}
+
+impl FilterChain {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an input label of the first filter.
+    pub fn input(mut self, label: &str) -> Self {
+        self.inputs.push(label.to_string());
+        self
+    }
+
+    /// Append a filter to the chain.
+    pub fn filter(mut self, filter: impl Into<Filter>) -> Self {
+        self.filters.push(filter.into());
+        self
+    }
+
+    /// Add an output label of the last filter.
+    pub fn output(mut self, label: &str) -> Self {
+        self.outputs.push(label.to_string());
+        self
+    }
+
+    /// Render the chain.
+    pub fn build(&self) -> CString {
+        CString::new(self.to_string()).unwrap()
+    }
+
+    /// Render several chains as one graph, separated by `;`.
+    pub fn join(chains: &[FilterChain]) -> CString {
+        let chains: Vec<_> = chains.iter().map(|x| x.to_string()).collect();
+        CString::new(chains.join(";")).unwrap()
+    }
+}
+
+impl Display for FilterChain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for label in &self.inputs {
+            write!(f, "[{}]", label)?;
+        }
+        for (i, filter) in self.filters.iter().enumerate() {
+            if i > 0 {
+                f.write_char(',')?;
+            }
+            filter.fmt(f)?;
+        }
+        for label in &self.outputs {
+            write!(f, "[{}]", label)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape_option("a:b"), "a\\:b");
+        assert_eq!(escape_option("it's"), "it\\'s");
+        assert_eq!(escape_option(" a b "), "\\ a b\\ ");
+        assert_eq!(escape_arguments("a,b;[c]"), "a\\,b\\;\\[c\\]");
+        assert_eq!(escape_arguments("a\\:b"), "a\\\\:b");
+    }
+
+    #[test]
+    fn test_filter() {
+        assert_eq!(Filter::new("null").build().to_str().unwrap(), "null");
+        assert_eq!(
+            Filter::new("buffersink").instance("out").to_string(),
+            "buffersink@out"
+        );
+        // The example of the filtergraph escaping documentation.
+        assert_eq!(
+            Filter::new("drawtext")
+                .option(
+                    "text",
+                    "this is a 'string': may contain one, or more, special characters"
+                )
+                .to_string(),
+            "drawtext=text=this is a \\\\\\'string\\\\\\'\\\\: \
+             may contain one\\, or more\\, special characters"
+        );
+    }
+
+    #[test]
+    fn test_builders() {
+        assert_eq!(
+            Scale::new()
+                .width(1280)
+                .height(-2)
+                .flags(ScaleAlgorithm::Lanczos)
+                .to_string(),
+            "scale=w=1280:h=-2:flags=lanczos"
+        );
+        assert_eq!(Scale::new().width("iw/2").to_string(), "scale=w=iw/2");
+        assert_eq!(
+            Crop::new("min(iw,ih)", "min(iw,ih)").to_string(),
+            "crop=w=min(iw\\,ih):h=min(iw\\,ih)"
+        );
+        assert_eq!(
+            Crop::new(64, 48).at(8, 4).to_string(),
+            "crop=w=64:h=48:x=8:y=4"
+        );
+        assert_eq!(
+            Fps::new(AVRational {
+                num: 30000,
+                den: 1001
+            })
+            .to_string(),
+            "fps=fps=30000/1001"
+        );
+        assert_eq!(
+            Overlay::at("main_w-overlay_w", 0)
+                .shortest(true)
+                .to_string(),
+            "overlay=x=main_w-overlay_w:y=0:shortest=1"
+        );
+        assert_eq!(Volume::db(-3.0).to_string(), "volume=volume=-3dB");
+        assert_eq!(Volume::factor(0.5).to_string(), "volume=volume=0.5");
+        assert_eq!(Atempo::new(1.25).to_string(), "atempo=tempo=1.25");
+    }
+
+    #[test]
+    fn test_filter_chain() {
+        let chain = FilterChain::new()
+            .input("in")
+            .filter(Scale::new().width(64).height(48))
+            .filter(Filter::new("null"))
+            .output("out");
+        assert_eq!(chain.to_string(), "[in]scale=w=64:h=48,null[out]");
+        let graph = FilterChain::join(&[
+            FilterChain::new()
+                .filter(Filter::new("split"))
+                .output("a")
+                .output("b"),
+            FilterChain::new()
+                .input("a")
+                .input("b")
+                .filter(Overlay::at(0, 0)),
+        ]);
+        assert_eq!(graph.to_str().unwrap(), "split[a][b];[a][b]overlay=x=0:y=0");
+    }
+}
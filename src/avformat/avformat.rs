@@ -1,15 +1,29 @@
 use std::{
     ffi::CStr,
-    os::raw::c_int,
+    iter::FusedIterator,
+    mem::size_of,
+    os::raw::{c_int, c_void},
+    panic::{self, AssertUnwindSafe},
     ptr::{self, NonNull},
+    slice,
+    time::Duration,
 };
 
+#[cfg(feature = "async-io")]
+use crate::avformat::AVIOContextAsync;
 use crate::{
     avcodec::{
-        AVCodecParameters, AVCodecParametersMut, AVCodecParametersRef, AVCodecRef, AVPacket,
+        AVCodec, AVCodecID, AVCodecParameters, AVCodecParametersMut, AVCodecParametersRef,
+        AVCodecRef, AVPacket, Compliance,
+    },
+    avformat::{
+        network_init_once, AVIOContext, AVIOContextCustom, AVIOContextURL, HlsOutputOptions,
+        NetworkOptions,
+    },
+    avutil::{
+        av_rescale_q, display_rotation_get, display_rotation_matrix, log::log_message, ra,
+        AVDictionary, AVDictionaryMut, AVDictionaryRef, AVFrame, AVRational, Rational,
     },
-    avformat::{AVIOContext, AVIOContextCustom, AVIOContextURL},
-    avutil::{AVDictionary, AVDictionaryMut, AVDictionaryRef, AVRational},
     error::{Result, RsmpegError},
     ffi,
     shared::*,
@@ -19,11 +33,63 @@ use crate::{
 pub enum AVIOContextContainer {
     Url(AVIOContextURL),
     Custom(AVIOContextCustom),
+    /// Read-only, for inputs, outputs reject it.
+    #[cfg(feature = "async-io")]
+    Async(AVIOContextAsync),
+}
+
+/// Target position of [`AVFormatContextInput::seek()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SeekTarget {
+    /// Timestamp in the time base of the selected stream, or in
+    /// `AV_TIME_BASE` units if no stream is selected.
+    Timestamp(i64),
+    /// Byte offset in the input.
+    Bytes(u64),
+}
+
+/// File embedded in a container as an attachment stream, e.g. a font for ASS
+/// subtitles in Matroska.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Attachment<'a> {
+    /// Value of the `filename` tag of the stream.
+    pub filename: Option<&'a CStr>,
+    /// Value of the `mimetype` tag of the stream.
+    pub mimetype: Option<&'a CStr>,
+    /// The payload, stored in the extradata of the stream.
+    pub data: &'a [u8],
+}
+
+/// Callback of [`AVFormatContextInput::open_with_interrupt()`], boxed twice
+/// for a thin pointer.
+type InterruptCallback = Box<dyn FnMut() -> bool + Send>;
+
+unsafe extern "C" fn interrupt_callback_c(opaque: *mut c_void) -> c_int {
+    let callback = unsafe { &mut *(opaque as *mut InterruptCallback) };
+    // Unwinding into C is undefined behavior, a panic aborts the operation.
+    panic::catch_unwind(AssertUnwindSafe(callback)).unwrap_or(true) as c_int
+}
+
+/// Interrupt callback calling `callback`, which should outlive its use.
+fn interrupt_callback(callback: &mut InterruptCallback) -> ffi::AVIOInterruptCB {
+    ffi::AVIOInterruptCB {
+        callback: Some(interrupt_callback_c),
+        opaque: callback as *mut InterruptCallback as *mut c_void,
+    }
+}
+
+/// Tell the operations aborted by the interrupt callback apart.
+fn interrupted(err: RsmpegError) -> RsmpegError {
+    match err.raw_error() {
+        Some(ffi::AVERROR_EXIT) => RsmpegError::Interrupted,
+        _ => err,
+    }
 }
 
 wrap! {
     AVFormatContextInput: ffi::AVFormatContext,
-    io_context: Option<AVIOContextContainer> = None,
+    pub io_context: Option<AVIOContextContainer> = None,
+    interrupt: Option<Box<InterruptCallback>> = None,
 }
 
 impl AVFormatContextInput {
@@ -32,7 +98,11 @@ impl AVFormatContextInput {
     ///
     /// - `url`: url of the stream to open.
     /// - `format`: input format hint. If `format` is some, this parameter forces
-    /// a specific input format.
+    /// a specific input format, like `-f` of the ffmpeg cli, e.g.
+    /// `AVInputFormat::find(cstr!("s16le"))` for raw pcm, which can't be probed.
+    /// [`Self::iformat()`] tells the one chosen. Capture devices are opened
+    /// the same way once registered by `avdevice::register_all()`, the url
+    /// being the device, e.g. `/dev/video0` with `v4l2`.
     /// - `options`: A dictionary filled with AVFormatContext and demuxer-private options.
     ///    On return this parameter will be destroyed and replaced with a dict containing
     ///    options that were not found.
@@ -41,7 +111,67 @@ impl AVFormatContextInput {
         fmt: Option<&AVInputFormat>,
         options: &mut Option<AVDictionary>,
     ) -> Result<Self> {
-        let mut input_format_context = ptr::null_mut();
+        let mut context = Self::open_without_stream_info(url, fmt, options)?;
+        context.find_stream_info(&mut None)?;
+        Ok(context)
+    }
+
+    /// Similar to [`Self::open()`], without reading packets to find the info
+    /// of the streams, which is faster but leaves the codec parameters and
+    /// durations the header doesn't have unknown, and may miss the streams
+    /// not declared in the header. Call [`Self::find_stream_info()`] to find
+    /// them later.
+    pub fn open_without_stream_info(
+        url: &CStr,
+        fmt: Option<&AVInputFormat>,
+        options: &mut Option<AVDictionary>,
+    ) -> Result<Self> {
+        unsafe { Self::open_input(ptr::null_mut(), url, fmt, options) }
+    }
+
+    /// Similar to [`Self::open()`], `callback` is polled during the blocking
+    /// operations of the input, opening included, e.g. while connecting or
+    /// waiting for the data of a network url. Returning true aborts the
+    /// operation with [`RsmpegError::Interrupted`], e.g. once a deadline is
+    /// passed or the user cancelled. A panic in the callback aborts the
+    /// operation too.
+    pub fn open_with_interrupt(
+        url: &CStr,
+        fmt: Option<&AVInputFormat>,
+        options: &mut Option<AVDictionary>,
+        callback: impl FnMut() -> bool + Send + 'static,
+    ) -> Result<Self> {
+        let mut callback: Box<InterruptCallback> = Box::new(Box::new(callback));
+        // Only fails on no memory, so unwrap().
+        let context = unsafe { ffi::avformat_alloc_context() }.upgrade().unwrap();
+        unsafe { (*context.as_ptr()).interrupt_callback = interrupt_callback(&mut *callback) };
+        // The context is freed on failure, before the callback.
+        let mut context = unsafe { Self::open_input(context.as_ptr(), url, fmt, options) }
+            .map_err(interrupted)?;
+        context.interrupt = Some(callback);
+        context.find_stream_info(&mut None).map_err(interrupted)?;
+        Ok(context)
+    }
+
+    /// Set the callback polled during the blocking operations of the input,
+    /// replacing the one of [`Self::open_with_interrupt()`].
+    pub fn set_interrupt_callback(&mut self, callback: impl FnMut() -> bool + Send + 'static) {
+        let mut callback: Box<InterruptCallback> = Box::new(Box::new(callback));
+        unsafe { self.deref_mut().interrupt_callback = interrupt_callback(&mut *callback) };
+        // The previous callback is dropped once replaced.
+        self.interrupt = Some(callback);
+    }
+
+    /// # Safety
+    /// `context` should be null or allocated by `avformat_alloc_context()`,
+    /// it's freed on failure.
+    unsafe fn open_input(
+        context: *mut ffi::AVFormatContext,
+        url: &CStr,
+        fmt: Option<&AVInputFormat>,
+        options: &mut Option<AVDictionary>,
+    ) -> Result<Self> {
+        let mut input_format_context = context;
         let fmt = fmt.map(|x| x.as_ptr()).unwrap_or_else(std::ptr::null) as _;
         let mut options_ptr = options
             .as_mut()
@@ -66,20 +196,75 @@ impl AVFormatContextInput {
         std::mem::swap(options, &mut new_options);
         std::mem::forget(new_options);
 
-        // Here we can be sure that context is non null, constructing here for
-        // dropping when `avformat_find_stream_info` fails.
-        let mut context = unsafe { Self::from_raw(NonNull::new(input_format_context).unwrap()) };
+        Ok(unsafe { Self::from_raw(NonNull::new(input_format_context).unwrap()) })
+    }
 
-        unsafe { ffi::avformat_find_stream_info(context.as_mut_ptr(), ptr::null_mut()) }
-            .upgrade()
-            .map_err(RsmpegError::FindStreamInfoError)?;
+    /// Read packets to find the info of the streams, already done by
+    /// [`Self::open()`]. `options` are the options of the decoders used for
+    /// probing, applied to all streams, e.g. `threads`. Like in
+    /// [`Self::open()`], the options no stream used are left in `options`,
+    /// which is set to `None` if all of them were used.
+    pub fn find_stream_info(&mut self, options: &mut Option<AVDictionary>) -> Result<()> {
+        let Some(dict) = options.as_ref() else {
+            unsafe { ffi::avformat_find_stream_info(self.as_mut_ptr(), ptr::null_mut()) }
+                .upgrade()
+                .map_err(RsmpegError::FindStreamInfoError)?;
+            return Ok(());
+        };
+        // One dictionary per stream, the unused options are left in them.
+        let mut stream_options = vec![ptr::null_mut(); self.nb_streams as usize];
+        for stream_dict in &mut stream_options {
+            // Only fails on no memory, so unwrap().
+            unsafe { ffi::av_dict_copy(stream_dict, dict.as_ptr(), 0) }
+                .upgrade()
+                .unwrap();
+        }
+        let ret = unsafe {
+            ffi::avformat_find_stream_info(self.as_mut_ptr(), stream_options.as_mut_ptr())
+        };
+        let unused = dict
+            .pairs()
+            .filter(|(key, _)| {
+                stream_options.iter().all(|stream_dict| {
+                    !unsafe { ffi::av_dict_get(*stream_dict, key.as_ptr(), ptr::null(), 0) }
+                        .is_null()
+                })
+            })
+            .fold(None, |unused: Option<AVDictionary>, (key, value)| {
+                Some(match unused {
+                    Some(unused) => unused.set(key, value, ffi::AV_DICT_MULTIKEY),
+                    None => AVDictionary::new(key, value, 0),
+                })
+            });
+        for stream_dict in &mut stream_options {
+            unsafe { ffi::av_dict_free(stream_dict) };
+        }
+        ret.upgrade().map_err(RsmpegError::FindStreamInfoError)?;
+        *options = unused;
+        Ok(())
+    }
 
-        Ok(context)
+    /// Similar to [`Self::open()`], for network urls. `options` are converted
+    /// into the protocol options of the url, see [`NetworkOptions`].
+    /// The network is initialized on first use.
+    pub fn open_network(url: &CStr, options: &NetworkOptions) -> Result<Self> {
+        network_init_once();
+        Self::open(url, None, &mut options.to_dict(url))
     }
 
     /// Create a [`AVFormatContextInput`] instance from an [`AVIOContext`], and find info of
     /// all streams.
-    pub fn from_io_context(mut io_context: AVIOContextContainer) -> Result<Self> {
+    pub fn from_io_context(io_context: AVIOContextContainer) -> Result<Self> {
+        Self::from_io_context_with_format(io_context, None, &mut None)
+    }
+
+    /// Similar to [`Self::from_io_context()`], with the `format` and `options`
+    /// parameters of [`Self::open()`].
+    pub fn from_io_context_with_format(
+        mut io_context: AVIOContextContainer,
+        fmt: Option<&AVInputFormat>,
+        options: &mut Option<AVDictionary>,
+    ) -> Result<Self> {
         let input_format_context = {
             // Only fails on no memory, so unwrap().
             // `avformat_open_input`'s documentation:
@@ -93,21 +278,36 @@ impl AVFormatContextInput {
                 (*input_format_context.as_ptr()).pb = match &mut io_context {
                     AVIOContextContainer::Url(ctx) => ctx.as_mut_ptr(),
                     AVIOContextContainer::Custom(ctx) => ctx.as_mut_ptr(),
+                    #[cfg(feature = "async-io")]
+                    AVIOContextContainer::Async(ctx) => ctx.as_mut_ptr(),
                 };
             }
             input_format_context
         };
 
-        unsafe {
+        let fmt = fmt.map(|x| x.as_ptr()).unwrap_or_else(std::ptr::null) as _;
+        let mut options_ptr = options
+            .as_mut()
+            .map(|x| x.as_mut_ptr())
+            .unwrap_or_else(std::ptr::null_mut);
+
+        let result = unsafe {
             ffi::avformat_open_input(
                 &mut input_format_context.as_ptr(),
                 ptr::null_mut(),
-                ptr::null_mut(),
-                ptr::null_mut(),
+                fmt,
+                &mut options_ptr,
             )
-        }
-        .upgrade()
-        .map_err(RsmpegError::OpenInputError)?;
+        };
+
+        // Forget the old options since it's ownership is transferred.
+        let mut new_options = options_ptr
+            .upgrade()
+            .map(|x| unsafe { AVDictionary::from_raw(x) });
+        std::mem::swap(options, &mut new_options);
+        std::mem::forget(new_options);
+
+        result.upgrade().map_err(RsmpegError::OpenInputError)?;
 
         // After `avformat_open_input`, we can `avformat_close_input` after it,
         // so here we can safely construct a `AVFormatContextInput`.
@@ -147,10 +347,112 @@ impl AVFormatContextInput {
         match unsafe { ffi::av_read_frame(self.as_mut_ptr(), packet.as_mut_ptr()) }.upgrade() {
             Ok(_) => Ok(Some(packet)),
             Err(ffi::AVERROR_EOF) => Ok(None),
+            Err(ffi::AVERROR_EXIT) if self.interrupt.is_some() => Err(RsmpegError::Interrupted),
             Err(x) => Err(x)?,
         }
     }
 
+    /// Seek to `target` near which the next packets are read.
+    ///
+    /// - `stream_index`: the stream [`SeekTarget::Timestamp`] refers to, if it's
+    ///   `None`, the timestamp is in `AV_TIME_BASE` units and a default stream
+    ///   is selected. Ignored by [`SeekTarget::Bytes`].
+    /// - `flags`: `AVSEEK_FLAG_BACKWARD`, `AVSEEK_FLAG_ANY` etc.,
+    ///   `AVSEEK_FLAG_BYTE` is set automatically for [`SeekTarget::Bytes`].
+    ///
+    /// Return `AVERROR_STREAM_NOT_FOUND` if there is no stream `stream_index`.
+    /// Return [`RsmpegError::ByteSeekUnsupportedError`] if byte seeking is
+    /// requested but not supported, check it with
+    /// [`Self::supports_byte_seek()`] up front.
+    /// Return [`RsmpegError::SeekBeyondEndError`] if seeking to a timestamp
+    /// after the end of the input fails, `AVSEEK_FLAG_BACKWARD` lands on the
+    /// last key frame instead.
+    ///
+    /// A byte offset usually lands in the middle of a packet, the demuxer
+    /// resynchronizes on the next start code, so the first packets after a
+    /// byte seek may be incomplete and decoders may output broken frames until
+    /// the next key frame. Flush the decoders after seeking.
+    pub fn seek(
+        &mut self,
+        stream_index: Option<usize>,
+        target: SeekTarget,
+        flags: u32,
+    ) -> Result<()> {
+        let (stream_index, timestamp, flags) = match target {
+            SeekTarget::Timestamp(ts) => {
+                let stream_index = match stream_index {
+                    // FFmpeg indexes the streams without checking.
+                    Some(x) if x >= self.nb_streams as usize => {
+                        return Err(RsmpegError::AVError(ffi::AVERROR_STREAM_NOT_FOUND))
+                    }
+                    Some(x) => x as c_int,
+                    None => -1,
+                };
+                (stream_index, ts, flags)
+            }
+            SeekTarget::Bytes(pos) => {
+                if !self.supports_byte_seek() {
+                    return Err(RsmpegError::ByteSeekUnsupportedError);
+                }
+                (-1, pos as i64, flags | ffi::AVSEEK_FLAG_BYTE)
+            }
+        };
+        match unsafe {
+            ffi::av_seek_frame(self.as_mut_ptr(), stream_index, timestamp, flags as c_int)
+        }
+        .upgrade()
+        {
+            Ok(_) => Ok(()),
+            Err(_)
+                if matches!(target, SeekTarget::Timestamp(_))
+                    && self.is_beyond_end(stream_index, timestamp) =>
+            {
+                Err(RsmpegError::SeekBeyondEndError)
+            }
+            Err(err) => Err(RsmpegError::AVError(err)),
+        }
+    }
+
+    /// Return true if `timestamp` of stream `stream_index`(or in
+    /// `AV_TIME_BASE` units if it's negative) is after the end of the input.
+    fn is_beyond_end(&self, stream_index: c_int, timestamp: i64) -> bool {
+        if self.duration == ffi::AV_NOPTS_VALUE || self.duration <= 0 {
+            return false;
+        }
+        let start_time = if self.start_time == ffi::AV_NOPTS_VALUE {
+            0
+        } else {
+            self.start_time
+        };
+        let end = start_time + self.duration;
+        let end = match usize::try_from(stream_index)
+            .ok()
+            .and_then(|x| self.streams().get(x))
+        {
+            Some(stream) => av_rescale_q(end, ra(1, ffi::AV_TIME_BASE as i32), stream.time_base),
+            None => end,
+        };
+        timestamp > end
+    }
+
+    /// Return true if the demuxer and the underlying IO support seeking by
+    /// byte offset.
+    pub fn supports_byte_seek(&self) -> bool {
+        if self.iformat().flags as u32 & ffi::AVFMT_NO_BYTE_SEEK != 0 {
+            return false;
+        }
+        NonNull::new(self.pb)
+            .map(|pb| unsafe { pb.as_ref() }.seekable & ffi::AVIO_SEEKABLE_NORMAL as c_int != 0)
+            .unwrap_or(false)
+    }
+
+    /// Return true if the format has discontinuous timestamps(e.g. MPEG-PS and
+    /// MPEG-TS), seeking by byte offset is more reliable than by timestamp for
+    /// them.
+    pub fn prefers_byte_seek(&self) -> bool {
+        self.iformat().flags as u32 & ffi::AVFMT_TS_DISCONT != 0 && self.supports_byte_seek()
+    }
+
     /// Return the stream index and stream decoder if there is any "best" stream.
     /// "best" means the most likely what the user wants.
     pub fn find_best_stream(
@@ -208,6 +510,59 @@ impl<'stream> AVFormatContextInput {
         unsafe { std::slice::from_raw_parts(streams, len) }
     }
 
+    /// Get the stream at `index`, `None` if out of range.
+    pub fn stream(&'stream self, index: usize) -> Option<&'stream AVStreamRef<'stream>> {
+        self.streams().get(index)
+    }
+
+    /// Iterate over the streams of `media_type` with their indexes, e.g. to
+    /// handle each of several audio tracks.
+    ///
+    /// The input is borrowed immutably, reading packets needs a mutable
+    /// borrow, so streams can't be added while iterating.
+    pub fn streams_of_type(
+        &'stream self,
+        media_type: ffi::AVMediaType,
+    ) -> impl Iterator<Item = (usize, &'stream AVStreamRef<'stream>)> + 'stream {
+        self.streams()
+            .iter()
+            .enumerate()
+            .filter(move |(_, stream)| stream.codecpar().codec_type == media_type)
+    }
+
+    /// Iterate over the video streams with their indexes. Cover arts are
+    /// video streams too, with `AV_DISPOSITION_ATTACHED_PIC`.
+    pub fn video_streams(
+        &'stream self,
+    ) -> impl Iterator<Item = (usize, &'stream AVStreamRef<'stream>)> + 'stream {
+        self.streams_of_type(ffi::AVMEDIA_TYPE_VIDEO)
+    }
+
+    /// Iterate over the audio streams with their indexes.
+    pub fn audio_streams(
+        &'stream self,
+    ) -> impl Iterator<Item = (usize, &'stream AVStreamRef<'stream>)> + 'stream {
+        self.streams_of_type(ffi::AVMEDIA_TYPE_AUDIO)
+    }
+
+    /// Iterate over the subtitle streams with their indexes.
+    pub fn subtitle_streams(
+        &'stream self,
+    ) -> impl Iterator<Item = (usize, &'stream AVStreamRef<'stream>)> + 'stream {
+        self.streams_of_type(ffi::AVMEDIA_TYPE_SUBTITLE)
+    }
+
+    /// Get the first video stream with its index. Unlike
+    /// [`Self::find_best_stream()`] the stream order is all that matters.
+    pub fn first_video(&'stream self) -> Option<(usize, &'stream AVStreamRef<'stream>)> {
+        self.video_streams().next()
+    }
+
+    /// Get the first audio stream with its index.
+    pub fn first_audio(&'stream self) -> Option<(usize, &'stream AVStreamRef<'stream>)> {
+        self.audio_streams().next()
+    }
+
     /// Return slice of [`AVStreamMut`].
     pub fn streams_mut(&'stream mut self) -> &'stream mut [AVStreamMut<'stream>] {
         // #define `<->` as "has the same layout due to repr(transparent)"
@@ -235,6 +590,34 @@ impl<'stream> AVFormatContextInput {
         unsafe { std::slice::from_raw_parts_mut(streams, len) }
     }
 
+    /// Get the duration of the input in microseconds (`AV_TIME_BASE` units),
+    /// `None` if unknown.
+    pub fn duration_us(&self) -> Option<i64> {
+        (self.duration != ffi::AV_NOPTS_VALUE && self.duration >= 0).then_some(self.duration)
+    }
+
+    /// Same as [`Self::duration_us()`], as a [`Duration`].
+    pub fn duration(&self) -> Option<Duration> {
+        self.duration_us()
+            .map(|duration| Duration::from_micros(duration as u64))
+    }
+
+    /// Get the position of the first frame in microseconds (`AV_TIME_BASE`
+    /// units), `None` if unknown.
+    pub fn start_time_us(&self) -> Option<i64> {
+        (self.start_time != ffi::AV_NOPTS_VALUE).then_some(self.start_time)
+    }
+
+    /// Get the total bitrate of the input in bit/s, `None` if unknown.
+    pub fn bit_rate(&self) -> Option<i64> {
+        (self.bit_rate > 0).then_some(self.bit_rate)
+    }
+
+    /// Get the number of streams, same as `self.streams().len()`.
+    pub fn nb_streams(&self) -> usize {
+        self.nb_streams as usize
+    }
+
     /// Get [`AVInputFormatRef`] in the [`AVFormatContextInput`].
     pub fn iformat(&'stream self) -> AVInputFormatRef<'stream> {
         // From the implementation of FFmpeg's `avformat_open_input`, we can be
@@ -251,6 +634,59 @@ impl<'stream> AVFormatContextInput {
         // `metadata` can be null.
         NonNull::new(self.metadata).map(|x| unsafe { AVDictionaryRef::from_raw(x) })
     }
+
+    /// Return the attachments of the input in stream order, i.e. the streams
+    /// of type `AVMEDIA_TYPE_ATTACHMENT`.
+    pub fn attachments(&'stream self) -> Vec<Attachment<'stream>> {
+        self.streams()
+            .iter()
+            .filter(|stream| stream.codecpar().codec_type == ffi::AVMEDIA_TYPE_ATTACHMENT)
+            .map(|stream| {
+                // Borrow from the raw stream rather than the temporary
+                // references, they live as long as `self`.
+                let tag = |key: &CStr| {
+                    unsafe { ffi::av_dict_get(stream.metadata, key.as_ptr(), ptr::null(), 0) }
+                        .upgrade()
+                        .map(|entry| unsafe { CStr::from_ptr(entry.as_ref().value) })
+                };
+                let codecpar = unsafe { &*stream.codecpar };
+                let data = if codecpar.extradata.is_null() {
+                    &[][..]
+                } else {
+                    unsafe {
+                        slice::from_raw_parts(codecpar.extradata, codecpar.extradata_size as usize)
+                    }
+                };
+                Attachment {
+                    filename: tag(CStr::from_bytes_with_nul(b"filename\0").unwrap()),
+                    mimetype: tag(CStr::from_bytes_with_nul(b"mimetype\0").unwrap()),
+                    data,
+                }
+            })
+            .collect()
+    }
+
+    /// Return the chapters of the input, e.g. the chapter markers of a
+    /// podcast.
+    pub fn chapters(&'stream self) -> &'stream [AVChapterRef<'stream>] {
+        if self.chapters.is_null() {
+            return &[];
+        }
+        // Same as `streams()`: AVChapterRef <-> *const ffi::AVChapter
+        let chapters = self.chapters as *const AVChapterRef<'stream>;
+        unsafe { slice::from_raw_parts(chapters, self.nb_chapters as usize) }
+    }
+
+    /// Return the programs of the input, e.g. the services of a MPEG-TS
+    /// broadcast, each grouping the streams played together.
+    pub fn programs(&'stream self) -> &'stream [AVProgramRef<'stream>] {
+        if self.programs.is_null() {
+            return &[];
+        }
+        // Same as `streams()`: AVProgramRef <-> *const ffi::AVProgram
+        let programs = self.programs as *const AVProgramRef<'stream>;
+        unsafe { slice::from_raw_parts(programs, self.nb_programs as usize) }
+    }
 }
 
 impl Drop for AVFormatContextInput {
@@ -260,15 +696,75 @@ impl Drop for AVFormatContextInput {
     }
 }
 
+/// Writing state of an [`AVFormatContextOutput`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputState {
+    /// The header is not written, streams can still be configured.
+    Unstarted,
+    /// The header is written, packets can be written.
+    Writing,
+    /// The trailer is written, nothing can be written anymore.
+    Finalized,
+}
+
 wrap! {
     AVFormatContextOutput: ffi::AVFormatContext,
-    io_context: Option<AVIOContextContainer> = None,
+    pub io_context: Option<AVIOContextContainer> = None,
+    state: OutputState = OutputState::Unstarted,
 }
 
 impl AVFormatContextOutput {
     /// Open a file and create a [`AVFormatContextOutput`] instance of that
     /// file. Give it an [`AVIOContext`] if you want custom IO.
     pub fn create(filename: &CStr, io_context: Option<AVIOContextContainer>) -> Result<Self> {
+        Self::create_with_format(filename, None, io_context)
+    }
+
+    /// Create an [`AVFormatContextOutput`] writing an HLS playlist to
+    /// `playlist_path`, with the `hls` muxer configured by `opts`.
+    ///
+    /// The `hls` muxer is `AVFMT_NOFILE`, it opens the playlist and segment
+    /// files by itself, so no [`AVIOContext`] is created here. Call
+    /// [`Self::write_trailer()`] at the end to finalize the playlist.
+    pub fn open_hls(playlist_path: &CStr, opts: HlsOutputOptions) -> Result<Self> {
+        let format_name = CStr::from_bytes_with_nul(b"hls\0").unwrap();
+        let mut output_format_context =
+            Self::create_with_format(playlist_path, Some(format_name), None)?;
+        if let Some(dict) = opts.into_dict() {
+            let mut dict_ptr = dict.into_raw().as_ptr();
+            // The muxer private data is allocated with the context.
+            let result =
+                unsafe { ffi::av_opt_set_dict2(output_format_context.priv_data, &mut dict_ptr, 0) };
+            // Options not found are left in the dictionary.
+            let unused = dict_ptr
+                .upgrade()
+                .map(|x| unsafe { AVDictionary::from_raw(x) });
+            result.upgrade()?;
+            if unused.is_some() {
+                return Err(RsmpegError::AVError(ffi::AVERROR_OPTION_NOT_FOUND));
+            }
+        }
+        Ok(output_format_context)
+    }
+
+    /// Similar to [`Self::create()`], while the output format is selected by
+    /// `format_name`(e.g. `image2`) instead of being guessed from `filename`.
+    ///
+    /// Muxers with the `AVFMT_NOFILE` flag(e.g. `image2` writing
+    /// `frame_%04d.png`) open their files by themselves, no [`AVIOContext`] is
+    /// opened and `io_context` is ignored for them.
+    ///
+    /// Return `AVERROR(EINVAL)` for an [`AVIOContextContainer::Async`]
+    /// `io_context`, which is read-only.
+    pub fn create_with_format(
+        filename: &CStr,
+        format_name: Option<&CStr>,
+        io_context: Option<AVIOContextContainer>,
+    ) -> Result<Self> {
+        #[cfg(feature = "async-io")]
+        if let Some(AVIOContextContainer::Async(_)) = io_context {
+            return Err(RsmpegError::AVError(ffi::AVERROR(ffi::EINVAL)));
+        }
         let mut output_format_context = ptr::null_mut();
 
         // Alloc the context
@@ -276,7 +772,7 @@ impl AVFormatContextOutput {
             ffi::avformat_alloc_output_context2(
                 &mut output_format_context,
                 ptr::null_mut(),
-                ptr::null_mut(),
+                format_name.map_or(ptr::null(), |x| x.as_ptr()),
                 filename.as_ptr(),
             )
         }
@@ -293,7 +789,7 @@ impl AVFormatContextOutput {
         //
         // For safeness, we don't use the user the given AVIOContext even if the
         // caller provides one.
-        if output_format_context.oformat().flags & ffi::AVFMT_NOFILE as i32 == 0 {
+        if !output_format_context.oformat().flags().is_nofile() {
             // If user provides us an `AVIOCustomContext`, use it, or we create a default one.
             let mut io_context = match io_context {
                 Some(x) => x,
@@ -305,6 +801,8 @@ impl AVFormatContextOutput {
                 output_format_context.deref_mut().pb = match &mut io_context {
                     AVIOContextContainer::Url(ctx) => ctx.as_mut_ptr(),
                     AVIOContextContainer::Custom(ctx) => ctx.as_mut_ptr(),
+                    #[cfg(feature = "async-io")]
+                    AVIOContextContainer::Async(_) => unreachable!(),
                 };
             }
             output_format_context.io_context = Some(io_context);
@@ -320,7 +818,11 @@ impl AVFormatContextOutput {
     ///     and muxer-private options. On return this parameter will be replaced
     ///     with a dict containing options that were not found. Set this to `None`
     ///     if it's not needed.
+    ///
+    /// Return [`RsmpegError::HeaderAlreadyWrittenError`] or
+    /// [`RsmpegError::AlreadyFinalizedError`] if called more than once.
     pub fn write_header(&mut self, dict: &mut Option<AVDictionary>) -> Result<()> {
+        self.check_unstarted()?;
         let mut dict_ptr = dict
             .take()
             .map(|x| x.into_raw().as_ptr())
@@ -334,17 +836,48 @@ impl AVFormatContextOutput {
             .map(|x| unsafe { AVDictionary::from_raw(x) });
 
         result.upgrade()?;
+        self.state = OutputState::Writing;
 
         Ok(())
     }
 
     /// Write the stream trailer to an output media file and free the file
-    /// private data.
+    /// private data. For HLS output this writes the final playlist.
+    ///
+    /// If the header is written, the trailer is written on drop when this
+    /// function isn't called, failures are logged there.
     pub fn write_trailer(&mut self) -> Result<()> {
+        self.check_writing()?;
+        // The private data is freed even on failure, so it's finalized anyway.
+        self.state = OutputState::Finalized;
         unsafe { ffi::av_write_trailer(self.as_mut_ptr()) }.upgrade()?;
         Ok(())
     }
 
+    /// Get the writing state of the output.
+    pub fn state(&self) -> OutputState {
+        self.state
+    }
+
+    /// Return an error if the header is already written.
+    fn check_unstarted(&self) -> Result<()> {
+        match self.state {
+            OutputState::Unstarted => Ok(()),
+            OutputState::Writing => Err(RsmpegError::HeaderAlreadyWrittenError),
+            OutputState::Finalized => Err(RsmpegError::AlreadyFinalizedError),
+        }
+    }
+
+    /// Return an error if the output is not between the header and the
+    /// trailer.
+    fn check_writing(&self) -> Result<()> {
+        match self.state {
+            OutputState::Unstarted => Err(RsmpegError::HeaderNotWrittenError),
+            OutputState::Writing => Ok(()),
+            OutputState::Finalized => Err(RsmpegError::AlreadyFinalizedError),
+        }
+    }
+
     /// Dump [`ffi::AVFormatContext`] info in the "FFmpeg" way.
     ///
     /// The filename here is just for info printing, it's really doesn't matter.
@@ -364,6 +897,7 @@ impl AVFormatContextOutput {
     /// libavformat to handle the interleaving should call
     /// [`Self::interleaved_write_frame()`] instead of this function.
     pub fn write_frame(&mut self, packet: &mut AVPacket) -> Result<()> {
+        self.check_writing()?;
         unsafe { ffi::av_write_frame(self.as_mut_ptr(), packet.as_mut_ptr()) }.upgrade()?;
         Ok(())
     }
@@ -375,13 +909,84 @@ impl AVFormatContextOutput {
     /// increasing dts. Callers doing their own interleaving should call
     /// [`Self::write_frame()`] instead of this function.
     pub fn interleaved_write_frame(&mut self, packet: &mut AVPacket) -> Result<()> {
+        self.check_writing()?;
         unsafe { ffi::av_interleaved_write_frame(self.as_mut_ptr(), packet.as_mut_ptr()) }
             .upgrade()?;
         Ok(())
     }
+
+    /// Write an uncoded frame to the stream of `stream_index`, for muxers and
+    /// devices that accept raw frames directly. The muxer takes the ownership
+    /// of `frame`. If `interleaved` is true, frames are interleaved like
+    /// [`Self::interleaved_write_frame()`], or they are passed to the muxer
+    /// directly like [`Self::write_frame()`].
+    ///
+    /// Return `AVERROR(ENOSYS)` if the muxer doesn't support uncoded frames,
+    /// check it with [`Self::can_write_uncoded()`].
+    pub fn write_uncoded_frame(
+        &mut self,
+        stream_index: usize,
+        frame: AVFrame,
+        interleaved: bool,
+    ) -> Result<()> {
+        self.check_writing()?;
+        let frame = frame.into_raw().as_ptr();
+        let stream_index = stream_index as c_int;
+        unsafe {
+            if interleaved {
+                ffi::av_interleaved_write_uncoded_frame(self.as_mut_ptr(), stream_index, frame)
+            } else {
+                ffi::av_write_uncoded_frame(self.as_mut_ptr(), stream_index, frame)
+            }
+        }
+        .upgrade()?;
+        Ok(())
+    }
+
+    /// Test whether the muxer supports uncoded frames on the stream of
+    /// `stream_index`.
+    pub fn can_write_uncoded(&self, stream_index: usize) -> bool {
+        if stream_index >= self.nb_streams as usize {
+            return false;
+        }
+        // `av_write_uncoded_frame_query` doesn't modify the context.
+        let ret = unsafe {
+            ffi::av_write_uncoded_frame_query(self.as_ptr() as *mut _, stream_index as c_int)
+        };
+        ret >= 0
+    }
 }
 
 impl<'stream> AVFormatContextOutput {
+    /// Return slice of [`AVStreamMut`], for configuring the output streams
+    /// before [`Self::write_header()`]. The slice borrows the context
+    /// mutably, so it can't be held across the header writing.
+    pub fn streams_mut(&'stream mut self) -> &'stream mut [AVStreamMut<'stream>] {
+        // #define `<->` as "has the same layout due to repr(transparent)"
+        // ```
+        // NonNull<ffi::AVStream> <-> *const ffi::AVStream
+        // AVStream <-> NonNull<ffi::AVStream>
+        // AVStreamMut <-> AVStream
+        // ```
+        // indicates: AVStreamMut <-> *const ffi::AVStream
+        let streams = self.streams as *mut AVStreamMut<'stream>;
+        // u32 to usize, safe
+        let len = self.nb_streams as usize;
+
+        // I trust that FFmpeg won't give me null pointers :-(
+        #[cfg(debug_assertions)]
+        {
+            let arr = unsafe {
+                std::slice::from_raw_parts(self.streams as *const *const ffi::AVStream, len)
+            };
+            for ptr in arr {
+                assert!(!ptr.is_null());
+            }
+        }
+
+        unsafe { std::slice::from_raw_parts_mut(streams, len) }
+    }
+
     /// Return slice of [`AVStreamRef`].
     pub fn streams(&'stream self) -> &'stream [AVStreamRef<'stream>] {
         // #define `<->` as "has the same layout due to repr(transparent)"
@@ -424,6 +1029,179 @@ impl<'stream> AVFormatContextOutput {
         }
     }
 
+    /// Find the encoder of the codec the output format suggests for the given
+    /// `media_type`, taking the output filename into account. Return `None` if
+    /// the muxer has no suitable codec or no encoder of it is available.
+    pub fn guess_encoder(&self, media_type: ffi::AVMediaType) -> Option<AVCodecRef<'static>> {
+        let filename = NonNull::new(self.url).map(|x| unsafe { CStr::from_ptr(x.as_ptr()) });
+        let codec_id = self.oformat().guess_codec(media_type, filename)?;
+        AVCodec::find_encoder(codec_id)
+    }
+
+    /// Get metadata of the output, written by the muxer in
+    /// [`Self::write_header()`].
+    pub fn metadata(&'stream self) -> Option<AVDictionaryRef<'stream>> {
+        NonNull::new(self.metadata).map(|x| unsafe { AVDictionaryRef::from_raw(x) })
+    }
+
+    /// Get mutable reference of metadata of the output.
+    pub fn metadata_mut(&'stream mut self) -> Option<AVDictionaryMut<'stream>> {
+        NonNull::new(self.metadata).map(|x| unsafe { AVDictionaryMut::from_raw(x) })
+    }
+
+    /// Set metadata of the output, e.g. the `title` tag. The muxer only reads
+    /// it in [`Self::write_header()`], so return
+    /// [`RsmpegError::HeaderAlreadyWrittenError`] or
+    /// [`RsmpegError::AlreadyFinalizedError`] if called after it.
+    pub fn set_metadata(&mut self, dict: Option<AVDictionary>) -> Result<()> {
+        self.check_unstarted()?;
+        // Drop the old_dict
+        let _ = NonNull::new(self.metadata).map(|x| unsafe { AVDictionary::from_raw(x) });
+
+        // Move in the new dict.
+        unsafe {
+            self.deref_mut().metadata = dict
+                .map(|x| x.into_raw().as_ptr())
+                .unwrap_or(ptr::null_mut());
+        }
+        Ok(())
+    }
+
+    /// Add a chapter from `start` to `end` in `time_base`, with an optional
+    /// `title` tag, should be called before [`Self::write_header()`]. Only
+    /// some muxers support chapters, e.g. Matroska and MP4. `id` must be
+    /// unique among the chapters.
+    pub fn add_chapter(
+        &mut self,
+        id: i64,
+        time_base: AVRational,
+        start: i64,
+        end: i64,
+        title: Option<&CStr>,
+    ) -> Result<()> {
+        self.check_unstarted()?;
+        let chapter = unsafe { ffi::av_mallocz(size_of::<ffi::AVChapter>()) }
+            .upgrade()
+            .ok_or(RsmpegError::AVError(ffi::AVERROR(ffi::ENOMEM)))?
+            .as_ptr() as *mut ffi::AVChapter;
+        let chapter_mut = unsafe { &mut *chapter };
+        chapter_mut.id = id;
+        chapter_mut.time_base = time_base;
+        chapter_mut.start = start;
+        chapter_mut.end = end;
+        let mut ret = 0;
+        if let Some(title) = title {
+            let key = CStr::from_bytes_with_nul(b"title\0").unwrap();
+            ret = unsafe {
+                ffi::av_dict_set(&mut chapter_mut.metadata, key.as_ptr(), title.as_ptr(), 0)
+            };
+        }
+        if ret >= 0 {
+            // The chapters are freed with the context.
+            let mut nb_chapters = self.nb_chapters as c_int;
+            ret = unsafe {
+                ffi::av_dynarray_add_nofree(
+                    &mut self.deref_mut().chapters as *mut _ as *mut c_void,
+                    &mut nb_chapters,
+                    chapter as *mut c_void,
+                )
+            };
+            unsafe { self.deref_mut() }.nb_chapters = nb_chapters as u32;
+        }
+        if ret < 0 {
+            unsafe {
+                ffi::av_dict_free(&mut chapter_mut.metadata);
+                ffi::av_free(chapter as *mut c_void);
+            }
+        }
+        ret.upgrade()?;
+        Ok(())
+    }
+
+    /// Embed `data` as an attachment stream with the `filename` and `mimetype`
+    /// tags, should be called before [`Self::write_header()`]. Only some
+    /// muxers support attachments, e.g. Matroska, which requires both tags.
+    ///
+    /// Attachments carry no disposition, `AV_DISPOSITION_ATTACHED_PIC` is for
+    /// cover art sent as a video packet, so it's left unset.
+    ///
+    /// Return [`RsmpegError::HeaderAlreadyWrittenError`] after
+    /// [`Self::write_header()`], the muxer wouldn't see the stream.
+    pub fn add_attachment(&mut self, filename: &CStr, mimetype: &CStr, data: &[u8]) -> Result<()> {
+        self.check_unstarted()?;
+        let mut codecpar = AVCodecParameters::new();
+        unsafe {
+            let raw = codecpar.deref_mut();
+            raw.codec_type = ffi::AVMEDIA_TYPE_ATTACHMENT;
+            raw.codec_id = ffi::AV_CODEC_ID_NONE;
+        }
+        codecpar.set_extradata(data)?;
+        let metadata = AVDictionary::new(
+            CStr::from_bytes_with_nul(b"filename\0").unwrap(),
+            filename,
+            0,
+        )
+        .set(
+            CStr::from_bytes_with_nul(b"mimetype\0").unwrap(),
+            mimetype,
+            0,
+        );
+
+        let mut stream = self.new_stream();
+        stream.set_codecpar(codecpar);
+        stream.set_metadata(Some(metadata));
+        Ok(())
+    }
+
+    /// Add a new stream carrying the coded data of `src` as is, for remuxing
+    /// without re-encoding, should be called before [`Self::write_header()`].
+    /// Return the index of the new stream.
+    ///
+    /// The codec parameters, disposition and `language` tag of `src` are
+    /// copied. Its `time_base` is only a hint, the muxer may pick another one
+    /// in [`Self::write_header()`], so rescale the packets to the time base of
+    /// the output stream.
+    ///
+    /// `codec_tag` is kept only if `dst_format_needs_tag_reset` isn't set and
+    /// the output format confirms the tag for the codec. It's cleared
+    /// otherwise, letting the muxer choose it, as muxers reject tags
+    /// incompatible with the codec.
+    pub fn new_stream_copy_from(
+        &mut self,
+        src: &AVStreamRef,
+        dst_format_needs_tag_reset: bool,
+    ) -> Result<usize> {
+        self.check_unstarted()?;
+
+        let mut codecpar = src.codecpar().clone();
+        let (codec_id, src_tag) = (codecpar.codec_id, codecpar.codec_tag);
+        let tags = self.oformat().codec_tag;
+        let mut tag = 0;
+        // Kept only if the output format knows a tag for the codec and maps
+        // `src_tag` to the codec too, as codecs may have several tags.
+        let confirmed = !dst_format_needs_tag_reset
+            && src_tag != 0
+            && !tags.is_null()
+            && unsafe { ffi::av_codec_get_tag2(tags, codec_id, &mut tag) } != 0
+            && (tag == src_tag || unsafe { ffi::av_codec_get_id(tags, src_tag) } == codec_id);
+        let codec_tag = if confirmed { src_tag } else { 0 };
+        unsafe { codecpar.deref_mut() }.codec_tag = codec_tag;
+
+        let language = CStr::from_bytes_with_nul(b"language\0").unwrap();
+        let src_metadata = src.metadata();
+        let metadata = src_metadata
+            .as_ref()
+            .and_then(|x| x.get(language, None, 0))
+            .map(|x| AVDictionary::new(language, x.value(), 0));
+
+        let mut stream = self.new_stream();
+        stream.set_codecpar(codecpar);
+        stream.set_time_base(src.time_base);
+        stream.set_disposition(src.disposition);
+        stream.set_metadata(metadata);
+        Ok(stream.index as usize)
+    }
+
     /// Add a new stream to a media file, should be called by the user before
     /// [`Self::write_header()`];
     pub fn new_stream(&'stream mut self) -> AVStreamMut<'stream> {
@@ -439,9 +1217,20 @@ impl<'stream> AVFormatContextOutput {
 
 impl Drop for AVFormatContextOutput {
     fn drop(&mut self) {
+        // Finalize the output, so the file stays readable if the writer bails
+        // out early, e.g. on errors or panics.
+        if self.state == OutputState::Writing {
+            if let Err(err) = self.write_trailer() {
+                let message = format!("Writing trailer on drop failed: {}", err);
+                unsafe {
+                    log_message(self.as_mut_ptr().cast(), ffi::AV_LOG_ERROR as i32, &message)
+                };
+            }
+        }
+
         // Here we drop the io context, which won't be touched by
         // avformat_free_context, so let it dangling is safe.
-        if unsafe { *self.oformat }.flags & ffi::AVFMT_NOFILE as i32 == 0 {
+        if !self.oformat().flags().is_nofile() {
             if let Some(pb) = NonNull::new(self.pb) {
                 let _ = unsafe { AVIOContext::from_raw(pb) };
             }
@@ -453,6 +1242,70 @@ impl Drop for AVFormatContextOutput {
     }
 }
 
+wrap_ref!(#[repr(transparent)] AVChapter: ffi::AVChapter);
+
+impl AVChapter {
+    /// Get the unique id of the chapter.
+    pub fn id(&self) -> i64 {
+        self.id
+    }
+
+    /// Get the time base of [`Self::start()`] and [`Self::end()`].
+    pub fn time_base(&self) -> AVRational {
+        self.time_base
+    }
+
+    /// Get the start time of the chapter in [`Self::time_base()`].
+    pub fn start(&self) -> i64 {
+        self.start
+    }
+
+    /// Get the end time of the chapter in [`Self::time_base()`].
+    pub fn end(&self) -> i64 {
+        self.end
+    }
+
+    /// Get the metadata of the chapter, e.g. the `title` tag.
+    pub fn metadata(&self) -> Option<AVDictionaryRef> {
+        NonNull::new(self.metadata).map(|x| unsafe { AVDictionaryRef::from_raw(x) })
+    }
+
+    /// Get the `title` tag of the chapter.
+    pub fn title(&self) -> Option<&CStr> {
+        let key = CStr::from_bytes_with_nul(b"title\0").unwrap();
+        unsafe { ffi::av_dict_get(self.metadata, key.as_ptr(), ptr::null(), 0) }
+            .upgrade()
+            .map(|entry| unsafe { CStr::from_ptr(entry.as_ref().value) })
+    }
+}
+
+wrap_ref!(#[repr(transparent)] AVProgram: ffi::AVProgram);
+
+impl AVProgram {
+    /// Get the id of the program, e.g. the service id of MPEG-TS.
+    pub fn id(&self) -> i32 {
+        self.id
+    }
+
+    /// Get the program number, e.g. of the PMT of MPEG-TS.
+    pub fn program_num(&self) -> i32 {
+        self.program_num
+    }
+
+    /// Get the indexes of the streams in the program.
+    pub fn stream_indexes(&self) -> &[u32] {
+        if self.stream_index.is_null() {
+            return &[];
+        }
+        unsafe { slice::from_raw_parts(self.stream_index, self.nb_stream_indexes as usize) }
+    }
+
+    /// Get the metadata of the program, e.g. the `service_name` tag.
+    pub fn metadata(&self) -> Option<AVDictionaryRef> {
+        NonNull::new(self.metadata).map(|x| unsafe { AVDictionaryRef::from_raw(x) })
+    }
+}
+
 wrap_ref!(AVInputFormat: ffi::AVInputFormat);
 
 impl AVInputFormat {
@@ -462,11 +1315,106 @@ impl AVInputFormat {
             .upgrade()
             .map(|x| unsafe { AVInputFormatRef::from_raw(x) })
     }
+
+    /// Iterate over all registered demuxers.
+    pub fn iterate() -> AVInputFormatIter {
+        AVInputFormatIter {
+            opaque: ptr::null_mut(),
+        }
+    }
+
+    /// Get short name of the demuxer, can be a comma-separated list of names,
+    /// e.g. `mov,mp4,m4a,3gp,3g2,mj2`.
+    pub fn name(&self) -> &CStr {
+        unsafe { CStr::from_ptr(self.name) }
+    }
+
+    /// Get descriptive name of the demuxer.
+    pub fn long_name(&self) -> Option<&CStr> {
+        // Can be null when FFmpeg is built with `--enable-small`.
+        NonNull::new(self.long_name as *mut _).map(|x| unsafe { CStr::from_ptr(x.as_ptr()) })
+    }
+}
+
+/// Iterator of the registered [`AVInputFormatRef`]s, created by
+/// [`AVInputFormat::iterate()`].
+pub struct AVInputFormatIter {
+    opaque: *mut c_void,
+}
+
+impl Iterator for AVInputFormatIter {
+    type Item = AVInputFormatRef<'static>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let ptr = unsafe { ffi::av_demuxer_iterate(&mut self.opaque) }.upgrade()?;
+        Some(unsafe { AVInputFormatRef::from_raw(ptr) })
+    }
+}
+
+// `av_demuxer_iterate` keeps returning null once the end is reached.
+impl FusedIterator for AVInputFormatIter {}
+
+/// Non exhaustive wrapper of the `flags` of [`AVOutputFormat`], a combination
+/// of `AVFMT_*`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AVOutputFormatFlags(pub c_int);
+
+impl AVOutputFormatFlags {
+    /// Return true if all the bits of `flag` are set.
+    pub fn contains(&self, flag: u32) -> bool {
+        self.0 & flag as c_int == flag as c_int
+    }
+
+    /// The muxer handles I/O itself, no `AVIOContext` should be opened.
+    pub fn is_nofile(&self) -> bool {
+        self.contains(ffi::AVFMT_NOFILE)
+    }
+
+    /// The muxer wants global headers, so encoders should set
+    /// `AV_CODEC_FLAG_GLOBAL_HEADER`.
+    pub fn is_globalheader(&self) -> bool {
+        self.contains(ffi::AVFMT_GLOBALHEADER)
+    }
+
+    /// The muxer supports variable fps.
+    pub fn is_variable_fps(&self) -> bool {
+        self.contains(ffi::AVFMT_VARIABLE_FPS)
+    }
+
+    /// The muxer does not need or use timestamps.
+    pub fn is_notimestamps(&self) -> bool {
+        self.contains(ffi::AVFMT_NOTIMESTAMPS)
+    }
 }
 
 wrap_ref!(AVOutputFormat: ffi::AVOutputFormat);
 
+/// Iterator of the registered [`AVOutputFormatRef`]s, created by
+/// [`AVOutputFormat::iterate()`].
+pub struct AVOutputFormatIter {
+    opaque: *mut c_void,
+}
+
+impl Iterator for AVOutputFormatIter {
+    type Item = AVOutputFormatRef<'static>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let ptr = unsafe { ffi::av_muxer_iterate(&mut self.opaque) }.upgrade()?;
+        Some(unsafe { AVOutputFormatRef::from_raw(ptr) })
+    }
+}
+
+// `av_muxer_iterate` keeps returning null once the end is reached.
+impl FusedIterator for AVOutputFormatIter {}
+
 impl AVOutputFormat {
+    /// Iterate over all registered muxers.
+    pub fn iterate() -> AVOutputFormatIter {
+        AVOutputFormatIter {
+            opaque: ptr::null_mut(),
+        }
+    }
+
     /// Return the output format in the list of registered output formats which
     /// best matches the provided parameters, or return NULL if there is no
     /// match.
@@ -483,6 +1431,99 @@ impl AVOutputFormat {
             .upgrade()
             .map(|x| unsafe { AVOutputFormatRef::from_raw(x) })
     }
+
+    /// Guess the codec ID based upon muxer and filename.
+    ///
+    /// `filename` only matters for video streams of image sequence muxers
+    /// (e.g. `image2`), where the codec is derived from the file extension.
+    /// Return `None` if the muxer has no suitable codec for `media_type`.
+    pub fn guess_codec(
+        &self,
+        media_type: ffi::AVMediaType,
+        filename: Option<&CStr>,
+    ) -> Option<AVCodecID> {
+        let filename = filename.map(|x| x.as_ptr()).unwrap_or_else(ptr::null);
+        let codec_id = unsafe {
+            ffi::av_guess_codec(
+                self.as_ptr(),
+                ptr::null(),
+                filename,
+                ptr::null(),
+                media_type,
+            )
+        };
+        (codec_id != ffi::AV_CODEC_ID_NONE).then_some(codec_id)
+    }
+
+    /// Get the default video codec of this muxer, `None` if it doesn't have one.
+    pub fn default_video_codec(&self) -> Option<AVCodecID> {
+        (self.video_codec != ffi::AV_CODEC_ID_NONE).then_some(self.video_codec)
+    }
+
+    /// Get the default audio codec of this muxer, `None` if it doesn't have one.
+    pub fn default_audio_codec(&self) -> Option<AVCodecID> {
+        (self.audio_codec != ffi::AV_CODEC_ID_NONE).then_some(self.audio_codec)
+    }
+
+    /// Get the default subtitle codec of this muxer, `None` if it doesn't have one.
+    pub fn default_subtitle_codec(&self) -> Option<AVCodecID> {
+        (self.subtitle_codec != ffi::AV_CODEC_ID_NONE).then_some(self.subtitle_codec)
+    }
+
+    /// Get short name of the muxer.
+    pub fn name(&self) -> &CStr {
+        unsafe { CStr::from_ptr(self.name) }
+    }
+
+    /// Get descriptive name of the muxer.
+    pub fn long_name(&self) -> Option<&CStr> {
+        // Can be null when FFmpeg is built with `--enable-small`.
+        NonNull::new(self.long_name as *mut _).map(|x| unsafe { CStr::from_ptr(x.as_ptr()) })
+    }
+
+    /// Get comma-separated filename extensions of the muxer.
+    pub fn extensions(&self) -> Option<&CStr> {
+        NonNull::new(self.extensions as *mut _).map(|x| unsafe { CStr::from_ptr(x.as_ptr()) })
+    }
+
+    /// Get comma-separated MIME types of the muxer.
+    pub fn mime_type(&self) -> Option<&CStr> {
+        NonNull::new(self.mime_type as *mut _).map(|x| unsafe { CStr::from_ptr(x.as_ptr()) })
+    }
+
+    /// Get the `AVFMT_*` flags of the muxer.
+    pub fn flags(&self) -> AVOutputFormatFlags {
+        AVOutputFormatFlags(self.flags)
+    }
+
+    /// Test if the given container can store a codec.
+    ///
+    /// Return `Some(true)` if codec with ID `codec_id` can be stored in this
+    /// format, `Some(false)` if it cannot, `None` if this information is not
+    /// available.
+    pub fn supports_codec(&self, codec_id: AVCodecID, compliance: Compliance) -> Option<bool> {
+        match unsafe { ffi::avformat_query_codec(self.as_ptr(), codec_id, compliance as i32) } {
+            0 => Some(false),
+            x if x > 0 => Some(true),
+            _ => None,
+        }
+    }
+
+    /// Similar to [`Self::supports_codec()`], but return
+    /// [`RsmpegError::CodecNotSupportedError`] naming both the codec and the
+    /// container if the codec cannot be stored in this format. Unknown
+    /// combinations are let through.
+    pub fn check_codec(&self, codec_id: AVCodecID, compliance: Compliance) -> Result<()> {
+        if self.supports_codec(codec_id, compliance) == Some(false) {
+            let codec = unsafe { CStr::from_ptr(ffi::avcodec_get_name(codec_id)) };
+            let container = unsafe { CStr::from_ptr(self.name) };
+            return Err(RsmpegError::CodecNotSupportedError(
+                codec.to_string_lossy().into_owned(),
+                container.to_string_lossy().into_owned(),
+            ));
+        }
+        Ok(())
+    }
 }
 
 wrap_ref_mut!(#[repr(transparent)] AVStream: ffi::AVStream);
@@ -492,6 +1533,7 @@ settable!(AVStream {
     disposition: c_int,
     duration: i64,
     event_flags: c_int,
+    id: c_int,
     sample_aspect_ratio: AVRational,
     time_base: AVRational,
 });
@@ -508,6 +1550,41 @@ impl AVStream {
             ffi::av_guess_frame_rate(ptr::null_mut(), self.as_ptr() as *mut _, ptr::null_mut())
         })
     }
+
+    /// Get the presentation timestamp of the first frame in the stream time
+    /// base, `None` if unknown.
+    pub fn start_time(&self) -> Option<i64> {
+        (self.start_time != ffi::AV_NOPTS_VALUE).then_some(self.start_time)
+    }
+
+    /// Get the duration of the stream in the stream time base, `None` if
+    /// unknown. Demuxers may estimate it from the bitrate.
+    pub fn duration(&self) -> Option<i64> {
+        (self.duration != ffi::AV_NOPTS_VALUE).then_some(self.duration)
+    }
+
+    /// Same as [`Self::duration()`], in seconds.
+    pub fn duration_secs(&self) -> Option<f64> {
+        self.duration()
+            .map(|duration| duration as f64 * self.time_base().to_f64())
+    }
+
+    /// Get the time base of the timestamps of the stream.
+    pub fn time_base(&self) -> Rational {
+        self.time_base.into()
+    }
+
+    /// Get the number of frames in the stream, `None` if unknown.
+    pub fn nb_frames(&self) -> Option<i64> {
+        (self.nb_frames > 0).then_some(self.nb_frames)
+    }
+
+    /// Get the average frame rate of the stream, `None` if unknown (e.g.
+    /// audio streams). Use [`Self::guess_framerate()`] for a best guess.
+    pub fn avg_frame_rate(&self) -> Option<Rational> {
+        let rate = self.avg_frame_rate;
+        (rate.num > 0 && rate.den > 0).then_some(rate.into())
+    }
 }
 
 impl<'stream> AVStream {
@@ -536,6 +1613,15 @@ impl<'stream> AVStream {
         }
     }
 
+    /// Set the number of priming samples at the start of an audio stream,
+    /// e.g. the encoder delay of the packets copied from another stream. It's
+    /// stored in the codec parameters, so call this after
+    /// [`Self::set_codecpar()`]. Muxers write it out in
+    /// [`AVFormatContextOutput::write_header()`], e.g. as the MP4 edit list.
+    pub fn set_initial_padding(&mut self, initial_padding: i32) {
+        unsafe { (*self.deref_mut().codecpar).initial_padding = initial_padding };
+    }
+
     /// Get metadata of current stream.
     pub fn metadata(&'stream self) -> Option<AVDictionaryRef<'stream>> {
         NonNull::new(self.metadata).map(|x| unsafe { AVDictionaryRef::from_raw(x) })
@@ -558,6 +1644,89 @@ impl<'stream> AVStream {
                 .unwrap_or(ptr::null_mut());
         }
     }
+
+    /// Get the side data of `side_data_type` of current stream, e.g. the
+    /// display matrix.
+    pub fn side_data(
+        &'stream self,
+        side_data_type: ffi::AVPacketSideDataType,
+    ) -> Option<&'stream [u8]> {
+        #[cfg(not(feature = "ffmpeg7"))]
+        let (data, size) = {
+            let mut size = 0;
+            let data =
+                unsafe { ffi::av_stream_get_side_data(self.as_ptr(), side_data_type, &mut size) };
+            (data, size)
+        };
+        // Stream side data moved into the codec parameters in FFmpeg 6.1.
+        #[cfg(feature = "ffmpeg7")]
+        let (data, size) = {
+            let codecpar = self.codecpar();
+            let side_data = unsafe {
+                ffi::av_packet_side_data_get(
+                    codecpar.coded_side_data,
+                    codecpar.nb_coded_side_data,
+                    side_data_type,
+                )
+                .as_ref()
+            }?;
+            (side_data.data, side_data.size)
+        };
+        let data = NonNull::new(data)?;
+        Some(unsafe { std::slice::from_raw_parts(data.as_ptr(), size) })
+    }
+
+    /// Add side data of `side_data_type` to current stream, an existing entry
+    /// of the same type is replaced. Muxers read it in
+    /// [`AVFormatContextOutput::write_header()`], and it's stored in the codec
+    /// parameters, so call this after [`Self::set_codecpar()`].
+    pub fn add_side_data(
+        &mut self,
+        side_data_type: ffi::AVPacketSideDataType,
+        data: &[u8],
+    ) -> Result<()> {
+        #[cfg(not(feature = "ffmpeg7"))]
+        let buffer = unsafe {
+            ffi::av_stream_new_side_data(self.as_mut_ptr(), side_data_type, data.len() as _)
+        };
+        #[cfg(feature = "ffmpeg7")]
+        let buffer = unsafe {
+            let codecpar = &mut *self.deref_mut().codecpar;
+            ffi::av_packet_side_data_new(
+                &mut codecpar.coded_side_data,
+                &mut codecpar.nb_coded_side_data,
+                side_data_type,
+                data.len(),
+                0,
+            )
+            .as_ref()
+            .map_or(ptr::null_mut(), |side_data| side_data.data)
+        };
+        let buffer = buffer.upgrade_or(ffi::AVERROR(ffi::ENOMEM))?;
+        unsafe { ptr::copy_nonoverlapping(data.as_ptr(), buffer.as_ptr(), data.len()) };
+        Ok(())
+    }
+
+    /// Get the rotation of the display matrix side data of current stream in
+    /// degrees counterclockwise, within [-180, 180]. Return `None` if there
+    /// is no valid display matrix.
+    pub fn rotation(&'stream self) -> Option<f64> {
+        display_rotation_get(self.side_data(ffi::AV_PKT_DATA_DISPLAYMATRIX)?)
+    }
+
+    /// Set the display matrix side data of current stream to a rotation of
+    /// `degrees` counterclockwise, replacing the existing one, e.g. for
+    /// portrait videos. Same as [`Self::add_side_data()`], call this after
+    /// [`Self::set_codecpar()`] and before
+    /// [`AVFormatContextOutput::write_header()`].
+    ///
+    /// MP4 stores the matrix as is, while Matroska only stores the rotation.
+    pub fn set_rotation(&mut self, degrees: f64) -> Result<()> {
+        self.add_side_data(
+            ffi::AV_PKT_DATA_DISPLAYMATRIX,
+            &display_rotation_matrix(degrees),
+        )
+    }
 }
 
 #[cfg(test)]
@@ -584,4 +1753,270 @@ mod test {
         let name = cstr!("__random__");
         assert!(AVInputFormat::find(name).is_none());
     }
+
+    #[test]
+    fn test_open_forced_format() {
+        let mpeg = AVInputFormat::find(cstr!("mpeg")).unwrap();
+        let input = AVFormatContextInput::open(
+            cstr!("tests/assets/vids/centaur.mpg"),
+            Some(&mpeg),
+            &mut None,
+        )
+        .unwrap();
+        assert_eq!(input.iformat().name(), cstr!("mpeg"));
+        assert_eq!(
+            input.iformat().long_name(),
+            Some(cstr!("MPEG-PS (MPEG-2 Program Stream)"))
+        );
+
+        // Headerless, so the demuxer takes its parameters from the options.
+        let s16le = AVInputFormat::find(cstr!("s16le")).unwrap();
+        let mut options = Some(AVDictionary::new(cstr!("sample_rate"), cstr!("8000"), 0));
+        let input = AVFormatContextInput::open(
+            cstr!("tests/assets/vids/centaur.mpg"),
+            Some(&s16le),
+            &mut options,
+        )
+        .unwrap();
+        assert_eq!(input.iformat().name(), cstr!("s16le"));
+        assert!(options.is_none());
+        let codecpar = input.streams()[0].codecpar();
+        assert_eq!(codecpar.codec_id, ffi::AV_CODEC_ID_PCM_S16LE);
+        assert_eq!(codecpar.sample_rate, 8000);
+    }
+
+    #[test]
+    fn test_input_duration() {
+        let input =
+            AVFormatContextInput::open(cstr!("tests/assets/vids/centaur.mpg"), None, &mut None)
+                .unwrap();
+        assert_eq!(input.nb_streams(), 1);
+        // 419 frames at 29.97 fps, estimated from the bitrate.
+        let duration = input.duration().unwrap();
+        assert!(
+            (Duration::from_secs(13)..Duration::from_secs(15)).contains(&duration),
+            "{duration:?}"
+        );
+        assert_eq!(
+            duration,
+            Duration::from_micros(input.duration_us().unwrap() as u64)
+        );
+        assert!(input.bit_rate().unwrap() > 0);
+
+        let input =
+            AVFormatContextInput::open(cstr!("tests/assets/vids/bear.mp4"), None, &mut None)
+                .unwrap();
+        assert_eq!(input.nb_streams(), 2);
+        assert!(input.start_time_us().is_some());
+    }
+
+    #[test]
+    fn test_streams_of_type() {
+        let input =
+            AVFormatContextInput::open(cstr!("tests/assets/vids/bear.mp4"), None, &mut None)
+                .unwrap();
+        let (video_index, video) = input.first_video().unwrap();
+        assert!(video.codecpar().codec_type().is_video());
+        assert_eq!(video.index as usize, video_index);
+        let (best_index, _) = input
+            .find_best_stream(ffi::AVMEDIA_TYPE_VIDEO)
+            .unwrap()
+            .unwrap();
+        assert_eq!(video_index, best_index);
+        let (audio_index, audio) = input.first_audio().unwrap();
+        assert_ne!(audio_index, video_index);
+        assert_eq!(
+            audio.codecpar().codec_type,
+            input.stream(audio_index).unwrap().codecpar().codec_type
+        );
+        assert_eq!(input.video_streams().count(), 1);
+        assert_eq!(input.audio_streams().count(), 1);
+        assert_eq!(input.subtitle_streams().count(), 0);
+        assert!(input.stream(2).is_none());
+    }
+
+    #[test]
+    fn test_find_stream_info() {
+        let mut input = AVFormatContextInput::open_without_stream_info(
+            cstr!("tests/assets/vids/centaur.mpg"),
+            None,
+            &mut None,
+        )
+        .unwrap();
+        // The raw stream has no header, its parameters are found by reading it.
+        assert_eq!(input.nb_streams(), 1);
+        assert_eq!(input.streams()[0].codecpar().width, 0);
+        assert!(input.duration().is_none());
+        let mut options = Some(AVDictionary::new(cstr!("threads"), cstr!("1"), 0).set(
+            cstr!("rsmpeg_unknown"),
+            cstr!("1"),
+            0,
+        ));
+        input.find_stream_info(&mut options).unwrap();
+        let codecpar = input.streams()[0].codecpar();
+        assert_eq!((codecpar.width, codecpar.height), (320, 240));
+        assert!(input.duration().is_some());
+        // The decoder took `threads`, the unknown option is left.
+        let options = options.unwrap();
+        assert!(options.get(cstr!("threads"), None, 0).is_none());
+        assert!(options.get(cstr!("rsmpeg_unknown"), None, 0).is_some());
+    }
+
+    #[test]
+    fn test_open_wrong_format() {
+        let wav = AVInputFormat::find(cstr!("wav")).unwrap();
+        assert_eq!(
+            AVFormatContextInput::open(
+                cstr!("tests/assets/vids/centaur.mpg"),
+                Some(&wav),
+                &mut None,
+            )
+            .unwrap_err(),
+            RsmpegError::OpenInputError(ffi::AVERROR_INVALIDDATA)
+        );
+    }
+
+    #[test]
+    fn test_format_iterators() {
+        assert!(AVInputFormat::iterate().any(|x| x.name() == cstr!("mpeg")));
+        assert!(AVInputFormat::iterate().any(|x| x.name() == cstr!("mov,mp4,m4a,3gp,3g2,mj2")));
+        assert!(AVOutputFormat::iterate().any(|x| x.name() == cstr!("mp4")));
+        assert!(AVOutputFormat::iterate().all(|x| !x.name().to_bytes().is_empty()));
+
+        let mut iter = AVInputFormat::iterate();
+        for _ in iter.by_ref() {}
+        assert!(iter.next().is_none());
+        let mut iter = AVOutputFormat::iterate();
+        for _ in iter.by_ref() {}
+        assert!(iter.next().is_none());
+    }
+
+    #[test]
+    fn test_stream_accessors() {
+        let input =
+            AVFormatContextInput::open(cstr!("tests/assets/vids/bear.mp4"), None, &mut None)
+                .unwrap();
+        let (video_index, _) = input
+            .find_best_stream(ffi::AVMEDIA_TYPE_VIDEO)
+            .unwrap()
+            .unwrap();
+        let (audio_index, _) = input
+            .find_best_stream(ffi::AVMEDIA_TYPE_AUDIO)
+            .unwrap()
+            .unwrap();
+        assert!(input
+            .find_best_stream(ffi::AVMEDIA_TYPE_SUBTITLE)
+            .unwrap()
+            .is_none());
+
+        let video = &input.streams()[video_index];
+        assert!(video.start_time().is_some());
+        assert!(video.time_base().den() > 0);
+        let duration = video.duration_secs().unwrap();
+        assert!((duration - 1.0).abs() < 0.1, "{}", duration);
+        assert!(video.nb_frames().unwrap() > 0);
+        let frame_rate = video.avg_frame_rate().unwrap().to_f64();
+        assert!((frame_rate - 29.97).abs() < 0.1, "{}", frame_rate);
+        assert!(input.streams()[audio_index].avg_frame_rate().is_none());
+
+        // Nothing is known about a new stream.
+        std::fs::create_dir_all("tests/output/").unwrap();
+        let mut output =
+            AVFormatContextOutput::create(cstr!("tests/output/stream_accessors.mp4"), None)
+                .unwrap();
+        let stream = output.new_stream();
+        assert_eq!(stream.start_time(), None);
+        assert_eq!(stream.duration(), None);
+        assert_eq!(stream.duration_secs(), None);
+        assert_eq!(stream.nb_frames(), None);
+    }
+
+    #[test]
+    fn test_open_options() {
+        // Recognized options are consumed, the others are left.
+        let mut options = Some(
+            AVDictionary::new(cstr!("probesize"), cstr!("100000"), 0).set(
+                cstr!("rtsp_transport"),
+                cstr!("tcp"),
+                0,
+            ),
+        );
+        let ifmt_ctx =
+            AVFormatContextInput::open(cstr!("tests/assets/vids/bear.mp4"), None, &mut options)
+                .unwrap();
+        assert_eq!(ifmt_ctx.probesize, 100000);
+        let options = options.unwrap();
+        let pairs: Vec<_> = options.pairs().collect();
+        assert_eq!(pairs, [(cstr!("rtsp_transport"), cstr!("tcp"))]);
+
+        let mut options = Some(AVDictionary::new(cstr!("probesize"), cstr!("100000"), 0));
+        AVFormatContextInput::open(cstr!("tests/assets/vids/bear.mp4"), None, &mut options)
+            .unwrap();
+        assert!(options.is_none());
+    }
+
+    #[test]
+    fn test_guess_codec() {
+        let mp4 = AVOutputFormat::guess_format(Some(cstr!("mp4")), None, None).unwrap();
+        let codec_id = mp4.guess_codec(ffi::AVMEDIA_TYPE_VIDEO, None).unwrap();
+        assert!(codec_id == ffi::AV_CODEC_ID_H264 || codec_id == ffi::AV_CODEC_ID_MPEG4);
+        assert_eq!(mp4.default_video_codec(), Some(codec_id));
+
+        let flac = AVOutputFormat::guess_format(None, Some(cstr!("foo.flac")), None).unwrap();
+        assert_eq!(
+            flac.guess_codec(ffi::AVMEDIA_TYPE_AUDIO, Some(cstr!("foo.flac"))),
+            Some(ffi::AV_CODEC_ID_FLAC)
+        );
+        assert_eq!(flac.default_audio_codec(), Some(ffi::AV_CODEC_ID_FLAC));
+    }
+
+    #[test]
+    fn test_output_format_accessors() {
+        let mp4 = AVOutputFormat::guess_format(Some(cstr!("mp4")), None, None).unwrap();
+        assert_eq!(mp4.name(), cstr!("mp4"));
+        assert_eq!(mp4.long_name(), Some(cstr!("MP4 (MPEG-4 Part 14)")));
+        assert_eq!(mp4.extensions(), Some(cstr!("mp4")));
+        assert_eq!(mp4.mime_type(), Some(cstr!("video/mp4")));
+        assert_eq!(mp4.default_audio_codec(), Some(ffi::AV_CODEC_ID_AAC));
+        assert!(mp4.flags().is_globalheader());
+        assert!(!mp4.flags().is_nofile());
+
+        let image2 = AVOutputFormat::guess_format(Some(cstr!("image2")), None, None).unwrap();
+        assert_eq!(image2.name(), cstr!("image2"));
+        assert_eq!(image2.default_audio_codec(), None);
+        assert!(image2.flags().is_nofile());
+        assert_ne!(mp4.flags().is_nofile(), image2.flags().is_nofile());
+    }
+
+    #[test]
+    fn test_supports_codec() {
+        let mp4 = AVOutputFormat::guess_format(Some(cstr!("mp4")), None, None).unwrap();
+        assert_eq!(
+            mp4.supports_codec(ffi::AV_CODEC_ID_H264, Compliance::Normal),
+            Some(true)
+        );
+        assert!(mp4
+            .check_codec(ffi::AV_CODEC_ID_H264, Compliance::Normal)
+            .is_ok());
+
+        let wav = AVOutputFormat::guess_format(Some(cstr!("wav")), None, None).unwrap();
+        assert_eq!(
+            wav.supports_codec(ffi::AV_CODEC_ID_H264, Compliance::Normal),
+            Some(false)
+        );
+        assert_eq!(
+            wav.check_codec(ffi::AV_CODEC_ID_H264, Compliance::Normal),
+            Err(RsmpegError::CodecNotSupportedError(
+                "h264".to_string(),
+                "wav".to_string()
+            ))
+        );
+
+        // The flac muxer has neither a codec tag list nor a query function.
+        let flac = AVOutputFormat::guess_format(Some(cstr!("flac")), None, None).unwrap();
+        assert_eq!(
+            flac.supports_codec(ffi::AV_CODEC_ID_H264, Compliance::Normal),
+            None
+        );
+    }
 }
@@ -1,14 +1,28 @@
 use std::{
     ffi::CStr,
+    io::{self, Read, Seek, SeekFrom, Write},
+    iter::FusedIterator,
     ops::Deref,
+    panic::{self, AssertUnwindSafe},
     ptr::{self, NonNull},
     slice,
+    sync::{Arc, Mutex},
 };
 
-use crate::{avutil::AVMem, error::*, ffi, shared::*};
+use crate::{
+    avutil::{AVDictionary, AVMem},
+    error::*,
+    ffi,
+    shared::*,
+};
 
 wrap!(AVIOContext: ffi::AVIOContext);
 
+// `whence` values of `fseek()`, the same on all the supported platforms.
+const SEEK_SET: i32 = 0;
+const SEEK_CUR: i32 = 1;
+const SEEK_END: i32 = 2;
+
 pub struct AVIOContextURL(AVIOContext);
 
 impl Deref for AVIOContextURL {
@@ -38,6 +52,16 @@ impl AVIOContextURL {
     }
 }
 
+impl AVIOContextURL {
+    /// Flush and close the context, returning the error of the last writes
+    /// that dropping it would panic on.
+    pub fn close(self) -> Result<()> {
+        let mut this = std::mem::ManuallyDrop::new(self);
+        unsafe { ffi::avio_close(this.as_mut_ptr()) }.upgrade()?;
+        Ok(())
+    }
+}
+
 impl Drop for AVIOContextURL {
     fn drop(&mut self) {
         unsafe { ffi::avio_close(self.as_mut_ptr()) }
@@ -97,26 +121,41 @@ impl AVIOContextCustom {
         let (read_packet_c, write_packet_c, seek_c) = {
             use std::os::raw::c_void;
             // Function is called when the function is given and opaque is not null.
+            //
+            // Panics of the callbacks must not unwind into FFmpeg, they are
+            // reported as `AVERROR_EXTERNAL`.
             unsafe extern "C" fn read_c(opaque: *mut c_void, data: *mut u8, len: i32) -> i32 {
                 let buf = unsafe { slice::from_raw_parts_mut(data, len as usize) };
                 let opaque = unsafe { (opaque as *mut Opaque).as_mut() }.unwrap();
-                opaque.read_packet.as_mut().unwrap()(&mut opaque.data, buf)
+                panic::catch_unwind(AssertUnwindSafe(|| {
+                    opaque.read_packet.as_mut().unwrap()(&mut opaque.data, buf)
+                }))
+                .unwrap_or(ffi::AVERROR_EXTERNAL)
             }
             #[cfg(not(feature = "ffmpeg7"))]
             unsafe extern "C" fn write_c(opaque: *mut c_void, data: *mut u8, len: i32) -> i32 {
                 let buf = unsafe { slice::from_raw_parts(data, len as usize) };
                 let opaque = unsafe { (opaque as *mut Opaque).as_mut() }.unwrap();
-                opaque.write_packet.as_mut().unwrap()(&mut opaque.data, buf)
+                panic::catch_unwind(AssertUnwindSafe(|| {
+                    opaque.write_packet.as_mut().unwrap()(&mut opaque.data, buf)
+                }))
+                .unwrap_or(ffi::AVERROR_EXTERNAL)
             }
             #[cfg(feature = "ffmpeg7")]
             unsafe extern "C" fn write_c(opaque: *mut c_void, data: *const u8, len: i32) -> i32 {
                 let buf = unsafe { slice::from_raw_parts(data, len as usize) };
                 let opaque = unsafe { (opaque as *mut Opaque).as_mut() }.unwrap();
-                opaque.write_packet.as_mut().unwrap()(&mut opaque.data, buf)
+                panic::catch_unwind(AssertUnwindSafe(|| {
+                    opaque.write_packet.as_mut().unwrap()(&mut opaque.data, buf)
+                }))
+                .unwrap_or(ffi::AVERROR_EXTERNAL)
             }
             unsafe extern "C" fn seek_c(opaque: *mut c_void, offset: i64, whence: i32) -> i64 {
                 let opaque = unsafe { (opaque as *mut Opaque).as_mut() }.unwrap();
-                opaque.seek.as_mut().unwrap()(&mut opaque.data, offset, whence)
+                panic::catch_unwind(AssertUnwindSafe(|| {
+                    opaque.seek.as_mut().unwrap()(&mut opaque.data, offset, whence)
+                }))
+                .unwrap_or(ffi::AVERROR_EXTERNAL as i64)
             }
 
             (
@@ -161,6 +200,68 @@ impl AVIOContextCustom {
         }
     }
 
+    /// Create a read-only context reading from `reader` with seeking
+    /// supported, so inputs needing random access(e.g. MP4 with the `moov`
+    /// atom at the end) can be opened.
+    ///
+    /// `AVSEEK_SIZE` queries are answered by seeking to the end and back, the
+    /// size is cached afterwards, so the source shouldn't grow. Seek errors of
+    /// kind [`io::ErrorKind::Unsupported`] are reported as `AVERROR(ESPIPE)`.
+    pub fn from_read_seek(reader: impl Read + Seek + Send + 'static) -> Self {
+        let reader = Arc::new(Mutex::new(reader));
+
+        let read_packet: ReadPacketCallback = {
+            let reader = reader.clone();
+            Box::new(move |_, buf| loop {
+                match reader.lock().unwrap().read(buf) {
+                    Ok(0) => return ffi::AVERROR_EOF,
+                    Ok(len) => return len as i32,
+                    Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                    Err(e) => return io_error_code(e),
+                }
+            })
+        };
+
+        let seek = seek_callback(reader, true);
+        Self::alloc_context(
+            AVMem::new(4096),
+            false,
+            vec![],
+            Some(read_packet),
+            None,
+            Some(seek),
+        )
+    }
+
+    /// Create a write-only context writing to `writer` with seeking
+    /// supported, so muxers going back to patch headers(e.g. MP4 writing the
+    /// `moov` atom, WAV writing its sizes) can be used.
+    ///
+    /// Seeking behaves as in [`Self::from_read_seek()`], except the size
+    /// queried by `AVSEEK_SIZE` is not cached as the output grows.
+    pub fn from_write_seek(writer: impl Write + Seek + Send + 'static) -> Self {
+        let writer = Arc::new(Mutex::new(writer));
+
+        let write_packet: WritePacketCallback = {
+            let writer = writer.clone();
+            Box::new(move |_, buf| match writer.lock().unwrap().write_all(buf) {
+                Ok(()) => buf.len() as i32,
+                Err(e) => io_error_code(e),
+            })
+        };
+
+        let seek = seek_callback(writer, false);
+
+        Self::alloc_context(
+            AVMem::new(4096),
+            true,
+            vec![],
+            None,
+            Some(write_packet),
+            Some(seek),
+        )
+    }
+
     /// Re-take the ownership of the `data` passed in `alloc_context`.
     /// The `data` inside this will be set to an empty vector.
     pub fn take_data(&mut self) -> Vec<u8> {
@@ -173,6 +274,55 @@ impl AVIOContextCustom {
     }
 }
 
+/// Seek callback of `stream`, answering `AVSEEK_SIZE` by seeking to the end
+/// and back, the size is cached if `cache_size` is set.
+fn seek_callback<S: Seek + Send + 'static>(
+    stream: Arc<Mutex<S>>,
+    cache_size: bool,
+) -> SeekCallback {
+    let mut size = None;
+    Box::new(move |_, offset, whence| {
+        let mut stream = stream.lock().unwrap();
+        let result = match whence & !(ffi::AVSEEK_FORCE as i32) {
+            whence if whence == ffi::AVSEEK_SIZE as i32 => match size {
+                Some(size) => Ok(size),
+                None => stream
+                    .stream_position()
+                    .and_then(|position| {
+                        let end = stream.seek(SeekFrom::End(0))?;
+                        stream.seek(SeekFrom::Start(position))?;
+                        Ok(end)
+                    })
+                    .map(|end| {
+                        if cache_size {
+                            size = Some(end);
+                        }
+                        end
+                    }),
+            },
+            SEEK_SET => match u64::try_from(offset) {
+                Ok(offset) => stream.seek(SeekFrom::Start(offset)),
+                Err(_) => return ffi::AVERROR(ffi::EINVAL) as i64,
+            },
+            SEEK_CUR => stream.seek(SeekFrom::Current(offset)),
+            SEEK_END => stream.seek(SeekFrom::End(offset)),
+            _ => return ffi::AVERROR(ffi::EINVAL) as i64,
+        };
+        match result {
+            Ok(position) => position as i64,
+            Err(e) if e.kind() == io::ErrorKind::Unsupported => ffi::AVERROR(ffi::ESPIPE) as i64,
+            Err(e) => io_error_code(e) as i64,
+        }
+    })
+}
+
+/// FFmpeg error code of an IO error.
+pub(crate) fn io_error_code(err: io::Error) -> i32 {
+    RsmpegError::from(err)
+        .raw_error()
+        .unwrap_or(ffi::AVERROR_EXTERNAL)
+}
+
 impl Drop for AVIOContextCustom {
     fn drop(&mut self) {
         // Recover the `AVMem` fom the buffer and drop it. We don't attach the
@@ -187,3 +337,192 @@ impl Drop for AVIOContextCustom {
         unsafe { ffi::avio_context_free(&mut self.as_mut_ptr()) };
     }
 }
+
+/// Check whether `url` can be accessed with `flags`, `AVIO_FLAG_READ` and/or
+/// `AVIO_FLAG_WRITE`, without opening it. Return the flags of the access
+/// allowed, e.g. 0 if the file exists but is read-only and `flags` is
+/// `AVIO_FLAG_WRITE`.
+///
+/// Local files are checked by their permissions, the protocols that can't
+/// check cheaply, e.g. HTTP, open the resource.
+pub fn avio_check(url: &CStr, flags: u32) -> Result<u32> {
+    unsafe { ffi::avio_check(url.as_ptr(), flags as _) }
+        .upgrade()
+        .map(|x| x as u32)
+        .map_err(RsmpegError::AVError)
+}
+
+/// Get the name of the protocol handling `url`, e.g. `file` or `https`.
+/// Return `None` if no protocol of the linked FFmpeg supports it.
+pub fn avio_find_protocol_name(url: &CStr) -> Option<&'static CStr> {
+    unsafe { ffi::avio_find_protocol_name(url.as_ptr()) }
+        .upgrade()
+        .map(|x| unsafe { CStr::from_ptr(x.as_ptr()) })
+}
+
+/// Iterate over the names of the protocols the linked FFmpeg supports for
+/// reading, or for writing if `output` is true.
+pub fn avio_protocols(output: bool) -> AVIOProtocolIter {
+    AVIOProtocolIter {
+        opaque: ptr::null_mut(),
+        output,
+        done: false,
+    }
+}
+
+/// Iterator of the protocol names, created by [`avio_protocols()`].
+pub struct AVIOProtocolIter {
+    opaque: *mut std::os::raw::c_void,
+    output: bool,
+    done: bool,
+}
+
+impl Iterator for AVIOProtocolIter {
+    type Item = &'static CStr;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        // The state is reset at the end, another call would restart.
+        let Some(name) =
+            unsafe { ffi::avio_enum_protocols(&mut self.opaque, self.output as _) }.upgrade()
+        else {
+            self.done = true;
+            return None;
+        };
+        Some(unsafe { CStr::from_ptr(name.as_ptr()) })
+    }
+}
+
+impl FusedIterator for AVIOProtocolIter {}
+
+/// Type of a directory entry, see [`AVIODirEntry::entry_type()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DirEntryType {
+    Unknown,
+    BlockDevice,
+    CharacterDevice,
+    Directory,
+    NamedPipe,
+    SymbolicLink,
+    Socket,
+    File,
+    /// A server, e.g. listed by the `smb` protocol.
+    Server,
+    Share,
+    Workgroup,
+}
+
+wrap!(AVIODirEntry: ffi::AVIODirEntry);
+
+impl AVIODirEntry {
+    /// Get the file name of the entry, without its directory.
+    pub fn name(&self) -> &CStr {
+        unsafe { CStr::from_ptr(self.name) }
+    }
+
+    pub fn entry_type(&self) -> DirEntryType {
+        match self.type_ as ffi::AVIODirEntryType {
+            ffi::AVIO_ENTRY_BLOCK_DEVICE => DirEntryType::BlockDevice,
+            ffi::AVIO_ENTRY_CHARACTER_DEVICE => DirEntryType::CharacterDevice,
+            ffi::AVIO_ENTRY_DIRECTORY => DirEntryType::Directory,
+            ffi::AVIO_ENTRY_NAMED_PIPE => DirEntryType::NamedPipe,
+            ffi::AVIO_ENTRY_SYMBOLIC_LINK => DirEntryType::SymbolicLink,
+            ffi::AVIO_ENTRY_SOCKET => DirEntryType::Socket,
+            ffi::AVIO_ENTRY_FILE => DirEntryType::File,
+            ffi::AVIO_ENTRY_SERVER => DirEntryType::Server,
+            ffi::AVIO_ENTRY_SHARE => DirEntryType::Share,
+            ffi::AVIO_ENTRY_WORKGROUP => DirEntryType::Workgroup,
+            _ => DirEntryType::Unknown,
+        }
+    }
+
+    /// Get the size in bytes, `None` if unknown.
+    pub fn size(&self) -> Option<u64> {
+        u64::try_from(self.size).ok()
+    }
+
+    /// Get the time of the last modification in microseconds since the Unix
+    /// epoch, `None` if unknown.
+    pub fn modification_time_us(&self) -> Option<i64> {
+        (self.modification_timestamp >= 0).then_some(self.modification_timestamp)
+    }
+}
+
+impl Drop for AVIODirEntry {
+    fn drop(&mut self) {
+        let mut entry = self.as_mut_ptr();
+        unsafe { ffi::avio_free_directory_entry(&mut entry) }
+    }
+}
+
+/// Iterator of the entries of a directory, whichever the protocol: local
+/// directories, `sftp`, `smb`, etc. The order of the entries is the one of
+/// the protocol, `.` and `..` may be listed.
+pub struct AVIODirIterator {
+    context: NonNull<ffi::AVIODirContext>,
+    done: bool,
+}
+
+impl AVIODirIterator {
+    /// Open the directory at `url` for listing, `options` are the ones of the
+    /// protocol and are replaced by the ones it didn't use.
+    ///
+    /// Return [`RsmpegError::DirListUnsupportedError`] if the protocol can't
+    /// list directories, e.g. `pipe`.
+    pub fn open(url: &CStr, options: &mut Option<AVDictionary>) -> Result<Self> {
+        let mut context = ptr::null_mut();
+        let mut options_ptr = options
+            .as_mut()
+            .map(|x| x.as_mut_ptr())
+            .unwrap_or_else(ptr::null_mut);
+        let ret = unsafe { ffi::avio_open_dir(&mut context, url.as_ptr(), &mut options_ptr) };
+
+        // The protocol may replace the options even when failing later.
+        let mut new_options = options_ptr
+            .upgrade()
+            .map(|x| unsafe { AVDictionary::from_raw(x) });
+        std::mem::swap(options, &mut new_options);
+        std::mem::forget(new_options);
+
+        match ret.upgrade() {
+            Ok(_) => Ok(Self {
+                context: NonNull::new(context).unwrap(),
+                done: false,
+            }),
+            Err(x) if x == ffi::AVERROR(ffi::ENOSYS) => Err(RsmpegError::DirListUnsupportedError),
+            Err(x) => Err(RsmpegError::AVError(x)),
+        }
+    }
+}
+
+impl Iterator for AVIODirIterator {
+    type Item = Result<AVIODirEntry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let mut entry = ptr::null_mut();
+        if let Err(e) = unsafe { ffi::avio_read_dir(self.context.as_ptr(), &mut entry) }.upgrade() {
+            self.done = true;
+            return Some(Err(RsmpegError::AVError(e)));
+        }
+        // Null once all the entries are read.
+        let Some(entry) = entry.upgrade() else {
+            self.done = true;
+            return None;
+        };
+        Some(Ok(unsafe { AVIODirEntry::from_raw(entry) }))
+    }
+}
+
+impl FusedIterator for AVIODirIterator {}
+
+impl Drop for AVIODirIterator {
+    fn drop(&mut self) {
+        let mut context = self.context.as_ptr();
+        unsafe { ffi::avio_close_dir(&mut context) };
+    }
+}
@@ -0,0 +1,106 @@
+use crate::{
+    avformat::{io_error_code, AVIOContextCustom},
+    avutil::AVMem,
+    ffi,
+};
+use std::{io, ops::Deref};
+use tokio::{
+    io::{AsyncRead, AsyncReadExt},
+    runtime::Handle,
+    sync::mpsc,
+    task::JoinHandle,
+};
+
+/// Size of each chunk read from the async reader.
+const CHUNK_SIZE: usize = 4096;
+/// Number of chunks buffered ahead of the demuxer.
+const CHUNK_COUNT: usize = 16;
+
+/// Read-only [`AVIOContextCustom`] pulling bytes from an [`AsyncRead`].
+///
+/// A task spawned on the given runtime reads the source into a bounded
+/// channel, the read callback blocks on that channel. FFmpeg calls using this
+/// context stay blocking, so run them outside of async contexts, e.g. in
+/// [`tokio::task::spawn_blocking`], blocking on the channel inside a runtime
+/// thread panics.
+///
+/// The context is not seekable. Dropping it(or the format context owning it)
+/// aborts the reading task, a pending read never blocks the drop.
+pub struct AVIOContextAsync {
+    inner: AVIOContextCustom,
+    task: JoinHandle<()>,
+}
+
+impl Deref for AVIOContextAsync {
+    type Target = AVIOContextCustom;
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl std::ops::DerefMut for AVIOContextAsync {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.inner
+    }
+}
+
+impl AVIOContextAsync {
+    /// Create a context reading from `reader`, the reading task is spawned on
+    /// `runtime`.
+    pub fn from_async_read(
+        mut reader: impl AsyncRead + Send + Unpin + 'static,
+        runtime: &Handle,
+    ) -> Self {
+        let (sender, mut receiver) = mpsc::channel::<io::Result<Vec<u8>>>(CHUNK_COUNT);
+        let task = runtime.spawn(async move {
+            loop {
+                let mut chunk = vec![0; CHUNK_SIZE];
+                let result = match reader.read(&mut chunk).await {
+                    // End of stream, closing the channel.
+                    Ok(0) => break,
+                    Ok(len) => {
+                        chunk.truncate(len);
+                        Ok(chunk)
+                    }
+                    Err(e) => Err(e),
+                };
+                let failed = result.is_err();
+                // The receiver is dropped with the context.
+                if sender.send(result).await.is_err() || failed {
+                    break;
+                }
+            }
+        });
+
+        // `data` holds the part of the last chunk not consumed yet.
+        let read_packet = Box::new(move |data: &mut Vec<u8>, buf: &mut [u8]| {
+            if data.is_empty() {
+                match receiver.blocking_recv() {
+                    Some(Ok(chunk)) => *data = chunk,
+                    Some(Err(e)) => return io_error_code(e),
+                    None => return ffi::AVERROR_EOF,
+                }
+            }
+            let len = buf.len().min(data.len());
+            buf[..len].copy_from_slice(&data[..len]);
+            data.drain(..len);
+            len as i32
+        });
+
+        let inner = AVIOContextCustom::alloc_context(
+            AVMem::new(CHUNK_SIZE),
+            false,
+            vec![],
+            Some(read_packet),
+            None,
+            None,
+        );
+        Self { inner, task }
+    }
+}
+
+impl Drop for AVIOContextAsync {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
@@ -0,0 +1,109 @@
+use crate::avutil::AVDictionary;
+use std::ffi::{CStr, CString};
+
+/// Typed options of the `hls` muxer, used by
+/// [`AVFormatContextOutput::open_hls()`](crate::avformat::AVFormatContextOutput::open_hls).
+///
+/// Options not set here keep the muxer defaults.
+#[derive(Debug, Clone, Default)]
+pub struct HlsOutputOptions {
+    hls_time: Option<f64>,
+    hls_list_size: Option<u32>,
+    hls_segment_filename: Option<CString>,
+    hls_flags: Option<CString>,
+}
+
+impl HlsOutputOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Target segment duration in seconds (`hls_time`).
+    pub fn hls_time(mut self, seconds: f64) -> Self {
+        self.hls_time = Some(seconds);
+        self
+    }
+
+    /// Maximum number of playlist entries (`hls_list_size`), 0 keeps all the
+    /// segments.
+    pub fn hls_list_size(mut self, size: u32) -> Self {
+        self.hls_list_size = Some(size);
+        self
+    }
+
+    /// Segment filename pattern (`hls_segment_filename`), e.g.
+    /// `out/segment_%03d.ts`. The pattern is passed to the muxer untouched, so
+    /// strftime patterns work together with the `second_level_segment_*`
+    /// flags and `strftime=1`.
+    pub fn hls_segment_filename(mut self, pattern: &CStr) -> Self {
+        self.hls_segment_filename = Some(pattern.to_owned());
+        self
+    }
+
+    /// Muxer flags (`hls_flags`), e.g. `independent_segments+program_date_time`.
+    pub fn hls_flags(mut self, flags: &CStr) -> Self {
+        self.hls_flags = Some(flags.to_owned());
+        self
+    }
+
+    /// Convert the options into an [`AVDictionary`], return `None` if no option
+    /// is set.
+    pub fn into_dict(self) -> Option<AVDictionary> {
+        let mut entries = vec![];
+        if let Some(time) = self.hls_time {
+            entries.push((
+                CString::new("hls_time").unwrap(),
+                CString::new(time.to_string()).unwrap(),
+            ));
+        }
+        if let Some(size) = self.hls_list_size {
+            entries.push((
+                CString::new("hls_list_size").unwrap(),
+                CString::new(size.to_string()).unwrap(),
+            ));
+        }
+        if let Some(pattern) = self.hls_segment_filename {
+            entries.push((CString::new("hls_segment_filename").unwrap(), pattern));
+        }
+        if let Some(flags) = self.hls_flags {
+            entries.push((CString::new("hls_flags").unwrap(), flags));
+        }
+        entries
+            .into_iter()
+            .fold(None, |dict: Option<AVDictionary>, (key, value)| {
+                Some(match dict {
+                    Some(dict) => dict.set(&key, &value, 0),
+                    None => AVDictionary::new(&key, &value, 0),
+                })
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cstr::cstr;
+
+    #[test]
+    fn test_hls_options_into_dict() {
+        assert!(HlsOutputOptions::new().into_dict().is_none());
+
+        let dict = HlsOutputOptions::new()
+            .hls_time(2.)
+            .hls_list_size(0)
+            .hls_segment_filename(cstr!("seg_%Y%m%d-%s.ts"))
+            .hls_flags(cstr!("second_level_segment_index"))
+            .into_dict()
+            .unwrap();
+        assert_eq!(dict.get_all(cstr!("hls_time")), [cstr!("2")]);
+        assert_eq!(dict.get_all(cstr!("hls_list_size")), [cstr!("0")]);
+        assert_eq!(
+            dict.get_all(cstr!("hls_segment_filename")),
+            [cstr!("seg_%Y%m%d-%s.ts")]
+        );
+        assert_eq!(
+            dict.get_all(cstr!("hls_flags")),
+            [cstr!("second_level_segment_index")]
+        );
+    }
+}
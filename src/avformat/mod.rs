@@ -1,6 +1,14 @@
 //! Everything related to `libavformat`.
 mod avformat;
 mod avio;
+#[cfg(feature = "async-io")]
+mod avio_async;
+mod hls;
+mod network;
 
 pub use avformat::*;
 pub use avio::*;
+#[cfg(feature = "async-io")]
+pub use avio_async::*;
+pub use hls::*;
+pub use network::*;
@@ -1,4 +1,9 @@
-use crate::{error::*, ffi, shared::*};
+use crate::{
+    avutil::{AVFrame, AVSamples, SampleFormat},
+    error::*,
+    ffi,
+    shared::*,
+};
 
 wrap!(
     /// Context for an Audio FIFO Buffer.
@@ -6,16 +11,122 @@ wrap!(
     /// - Operates at the sample level rather than the byte level.
     /// - Supports multiple channels with either planar or packed sample format.
     /// - Automatic reallocation when writing to a full buffer.
-    AVAudioFifo: ffi::AVAudioFifo
+    ///
+    /// `sample_fmt` and `nb_channels` are the ones the fifo is allocated with,
+    /// the safe methods check the frames and samples against them.
+    AVAudioFifo: ffi::AVAudioFifo,
+    sample_fmt: SampleFormat = SampleFormat(ffi::AV_SAMPLE_FMT_NONE),
+    nb_channels: i32 = 0,
 );
 
 impl AVAudioFifo {
     /// Allocate an AVAudioFifo.
-    pub fn new(sample_fmt: ffi::AVSampleFormat, channels: i32, nb_samples: i32) -> Self {
-        let fifo = unsafe { ffi::av_audio_fifo_alloc(sample_fmt, channels, nb_samples) }
+    pub fn new(sample_fmt: impl Into<SampleFormat>, channels: i32, nb_samples: i32) -> Self {
+        let sample_fmt = sample_fmt.into();
+        let fifo = unsafe { ffi::av_audio_fifo_alloc(sample_fmt.0, channels, nb_samples) }
             .upgrade()
             .unwrap();
-        unsafe { Self::from_raw(fifo) }
+        let mut fifo = unsafe { Self::from_raw(fifo) };
+        fifo.sample_fmt = sample_fmt;
+        fifo.nb_channels = channels;
+        fifo
+    }
+
+    /// Get the sample format the fifo is allocated with.
+    pub fn sample_format(&self) -> SampleFormat {
+        self.sample_fmt
+    }
+
+    /// Get the number of channels the fifo is allocated with.
+    pub fn nb_channels(&self) -> i32 {
+        self.nb_channels
+    }
+
+    /// Return `AVERROR(EINVAL)` if `sample_fmt` and `nb_channels` differ from
+    /// the ones of the fifo.
+    fn check_params(&self, sample_fmt: ffi::AVSampleFormat, nb_channels: i32) -> Result<()> {
+        if (sample_fmt, nb_channels) != (self.sample_fmt.0, self.nb_channels) {
+            return Err(RsmpegError::AVError(ffi::AVERROR(ffi::EINVAL)));
+        }
+        Ok(())
+    }
+
+    /// Write all the samples of an audio `frame`, the fifo grows if needed.
+    ///
+    /// Returns `AVERROR(EINVAL)` if the sample format or the channel count of
+    /// `frame` differs from the ones of the fifo, or if the buffers of `frame`
+    /// can't hold `nb_samples`.
+    pub fn write_frame(&mut self, frame: &AVFrame) -> Result<()> {
+        self.check_params(frame.format, frame.ch_layout.nb_channels)?;
+        if frame.nb_samples == 0 {
+            return Ok(());
+        }
+        // `nb_samples` can be set past the end of the buffers.
+        if frame
+            .samples_capacity()
+            .map_or(true, |capacity| capacity < frame.nb_samples)
+        {
+            return Err(RsmpegError::AVError(ffi::AVERROR(ffi::EINVAL)));
+        }
+        unsafe { self.write(frame.extended_data, frame.nb_samples) }
+    }
+
+    /// Write the first `nb_samples` samples of `samples`, the fifo grows if
+    /// needed.
+    ///
+    /// Returns `AVERROR(EINVAL)` if the sample format or the channel count of
+    /// `samples` differs from the ones of the fifo, or if `samples` or its
+    /// buffer holds fewer than `nb_samples`.
+    pub fn write_samples(&mut self, samples: &AVSamples, nb_samples: i32) -> Result<()> {
+        self.check_params(samples.sample_fmt, samples.nb_channels)?;
+        let capacity = samples
+            .capacity()
+            .ok_or(RsmpegError::AVError(ffi::AVERROR(ffi::EINVAL)))?;
+        if !(0..=samples.nb_samples.min(capacity)).contains(&nb_samples) {
+            return Err(RsmpegError::AVError(ffi::AVERROR(ffi::EINVAL)));
+        }
+        unsafe { self.write(samples.audio_data.as_ptr(), nb_samples) }
+    }
+
+    /// Read up to `frame.nb_samples` samples into an allocated audio `frame`,
+    /// which is made writable first, bounded by the size of its buffers.
+    /// `frame.nb_samples` is set to the number of samples read, which is
+    /// returned.
+    ///
+    /// Returns `AVERROR(EINVAL)` if the sample format or the channel count of
+    /// `frame` differs from the ones of the fifo, or if `frame` is not
+    /// allocated.
+    pub fn read_frame(&mut self, frame: &mut AVFrame) -> Result<i32> {
+        self.check_params(frame.format, frame.ch_layout.nb_channels)?;
+        if !frame.is_allocated() {
+            return Err(RsmpegError::AVError(ffi::AVERROR(ffi::EINVAL)));
+        }
+        frame.make_writable()?;
+        let capacity = frame
+            .samples_capacity()
+            .ok_or(RsmpegError::AVError(ffi::AVERROR(ffi::EINVAL)))?;
+        let nb_samples = unsafe { self.read(frame.extended_data, frame.nb_samples.min(capacity)) }?;
+        frame.set_nb_samples(nb_samples);
+        Ok(nb_samples)
+    }
+
+    /// Read up to `samples.nb_samples` samples into `samples`, bounded by the
+    /// size of its buffer, return the number of samples read.
+    ///
+    /// Returns `AVERROR(EINVAL)` if the sample format or the channel count of
+    /// `samples` differs from the ones of the fifo, or if its planes point out
+    /// of its buffer.
+    pub fn read_samples(&mut self, samples: &mut AVSamples) -> Result<i32> {
+        self.check_params(samples.sample_fmt, samples.nb_channels)?;
+        let capacity = samples
+            .capacity()
+            .ok_or(RsmpegError::AVError(ffi::AVERROR(ffi::EINVAL)))?;
+        unsafe {
+            self.read(
+                samples.audio_data.as_ptr(),
+                samples.nb_samples.min(capacity),
+            )
+        }
     }
 
     /// Reallocate an AVAudioFifo.
@@ -120,3 +231,117 @@ impl Drop for AVAudioFifo {
         unsafe { ffi::av_audio_fifo_free(self.as_mut_ptr()) }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::avutil::AVChannelLayout;
+    use std::ptr;
+
+    fn audio_frame(nb_samples: i32) -> AVFrame {
+        let mut frame = AVFrame::new();
+        frame.set_ch_layout(AVChannelLayout::from_nb_channels(2));
+        frame.set_sample_rate(48000);
+        frame.set_format(ffi::AV_SAMPLE_FMT_FLTP);
+        frame.set_nb_samples(nb_samples);
+        frame.alloc_buffer().unwrap();
+        frame
+    }
+
+    #[test]
+    fn test_frame_size_adaptation() {
+        let mut fifo = AVAudioFifo::new(ffi::AV_SAMPLE_FMT_FLTP, 2, 1);
+
+        // Decoded frames of variable sizes, sample `i` of the stream is `i`.
+        let mut nb_written = 0;
+        let mut nb_read = 0;
+        let check = |frame: &AVFrame, nb_read: &mut i32| {
+            for ch in 0..2 {
+                let samples = frame.channel_samples::<f32>(ch).unwrap();
+                for (i, sample) in samples.iter().enumerate() {
+                    assert_eq!(*sample, (*nb_read + i as i32) as f32);
+                }
+            }
+            *nb_read += frame.nb_samples;
+        };
+        for nb_samples in [1152, 576, 2048, 100, 1024, 333].repeat(4) {
+            let mut frame = audio_frame(nb_samples);
+            for ch in 0..2 {
+                for (i, sample) in frame
+                    .channel_samples_mut::<f32>(ch)
+                    .unwrap()
+                    .iter_mut()
+                    .enumerate()
+                {
+                    *sample = (nb_written + i as i32) as f32;
+                }
+            }
+            fifo.write_frame(&frame).unwrap();
+            nb_written += nb_samples;
+
+            // Encoder frames of exactly 1024 samples.
+            while fifo.size() >= 1024 {
+                let mut frame = audio_frame(1024);
+                assert_eq!(fifo.read_frame(&mut frame).unwrap(), 1024);
+                check(&frame, &mut nb_read);
+            }
+        }
+        // The last frame is shorter.
+        let remaining = fifo.size();
+        assert!(remaining > 0 && remaining < 1024);
+        let mut frame = audio_frame(1024);
+        assert_eq!(fifo.read_frame(&mut frame).unwrap(), remaining);
+        assert_eq!(frame.nb_samples, remaining);
+        check(&frame, &mut nb_read);
+
+        assert_eq!(fifo.size(), 0);
+        assert_eq!(nb_read, nb_written);
+    }
+
+    #[test]
+    fn test_mismatched_params() {
+        let mut fifo = AVAudioFifo::new("s16".parse::<SampleFormat>().unwrap(), 2, 1024);
+        assert_eq!(fifo.sample_format(), SampleFormat(ffi::AV_SAMPLE_FMT_S16));
+        assert_eq!(fifo.nb_channels(), 2);
+        let einval = Err(RsmpegError::AVError(ffi::AVERROR(ffi::EINVAL)));
+        let mut frame = audio_frame(1024);
+        assert_eq!(fifo.write_frame(&frame), einval);
+        assert_eq!(fifo.read_frame(&mut frame).map(|_| ()), einval);
+
+        let mut samples = AVSamples::new(1, 1024, ffi::AV_SAMPLE_FMT_S16, 0).unwrap();
+        assert_eq!(fifo.write_samples(&samples, 1024), einval);
+        assert_eq!(fifo.read_samples(&mut samples).map(|_| ()), einval);
+
+        let mut samples = AVSamples::new(2, 1024, ffi::AV_SAMPLE_FMT_S16, 0).unwrap();
+        assert_eq!(fifo.write_samples(&samples, 1025), einval);
+        fifo.write_samples(&samples, 1000).unwrap();
+        assert_eq!(fifo.read_samples(&mut samples), Ok(1000));
+        assert_eq!(fifo.size(), 0);
+    }
+
+    #[test]
+    fn test_buffer_bounds() {
+        let mut fifo = AVAudioFifo::new(ffi::AV_SAMPLE_FMT_FLTP, 2, 1024);
+        let einval = Err(RsmpegError::AVError(ffi::AVERROR(ffi::EINVAL)));
+
+        // `nb_samples` past the end of the buffers.
+        let mut frame = audio_frame(1024);
+        frame.set_nb_samples(1 << 20);
+        assert_eq!(fifo.write_frame(&frame), einval);
+        fifo.write_frame(&audio_frame(1024)).unwrap();
+        fifo.write_frame(&audio_frame(1024)).unwrap();
+        let capacity = frame.samples_capacity().unwrap();
+        assert!((1024..2048).contains(&capacity));
+        assert_eq!(fifo.read_frame(&mut frame), Ok(capacity));
+        fifo.reset();
+
+        let mut samples = AVSamples::new(2, 1024, ffi::AV_SAMPLE_FMT_FLTP, 1).unwrap();
+        samples.nb_samples = 1 << 20;
+        assert_eq!(fifo.write_samples(&samples, 1025), einval);
+        fifo.write_samples(&samples, 1024).unwrap();
+        fifo.write_samples(&samples, 1024).unwrap();
+        assert_eq!(fifo.read_samples(&mut samples), Ok(1024));
+        samples.audio_data[1] = ptr::null_mut();
+        assert_eq!(fifo.read_samples(&mut samples), einval);
+    }
+}
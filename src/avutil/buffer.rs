@@ -2,31 +2,58 @@ use crate::{
     ffi,
     shared::{PointerUpgrade, RetUpgrade},
 };
-use std::os::raw::c_int;
+use std::{
+    any::TypeId,
+    mem,
+    os::raw::{c_int, c_void},
+    ptr, slice,
+    sync::atomic::{AtomicUsize, Ordering},
+};
 
 wrap!(AVBufferRef: ffi::AVBufferRef);
 
+/// Content of the buffers created by [`AVBufferRef::from_value()`], `repr(C)`
+/// puts `type_id` at the start, so it can be read before `T` is known.
+#[repr(C)]
+struct BufferValue<T> {
+    type_id: TypeId,
+    value: T,
+}
+
+/// Address passed as the `opaque` of the buffers created by
+/// [`AVBufferRef::from_value()`], telling them apart from buffers of other
+/// origins, which could hold anything.
+static BUFFER_VALUE_MARKER: u8 = 0;
+
+fn buffer_value_marker() -> *mut c_void {
+    &BUFFER_VALUE_MARKER as *const u8 as *mut c_void
+}
+
 impl AVBufferRef {
-    /// Allocate an AVBuffer of the given size using av_malloc().
+    /// Allocate an AVBuffer of the given size using av_malloc(). The buffer
+    /// is initialized to zero, its bytes are readable with [`Self::data()`].
     pub fn new(size: usize) -> Self {
-        // Safety: Only fail on OOM.
-        let ptr = unsafe { ffi::av_buffer_alloc(size) }.upgrade().unwrap();
-        unsafe { Self::from_raw(ptr) }
+        Self::zeroed(size)
     }
 
-    /// Same as [`Self::new()`], except the returned buffer will be initialized
-    /// to zero.
+    /// Same as [`Self::new()`].
     pub fn zeroed(size: usize) -> Self {
         // Safety: only fail on OOM.
         let ptr = unsafe { ffi::av_buffer_allocz(size) }.upgrade().unwrap();
         unsafe { Self::from_raw(ptr) }
     }
 
-    /// Reallocate a given buffer.
+    /// Reallocate a given buffer, the bytes added are initialized to zero.
     ///
     /// buf will be unreferenced and a new reference with the required size will
-    /// be written in its place.
+    /// be written in its place. A buffer created by [`Self::from_value()`] is
+    /// replaced by a zeroed one.
     pub fn realloc(&mut self, size: usize) {
+        if self.is_value() {
+            *self = Self::zeroed(size);
+            return;
+        }
+        let old_size = self.size;
         let mut ptr = self.as_mut_ptr();
         // Safety: Implementation checked, this function only fail on OOM.
         unsafe { ffi::av_buffer_realloc(&mut ptr, size) }
@@ -35,6 +62,9 @@ impl AVBufferRef {
         // Safety: only fail on OOM.
         let ptr = ptr.upgrade().unwrap();
         unsafe { self.set_ptr(ptr) }
+        if size > old_size {
+            unsafe { ptr::write_bytes(self.data.add(old_size), 0, size - old_size) }
+        }
     }
 
     /// Return true if the caller may write to the data referred to by buf (which is
@@ -53,8 +83,13 @@ impl AVBufferRef {
     /// if possible.
     ///
     /// self is either left untouched, or it is unreferenced and turned into new
-    /// writable [`AVBufferRef`].
+    /// writable [`AVBufferRef`]. The bytes of a buffer created by
+    /// [`Self::from_value()`] are not copied, the new buffer is zeroed.
     pub fn make_writable(&mut self) {
+        if self.is_value() {
+            *self = Self::zeroed(self.size);
+            return;
+        }
         let mut ptr = self.as_mut_ptr();
         // Safety: Implementation checked, this function only fails on OOM.
         unsafe { ffi::av_buffer_make_writable(&mut ptr) }
@@ -64,6 +99,121 @@ impl AVBufferRef {
         let ptr = ptr.upgrade().unwrap();
         unsafe { self.set_ptr(ptr) }
     }
+
+    /// Create a buffer over the bytes of `vec` without copying them. The
+    /// vector is dropped with the last reference to the buffer, on whichever
+    /// thread releasing it.
+    pub fn from_vec(vec: Vec<u8>) -> Self {
+        Self::from_owner(vec)
+    }
+
+    /// Same as [`Self::from_vec()`] for any owner of bytes, e.g. a buffer
+    /// allocated by another library. The buffer refers to the bytes of
+    /// `owner.as_mut()` at creation, `owner` is dropped with the last
+    /// reference to the buffer.
+    pub fn from_owner<T: AsMut<[u8]> + Send + 'static>(owner: T) -> Self {
+        unsafe extern "C" fn free<T>(opaque: *mut c_void, _data: *mut u8) {
+            let _ = unsafe { Box::from_raw(opaque as *mut T) };
+        }
+
+        // Boxed for the bytes not to move with the owner.
+        let owner = Box::into_raw(Box::new(owner));
+        let data = unsafe { (*owner).as_mut() };
+        // Only fails on OOM.
+        let buffer = unsafe {
+            ffi::av_buffer_create(
+                data.as_mut_ptr(),
+                data.len(),
+                Some(free::<T>),
+                owner as *mut c_void,
+                0,
+            )
+        }
+        .upgrade()
+        .unwrap();
+        unsafe { Self::from_raw(buffer) }
+    }
+
+    /// Get the bytes of the buffer. Return `None` for the buffers created by
+    /// [`Self::from_value()`], whose bytes are a Rust value.
+    pub fn data(&self) -> Option<&[u8]> {
+        if self.is_value() {
+            return None;
+        }
+        if self.data.is_null() || self.size == 0 {
+            return Some(&[]);
+        }
+        Some(unsafe { slice::from_raw_parts(self.data, self.size) })
+    }
+
+    /// Get the bytes of the buffer for writing. Return `None` if the buffer
+    /// is not writable, see [`Self::make_writable()`].
+    pub fn data_mut(&mut self) -> Option<&mut [u8]> {
+        if !self.is_writable() || self.is_value() {
+            return None;
+        }
+        if self.data.is_null() || self.size == 0 {
+            return Some(&mut []);
+        }
+        Some(unsafe { slice::from_raw_parts_mut(self.data, self.size) })
+    }
+
+    /// Create a read-only buffer owning `value`, it's dropped with the last
+    /// reference to the buffer, on whichever thread releasing it. References
+    /// may be shared across threads, hence `Send + Sync`.
+    pub fn from_value<T: Send + Sync + 'static>(value: T) -> Self {
+        unsafe extern "C" fn free<T>(_opaque: *mut c_void, data: *mut u8) {
+            let _ = unsafe { Box::from_raw(data as *mut BufferValue<T>) };
+        }
+
+        let data = Box::into_raw(Box::new(BufferValue {
+            type_id: TypeId::of::<T>(),
+            value,
+        }));
+        // Only fails on OOM.
+        let buffer = unsafe {
+            ffi::av_buffer_create(
+                data as *mut u8,
+                mem::size_of::<BufferValue<T>>(),
+                Some(free::<T>),
+                buffer_value_marker(),
+                ffi::AV_BUFFER_FLAG_READONLY as c_int,
+            )
+        }
+        .upgrade()
+        .unwrap();
+        unsafe { Self::from_raw(buffer) }
+    }
+
+    /// Get the value stored by [`Self::from_value()`]. Return `None` if the
+    /// buffer is not created by it or the value is not a `T`.
+    pub fn value<T: Send + Sync + 'static>(&self) -> Option<&T> {
+        unsafe { buffer_value(self.as_ptr()) }
+    }
+
+    /// Return true if the buffer is created by [`Self::from_value()`].
+    fn is_value(&self) -> bool {
+        unsafe { ffi::av_buffer_get_opaque(self.as_ptr()) == buffer_value_marker() }
+    }
+}
+
+/// Same as [`AVBufferRef::value()`], for buffers not owned by an
+/// [`AVBufferRef`], e.g. the `opaque_ref` of frames and packets.
+///
+/// # Safety
+/// `buffer` should be null or a valid buffer reference living for `'a`.
+pub(crate) unsafe fn buffer_value<'a, T: Send + Sync + 'static>(
+    buffer: *const ffi::AVBufferRef,
+) -> Option<&'a T> {
+    let buffer = unsafe { buffer.as_ref() }?;
+    // The data of buffers of other origins can't be interpreted at all.
+    if unsafe { ffi::av_buffer_get_opaque(buffer) } != buffer_value_marker() {
+        return None;
+    }
+    let data = buffer.data as *const BufferValue<T>;
+    // Buffers with the marker start with the type id whatever `T` is.
+    let type_id = unsafe { *(data as *const TypeId) };
+    (type_id == TypeId::of::<T>()).then(|| unsafe { &(*data).value })
 }
 
 impl Clone for AVBufferRef {
@@ -82,9 +232,145 @@ impl Drop for AVBufferRef {
     }
 }
 
+wrap!(
+    /// A pool of refcounted buffers of the same size. Buffers got from the pool
+    /// are returned to it instead of being freed when their last reference is
+    /// dropped.
+    AVBufferPool: ffi::AVBufferPool,
+    nb_allocated: Box<AtomicUsize> = Box::new(AtomicUsize::new(0)),
+);
+
+impl AVBufferPool {
+    /// Allocate and initialize a buffer pool, all the buffers in it have `size` bytes.
+    pub fn new(size: usize) -> Self {
+        unsafe extern "C" fn alloc(opaque: *mut c_void, size: usize) -> *mut ffi::AVBufferRef {
+            // `opaque` is the allocation counter owned by the pool wrapper,
+            // this function is only called in `av_buffer_pool_get()`.
+            let nb_allocated = unsafe { &*(opaque as *const AtomicUsize) };
+            nb_allocated.fetch_add(1, Ordering::Relaxed);
+            unsafe { ffi::av_buffer_allocz(size) }
+        }
+
+        let nb_allocated = Box::new(AtomicUsize::new(0));
+        // Only fails on OOM.
+        let pool = unsafe {
+            ffi::av_buffer_pool_init2(
+                size,
+                &*nb_allocated as *const _ as *mut c_void,
+                Some(alloc),
+                None,
+            )
+        }
+        .upgrade()
+        .unwrap();
+        let mut pool = unsafe { Self::from_raw(pool) };
+        pool.nb_allocated = nb_allocated;
+        pool
+    }
+
+    /// Get a buffer from the pool, a new buffer is allocated if there is no
+    /// free buffer available.
+    pub fn get(&mut self) -> AVBufferRef {
+        // Only fails on OOM.
+        let buffer = unsafe { ffi::av_buffer_pool_get(self.as_mut_ptr()) }
+            .upgrade()
+            .unwrap();
+        unsafe { AVBufferRef::from_raw(buffer) }
+    }
+
+    /// Number of buffers allocated by this pool so far.
+    pub fn nb_allocated(&self) -> usize {
+        self.nb_allocated.load(Ordering::Relaxed)
+    }
+}
+
+impl Drop for AVBufferPool {
+    /// Mark the pool as being available for freeing. It will actually be freed
+    /// only once all the allocated buffers associated with the pool are released.
+    fn drop(&mut self) {
+        let mut ptr = self.as_mut_ptr();
+        unsafe { ffi::av_buffer_pool_uninit(&mut ptr) }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_av_buffer_pool() {
+        let mut pool = AVBufferPool::new(1024);
+        assert_eq!(pool.nb_allocated(), 0);
+        {
+            let buf0 = pool.get();
+            let buf1 = pool.get();
+            assert_eq!(buf0.size, 1024);
+            assert_eq!(buf1.size, 1024);
+            assert_eq!(pool.nb_allocated(), 2);
+        }
+        for _ in 0..10 {
+            let buf = pool.get();
+            assert_eq!(buf.get_ref_count(), 1);
+        }
+        assert_eq!(pool.nb_allocated(), 2);
+
+        // Buffers outlive the pool.
+        let buf = pool.get();
+        drop(pool);
+        assert_eq!(buf.size, 1024);
+    }
+
+    #[test]
+    fn test_av_buffer_value() {
+        let buf = AVBufferRef::from_value(String::from("value"));
+        assert_eq!(buf.value::<String>().unwrap(), "value");
+        assert!(buf.value::<u64>().is_none());
+        assert!(!buf.is_writable());
+        assert!(buf.data().is_none());
+        assert!(AVBufferRef::zeroed(64).value::<u8>().is_none());
+
+        // The copy made for writing is plain bytes.
+        let mut buf1 = buf.clone();
+        buf1.make_writable();
+        assert!(buf1.is_writable());
+        assert!(buf1.value::<String>().is_none());
+        assert!(buf1.data().unwrap().iter().all(|&x| x == 0));
+        assert_eq!(buf.value::<String>().unwrap(), "value");
+    }
+
+    #[test]
+    fn test_av_buffer_from_vec() {
+        struct Owner(Vec<u8>, Arc<AtomicUsize>);
+        impl AsMut<[u8]> for Owner {
+            fn as_mut(&mut self) -> &mut [u8] {
+                &mut self.0
+            }
+        }
+        impl Drop for Owner {
+            fn drop(&mut self) {
+                self.1.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+
+        let mut buf = AVBufferRef::from_vec(vec![1, 2, 3]);
+        assert_eq!(buf.data(), Some(&[1, 2, 3][..]));
+        buf.data_mut().unwrap()[0] = 4;
+        assert_eq!(buf.data(), Some(&[4, 2, 3][..]));
+        let buf1 = buf.clone();
+        assert!(buf.data_mut().is_none());
+        drop(buf1);
+        assert!(buf.data_mut().is_some());
+
+        let nb_dropped = Arc::new(AtomicUsize::new(0));
+        let buf = AVBufferRef::from_owner(Owner(vec![0; 16], nb_dropped.clone()));
+        assert_eq!(buf.size, 16);
+        let buf1 = buf.clone();
+        std::thread::spawn(move || drop(buf1)).join().unwrap();
+        assert_eq!(nb_dropped.load(Ordering::SeqCst), 0);
+        drop(buf);
+        assert_eq!(nb_dropped.load(Ordering::SeqCst), 1);
+    }
 
     #[test]
     fn test_av_buffer_alloc() {
@@ -107,6 +393,17 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_av_buffer_alloc_zeroed() {
+        let mut buf = AVBufferRef::new(16);
+        assert_eq!(buf.data(), Some(&[0; 16][..]));
+        buf.data_mut().unwrap().fill(1);
+        buf.realloc(32);
+        let data = buf.data().unwrap();
+        assert_eq!(data[..16], [1; 16]);
+        assert_eq!(data[16..], [0; 16]);
+    }
+
     #[test]
     fn test_av_buffer_realloc() {
         let mut buf = AVBufferRef::new(1024);
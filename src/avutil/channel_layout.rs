@@ -5,6 +5,7 @@ use crate::{
 };
 use std::{
     ffi::{CStr, CString},
+    fmt,
     mem::MaybeUninit,
     os::raw::c_void,
     ptr::NonNull,
@@ -32,7 +33,48 @@ impl Clone for AVChannelLayout {
     }
 }
 
+impl PartialEq for AVChannelLayout {
+    /// Same as [`Self::equal()`], layouts failing to compare are unequal.
+    fn eq(&self, other: &Self) -> bool {
+        self.equal(other).unwrap_or(false)
+    }
+}
+
+impl fmt::Display for AVChannelLayout {
+    /// Write the description of the layout, see [`Self::describe()`].
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let description = self.describe().map_err(|_| fmt::Error)?;
+        f.write_str(&description.to_string_lossy())
+    }
+}
+
+impl From<AVChannelLayout> for ffi::AVChannelLayout {
+    fn from(layout: AVChannelLayout) -> Self {
+        layout.into_inner()
+    }
+}
+
 impl AVChannelLayout {
+    /// The `mono` layout.
+    pub fn mono() -> Self {
+        Self::from_nb_channels(1)
+    }
+
+    /// The `stereo` layout.
+    pub fn stereo() -> Self {
+        Self::from_nb_channels(2)
+    }
+
+    /// The `5.1(side)` layout, `AV_CH_LAYOUT_5POINT1`.
+    pub fn five_point_one() -> Self {
+        Self::from_nb_channels(6)
+    }
+
+    /// Get the number of channels in the layout.
+    pub fn nb_channels(&self) -> i32 {
+        self.nb_channels
+    }
+
     /// Convert self into [`ffi::AVChannelLayout`]`.
     ///
     /// Be careful when using it. Since this fucntion leaks the raw type,
@@ -215,6 +257,28 @@ impl Iterator for AVChannelLayoutIter {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use cstr::cstr;
+
+    #[test]
+    fn channel_layout_round_trip_test() {
+        let layout = AVChannelLayout::from_string(cstr!("5.1(side)")).unwrap();
+        assert_eq!(layout.nb_channels(), 6);
+        assert_eq!(layout.describe().unwrap().as_c_str(), cstr!("5.1(side)"));
+        assert_eq!(layout.to_string(), "5.1(side)");
+        assert!(layout == AVChannelLayout::five_point_one());
+        assert!(layout == layout.clone());
+        assert!(layout != AVChannelLayout::from_string(cstr!("5.1")).unwrap());
+
+        assert_eq!(AVChannelLayout::mono().to_string(), "mono");
+        assert_eq!(AVChannelLayout::stereo().to_string(), "stereo");
+        assert!(AVChannelLayout::stereo() == AVChannelLayout::from_string(cstr!("FL+FR")).unwrap());
+        assert!(AVChannelLayout::from_string(cstr!("not a layout")).is_none());
+
+        // Handed to frames as is.
+        let mut frame = crate::avutil::AVFrame::new();
+        frame.set_ch_layout(layout);
+        assert_eq!(frame.ch_layout().nb_channels, 6);
+    }
 
     #[test]
     fn channel_layout_iterator_test() {
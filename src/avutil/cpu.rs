@@ -0,0 +1,128 @@
+//! CPU features detected by FFmpeg, selecting the SIMD code paths.
+use crate::ffi;
+use std::{
+    ops::{BitAnd, BitOr, Not},
+    os::raw::c_int,
+};
+
+/// Non exhaustive wrapper of the CPU flags, a combination of `AV_CPU_FLAG_*`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CpuFlags(pub c_int);
+
+impl CpuFlags {
+    pub const MMX: Self = Self(ffi::AV_CPU_FLAG_MMX as c_int);
+    pub const MMXEXT: Self = Self(ffi::AV_CPU_FLAG_MMXEXT as c_int);
+    pub const SSE: Self = Self(ffi::AV_CPU_FLAG_SSE as c_int);
+    pub const SSE2: Self = Self(ffi::AV_CPU_FLAG_SSE2 as c_int);
+    pub const SSE3: Self = Self(ffi::AV_CPU_FLAG_SSE3 as c_int);
+    pub const SSSE3: Self = Self(ffi::AV_CPU_FLAG_SSSE3 as c_int);
+    pub const SSE4: Self = Self(ffi::AV_CPU_FLAG_SSE4 as c_int);
+    pub const SSE42: Self = Self(ffi::AV_CPU_FLAG_SSE42 as c_int);
+    pub const AVX: Self = Self(ffi::AV_CPU_FLAG_AVX as c_int);
+    pub const FMA3: Self = Self(ffi::AV_CPU_FLAG_FMA3 as c_int);
+    pub const AVX2: Self = Self(ffi::AV_CPU_FLAG_AVX2 as c_int);
+    pub const AVX512: Self = Self(ffi::AV_CPU_FLAG_AVX512 as c_int);
+    pub const ARMV8: Self = Self(ffi::AV_CPU_FLAG_ARMV8 as c_int);
+    pub const VFP: Self = Self(ffi::AV_CPU_FLAG_VFP as c_int);
+    pub const NEON: Self = Self(ffi::AV_CPU_FLAG_NEON as c_int);
+
+    /// No flags, only the C code paths are used.
+    pub const fn empty() -> Self {
+        Self(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    /// Return true if all the bits of `flags` are set.
+    pub fn contains(&self, flags: Self) -> bool {
+        self.0 & flags.0 == flags.0
+    }
+}
+
+impl BitOr for CpuFlags {
+    type Output = Self;
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl BitAnd for CpuFlags {
+    type Output = Self;
+    fn bitand(self, rhs: Self) -> Self {
+        Self(self.0 & rhs.0)
+    }
+}
+
+impl Not for CpuFlags {
+    type Output = Self;
+    fn not(self) -> Self {
+        Self(!self.0)
+    }
+}
+
+/// Get the flags of the CPU features used by FFmpeg, detected at the first
+/// call unless forced by [`force_flags()`].
+pub fn flags() -> CpuFlags {
+    CpuFlags(unsafe { ffi::av_get_cpu_flags() })
+}
+
+/// Make FFmpeg use only the code paths of `flags`, e.g.
+/// `force_flags(flags() & !CpuFlags::AVX2)` rules out the AVX2 ones.
+///
+/// The flags are process-global: they apply to the contexts initialized
+/// afterwards on all threads, contexts initialized before keep their code
+/// paths. Tests running in parallel observe each other's forced flags.
+///
+/// # Safety
+/// `flags` must only contain features the CPU supports, i.e. flags of
+/// [`flags()`] as detected, or FFmpeg executes instructions the CPU doesn't
+/// have.
+pub unsafe fn force_flags(flags: CpuFlags) {
+    unsafe { ffi::av_force_cpu_flags(flags.0) }
+}
+
+/// Go back to the detected flags after [`force_flags()`].
+pub fn reset_flags() {
+    unsafe { ffi::av_force_cpu_flags(-1) }
+}
+
+/// Get the number of logical CPU cores.
+pub fn count() -> usize {
+    unsafe { ffi::av_cpu_count() }.max(1) as usize
+}
+
+/// Get the maximum data alignment required by the SIMD code paths of the
+/// CPU, in bytes.
+pub fn max_align() -> usize {
+    unsafe { ffi::av_cpu_max_align() }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cpu_flags() {
+        // Forcing flags is tested in its own test binary, as the forced flags
+        // are seen by parallel tests.
+        let detected = flags();
+        if cfg!(target_arch = "x86_64") {
+            assert!(detected.contains(CpuFlags::SSE2));
+        } else if cfg!(target_arch = "aarch64") {
+            assert!(detected.contains(CpuFlags::NEON));
+        }
+        if cfg!(any(target_arch = "x86_64", target_arch = "aarch64")) {
+            assert!(!detected.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_cpu_count_and_align() {
+        assert!(count() >= 1);
+        let align = max_align();
+        assert!(align.is_power_of_two());
+        assert!(align >= 8);
+    }
+}
@@ -2,12 +2,23 @@ use crate::{error::Result, ffi, shared::*};
 
 use std::{
     ffi::{CStr, CString},
-    os::raw::c_void,
+    os::raw::{c_int, c_void},
     ptr::{self, NonNull},
 };
 
 wrap_ref_mut!(AVDictionary: ffi::AVDictionary);
 
+/// Strip the `AV_DICT_DONT_STRDUP_*` flags, with them FFmpeg takes the
+/// ownership of the passed in key or value, which is a borrowed [`CStr`] here.
+fn owned_flags(flags: u32) -> c_int {
+    (flags & !(ffi::AV_DICT_DONT_STRDUP_KEY | ffi::AV_DICT_DONT_STRDUP_VAL)) as c_int
+}
+
+/// Memory rules: the dictionary always owns copies of its keys and values,
+/// the `AV_DICT_DONT_STRDUP_KEY` and `AV_DICT_DONT_STRDUP_VAL` flags are
+/// ignored by all the methods. Other `AV_DICT_*` flags such as
+/// `AV_DICT_MULTIKEY`, `AV_DICT_DONT_OVERWRITE` and `AV_DICT_APPEND` are
+/// passed through.
 impl AVDictionary {
     /// Create a dictionary while calling `set()`.
     pub fn new(key: &CStr, value: &CStr, flags: u32) -> Self {
@@ -15,7 +26,7 @@ impl AVDictionary {
         // Without a new macro `wrap_nullable`, we cannot new a Self containing
         // null pointer.
         let mut dict = ptr::null_mut();
-        unsafe { ffi::av_dict_set(&mut dict, key.as_ptr(), value.as_ptr(), flags as i32) }
+        unsafe { ffi::av_dict_set(&mut dict, key.as_ptr(), value.as_ptr(), owned_flags(flags)) }
             .upgrade()
             .unwrap();
         unsafe { Self::from_raw(NonNull::new(dict).unwrap()) }
@@ -24,7 +35,7 @@ impl AVDictionary {
     /// Create a dictionary while calling `set_int()`.
     pub fn new_int(key: &CStr, value: i64, flags: u32) -> Self {
         let mut dict = ptr::null_mut();
-        unsafe { ffi::av_dict_set_int(&mut dict, key.as_ptr(), value, flags as i32) }
+        unsafe { ffi::av_dict_set_int(&mut dict, key.as_ptr(), value, owned_flags(flags)) }
             .upgrade()
             .unwrap();
         unsafe { Self::from_raw(NonNull::new(dict).unwrap()) }
@@ -44,7 +55,7 @@ impl AVDictionary {
                 str.as_ptr(),
                 key_val_sep.as_ptr(),
                 pairs_sep.as_ptr(),
-                flags as i32,
+                owned_flags(flags),
             )
         }
         .upgrade()
@@ -58,7 +69,7 @@ impl AVDictionary {
     pub fn set(mut self, key: &CStr, value: &CStr, flags: u32) -> Self {
         let mut dict = self.as_mut_ptr();
         // Only error on AVERROR_ENOMEM, so unwrap
-        unsafe { ffi::av_dict_set(&mut dict, key.as_ptr(), value.as_ptr(), flags as i32) }
+        unsafe { ffi::av_dict_set(&mut dict, key.as_ptr(), value.as_ptr(), owned_flags(flags)) }
             .upgrade()
             .unwrap();
         unsafe { self.set_ptr(NonNull::new(dict).unwrap()) };
@@ -69,7 +80,7 @@ impl AVDictionary {
     pub fn set_int(mut self, key: &CStr, value: i64, flags: u32) -> Self {
         let mut dict = self.as_mut_ptr();
         // Only error on AVERROR_ENOMEM, so unwrap
-        unsafe { ffi::av_dict_set_int(&mut dict, key.as_ptr(), value, flags as i32) }
+        unsafe { ffi::av_dict_set_int(&mut dict, key.as_ptr(), value, owned_flags(flags)) }
             .upgrade()
             .unwrap();
         unsafe { self.set_ptr(NonNull::new(dict).unwrap()) };
@@ -92,7 +103,7 @@ impl AVDictionary {
                 str.as_ptr(),
                 key_val_sep.as_ptr(),
                 pairs_sep.as_ptr(),
-                flags as i32,
+                owned_flags(flags),
             )
         }
         .upgrade()?;
@@ -104,13 +115,21 @@ impl AVDictionary {
     pub fn copy(mut self, another: &AVDictionary, flags: u32) -> Self {
         let mut dict = self.as_mut_ptr();
         // Only error on AVERROR_ENOMEM, so unwrap
-        unsafe { ffi::av_dict_copy(&mut dict, another.as_ptr(), flags as i32) }
+        unsafe { ffi::av_dict_copy(&mut dict, another.as_ptr(), owned_flags(flags)) }
             .upgrade()
             .unwrap();
         unsafe { self.set_ptr(NonNull::new(dict).unwrap()) };
         self
     }
 
+    /// Merge entries of `other` into self, entries are inserted in the order
+    /// of `other`. `flags` controls how existing keys are treated, e.g.
+    /// `AV_DICT_DONT_OVERWRITE` keeps the existing values, `AV_DICT_MULTIKEY`
+    /// keeps both and `AV_DICT_APPEND` concatenates the values.
+    pub fn merge_from(self, other: &AVDictionary, flags: u32) -> Self {
+        self.copy(other, flags)
+    }
+
     /// Get dictionary entries as a string.
     ///
     /// Create a string containing dictionary's entries.
@@ -150,7 +169,39 @@ impl<'dict> AVDictionary {
             .map(|ptr| unsafe { AVDictionaryEntryRef::from_raw(ptr) })
     }
 
-    /// Iterates through all entries in the dictionary by reference.
+    /// Get values of all the entries with matching key in insertion order,
+    /// useful for keys set multiple times with `AV_DICT_MULTIKEY`.
+    pub fn get_all(&'dict self, key: &CStr) -> Vec<&'dict CStr> {
+        let mut values = vec![];
+        let mut prev = ptr::null();
+        loop {
+            prev = unsafe { ffi::av_dict_get(self.as_ptr(), key.as_ptr(), prev, 0) };
+            match prev.upgrade() {
+                Some(entry) => values.push(unsafe { CStr::from_ptr(entry.as_ref().value) }),
+                None => break values,
+            }
+        }
+    }
+
+    /// Iterates through the entries whose key starts with `prefix`
+    /// (`AV_DICT_IGNORE_SUFFIX` matching), in insertion order, e.g. `"com."`
+    /// for the `com.apple.quicktime.*` tags.
+    pub fn get_prefixed(
+        &'dict self,
+        prefix: &'dict CStr,
+    ) -> impl Iterator<Item = AVDictionaryEntryRef<'dict>> + 'dict {
+        let mut prev = ptr::null();
+        std::iter::from_fn(move || {
+            let flags = ffi::AV_DICT_IGNORE_SUFFIX as c_int;
+            prev = unsafe { ffi::av_dict_get(self.as_ptr(), prefix.as_ptr(), prev, flags) };
+            prev.upgrade()
+                .map(|ptr| unsafe { AVDictionaryEntryRef::from_raw(ptr) })
+        })
+    }
+
+    /// Iterates through all entries in the dictionary by reference. Entries
+    /// with duplicated keys(`AV_DICT_MULTIKEY`) are all yielded, in insertion
+    /// order.
     pub fn iter(&'dict self) -> AVDictionaryIter<'dict> {
         AVDictionaryIter {
             dict: self,
@@ -158,6 +209,13 @@ impl<'dict> AVDictionary {
             _phantom: std::marker::PhantomData,
         }
     }
+
+    /// Same as [`Self::iter()`], yielding the key and value of each entry,
+    /// borrowed from the dictionary.
+    pub fn pairs(&'dict self) -> impl Iterator<Item = (&'dict CStr, &'dict CStr)> + 'dict {
+        self.iter()
+            .map(|x| unsafe { (CStr::from_ptr(x.key), CStr::from_ptr(x.value)) })
+    }
 }
 
 impl std::clone::Clone for AVDictionary {
@@ -218,6 +276,7 @@ impl AVDictionaryEntry {
 #[cfg(test)]
 mod test {
     use super::AVDictionary;
+    use crate::ffi;
     use cstr::cstr;
 
     #[test]
@@ -367,4 +426,107 @@ mod test {
             dict.get_string(b':', b'-').unwrap().as_c_str()
         );
     }
+
+    #[test]
+    fn multikey() {
+        let dict = AVDictionary::new(cstr!("map"), cstr!("0:v"), 0)
+            .set(cstr!("foo"), cstr!("bar"), 0)
+            .set(cstr!("map"), cstr!("0:a"), ffi::AV_DICT_MULTIKEY)
+            .set(cstr!("map"), cstr!("1:s"), ffi::AV_DICT_MULTIKEY);
+        assert_eq!(
+            dict.get_all(cstr!("map")),
+            [cstr!("0:v"), cstr!("0:a"), cstr!("1:s")]
+        );
+        assert!(dict.get_all(cstr!("bob")).is_empty());
+
+        let entries: Vec<_> = dict
+            .iter()
+            .map(|x| {
+                format!(
+                    "{}={}",
+                    x.key().to_str().unwrap(),
+                    x.value().to_str().unwrap()
+                )
+            })
+            .collect();
+        assert_eq!(entries, ["map=0:v", "foo=bar", "map=0:a", "map=1:s"]);
+        let pairs: Vec<_> = dict.pairs().collect();
+        assert_eq!(pairs[1], (cstr!("foo"), cstr!("bar")));
+        assert_eq!(pairs.len(), 4);
+
+        // Without `AV_DICT_MULTIKEY`, the value is replaced.
+        let dict = dict.set(cstr!("foo"), cstr!("baz"), 0);
+        assert_eq!(dict.get_all(cstr!("foo")), [cstr!("baz")]);
+    }
+
+    #[test]
+    fn get_prefixed() {
+        let dict = AVDictionary::new(cstr!("com.apple.make"), cstr!("Apple"), 0)
+            .set(cstr!("title"), cstr!("bear"), 0)
+            .set(cstr!("com.apple.model"), cstr!("iPhone"), 0);
+        let keys: Vec<_> = dict
+            .get_prefixed(cstr!("com.apple."))
+            .map(|x| x.key().to_str().unwrap().to_string())
+            .collect();
+        assert_eq!(keys, ["com.apple.make", "com.apple.model"]);
+        assert_eq!(dict.get_prefixed(cstr!("")).count(), 3);
+        assert_eq!(dict.get_prefixed(cstr!("artist")).count(), 0);
+    }
+
+    #[test]
+    fn dont_overwrite_and_append() {
+        let dict = AVDictionary::new(cstr!("a"), cstr!("b"), 0).set(
+            cstr!("a"),
+            cstr!("c"),
+            ffi::AV_DICT_DONT_OVERWRITE,
+        );
+        assert_eq!(dict.get_all(cstr!("a")), [cstr!("b")]);
+
+        let dict = dict.set(cstr!("a"), cstr!("c"), ffi::AV_DICT_APPEND);
+        assert_eq!(dict.get_all(cstr!("a")), [cstr!("bc")]);
+
+        // `AV_DICT_DONT_STRDUP_*` is ignored, the dictionary owns a copy.
+        let key = cstr!("k").to_owned();
+        let dict = dict.set(&key, cstr!("v"), ffi::AV_DICT_DONT_STRDUP_KEY);
+        drop(key);
+        assert_eq!(dict.get_all(cstr!("k")), [cstr!("v")]);
+    }
+
+    #[test]
+    fn merge_from() {
+        let other = AVDictionary::new(cstr!("a"), cstr!("x"), 0).set(cstr!("c"), cstr!("d"), 0);
+
+        let dict = AVDictionary::new(cstr!("a"), cstr!("b"), 0)
+            .merge_from(&other, ffi::AV_DICT_DONT_OVERWRITE);
+        assert_eq!(
+            cstr!("a=b:c=d"),
+            dict.get_string(b'=', b':').unwrap().as_c_str()
+        );
+
+        let dict =
+            AVDictionary::new(cstr!("a"), cstr!("b"), 0).merge_from(&other, ffi::AV_DICT_MULTIKEY);
+        assert_eq!(dict.get_all(cstr!("a")), [cstr!("b"), cstr!("x")]);
+
+        let dict = AVDictionary::new(cstr!("a"), cstr!("b"), 0).merge_from(&other, 0);
+        assert_eq!(
+            cstr!("a=x:c=d"),
+            dict.get_string(b'=', b':').unwrap().as_c_str()
+        );
+    }
+
+    #[test]
+    fn parse_string_escaped() {
+        let dict = AVDictionary::new(cstr!("x"), cstr!("y"), 0)
+            .parse_string(
+                cstr!(r"time=00\:01\:02:title=a\=b:map=0:map=1"),
+                cstr!("="),
+                cstr!(":"),
+                ffi::AV_DICT_MULTIKEY,
+            )
+            .unwrap();
+        assert_eq!(dict.get_all(cstr!("time")), [cstr!("00:01:02")]);
+        assert_eq!(dict.get_all(cstr!("title")), [cstr!("a=b")]);
+        assert_eq!(dict.get_all(cstr!("map")), [cstr!("0"), cstr!("1")]);
+        assert_eq!(dict.get_all(cstr!("x")), [cstr!("y")]);
+    }
 }
@@ -0,0 +1,58 @@
+//! Display matrix side data, the transformation to apply to decoded video
+//! frames for presentation, e.g. the rotation of videos shot in portrait.
+use crate::ffi;
+use std::mem::size_of;
+
+/// Size in bytes of the display matrix side data, 9 native endian `i32`s.
+pub const DISPLAY_MATRIX_SIZE: usize = 9 * size_of::<i32>();
+
+/// Get the rotation of the display matrix side data `data` in degrees
+/// counterclockwise, within [-180, 180]. Return `None` if `data` is too short
+/// or the matrix is degenerate.
+pub fn display_rotation_get(data: &[u8]) -> Option<f64> {
+    let matrix: Vec<i32> = data
+        .get(..DISPLAY_MATRIX_SIZE)?
+        .chunks_exact(size_of::<i32>())
+        .map(|x| i32::from_ne_bytes(x.try_into().unwrap()))
+        .collect();
+    let rotation = unsafe { ffi::av_display_rotation_get(matrix.as_ptr()) };
+    (!rotation.is_nan()).then_some(rotation)
+}
+
+/// Build the display matrix side data of a rotation of `degrees`
+/// counterclockwise.
+pub fn display_rotation_matrix(degrees: f64) -> [u8; DISPLAY_MATRIX_SIZE] {
+    let mut matrix = [0i32; 9];
+    unsafe { ffi::av_display_rotation_set(matrix.as_mut_ptr(), degrees) };
+    let mut data = [0u8; DISPLAY_MATRIX_SIZE];
+    for (dst, src) in data.chunks_exact_mut(size_of::<i32>()).zip(matrix) {
+        dst.copy_from_slice(&src.to_ne_bytes());
+    }
+    data
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display_rotation() {
+        // Hand built matrix of a 90 degrees clockwise rotation, in 16.16
+        // fixed point except the last column in 2.30.
+        let matrix: [i32; 9] = [0, -0x10000, 0, 0x10000, 0, 0, 0, 0, 0x40000000];
+        let data: Vec<u8> = matrix.iter().flat_map(|x| x.to_ne_bytes()).collect();
+        assert_eq!(display_rotation_get(&data), Some(90.));
+        assert_eq!(display_rotation_get(&data[..DISPLAY_MATRIX_SIZE - 1]), None);
+        assert_eq!(display_rotation_get(&[0; DISPLAY_MATRIX_SIZE]), None);
+
+        for degrees in [0., 90., -90., 180., 45.] {
+            let rotation = display_rotation_get(&display_rotation_matrix(degrees)).unwrap();
+            assert!(
+                (rotation - degrees).abs() < 1e-6,
+                "{} {}",
+                degrees,
+                rotation
+            );
+        }
+    }
+}
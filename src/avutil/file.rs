@@ -13,7 +13,10 @@ pub struct AVMmap {
     size: usize,
 }
 
+// SAFETY: The buffer is owned by the `AVMmap` and never written after
+// creation, it can be read from and unmapped on any thread.
 unsafe impl Send for AVMmap {}
+unsafe impl Sync for AVMmap {}
 
 impl Deref for AVMmap {
     type Target = [u8];
@@ -1,11 +1,23 @@
 use crate::{
-    avutil::{av_image_fill_arrays, AVChannelLayoutRef, AVImage, AVMotionVector, AVPixelFormat},
+    avutil::{
+        av_image_fill_arrays, buffer_value, check_image_planes, display_rotation_get,
+        image_copy_to_buffer, AVBufferRef, AVChannelLayoutRef, AVDictionary, AVDictionaryRef,
+        AVImage, AVMotionVector, AVPixFmtDescriptorRef, AVPixelFormat, AVSamples, ChromaLocation,
+        ColorPrimaries, ColorRange, ColorSpace, ColorTransfer, Sample, SampleFormat, Timestamp,
+    },
     error::*,
     ffi,
     shared::*,
 };
 
-use std::{fmt, mem::size_of, os::raw::c_int, ptr::NonNull, slice};
+use std::{
+    fmt,
+    io::{Read, Write},
+    mem::{self, size_of},
+    os::raw::c_int,
+    ptr::{self, NonNull},
+    slice,
+};
 
 wrap!(AVFrame: ffi::AVFrame);
 settable!(AVFrame {
@@ -16,8 +28,10 @@ settable!(AVFrame {
     pict_type: ffi::AVPictureType,
     nb_samples: i32,
     format: i32,
-    ch_layout: ffi::AVChannelLayout,
     sample_rate: i32,
+    quality: i32,
+    duration: i64,
+    sample_aspect_ratio: ffi::AVRational,
 });
 
 impl fmt::Debug for AVFrame {
@@ -41,11 +55,221 @@ impl AVFrame {
         unsafe { Self::from_raw(frame) }
     }
 
+    /// Create an audio frame of `nb_samples` samples per channel with
+    /// allocated, writable buffers, e.g. for feeding an encoder. `ch_layout`
+    /// is an [`AVChannelLayout`](crate::avutil::AVChannelLayout) or a raw one.
+    pub fn new_audio(
+        format: impl Into<SampleFormat>,
+        ch_layout: impl Into<ffi::AVChannelLayout>,
+        sample_rate: i32,
+        nb_samples: i32,
+    ) -> Result<Self> {
+        let mut frame = Self::new();
+        frame.set_format(format.into().0);
+        frame.set_ch_layout(ch_layout);
+        frame.set_sample_rate(sample_rate);
+        frame.set_nb_samples(nb_samples);
+        frame.alloc_buffer()?;
+        Ok(frame)
+    }
+
+    /// Create a video frame over the bytes of `buffer` without copying them,
+    /// e.g. an [`AVBufferRef::from_vec()`] of an image got from another
+    /// library. The planes follow each other in `buffer`, the lines of plane
+    /// `i` taking `linesizes[i]` bytes, e.g. the ones of
+    /// [`AVImage::fill_arrays()`]. The frame is writable if `buffer` is.
+    ///
+    /// Return [`RsmpegError::BufferTooSmallError`] if `buffer` is too small
+    /// for the image, `AVERROR(EINVAL)` if a line doesn't fit in its linesize.
+    pub fn from_buffer(
+        buffer: AVBufferRef,
+        width: i32,
+        height: i32,
+        pix_fmt: AVPixelFormat,
+        linesizes: [i32; 4],
+    ) -> Result<Self> {
+        let mut min_linesizes = [0; 4];
+        unsafe {
+            ffi::av_image_check_size(width as u32, height as u32, 0, ptr::null_mut()).upgrade()?;
+            ffi::av_image_fill_linesizes(min_linesizes.as_mut_ptr(), pix_fmt, width).upgrade()?;
+        }
+        if linesizes.iter().zip(&min_linesizes).any(|(x, min)| x < min) {
+            return Err(RsmpegError::AVError(ffi::AVERROR(ffi::EINVAL)));
+        }
+        let mut data = [ptr::null_mut(); 4];
+        let size = unsafe {
+            ffi::av_image_fill_pointers(
+                data.as_mut_ptr(),
+                pix_fmt,
+                height,
+                buffer.data,
+                linesizes.as_ptr(),
+            )
+        }
+        .upgrade()? as usize;
+        if buffer.size < size {
+            return Err(RsmpegError::BufferTooSmallError(buffer.size, size));
+        }
+
+        let mut frame = Self::new();
+        frame.set_width(width);
+        frame.set_height(height);
+        frame.set_format(pix_fmt);
+        frame.set_buffer(0, buffer, linesizes[0])?;
+        unsafe {
+            let frame = frame.deref_mut();
+            frame.data[..4].copy_from_slice(&data);
+            frame.linesize[..4].copy_from_slice(&linesizes);
+        }
+        Ok(frame)
+    }
+
+    /// Create an audio frame of `nb_samples` samples per channel over the
+    /// bytes of `buffer` without copying them. The planes follow each other
+    /// in `buffer`, aligned to `align` (0 for the default, 1 for no
+    /// alignment) as sized by [`AVSamples::get_buffer_size()`]. The frame is
+    /// writable if `buffer` is.
+    ///
+    /// Return [`RsmpegError::BufferTooSmallError`] if `buffer` is too small
+    /// for the samples, `AVERROR(EINVAL)` if there are more planes than
+    /// `AV_NUM_DATA_POINTERS`.
+    pub fn from_audio_buffer(
+        buffer: AVBufferRef,
+        format: impl Into<SampleFormat>,
+        ch_layout: impl Into<ffi::AVChannelLayout>,
+        sample_rate: i32,
+        nb_samples: i32,
+        align: i32,
+    ) -> Result<Self> {
+        let format = format.into();
+        let mut frame = Self::new();
+        frame.set_format(format.0);
+        frame.set_ch_layout(ch_layout);
+        frame.set_sample_rate(sample_rate);
+        frame.set_nb_samples(nb_samples);
+
+        let nb_channels = frame.ch_layout.nb_channels;
+        let nb_planes = if format.is_planar() { nb_channels } else { 1 };
+        // More planes would need an `extended_data` array owned by the frame.
+        if nb_planes > ffi::AV_NUM_DATA_POINTERS as i32 {
+            return Err(RsmpegError::AVError(ffi::AVERROR(ffi::EINVAL)));
+        }
+        let (linesize, size) = AVSamples::get_buffer_size(nb_channels, nb_samples, format.0, align)
+            .ok_or(RsmpegError::AVError(ffi::AVERROR(ffi::EINVAL)))?;
+        let size = size as usize;
+        if buffer.size < size {
+            return Err(RsmpegError::BufferTooSmallError(buffer.size, size));
+        }
+
+        frame.set_buffer(0, buffer, linesize)?;
+        unsafe {
+            let frame = frame.deref_mut();
+            ffi::av_samples_fill_arrays(
+                frame.data.as_mut_ptr(),
+                ptr::null_mut(),
+                frame.data[0],
+                nb_channels,
+                nb_samples,
+                format.0,
+                align,
+            )
+        }
+        .upgrade()?;
+        Ok(frame)
+    }
+
+    /// Get the sample format of an audio frame.
+    pub fn sample_format(&self) -> SampleFormat {
+        SampleFormat(self.format)
+    }
+
+    /// Get the presentation timestamp in the time base of the frame, `None` if
+    /// unknown.
+    pub fn pts(&self) -> Option<i64> {
+        Timestamp(self.pts).get()
+    }
+
+    /// Get the decompression timestamp of the packet the frame is decoded
+    /// from, `None` if unknown.
+    pub fn dts(&self) -> Option<i64> {
+        Timestamp(self.pkt_dts).get()
+    }
+
+    /// Get the duration in the time base of the frame, `None` if unknown.
+    pub fn duration(&self) -> Option<i64> {
+        (self.duration > 0).then_some(self.duration)
+    }
+
+    /// Set the presentation timestamp, `None` for unknown.
+    pub fn set_pts_opt(&mut self, pts: Option<i64>) {
+        self.set_pts(Timestamp::from(pts).0);
+    }
+
+    /// Set the decompression timestamp of the packet the frame is decoded
+    /// from, `None` for unknown.
+    pub fn set_dts_opt(&mut self, dts: Option<i64>) {
+        unsafe { self.deref_mut() }.pkt_dts = Timestamp::from(dts).0;
+    }
+
+    /// Set the duration, `None` for unknown.
+    pub fn set_duration_opt(&mut self, duration: Option<i64>) {
+        self.set_duration(duration.unwrap_or(0));
+    }
+
     /// Return true if the data and buffer of current frame is allocated.
     pub fn is_allocated(&self) -> bool {
         !(self.data[0].is_null() && self.buf[0].is_null())
     }
 
+    /// Unreference all the buffers referenced by frame and reset the frame
+    /// fields, the frame object itself can then be reused.
+    pub fn unref(&mut self) {
+        unsafe { ffi::av_frame_unref(self.as_mut_ptr()) }
+    }
+
+    /// Make plane `index` of current frame the start of `buffer` with
+    /// `linesize`, the frame takes the ownership of `buffer` and drops the
+    /// buffer previously at `index`. Useful for allocating frames by hand,
+    /// e.g. in [`AVCodecContext::set_get_buffer2()`].
+    ///
+    /// Return `AVERROR(EINVAL)` if `index` isn't below `AV_NUM_DATA_POINTERS`.
+    ///
+    /// [`AVCodecContext::set_get_buffer2()`]: crate::avcodec::AVCodecContext::set_get_buffer2
+    pub fn set_buffer(&mut self, index: usize, buffer: AVBufferRef, linesize: i32) -> Result<()> {
+        if index >= ffi::AV_NUM_DATA_POINTERS as usize {
+            return Err(RsmpegError::AVError(ffi::AVERROR(ffi::EINVAL)));
+        }
+        unsafe {
+            let frame = self.deref_mut();
+            ffi::av_buffer_unref(&mut frame.buf[index]);
+            frame.data[index] = buffer.data;
+            frame.linesize[index] = linesize;
+            frame.buf[index] = buffer.into_raw().as_ptr();
+        }
+        Ok(())
+    }
+
+    /// Attach `value` to current frame as its `opaque_ref`, replacing the
+    /// previous one. It's passed along by filters, and copied from the packet
+    /// to the decoded frame or from the frame to the encoded packet when the
+    /// codec context has [`AVCodecContext::set_copy_opaque()`] enabled.
+    ///
+    /// [`AVCodecContext::set_copy_opaque()`]: crate::avcodec::AVCodecContext::set_copy_opaque
+    pub fn set_opaque<T: Send + Sync + 'static>(&mut self, value: T) {
+        let buffer = AVBufferRef::from_value(value);
+        unsafe {
+            let frame = self.deref_mut();
+            ffi::av_buffer_unref(&mut frame.opaque_ref);
+            frame.opaque_ref = buffer.into_raw().as_ptr();
+        }
+    }
+
+    /// Get the value attached by [`Self::set_opaque()`]. Return `None` if
+    /// there is no `opaque_ref` or it doesn't hold a `T`.
+    pub fn opaque_ref<T: Send + Sync + 'static>(&self) -> Option<&T> {
+        unsafe { buffer_value(self.opaque_ref) }
+    }
+
     /// Allocate new buffer(s) for audio or video data.
     /// The following fields must be set on frame before calling this function:
     ///
@@ -63,15 +287,7 @@ impl AVFrame {
     /// buffer for an already initialized frame or allocation fails because of
     /// no memory.
     pub fn alloc_buffer(&mut self) -> Result<()> {
-        // If frame has already been allocated, calling av_frame_get_buffer will
-        // leak memory. So we do a check here.
-        if self.is_allocated() {
-            return Err(RsmpegError::AVFrameDoubleAllocatingError);
-        }
-        unsafe { ffi::av_frame_get_buffer(self.as_mut_ptr(), 0) }
-            .upgrade()
-            .map_err(RsmpegError::AVFrameInvalidAllocatingError)?;
-        Ok(())
+        self.get_buffer(0)
     }
 
     /// Allocate new buffer(s) for audio or video data.
@@ -84,15 +300,138 @@ impl AVFrame {
     /// This function will fill AVFrame.data and AVFrame.buf arrays and, if
     /// necessary, allocate and fill AVFrame.extended_data and AVFrame.extended_buf.
     /// For planar formats, one buffer will be allocated for each plane.
+    ///
+    /// `align` is the alignment of the buffer size, 0 picks the one of the
+    /// current CPU.
+    ///
+    /// Return [`RsmpegError::AVFrameDoubleAllocatingError`] if the frame is
+    /// already allocated, and [`RsmpegError::AVFrameInvalidAllocatingError`]
+    /// if the fields above are missing or invalid.
     pub fn get_buffer(&mut self, align: i32) -> Result<()> {
-        unsafe { ffi::av_frame_get_buffer(self.as_mut_ptr(), align) }.upgrade()?;
+        // If frame has already been allocated, calling av_frame_get_buffer will
+        // leak memory. So we do a check here.
+        if self.is_allocated() {
+            return Err(RsmpegError::AVFrameDoubleAllocatingError);
+        }
+        unsafe { ffi::av_frame_get_buffer(self.as_mut_ptr(), align) }
+            .upgrade()
+            .map_err(RsmpegError::AVFrameInvalidAllocatingError)?;
         Ok(())
     }
 
-    pub fn data_mut(&mut self) -> &mut [*mut u8; 8] {
+    /// Get the data pointers of the planes, check [`Self::data_mut()`] for
+    /// the safe version.
+    pub fn data_raw_mut(&mut self) -> &mut [*mut u8; 8] {
         unsafe { &mut self.deref_mut().data }
     }
 
+    /// Get plane `plane` of the frame, including the padding at the end of
+    /// the rows. Its length is `linesize * height` for video, with the height
+    /// of the chroma planes subsampled, and the palette of paletted formats as
+    /// plane 1. For audio, each plane holds `nb_samples` samples of one
+    /// channel, or of all the channels for packed formats.
+    ///
+    /// Returns `None` if `plane` is out of range, the frame isn't allocated,
+    /// or the plane doesn't fit in the buffer of the frame holding it, e.g.
+    /// when the frame borrows memory it has no `buf` for.
+    pub fn data(&self, plane: usize) -> Option<&[u8]> {
+        let (data, len) = self.plane(plane)?;
+        Some(unsafe { slice::from_raw_parts(data, len) })
+    }
+
+    /// Mutable version of [`Self::data()`]. The frame is made writable first,
+    /// which copies the data if the buffer is shared, `None` is returned if
+    /// that fails.
+    ///
+    /// This is copy-on-write: a frame sharing its buffers with a
+    /// [`Clone::clone()`] of it gets its own copy here, so writes are never
+    /// visible through the other references. Writing into shared buffers would
+    /// race with decoders and filters holding them, which is why there is no
+    /// safe way to do so.
+    pub fn data_mut(&mut self, plane: usize) -> Option<&mut [u8]> {
+        self.plane(plane)?;
+        self.make_writable().ok()?;
+        let (data, len) = self.plane(plane)?;
+        Some(unsafe { slice::from_raw_parts_mut(data, len) })
+    }
+
+    /// Get the data pointer and the length of plane `plane`, checked to fit in
+    /// the buffer holding it.
+    fn plane(&self, plane: usize) -> Option<(*mut u8, usize)> {
+        if !self.is_allocated() {
+            return None;
+        }
+        let fits = |len| self.plane_buffer_len(plane).map_or(false, |x| x >= len);
+        if self.nb_samples > 0 && self.ch_layout.nb_channels > 0 {
+            let nb_channels = self.ch_layout.nb_channels as usize;
+            let sample_fmt = self.sample_format();
+            let (nb_planes, nb_interleaved) = if sample_fmt.is_planar() {
+                (nb_channels, 1)
+            } else {
+                (1, nb_channels)
+            };
+            if plane >= nb_planes {
+                return None;
+            }
+            let len = sample_fmt.bytes()? * nb_interleaved * self.nb_samples as usize;
+            // `extended_data` points to `data` when channels fit in it.
+            let data = unsafe { *self.extended_data.add(plane) };
+            return fits(len).then_some((data, len));
+        }
+
+        let desc = AVPixFmtDescriptorRef::get(self.format)?;
+        if desc.is_hwaccel() {
+            return None;
+        }
+        let nb_planes = desc.nb_planes();
+        let data = *self.data.get(plane)?;
+        if data.is_null() {
+            return None;
+        }
+        if plane == nb_planes && desc.is_paletted() {
+            let len = ffi::AVPALETTE_SIZE as usize;
+            return fits(len).then_some((data, len));
+        }
+        if plane >= nb_planes || self.linesize[plane] <= 0 {
+            return None;
+        }
+        let height = desc.plane_height(plane, self.height);
+        let len = self.linesize[plane] as usize * height as usize;
+        fits(len).then_some((data, len))
+    }
+
+    /// Get the number of bytes from the start of plane `plane` to the end of
+    /// the buffer holding it, `None` if no buffer of the frame holds it.
+    pub(crate) fn plane_buffer_len(&self, plane: usize) -> Option<usize> {
+        let buf =
+            unsafe { ffi::av_frame_get_plane_buffer(self.as_ptr() as _, plane.try_into().ok()?) };
+        let buf = unsafe { buf.as_ref() }?;
+        // The plane is checked to start in the buffer.
+        let data = unsafe { *self.extended_data.add(plane) };
+        Some(buf.data as usize + buf.size - data as usize)
+    }
+
+    /// Get the number of samples per channel the buffers of an audio frame
+    /// can hold, which may be more than `nb_samples`. `None` if the frame
+    /// isn't allocated.
+    pub(crate) fn samples_capacity(&self) -> Option<i32> {
+        let nb_channels = usize::try_from(self.ch_layout.nb_channels)
+            .ok()
+            .filter(|x| *x > 0)?;
+        let sample_fmt = self.sample_format();
+        let (nb_planes, nb_interleaved) = if sample_fmt.is_planar() {
+            (nb_channels, 1)
+        } else {
+            (1, nb_channels)
+        };
+        let sample_size = sample_fmt.bytes()? * nb_interleaved;
+        let capacity = (0..nb_planes)
+            .map(|plane| Some(self.plane_buffer_len(plane)? / sample_size))
+            .min()
+            .flatten()?;
+        Some(capacity.min(i32::MAX as usize) as i32)
+    }
+
     pub fn linesize_mut(&mut self) -> &mut [c_int; 8] {
         unsafe { &mut self.deref_mut().linesize }
     }
@@ -103,6 +442,91 @@ impl AVFrame {
         unsafe { AVChannelLayoutRef::from_raw(inner) }
     }
 
+    /// Set channel layout, the previous layout is uninitialized and the
+    /// frame takes the ownership of `ch_layout`, an
+    /// [`AVChannelLayout`](crate::avutil::AVChannelLayout) or a raw one.
+    pub fn set_ch_layout(&mut self, ch_layout: impl Into<ffi::AVChannelLayout>) {
+        let ch_layout = ch_layout.into();
+        unsafe {
+            ffi::av_channel_layout_uninit(&mut self.deref_mut().ch_layout);
+            self.deref_mut().ch_layout = ch_layout;
+        }
+    }
+
+    /// Get the range of the values of the components.
+    pub fn color_range(&self) -> ColorRange {
+        ColorRange::from_raw(self.color_range)
+    }
+
+    /// Set the range of the values of the components.
+    pub fn set_color_range(&mut self, color_range: ColorRange) {
+        unsafe { self.deref_mut().color_range = color_range.into_raw() }
+    }
+
+    /// Get the chromaticity coordinates of the source primaries.
+    pub fn color_primaries(&self) -> ColorPrimaries {
+        ColorPrimaries::from_raw(self.color_primaries)
+    }
+
+    /// Set the chromaticity coordinates of the source primaries.
+    pub fn set_color_primaries(&mut self, color_primaries: ColorPrimaries) {
+        unsafe { self.deref_mut().color_primaries = color_primaries.into_raw() }
+    }
+
+    /// Get the transfer characteristic.
+    pub fn color_trc(&self) -> ColorTransfer {
+        ColorTransfer::from_raw(self.color_trc)
+    }
+
+    /// Set the transfer characteristic.
+    pub fn set_color_trc(&mut self, color_trc: ColorTransfer) {
+        unsafe { self.deref_mut().color_trc = color_trc.into_raw() }
+    }
+
+    /// Get the YUV colorspace type.
+    pub fn colorspace(&self) -> ColorSpace {
+        ColorSpace::from_raw(self.colorspace)
+    }
+
+    /// Set the YUV colorspace type.
+    pub fn set_colorspace(&mut self, colorspace: ColorSpace) {
+        unsafe { self.deref_mut().colorspace = colorspace.into_raw() }
+    }
+
+    /// Get the location of the chroma samples.
+    pub fn chroma_location(&self) -> ChromaLocation {
+        ChromaLocation::from_raw(self.chroma_location)
+    }
+
+    /// Set the location of the chroma samples.
+    pub fn set_chroma_location(&mut self, chroma_location: ChromaLocation) {
+        unsafe { self.deref_mut().chroma_location = chroma_location.into_raw() }
+    }
+
+    /// Get the metadata of the frame, e.g. the ones set by filters like
+    /// `cropdetect`, `None` if there is none.
+    pub fn metadata(&self) -> Option<AVDictionaryRef<'_>> {
+        NonNull::new(self.metadata).map(|x| unsafe { AVDictionaryRef::from_raw(x) })
+    }
+
+    /// Set the metadata of the frame, dropping the previous ones.
+    pub fn set_metadata(&mut self, dict: Option<AVDictionary>) {
+        let _ = NonNull::new(self.metadata).map(|x| unsafe { AVDictionary::from_raw(x) });
+        unsafe {
+            self.deref_mut().metadata = dict
+                .map(|x| x.into_raw().as_ptr())
+                .unwrap_or(ptr::null_mut())
+        }
+    }
+
+    /// Copy the properties of `src` into current frame, i.e. everything but
+    /// the data and its layout: timestamps, color properties, metadata, side
+    /// data etc.
+    pub fn copy_props_from(&mut self, src: &AVFrame) -> Result<()> {
+        unsafe { ffi::av_frame_copy_props(self.as_mut_ptr(), src.as_ptr()) }.upgrade()?;
+        Ok(())
+    }
+
     /// Setup the data pointers and linesizes based on the specified image
     /// parameters and the provided array.
     ///
@@ -119,7 +543,7 @@ impl AVFrame {
     ) -> Result<()> {
         unsafe {
             av_image_fill_arrays(
-                self.data_mut().as_mut_ptr(),
+                self.data_raw_mut().as_mut_ptr(),
                 self.linesize_mut().as_mut_ptr(),
                 src,
                 pix_fmt,
@@ -132,6 +556,121 @@ impl AVFrame {
         Ok(())
     }
 
+    /// Copy the planes of a video frame into `dst` in plane order, lines
+    /// aligned to `align` (1 for tightly packed, without the linesize
+    /// padding), return the number of bytes written. `dst` needs
+    /// [`AVImage::get_buffer_size()`] bytes, e.g. `width * height * 3` for a
+    /// packed RGB24 frame and `align` 1.
+    ///
+    /// Return an error if `dst` is too small, or if the planes don't fit in
+    /// the buffers of the frame, nothing is written then.
+    pub fn copy_image_to_buffer(&self, dst: &mut [u8], align: i32) -> Result<usize> {
+        self.copy_image_to_buffer_within(dst, align, |plane| self.plane_buffer_len(plane))
+    }
+
+    /// [`Self::copy_image_to_buffer()`] with the planes checked against
+    /// `buffer_len(plane)` bytes.
+    fn copy_image_to_buffer_within(
+        &self,
+        dst: &mut [u8],
+        align: i32,
+        buffer_len: impl Fn(usize) -> Option<usize>,
+    ) -> Result<usize> {
+        if !self.is_allocated() {
+            return Err(RsmpegError::AVError(ffi::AVERROR(ffi::EINVAL)));
+        }
+        check_image_planes(
+            &self.linesize,
+            self.format,
+            self.width,
+            self.height,
+            buffer_len,
+        )?;
+        unsafe {
+            image_copy_to_buffer(
+                dst,
+                self.data.as_ptr() as *const *const u8,
+                self.linesize.as_ptr(),
+                self.format,
+                self.width,
+                self.height,
+                align,
+            )
+        }
+    }
+
+    /// Write the planes of a video frame to `w` in plane order, tightly packed
+    /// (without the linesize padding), return the number of bytes written.
+    ///
+    /// Plane sizes follow FFmpeg's image layout, e.g. chroma planes of an odd
+    /// sized yuv420p frame are rounded up. The output can be read back by
+    /// [`Self::read_raw_from()`].
+    pub fn write_raw_to(&self, w: &mut impl Write) -> Result<u64> {
+        self.write_raw_to_within(w, |plane| self.plane_buffer_len(plane))
+    }
+
+    /// [`Self::write_raw_to()`] with the planes checked against
+    /// `buffer_len(plane)` bytes.
+    fn write_raw_to_within(
+        &self,
+        w: &mut impl Write,
+        buffer_len: impl Fn(usize) -> Option<usize>,
+    ) -> Result<u64> {
+        let size = AVImage::get_buffer_size(self.format, self.width, self.height, 1)
+            .ok_or(RsmpegError::AVError(ffi::AVERROR(ffi::EINVAL)))?;
+        let mut buffer = vec![0u8; size as usize];
+        let size = self.copy_image_to_buffer_within(&mut buffer, 1, buffer_len)?;
+        w.write_all(&buffer[..size])?;
+        Ok(size as u64)
+    }
+
+    /// Read a video frame of given size and pixel format from tightly packed
+    /// planes, the counterpart of [`Self::write_raw_to()`].
+    pub fn read_raw_from(
+        r: &mut impl Read,
+        width: i32,
+        height: i32,
+        pix_fmt: AVPixelFormat,
+    ) -> Result<Self> {
+        let size = AVImage::get_buffer_size(pix_fmt, width, height, 1)
+            .ok_or(RsmpegError::AVError(ffi::AVERROR(ffi::EINVAL)))?;
+        let mut buffer = vec![0u8; size as usize];
+        r.read_exact(&mut buffer)?;
+
+        let mut frame = AVFrame::new();
+        frame.set_width(width);
+        frame.set_height(height);
+        frame.set_format(pix_fmt);
+        frame.alloc_buffer()?;
+
+        let mut src_data = [ptr::null_mut(); 4];
+        let mut src_linesize = [0; 4];
+        unsafe {
+            av_image_fill_arrays(
+                src_data.as_mut_ptr(),
+                src_linesize.as_mut_ptr(),
+                buffer.as_ptr(),
+                pix_fmt,
+                width,
+                height,
+                1,
+            )
+        }
+        .upgrade()?;
+        unsafe {
+            ffi::av_image_copy(
+                frame.data_raw_mut().as_mut_ptr(),
+                frame.linesize_mut().as_mut_ptr(),
+                src_data.as_ptr() as *const *const u8,
+                src_linesize.as_ptr(),
+                pix_fmt,
+                width,
+                height,
+            )
+        };
+        Ok(frame)
+    }
+
     /// Ensure that the frame data is writable, avoiding data copy if possible.
     ///
     /// Do nothing if the frame is writable, allocate new buffers and copy the
@@ -151,6 +690,64 @@ impl AVFrame {
         }
     }
 
+    /// Get samples of channel `ch` of an audio frame as a typed slice, the
+    /// slice length is `nb_samples`.
+    ///
+    /// `T` must match the sample format of the frame, e.g. `f32` for
+    /// `AV_SAMPLE_FMT_FLT` and `AV_SAMPLE_FMT_FLTP`, or
+    /// [`RsmpegError::SampleTypeMismatchError`] is returned. Channels of a
+    /// packed frame are interleaved, so they can only be accessed when the frame
+    /// is mono, [`RsmpegError::PackedChannelAccessError`] is returned otherwise.
+    /// `AVERROR(EINVAL)` is returned if the frame isn't allocated, if
+    /// `nb_samples` is more than its buffers hold, or if the plane isn't
+    /// aligned for `T`, e.g. in a buffer of [`Self::from_audio_buffer()`].
+    pub fn channel_samples<T: Sample>(&self, ch: usize) -> Result<&[T]> {
+        let (plane, len) = self.channel_plane::<T>(ch)?;
+        Ok(unsafe { slice::from_raw_parts(plane, len) })
+    }
+
+    /// Mutable version of [`Self::channel_samples()`]. The frame is made
+    /// writable before the slice is returned, which copies the data if the
+    /// buffer is shared.
+    pub fn channel_samples_mut<T: Sample>(&mut self, ch: usize) -> Result<&mut [T]> {
+        self.channel_plane::<T>(ch)?;
+        self.make_writable()?;
+        let (plane, len) = self.channel_plane::<T>(ch)?;
+        Ok(unsafe { slice::from_raw_parts_mut(plane, len) })
+    }
+
+    /// Validate the sample type and return the typed data pointer of channel
+    /// `ch` and the number of samples, checked to fit in its buffer.
+    fn channel_plane<T: Sample>(&self, ch: usize) -> Result<(*mut T, usize)> {
+        let planar = match self.format {
+            x if x == T::PLANAR => true,
+            x if x == T::PACKED => false,
+            _ => return Err(RsmpegError::SampleTypeMismatchError),
+        };
+        let nb_channels = self.ch_layout.nb_channels as usize;
+        if ch >= nb_channels {
+            return Err(RsmpegError::ChannelIndexOutOfRangeError(ch));
+        }
+        if !planar && nb_channels != 1 {
+            return Err(RsmpegError::PackedChannelAccessError(ch));
+        }
+        let einval = || RsmpegError::AVError(ffi::AVERROR(ffi::EINVAL));
+        if !self.is_allocated() {
+            return Err(einval());
+        }
+        let capacity = self.samples_capacity().ok_or_else(einval)?;
+        if !(0..=capacity).contains(&self.nb_samples) {
+            return Err(einval());
+        }
+        // `extended_data` points to `data` when channels fit in it.
+        let data = unsafe { *self.extended_data.add(ch) };
+        // The buffer may come from anywhere, e.g. bytes of a `Vec<u8>`.
+        if data as usize % mem::align_of::<T>() != 0 {
+            return Err(einval());
+        }
+        Ok((data as *mut T, self.nb_samples as usize))
+    }
+
     /// Copy data to or from a hw surface. At least one of self/src must have an
     /// AVHWFramesContext attached.
     ///
@@ -177,9 +774,35 @@ impl AVFrame {
         unsafe { ffi::av_hwframe_transfer_data(self.as_mut_ptr(), src.as_ptr(), 0) }.upgrade()?;
         Ok(())
     }
+
+    /// Copy the frame into new buffers, unlike [`Clone::clone()`] which
+    /// references the buffers of the frame. The properties (timestamps, side
+    /// data, etc.) are copied too.
+    ///
+    /// Hardware frames can't be copied, transfer them with
+    /// [`Self::hwframe_transfer_data()`] instead.
+    pub fn deep_clone(&self) -> Result<AVFrame> {
+        let mut frame = AVFrame::new();
+        frame.set_format(self.format);
+        frame.set_width(self.width);
+        frame.set_height(self.height);
+        frame.set_nb_samples(self.nb_samples);
+        frame.set_ch_layout(self.ch_layout().clone().into_inner());
+        frame.get_buffer(0)?;
+        unsafe { ffi::av_frame_copy(frame.as_mut_ptr(), self.as_ptr()) }.upgrade()?;
+        frame.copy_props_from(self)?;
+        Ok(frame)
+    }
 }
 
 impl Clone for AVFrame {
+    /// Create a frame referencing the same buffers, cheap as only the
+    /// reference counts are increased.
+    ///
+    /// The clone is not a shared view of the data: writing through
+    /// [`AVFrame::data_mut()`] on either frame copies the shared buffers
+    /// first, so the frames never observe each other's writes. Use
+    /// [`AVFrame::deep_clone()`] for a copy of the data upfront.
     fn clone(&self) -> Self {
         let new_frame = unsafe { ffi::av_frame_clone(self.as_ptr()) }
             .upgrade()
@@ -203,6 +826,49 @@ impl<'frame> AVFrame {
             .upgrade()
             .map(|side_data_ptr| unsafe { AVFrameSideDataRef::from_raw(side_data_ptr) })
     }
+
+    /// Get the payload of the side data of `side_data_type` of current frame,
+    /// e.g. `AV_FRAME_DATA_MASTERING_DISPLAY_METADATA` of HDR videos.
+    pub fn side_data(
+        &'frame self,
+        side_data_type: ffi::AVFrameSideDataType,
+    ) -> Option<&'frame [u8]> {
+        self.get_side_data(side_data_type).map(|x| x.data())
+    }
+
+    /// Add a zeroed side data of `side_data_type` and `size` bytes to current
+    /// frame, replacing an existing one of the same type, and return its
+    /// payload for filling.
+    pub fn new_side_data(
+        &'frame mut self,
+        side_data_type: ffi::AVFrameSideDataType,
+        size: usize,
+    ) -> Result<&'frame mut [u8]> {
+        self.remove_side_data(side_data_type);
+        let side_data =
+            unsafe { ffi::av_frame_new_side_data(self.as_mut_ptr(), side_data_type, size) }
+                .upgrade_or(ffi::AVERROR(ffi::ENOMEM))?;
+        let data = unsafe { side_data.as_ref() }.data;
+        if size == 0 {
+            return Ok(&mut []);
+        }
+        unsafe {
+            // The buffer isn't initialized by FFmpeg.
+            ptr::write_bytes(data, 0, size);
+            Ok(slice::from_raw_parts_mut(data, size))
+        }
+    }
+
+    /// Remove all the side data of `side_data_type` from current frame.
+    pub fn remove_side_data(&mut self, side_data_type: ffi::AVFrameSideDataType) {
+        unsafe { ffi::av_frame_remove_side_data(self.as_mut_ptr(), side_data_type) }
+    }
+
+    /// Get the rotation of the display matrix side data of current frame in
+    /// degrees counterclockwise, see [`display_rotation_get()`].
+    pub fn rotation(&self) -> Option<f64> {
+        display_rotation_get(self.side_data(ffi::AV_FRAME_DATA_DISPLAYMATRIX)?)
+    }
 }
 
 impl Drop for AVFrame {
@@ -212,6 +878,43 @@ impl Drop for AVFrame {
     }
 }
 
+/// Pool of reusable [`AVFrame`] shells, avoids allocating a frame object per
+/// decoded or filtered frame, e.g. with
+/// [`AVCodecContext::receive_frame_into()`](crate::avcodec::AVCodecContext::receive_frame_into).
+///
+/// Frames are unreferenced when returned to the pool, the data buffers are
+/// released to FFmpeg's buffer pools while the frame objects are kept.
+#[derive(Default)]
+pub struct FramePool {
+    frames: Vec<AVFrame>,
+}
+
+impl FramePool {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Take a blank frame from the pool, allocate one if the pool is empty.
+    pub fn get(&mut self) -> AVFrame {
+        self.frames.pop().unwrap_or_default()
+    }
+
+    /// Return a frame to the pool, its buffers are unreferenced.
+    pub fn put(&mut self, mut frame: AVFrame) {
+        frame.unref();
+        self.frames.push(frame);
+    }
+
+    /// Number of frames available in the pool.
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+}
+
 /// It's a `AVFrame` bound with `AVImage`, the `AVFrame` references the buffer
 /// owned by the `AVImage`.
 pub struct AVFrameWithImage {
@@ -260,11 +963,40 @@ impl AVFrameWithImage {
     pub fn into_image(self) -> AVImage {
         self.image
     }
+
+    /// Same as [`AVFrame::copy_image_to_buffer()`], the planes are checked
+    /// against the buffer of the image as the frame doesn't reference it.
+    pub fn copy_image_to_buffer(&self, dst: &mut [u8], align: i32) -> Result<usize> {
+        self.frame.copy_image_to_buffer_within(dst, align, |plane| {
+            self.image.buffer_len_after(self.frame.data[plane])
+        })
+    }
+
+    /// Same as [`AVFrame::write_raw_to()`], the planes are checked against the
+    /// buffer of the image as the frame doesn't reference it.
+    pub fn write_raw_to(&self, w: &mut impl Write) -> Result<u64> {
+        self.frame.write_raw_to_within(w, |plane| {
+            self.image.buffer_len_after(self.frame.data[plane])
+        })
+    }
 }
 
 wrap_ref!(AVFrameSideData: ffi::AVFrameSideData);
 
 impl<'frame> AVFrameSideDataRef<'frame> {
+    /// Get the type of the side data.
+    pub fn r#type(&self) -> ffi::AVFrameSideDataType {
+        self.type_
+    }
+
+    /// Get the payload of the side data.
+    pub fn data(&self) -> &'frame [u8] {
+        if self.data.is_null() || self.size == 0 {
+            return &[];
+        }
+        unsafe { slice::from_raw_parts(self.data, self.size) }
+    }
+
     /// # Safety
     ///
     /// You should only call this function when you ensure side data is motion vector.
@@ -281,14 +1013,63 @@ impl<'frame> AVFrameSideDataRef<'frame> {
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::{avcodec::AVCodec, avutil::AVChannelLayout};
+    use crate::{
+        avcodec::{AVCodec, AVCodecContext},
+        avutil::AVChannelLayout,
+        swscale::{ScaleSpec, SwsFlags, SwsScaler},
+    };
+    use cstr::cstr;
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    #[test]
+    fn test_setters() {
+        let mut frame = AVFrame::new();
+        frame.set_width(64);
+        frame.set_height(48);
+        frame.set_pts(42);
+        frame.set_duration(2);
+        frame.set_format(ffi::AV_PIX_FMT_YUV420P);
+        frame.set_pict_type(ffi::AV_PICTURE_TYPE_I);
+        frame.set_sample_aspect_ratio(ffi::AVRational { num: 4, den: 3 });
+        frame.set_nb_samples(1024);
+        frame.set_sample_rate(48000);
+        frame.set_quality(3);
+        frame.set_time_base(ffi::AVRational { num: 1, den: 25 });
+        assert_eq!((frame.width, frame.height), (64, 48));
+        assert_eq!((frame.pts, frame.duration), (42, 2));
+        assert_eq!((frame.pts(), frame.duration()), (Some(42), Some(2)));
+        assert_eq!(frame.dts(), None);
+        frame.set_dts_opt(Some(40));
+        assert_eq!(frame.dts(), Some(40));
+        frame.set_pts_opt(None);
+        frame.set_duration_opt(None);
+        assert_eq!((frame.pts(), frame.duration()), (None, None));
+        assert_eq!(frame.pts, ffi::AV_NOPTS_VALUE);
+        frame.set_pts_opt(Some(42));
+        frame.set_duration_opt(Some(2));
+        assert_eq!(frame.format, ffi::AV_PIX_FMT_YUV420P);
+        assert_eq!(frame.pict_type, ffi::AV_PICTURE_TYPE_I);
+        assert_eq!(frame.sample_aspect_ratio.num, 4);
+        assert_eq!(frame.sample_aspect_ratio.den, 3);
+        assert_eq!((frame.nb_samples, frame.sample_rate), (1024, 48000));
+        assert_eq!(frame.quality, 3);
+        assert_eq!(frame.time_base.den, 25);
+
+        // Replacing the channel layout releases the previous one.
+        frame.set_ch_layout(AVChannelLayout::from_nb_channels(2));
+        frame.set_ch_layout(AVChannelLayout::from_nb_channels(1));
+        assert_eq!(frame.ch_layout.nb_channels, 1);
+    }
 
     #[test]
     fn test_get_buffer() {
         let encoder = AVCodec::find_encoder(ffi::AV_CODEC_ID_AAC).unwrap();
         let mut frame = AVFrame::new();
         frame.set_nb_samples(2);
-        frame.set_ch_layout(AVChannelLayout::from_nb_channels(2).into_inner());
+        frame.set_ch_layout(AVChannelLayout::from_nb_channels(2));
         frame.set_format(encoder.sample_fmts().unwrap()[0]);
         assert!(frame.alloc_buffer().is_ok());
     }
@@ -307,7 +1088,7 @@ mod test {
         let encoder = AVCodec::find_encoder(ffi::AV_CODEC_ID_AAC).unwrap();
         let mut frame = AVFrame::new();
         frame.set_nb_samples(2);
-        frame.set_ch_layout(AVChannelLayout::from_nb_channels(2).into_inner());
+        frame.set_ch_layout(AVChannelLayout::from_nb_channels(2));
         frame.set_format(encoder.sample_fmts().unwrap()[0]);
         frame.alloc_buffer().unwrap();
         assert!(matches!(
@@ -316,10 +1097,542 @@ mod test {
         ));
     }
 
+    #[test]
+    fn test_plane_data() {
+        let mut frame = AVFrame::new();
+        assert!(frame.data(0).is_none());
+        // Odd size, chroma planes have 2 rows.
+        frame.set_width(5);
+        frame.set_height(3);
+        frame.set_format(ffi::AV_PIX_FMT_YUV420P);
+        frame.get_buffer(32).unwrap();
+        let (linesize_y, linesize_u) = (frame.linesize[0] as usize, frame.linesize[1] as usize);
+        assert!(linesize_y >= 5 && linesize_u >= 3);
+        assert_eq!(frame.data(0).unwrap().len(), linesize_y * 3);
+        assert_eq!(frame.data(1).unwrap().len(), linesize_u * 2);
+        assert_eq!(frame.data(2).unwrap().len(), frame.linesize[2] as usize * 2);
+        assert!(frame.data(3).is_none());
+        assert!(frame.data(8).is_none());
+
+        frame.data_mut(1).unwrap().fill(7);
+        assert!(frame.data(1).unwrap().iter().all(|x| *x == 7));
+
+        // A shared frame is copied before writing.
+        let mut copy = frame.clone();
+        copy.data_mut(1).unwrap().fill(9);
+        assert!(frame.data(1).unwrap().iter().all(|x| *x == 7));
+        assert!(copy.data(1).unwrap().iter().all(|x| *x == 9));
+
+        let mut frame = AVFrame::new();
+        frame.set_width(4);
+        frame.set_height(4);
+        frame.set_format(ffi::AV_PIX_FMT_PAL8);
+        frame.alloc_buffer().unwrap();
+        assert_eq!(frame.data(1).unwrap().len(), ffi::AVPALETTE_SIZE as usize);
+
+        let mut frame = AVFrame::new();
+        frame.set_nb_samples(100);
+        frame.set_ch_layout(AVChannelLayout::from_nb_channels(2));
+        frame.set_format(ffi::AV_SAMPLE_FMT_S16);
+        frame.alloc_buffer().unwrap();
+        assert_eq!(frame.data(0).unwrap().len(), 100 * 2 * 2);
+        assert!(frame.data(1).is_none());
+
+        let mut frame = AVFrame::new();
+        frame.set_nb_samples(100);
+        frame.set_ch_layout(AVChannelLayout::from_nb_channels(2));
+        frame.set_format(ffi::AV_SAMPLE_FMT_FLTP);
+        frame.alloc_buffer().unwrap();
+        assert_eq!(frame.data(0).unwrap().len(), 100 * 4);
+        assert_eq!(frame.data(1).unwrap().len(), 100 * 4);
+        assert!(frame.data(2).is_none());
+
+        // Fields changed after allocation don't let the slices outgrow the buffers.
+        frame.set_nb_samples(200);
+        assert!(frame.data(0).is_none());
+        assert!(frame.data_mut(1).is_none());
+        frame.set_nb_samples(50);
+        assert_eq!(frame.data(0).unwrap().len(), 50 * 4);
+
+        let mut frame = AVFrame::new();
+        frame.set_width(16);
+        frame.set_height(16);
+        frame.set_format(ffi::AV_PIX_FMT_GRAY8);
+        frame.alloc_buffer().unwrap();
+        frame.set_height(1024);
+        assert!(frame.data(0).is_none());
+    }
+
+    #[test]
+    fn test_clone() {
+        let mut frame = AVFrame::new();
+        frame.set_width(16);
+        frame.set_height(8);
+        frame.set_format(ffi::AV_PIX_FMT_YUV420P);
+        frame.set_pts(42);
+        frame.alloc_buffer().unwrap();
+        for plane in 0..3 {
+            frame.data_mut(plane).unwrap().fill(100);
+        }
+
+        let ref_clone = frame.clone();
+        let deep_clone = frame.deep_clone().unwrap();
+        assert_eq!(
+            ref_clone.data(0).unwrap().as_ptr(),
+            frame.data(0).unwrap().as_ptr()
+        );
+        assert_ne!(
+            deep_clone.data(0).unwrap().as_ptr(),
+            frame.data(0).unwrap().as_ptr()
+        );
+        assert_eq!(deep_clone.pts, 42);
+        assert_eq!((deep_clone.width, deep_clone.height), (16, 8));
+
+        // Writing to a shared frame copies its buffers first, neither clone
+        // observes the change.
+        frame.data_mut(0).unwrap().fill(0);
+        assert!(frame.data(0).unwrap().iter().all(|x| *x == 0));
+        for clone in [&ref_clone, &deep_clone] {
+            for plane in 0..3 {
+                let width = if plane == 0 { 16 } else { 8 };
+                let linesize = clone.linesize[plane] as usize;
+                for row in clone.data(plane).unwrap().chunks(linesize) {
+                    assert!(row[..width].iter().all(|x| *x == 100));
+                }
+            }
+        }
+
+        // Dropping in either order is fine.
+        let ref_clone2 = ref_clone.clone();
+        drop(ref_clone);
+        assert!(ref_clone2.data(1).unwrap()[..8].iter().all(|x| *x == 100));
+        drop(frame);
+        drop(ref_clone2);
+    }
+
+    #[test]
+    fn test_side_data() {
+        let mut frame = AVFrame::new();
+        assert!(frame
+            .side_data(ffi::AV_FRAME_DATA_MASTERING_DISPLAY_METADATA)
+            .is_none());
+        assert_eq!(frame.rotation(), None);
+
+        let size = size_of::<ffi::AVMasteringDisplayMetadata>();
+        let data = frame
+            .new_side_data(ffi::AV_FRAME_DATA_MASTERING_DISPLAY_METADATA, size)
+            .unwrap();
+        assert!(data.iter().all(|&x| x == 0));
+        data[size - 1] = 1;
+        let side_data = frame
+            .get_side_data(ffi::AV_FRAME_DATA_MASTERING_DISPLAY_METADATA)
+            .unwrap();
+        assert_eq!(
+            side_data.r#type(),
+            ffi::AV_FRAME_DATA_MASTERING_DISPLAY_METADATA
+        );
+        assert_eq!(side_data.data().len(), size);
+        assert_eq!(side_data.data()[size - 1], 1);
+
+        let matrix = crate::avutil::display_rotation_matrix(90.);
+        frame
+            .new_side_data(ffi::AV_FRAME_DATA_DISPLAYMATRIX, matrix.len())
+            .unwrap()
+            .copy_from_slice(&matrix);
+        assert_eq!(frame.rotation(), Some(90.));
+        // Replaced, not appended.
+        frame
+            .new_side_data(ffi::AV_FRAME_DATA_DISPLAYMATRIX, matrix.len())
+            .unwrap()
+            .copy_from_slice(&crate::avutil::display_rotation_matrix(180.));
+        assert_eq!(frame.nb_side_data, 2);
+        assert_eq!(frame.rotation().map(f64::abs), Some(180.));
+        // Side data is kept by clones.
+        assert_eq!(frame.clone().rotation().map(f64::abs), Some(180.));
+
+        frame.remove_side_data(ffi::AV_FRAME_DATA_DISPLAYMATRIX);
+        assert_eq!(frame.rotation(), None);
+        assert_eq!(frame.nb_side_data, 1);
+    }
+
+    #[test]
+    fn test_deep_clone_audio() {
+        let mut frame = AVFrame::new();
+        frame.set_nb_samples(64);
+        frame.set_ch_layout(AVChannelLayout::from_nb_channels(2));
+        frame.set_format(ffi::AV_SAMPLE_FMT_FLTP);
+        frame.set_sample_rate(48000);
+        frame.alloc_buffer().unwrap();
+        frame.channel_samples_mut::<f32>(1).unwrap().fill(0.5);
+
+        let clone = frame.deep_clone().unwrap();
+        frame.channel_samples_mut::<f32>(1).unwrap().fill(0.);
+        assert_eq!(clone.nb_samples, 64);
+        assert_eq!(clone.sample_rate, 48000);
+        assert_eq!(clone.ch_layout.nb_channels, 2);
+        assert!(clone
+            .channel_samples::<f32>(1)
+            .unwrap()
+            .iter()
+            .all(|x| *x == 0.5));
+    }
+
+    #[test]
+    fn test_raw_round_trip_yuv420p() {
+        // Odd size, chroma planes are 3x2.
+        let raw: Vec<u8> = (0..5 * 3 + 2 * 3 * 2).collect();
+        let frame = AVFrame::read_raw_from(&mut &raw[..], 5, 3, ffi::AV_PIX_FMT_YUV420P).unwrap();
+        assert_eq!(frame.width, 5);
+        assert_eq!(frame.height, 3);
+        let row = |plane: usize, y: usize, len: usize| unsafe {
+            slice::from_raw_parts(
+                frame.data[plane].add(y * frame.linesize[plane] as usize),
+                len,
+            )
+        };
+        assert_eq!(row(0, 2, 5), &[10, 11, 12, 13, 14]);
+        assert_eq!(row(1, 1, 3), &[18, 19, 20]);
+        assert_eq!(row(2, 0, 3), &[21, 22, 23]);
+
+        let mut output = vec![];
+        assert_eq!(frame.write_raw_to(&mut output).unwrap(), raw.len() as u64);
+        assert_eq!(output, raw);
+
+        // Not enough data.
+        assert!(AVFrame::read_raw_from(&mut &raw[1..], 5, 3, ffi::AV_PIX_FMT_YUV420P).is_err());
+    }
+
+    #[test]
+    fn test_raw_round_trip_rgb24() {
+        let raw: Vec<u8> = (0..4 * 3 * 3).collect();
+        let frame = AVFrame::read_raw_from(&mut &raw[..], 4, 3, ffi::AV_PIX_FMT_RGB24).unwrap();
+        // Rows are padded in the frame.
+        assert!(frame.linesize[0] >= 12);
+        let mut output = vec![];
+        assert_eq!(frame.write_raw_to(&mut output).unwrap(), 36);
+        assert_eq!(output, raw);
+
+        // Planes out of the frame's buffers aren't read.
+        let mut frame = frame;
+        frame.set_height(1 << 16);
+        assert!(frame
+            .copy_image_to_buffer(&mut vec![0; 12 << 16], 1)
+            .is_err());
+
+        assert!(AVFrame::new().write_raw_to(&mut output).is_err());
+    }
+
+    #[test]
+    fn test_channel_samples_planar() {
+        let mut frame = AVFrame::new();
+        frame.set_nb_samples(4);
+        frame.set_ch_layout(AVChannelLayout::from_nb_channels(2));
+        frame.set_format(ffi::AV_SAMPLE_FMT_FLTP);
+        frame.alloc_buffer().unwrap();
+
+        for ch in 0..2 {
+            let samples = frame.channel_samples_mut::<f32>(ch).unwrap();
+            for (i, sample) in samples.iter_mut().enumerate() {
+                *sample = (ch * 10 + i) as f32 * 0.5;
+            }
+        }
+
+        assert_eq!(
+            frame.channel_samples::<f32>(0).unwrap(),
+            &[0.0, 0.5, 1.0, 1.5]
+        );
+        assert_eq!(
+            frame.channel_samples::<f32>(1).unwrap(),
+            &[5.0, 5.5, 6.0, 6.5]
+        );
+        assert!(matches!(
+            frame.channel_samples::<f32>(2),
+            Err(RsmpegError::ChannelIndexOutOfRangeError(2))
+        ));
+        assert!(matches!(
+            frame.channel_samples::<i16>(0),
+            Err(RsmpegError::SampleTypeMismatchError)
+        ));
+        assert!(matches!(
+            frame.channel_samples::<f64>(0),
+            Err(RsmpegError::SampleTypeMismatchError)
+        ));
+
+        // The slices can't reach past the buffers.
+        frame.set_nb_samples(frame.samples_capacity().unwrap() + 1);
+        assert!(matches!(
+            frame.channel_samples::<f32>(0),
+            Err(RsmpegError::AVError(_))
+        ));
+        assert!(matches!(
+            frame.channel_samples_mut::<f32>(1),
+            Err(RsmpegError::AVError(_))
+        ));
+        frame.set_nb_samples(-1);
+        assert!(frame.channel_samples::<f32>(0).is_err());
+    }
+
+    #[test]
+    fn test_channel_samples_misaligned() {
+        struct Misaligned(Vec<u8>);
+        impl AsMut<[u8]> for Misaligned {
+            fn as_mut(&mut self) -> &mut [u8] {
+                &mut self.0[1..]
+            }
+        }
+
+        let buffer = AVBufferRef::from_owner(Misaligned(vec![0; 17]));
+        let frame = AVFrame::from_audio_buffer(
+            buffer,
+            ffi::AV_SAMPLE_FMT_FLTP,
+            AVChannelLayout::from_nb_channels(1),
+            48000,
+            4,
+            1,
+        )
+        .unwrap();
+        assert!(matches!(
+            frame.channel_samples::<f32>(0),
+            Err(RsmpegError::AVError(_))
+        ));
+    }
+
+    #[test]
+    fn test_channel_samples_packed() {
+        let mut frame = AVFrame::new();
+        frame.set_nb_samples(3);
+        frame.set_ch_layout(AVChannelLayout::from_nb_channels(1));
+        frame.set_format(ffi::AV_SAMPLE_FMT_S16);
+        frame.alloc_buffer().unwrap();
+
+        frame
+            .channel_samples_mut::<i16>(0)
+            .unwrap()
+            .copy_from_slice(&[1, -2, 3]);
+        let cloned = frame.clone();
+        assert_eq!(cloned.channel_samples::<i16>(0).unwrap(), &[1, -2, 3]);
+        assert!(matches!(
+            cloned.channel_samples::<f32>(0),
+            Err(RsmpegError::SampleTypeMismatchError)
+        ));
+
+        let mut frame = AVFrame::new();
+        frame.set_nb_samples(3);
+        frame.set_ch_layout(AVChannelLayout::from_nb_channels(2));
+        frame.set_format(ffi::AV_SAMPLE_FMT_S16);
+        frame.alloc_buffer().unwrap();
+        assert!(matches!(
+            frame.channel_samples::<i16>(1),
+            Err(RsmpegError::PackedChannelAccessError(1))
+        ));
+    }
+
     #[test]
     fn test_frame_with_image_buffer() {
         let image = AVImage::new(ffi::AV_PIX_FMT_RGB24, 256, 256, 0).unwrap();
-        let frame = AVFrameWithImage::new(image);
+        let mut frame = AVFrameWithImage::new(image);
         let _: &Vec<u8> = &frame.image;
+
+        let mut output = vec![];
+        assert_eq!(frame.write_raw_to(&mut output).unwrap(), 256 * 256 * 3);
+        // The frame fields can't make it read past the image.
+        frame.set_height(512);
+        assert_eq!(
+            frame.copy_image_to_buffer(&mut vec![0; 512 * 256 * 3], 1),
+            Err(RsmpegError::BufferTooSmallError(
+                256 * 256 * 3,
+                511 * 768 + 768
+            ))
+        );
+    }
+
+    #[test]
+    fn test_new_audio() {
+        let encoder = AVCodec::find_encoder(ffi::AV_CODEC_ID_AAC).unwrap();
+        let mut enc_ctx = AVCodecContext::new(&encoder);
+        enc_ctx.try_set_sample_fmt(ffi::AV_SAMPLE_FMT_FLTP).unwrap();
+        enc_ctx
+            .try_set_ch_layout(AVChannelLayout::stereo())
+            .unwrap();
+        enc_ctx.try_set_sample_rate(48000).unwrap();
+        enc_ctx.try_set_bit_rate(128_000).unwrap();
+        enc_ctx.open(None).unwrap();
+        assert_eq!(enc_ctx.frame_size, 1024);
+
+        let mut nb_packets = 0;
+        for i in 0..10 {
+            let mut frame = AVFrame::new_audio(
+                ffi::AV_SAMPLE_FMT_FLTP,
+                AVChannelLayout::stereo(),
+                48000,
+                1024,
+            )
+            .unwrap();
+            assert!(frame.is_writable().unwrap());
+            assert_eq!(frame.nb_samples, 1024);
+            assert_eq!(frame.sample_rate, 48000);
+            assert_eq!(frame.ch_layout().nb_channels, 2);
+            for ch in 0..2 {
+                let plane = frame.data_mut(ch).unwrap();
+                assert_eq!(plane.len(), 1024 * 4);
+                for (j, sample) in plane.chunks_exact_mut(4).enumerate() {
+                    let t = (i * 1024 + j) as f32 / 48000.0;
+                    let value = (t * 440.0 * 2.0 * std::f32::consts::PI).sin();
+                    sample.copy_from_slice(&value.to_ne_bytes());
+                }
+            }
+            frame.set_pts(i as i64 * 1024);
+            enc_ctx.send_frame(Some(&frame)).unwrap();
+            while enc_ctx.receive_packet().is_ok() {
+                nb_packets += 1;
+            }
+        }
+        nb_packets += enc_ctx.flush_encoder().unwrap().len();
+        assert!(nb_packets >= 10);
+
+        assert!(matches!(
+            AVFrame::new_audio(ffi::AV_SAMPLE_FMT_FLTP, AVChannelLayout::stereo(), 48000, 0),
+            Err(RsmpegError::AVFrameInvalidAllocatingError(_))
+        ));
+    }
+
+    #[test]
+    fn test_from_buffer() {
+        /// Image bytes counting their drops.
+        struct Image(Vec<u8>, Arc<AtomicUsize>);
+        impl AsMut<[u8]> for Image {
+            fn as_mut(&mut self) -> &mut [u8] {
+                &mut self.0
+            }
+        }
+        impl Drop for Image {
+            fn drop(&mut self) {
+                self.1.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+
+        // Gray yuv420p, the luma plane followed by the chroma ones.
+        let mut bytes = vec![128; 64 * 48];
+        bytes.extend(vec![128; 32 * 24 * 2]);
+        let nb_dropped = Arc::new(AtomicUsize::new(0));
+        let buffer = AVBufferRef::from_owner(Image(bytes, nb_dropped.clone()));
+        let data = buffer.data;
+        let frame =
+            AVFrame::from_buffer(buffer, 64, 48, ffi::AV_PIX_FMT_YUV420P, [64, 32, 32, 0]).unwrap();
+        assert_eq!((frame.width, frame.height), (64, 48));
+        assert_eq!(frame.data[0], data);
+        assert_eq!(frame.data[1], unsafe { data.add(64 * 48) });
+        assert_eq!(frame.data[2], unsafe { data.add(64 * 48 + 32 * 24) });
+        assert_eq!(frame.linesize[..3], [64, 32, 32]);
+        assert!(frame.is_writable().unwrap());
+
+        let spec = ScaleSpec {
+            width: 32,
+            height: 24,
+            format: ffi::AV_PIX_FMT_RGB24,
+            flags: SwsFlags::BILINEAR,
+            policy: Default::default(),
+        };
+        let rgb = SwsScaler::new().scale(&frame, &spec).unwrap();
+        let pixel = &rgb.data(0).unwrap()[..3];
+        assert!(pixel.iter().all(|&x| (120..=136).contains(&x)), "{pixel:?}");
+        drop(rgb);
+
+        // The bytes are released once, by the last reference, on any thread.
+        let frame1 = frame.clone();
+        std::thread::spawn(move || drop(frame1)).join().unwrap();
+        assert_eq!(nb_dropped.load(Ordering::SeqCst), 0);
+        drop(frame);
+        assert_eq!(nb_dropped.load(Ordering::SeqCst), 1);
+
+        assert!(matches!(
+            AVFrame::from_buffer(
+                AVBufferRef::from_vec(vec![0; 64 * 48]),
+                64,
+                48,
+                ffi::AV_PIX_FMT_YUV420P,
+                [64, 32, 32, 0],
+            ),
+            Err(RsmpegError::BufferTooSmallError(3072, 4608))
+        ));
+        assert!(matches!(
+            AVFrame::from_buffer(
+                AVBufferRef::from_vec(vec![0; 64 * 48 * 2]),
+                64,
+                48,
+                ffi::AV_PIX_FMT_YUV420P,
+                [32, 32, 32, 0],
+            ),
+            Err(RsmpegError::AVError(_))
+        ));
+    }
+
+    #[test]
+    fn test_from_audio_buffer() {
+        let mut bytes = vec![0; 1024 * 4 * 2];
+        bytes[1024 * 4..1024 * 4 + 4].copy_from_slice(&0.5f32.to_ne_bytes());
+        let frame = AVFrame::from_audio_buffer(
+            AVBufferRef::from_vec(bytes),
+            ffi::AV_SAMPLE_FMT_FLTP,
+            AVChannelLayout::stereo(),
+            48000,
+            1024,
+            1,
+        )
+        .unwrap();
+        assert_eq!(frame.nb_samples, 1024);
+        assert_eq!(frame.ch_layout().nb_channels, 2);
+        assert_eq!(frame.channel_samples::<f32>(0).unwrap(), &[0.0; 1024][..]);
+        assert_eq!(frame.channel_samples::<f32>(1).unwrap()[0], 0.5);
+
+        assert!(matches!(
+            AVFrame::from_audio_buffer(
+                AVBufferRef::from_vec(vec![0; 1024]),
+                ffi::AV_SAMPLE_FMT_S16,
+                AVChannelLayout::stereo(),
+                48000,
+                1024,
+                1,
+            ),
+            Err(RsmpegError::BufferTooSmallError(1024, 4096))
+        ));
+    }
+
+    #[test]
+    fn test_color_properties_and_metadata() {
+        let mut frame = AVFrame::new();
+        assert_eq!(frame.color_range(), ColorRange::Unspecified);
+        assert_eq!(frame.colorspace(), ColorSpace::Unspecified);
+        assert!(frame.metadata().is_none());
+        frame.set_color_range(ColorRange::Jpeg);
+        frame.set_color_primaries(ColorPrimaries::Bt2020);
+        frame.set_color_trc(ColorTransfer::Smpte2084);
+        frame.set_colorspace(ColorSpace::Bt2020Ncl);
+        frame.set_chroma_location(ChromaLocation::TopLeft);
+        frame.set_metadata(Some(AVDictionary::new(
+            cstr!("lavfi.key"),
+            cstr!("value"),
+            0,
+        )));
+        assert_eq!(frame.color_range(), ColorRange::Jpeg);
+        assert_eq!(frame.color_primaries(), ColorPrimaries::Bt2020);
+        assert_eq!(frame.color_trc(), ColorTransfer::Smpte2084);
+        assert_eq!(frame.colorspace(), ColorSpace::Bt2020Ncl);
+        assert_eq!(frame.chroma_location(), ChromaLocation::TopLeft);
+
+        let mut copy = AVFrame::new();
+        copy.copy_props_from(&frame).unwrap();
+        assert_eq!(copy.color_trc(), ColorTransfer::Smpte2084);
+        assert_eq!(copy.chroma_location(), ChromaLocation::TopLeft);
+        let metadata = copy.metadata().unwrap();
+        let entry = metadata.get(cstr!("lavfi.key"), None, 0).unwrap();
+        assert_eq!(entry.value(), cstr!("value"));
+        copy.set_metadata(None);
+        assert!(copy.metadata().is_none());
+
+        // Unknown values are stored as is, FFmpeg treats them as unknown.
+        frame.set_colorspace(ColorSpace::Unknown(1000));
+        assert_eq!(frame.colorspace(), ColorSpace::Unknown(1000));
+        assert_eq!(frame.colorspace().name(), None);
     }
 }
@@ -11,6 +11,9 @@ use std::{
     ptr::{self, NonNull},
 };
 
+/// A reference to a hardware device, cloning it adds a reference to the same
+/// device.
+#[derive(Clone)]
 #[repr(transparent)]
 pub struct AVHWDeviceContext {
     buffer_ref: AVBufferRef,
@@ -119,6 +122,34 @@ impl AVHWDeviceContext {
         })
     }
 
+    /// Iterate over the device types supported by the linked FFmpeg build,
+    /// which doesn't mean a device of the type is present.
+    pub fn iterate_types() -> impl Iterator<Item = ffi::AVHWDeviceType> {
+        let mut r#type = ffi::AV_HWDEVICE_TYPE_NONE;
+        std::iter::from_fn(move || {
+            r#type = unsafe { ffi::av_hwdevice_iterate_types(r#type) };
+            (r#type != ffi::AV_HWDEVICE_TYPE_NONE).then_some(r#type)
+        })
+    }
+
+    /// Get the device type of the given name, e.g. `vaapi`, `cuda` or
+    /// `videotoolbox`.
+    pub fn find_type_by_name(name: &CStr) -> Option<ffi::AVHWDeviceType> {
+        match unsafe { ffi::av_hwdevice_find_type_by_name(name.as_ptr()) } {
+            ffi::AV_HWDEVICE_TYPE_NONE => None,
+            r#type => Some(r#type),
+        }
+    }
+
+    /// Get the name of the device type.
+    pub fn type_name(r#type: ffi::AVHWDeviceType) -> Option<&'static CStr> {
+        unsafe {
+            ffi::av_hwdevice_get_type_name(r#type)
+                .upgrade()
+                .map(|x| CStr::from_ptr(x.as_ptr()))
+        }
+    }
+
     /// Allocate an [`AVHWFramesContext`] tied to a given device context.
     pub fn hwframe_ctx_alloc(&self) -> AVHWFramesContext {
         let buffer_ref = unsafe {
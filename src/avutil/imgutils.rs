@@ -1,20 +1,44 @@
-use crate::{avutil::AVPixelFormat, ffi, shared::*};
+use crate::{
+    avutil::{cpu, AVPixFmtDescriptorRef, AVPixelFormat},
+    error::*,
+    ffi,
+    shared::*,
+};
 use std::ptr::{self, NonNull};
 
 const AV_NUM_DATA_POINTERS: usize = ffi::AV_NUM_DATA_POINTERS as usize;
 
+/// Resolve the `align` argument of the functions below, 0 picks the alignment
+/// the SIMD code paths of the current CPU require.
+fn image_align(align: i32) -> i32 {
+    if align == 0 {
+        cpu::max_align() as i32
+    } else {
+        align
+    }
+}
+
 wrap! {
+    !Send
     AVImage: Vec<u8>,
-    data: [*mut u8; AV_NUM_DATA_POINTERS] = [ptr::null_mut(); AV_NUM_DATA_POINTERS],
-    linesizes: [i32; AV_NUM_DATA_POINTERS] = [0; AV_NUM_DATA_POINTERS],
-    width: i32 = 0,
-    height: i32 = 0,
-    pix_fmt: AVPixelFormat = ffi::AV_PIX_FMT_NONE,
+    pub data: [*mut u8; AV_NUM_DATA_POINTERS] = [ptr::null_mut(); AV_NUM_DATA_POINTERS],
+    pub linesizes: [i32; AV_NUM_DATA_POINTERS] = [0; AV_NUM_DATA_POINTERS],
+    pub width: i32 = 0,
+    pub height: i32 = 0,
+    pub pix_fmt: AVPixelFormat = ffi::AV_PIX_FMT_NONE,
 }
 
+// SAFETY: The `data` pointers point into the buffer owned by the image, which
+// moves along with it, and the other attachments are plain values.
+unsafe impl Send for AVImage {}
+
 impl AVImage {
+    /// Allocate an image with lines aligned to `align`, 0 for the alignment
+    /// of [`cpu::max_align()`].
+    ///
     /// Returns `None` when parameters are invalid, panic when no memory.
     pub fn new(pix_fmt: AVPixelFormat, width: i32, height: i32, align: i32) -> Option<Self> {
+        let align = image_align(align);
         let num_of_bytes = Self::get_buffer_size(pix_fmt, width, height, align)?;
 
         let mut data = [ptr::null_mut(); AV_NUM_DATA_POINTERS];
@@ -53,14 +77,84 @@ impl AVImage {
     }
 
     /// Return the size in bytes of the amount of data required to store an image
-    /// with the given parameters.
+    /// with the given parameters, `align` 0 picks [`cpu::max_align()`].
     /// Return None when invalid.
     pub fn get_buffer_size(fmt: AVPixelFormat, width: i32, height: i32, align: i32) -> Option<i32> {
-        unsafe { ffi::av_image_get_buffer_size(fmt, width, height, align) }
+        unsafe { ffi::av_image_get_buffer_size(fmt, width, height, image_align(align)) }
             .upgrade()
             .ok()
     }
 
+    /// Setup the data pointers and linesizes of an image with the given
+    /// parameters stored in `src`, without copying it. The pointers point
+    /// into `src`, they are valid as long as it is and may be written through
+    /// as `src` is borrowed mutably.
+    ///
+    /// Return an error if `src` is too small for the image.
+    pub fn fill_arrays(
+        src: &mut [u8],
+        pix_fmt: AVPixelFormat,
+        width: i32,
+        height: i32,
+        align: i32,
+    ) -> Result<([*mut u8; 4], [i32; 4])> {
+        let align = image_align(align);
+        let size = Self::get_buffer_size(pix_fmt, width, height, align)
+            .ok_or(RsmpegError::AVError(ffi::AVERROR(ffi::EINVAL)))? as usize;
+        if src.len() < size {
+            return Err(RsmpegError::BufferTooSmallError(src.len(), size));
+        }
+        let mut data = [ptr::null_mut(); 4];
+        let mut linesizes = [0; 4];
+        unsafe {
+            ffi::av_image_fill_arrays(
+                data.as_mut_ptr(),
+                linesizes.as_mut_ptr(),
+                src.as_mut_ptr(),
+                pix_fmt,
+                width,
+                height,
+                align,
+            )
+        }
+        .upgrade()?;
+        Ok((data, linesizes))
+    }
+
+    /// Copy the image into `dst` with the planes tightly packed (lines
+    /// aligned to `align`, 1 for no padding), return the number of bytes
+    /// written.
+    ///
+    /// Return an error if `dst` is smaller than [`Self::get_buffer_size()`],
+    /// or if the fields of the image describe planes out of its buffer.
+    pub fn copy_to_buffer(&self, dst: &mut [u8], align: i32) -> Result<usize> {
+        check_image_planes(
+            &self.linesizes,
+            self.pix_fmt,
+            self.width,
+            self.height,
+            |plane| self.buffer_len_after(self.data[plane]),
+        )?;
+        unsafe {
+            image_copy_to_buffer(
+                dst,
+                self.data.as_ptr() as *const *const u8,
+                self.linesizes.as_ptr(),
+                self.pix_fmt,
+                self.width,
+                self.height,
+                align,
+            )
+        }
+    }
+
+    /// Get the number of bytes from `data` to the end of the buffer of the
+    /// image, `None` if `data` doesn't point into it.
+    pub(crate) fn buffer_len_after(&self, data: *const u8) -> Option<usize> {
+        let buffer = unsafe { &**self.as_ptr() }.as_ptr_range();
+        (buffer.start <= data && data <= buffer.end).then(|| buffer.end as usize - data as usize)
+    }
+
     pub fn data(&self) -> &[*mut u8; AV_NUM_DATA_POINTERS] {
         &self.data
     }
@@ -77,6 +171,91 @@ impl Drop for AVImage {
     }
 }
 
+/// Check that the planes of an image of the given parameters, with lines of
+/// `linesizes` bytes, fit in the buffers holding them. `buffer_len(plane)`
+/// returns the number of bytes from the start of `plane` to the end of its
+/// buffer, `None` if it has none.
+///
+/// Return `AVERROR(EINVAL)` for invalid parameters or linesizes shorter than
+/// a line, [`RsmpegError::BufferTooSmallError`] if a plane doesn't fit.
+pub(crate) fn check_image_planes(
+    linesizes: &[i32],
+    pix_fmt: AVPixelFormat,
+    width: i32,
+    height: i32,
+    buffer_len: impl Fn(usize) -> Option<usize>,
+) -> Result<()> {
+    let einval = || RsmpegError::AVError(ffi::AVERROR(ffi::EINVAL));
+    let desc = AVPixFmtDescriptorRef::get(pix_fmt)
+        .filter(|desc| !desc.is_hwaccel())
+        .ok_or_else(einval)?;
+    let mut min_linesizes = [0; 4];
+    unsafe {
+        ffi::av_image_check_size(width as u32, height as u32, 0, ptr::null_mut()).upgrade()?;
+        ffi::av_image_fill_linesizes(min_linesizes.as_mut_ptr(), pix_fmt, width).upgrade()?;
+    }
+    let check = |plane: usize, need: usize| {
+        let have = buffer_len(plane).unwrap_or(0);
+        if have < need {
+            return Err(RsmpegError::BufferTooSmallError(have, need));
+        }
+        Ok(())
+    };
+    let nb_planes = desc.nb_planes();
+    for plane in 0..nb_planes {
+        let linesize = *linesizes.get(plane).ok_or_else(einval)?;
+        if linesize < min_linesizes[plane] {
+            return Err(einval());
+        }
+        // The last line doesn't need its padding.
+        let nb_lines = desc.plane_height(plane, height) as usize;
+        check(
+            plane,
+            linesize as usize * (nb_lines - 1) + min_linesizes[plane] as usize,
+        )?;
+    }
+    if desc.is_paletted() {
+        check(nb_planes, ffi::AVPALETTE_SIZE as usize)?;
+    }
+    Ok(())
+}
+
+/// `av_image_copy_to_buffer()` checking the size of `dst` up front.
+///
+/// # Safety
+/// `src_data` and `src_linesizes` need to describe a valid image of the given
+/// parameters, e.g. checked by [`check_image_planes()`].
+pub(crate) unsafe fn image_copy_to_buffer(
+    dst: &mut [u8],
+    src_data: *const *const u8,
+    src_linesizes: *const i32,
+    pix_fmt: AVPixelFormat,
+    width: i32,
+    height: i32,
+    align: i32,
+) -> Result<usize> {
+    let align = image_align(align);
+    let size = AVImage::get_buffer_size(pix_fmt, width, height, align)
+        .ok_or(RsmpegError::AVError(ffi::AVERROR(ffi::EINVAL)))? as usize;
+    if dst.len() < size {
+        return Err(RsmpegError::BufferTooSmallError(dst.len(), size));
+    }
+    let written = unsafe {
+        ffi::av_image_copy_to_buffer(
+            dst.as_mut_ptr(),
+            size as i32,
+            src_data,
+            src_linesizes,
+            pix_fmt,
+            width,
+            height,
+            align,
+        )
+    }
+    .upgrade()?;
+    Ok(written as usize)
+}
+
 /// Setup the data pointers and linesizes based on the specified image parameters
 /// and the provided array.
 ///
@@ -92,3 +271,44 @@ impl Drop for AVImage {
 /// use av_image_alloc()
 /// Hint: it doesn't copy the buffer, it just splits the buffer.
 pub use ffi::av_image_fill_arrays;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_align() {
+        let align = cpu::max_align() as i32;
+        let image = AVImage::new(ffi::AV_PIX_FMT_YUV420P, 33, 2, 0).unwrap();
+        assert_eq!(image.linesizes[0] % align, 0);
+        assert_eq!(
+            AVImage::get_buffer_size(ffi::AV_PIX_FMT_YUV420P, 33, 2, 0),
+            AVImage::get_buffer_size(ffi::AV_PIX_FMT_YUV420P, 33, 2, align)
+        );
+    }
+
+    #[test]
+    fn test_copy_to_buffer_bounds() {
+        let mut image = AVImage::new(ffi::AV_PIX_FMT_RGB24, 4, 2, 1).unwrap();
+        let mut dst = vec![0; 4 * 3 * 4];
+        assert_eq!(image.copy_to_buffer(&mut dst, 1), Ok(24));
+        // The public fields can't make it read past the buffer.
+        image.height = 4;
+        assert_eq!(
+            image.copy_to_buffer(&mut dst, 1),
+            Err(RsmpegError::BufferTooSmallError(24, 48))
+        );
+        image.height = 2;
+        image.linesizes[0] = 6;
+        assert_eq!(
+            image.copy_to_buffer(&mut dst, 1),
+            Err(RsmpegError::AVError(ffi::AVERROR(ffi::EINVAL)))
+        );
+        image.linesizes[0] = 12;
+        image.data[0] = dst.as_mut_ptr();
+        assert_eq!(
+            image.copy_to_buffer(&mut vec![0; 24], 1),
+            Err(RsmpegError::BufferTooSmallError(0, 24))
+        );
+    }
+}
@@ -0,0 +1,155 @@
+//! Logging of FFmpeg: the level of the messages printed and where they go.
+//!
+//! Messages go to stderr by default, [`set_callback()`] routes them to a Rust
+//! closure instead, e.g. a test collector or a logging framework. With the
+//! `log` feature, [`set_log_crate_callback()`] forwards them to the `log`
+//! crate.
+use crate::ffi;
+use std::{
+    cell::{Cell, RefCell},
+    ffi::{CStr, CString},
+    os::raw::{c_char, c_int, c_void},
+    panic::{self, AssertUnwindSafe},
+    sync::{Arc, RwLock},
+};
+
+/// Type of the `va_list` argument of the log callback, as generated by
+/// bindgen: the array type of x86_64 decays to a pointer in arguments.
+#[cfg(all(target_arch = "x86_64", not(target_os = "windows")))]
+type VaList = *mut ffi::__va_list_tag;
+#[cfg(not(all(target_arch = "x86_64", not(target_os = "windows"))))]
+type VaList = ffi::va_list;
+
+type LogCallback = Arc<dyn Fn(c_int, &str) + Send + Sync>;
+
+static CALLBACK: RwLock<Option<LogCallback>> = RwLock::new(None);
+
+thread_local! {
+    /// Whether the next message starts a new line, kept up to date by
+    /// `av_log_format_line2()`.
+    static PRINT_PREFIX: Cell<c_int> = Cell::new(1);
+    /// Beginning of a line logged in several parts, with the level of its
+    /// first part.
+    static PENDING: RefCell<(c_int, String)> = RefCell::new((0, String::new()));
+}
+
+/// Set the log level, messages less important than `level` are dropped, e.g.
+/// `ffi::AV_LOG_ERROR as i32`. The level is process-global.
+pub fn set_level(level: c_int) {
+    unsafe { ffi::av_log_set_level(level) }
+}
+
+/// Get the log level, `AV_LOG_INFO` by default.
+pub fn get_level() -> c_int {
+    unsafe { ffi::av_log_get_level() }
+}
+
+/// Route the log messages of FFmpeg to `callback` instead of stderr, taking
+/// the level and the line, prefixed by the name of the logging context (e.g.
+/// `[h264 @ 0x...] `) and without the trailing newline.
+///
+/// The callback is process-global and replaces the previous one. It's called
+/// from the thread logging the message, possibly several ones at once. Lines
+/// logged in several parts are passed in one call once complete. Messages
+/// less important than [`get_level()`] are dropped before reaching it, and
+/// panics in it are caught.
+pub fn set_callback<F>(callback: F)
+where
+    F: Fn(c_int, &str) + Send + Sync + 'static,
+{
+    *CALLBACK.write().unwrap_or_else(|e| e.into_inner()) = Some(Arc::new(callback));
+    unsafe { ffi::av_log_set_callback(Some(log_callback_c)) }
+}
+
+/// Go back to FFmpeg's default callback printing to stderr.
+pub fn reset_callback() {
+    unsafe { ffi::av_log_set_callback(Some(ffi::av_log_default_callback)) }
+    *CALLBACK.write().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+/// Forward the log messages of FFmpeg to the `log` crate, with the `rsmpeg`
+/// target. `AV_LOG_PANIC` to `AV_LOG_ERROR` map to [`log::Level::Error`],
+/// `AV_LOG_WARNING` to [`log::Level::Warn`], `AV_LOG_INFO` to
+/// [`log::Level::Info`], `AV_LOG_VERBOSE` and `AV_LOG_DEBUG` to
+/// [`log::Level::Debug`], `AV_LOG_TRACE` to [`log::Level::Trace`].
+#[cfg(feature = "log")]
+pub fn set_log_crate_callback() {
+    set_callback(|level, message| {
+        let level = if level <= ffi::AV_LOG_ERROR as c_int {
+            ::log::Level::Error
+        } else if level <= ffi::AV_LOG_WARNING as c_int {
+            ::log::Level::Warn
+        } else if level <= ffi::AV_LOG_INFO as c_int {
+            ::log::Level::Info
+        } else if level <= ffi::AV_LOG_DEBUG as c_int {
+            ::log::Level::Debug
+        } else {
+            ::log::Level::Trace
+        };
+        ::log::log!(target: "rsmpeg", level, "{}", message);
+    })
+}
+
+/// Log a line of text with `av_log()`, the newline is appended. Nul bytes in
+/// `message` are dropped.
+///
+/// # Safety
+/// `avcl` must be null or point to a struct whose first field is a pointer to
+/// an `AVClass`, e.g. a codec or format context.
+pub(crate) unsafe fn log_message(avcl: *mut c_void, level: c_int, message: &str) {
+    let message = CString::new(format!("{}\n", message.replace('\0', ""))).unwrap();
+    let fmt = CStr::from_bytes_with_nul(b"%s\0").unwrap();
+    unsafe { ffi::av_log(avcl, level, fmt.as_ptr(), message.as_ptr()) }
+}
+
+unsafe extern "C" fn log_callback_c(
+    avcl: *mut c_void,
+    level: c_int,
+    fmt: *const c_char,
+    vl: VaList,
+) {
+    // The upper bits can hold a color, like `AV_LOG_C()` does.
+    if level & 0xff > get_level() {
+        return;
+    }
+    let mut buf = [0 as c_char; 1024];
+    let mut print_prefix = PRINT_PREFIX.with(Cell::get);
+    let ret = unsafe {
+        ffi::av_log_format_line2(
+            avcl,
+            level,
+            fmt,
+            vl,
+            buf.as_mut_ptr(),
+            buf.len() as c_int,
+            &mut print_prefix,
+        )
+    };
+    PRINT_PREFIX.with(|x| x.set(print_prefix));
+    if ret < 0 {
+        return;
+    }
+    // Truncated to the buffer size if longer.
+    let part = unsafe { CStr::from_ptr(buf.as_ptr()) }.to_string_lossy();
+    let line = PENDING.with(|pending| {
+        let mut pending = pending.borrow_mut();
+        if pending.1.is_empty() {
+            pending.0 = level & 0xff;
+        }
+        pending.1.push_str(&part);
+        // Set once the part ends with a newline.
+        (print_prefix != 0).then(|| (pending.0, std::mem::take(&mut pending.1)))
+    });
+    let Some((level, line)) = line else {
+        return;
+    };
+    // Unwinding into C is undefined behavior.
+    let _ = panic::catch_unwind(AssertUnwindSafe(|| {
+        // Release the lock before calling, the callback may log or replace
+        // itself.
+        let callback = CALLBACK.read().unwrap_or_else(|e| e.into_inner()).clone();
+        if let Some(callback) = callback {
+            callback(level, line.trim_end_matches('\n'));
+        }
+    }));
+}
@@ -2,7 +2,7 @@ use crate::{ffi, shared::PointerUpgrade};
 
 wrap! {
     AVMem: u8,
-    len: usize = 0
+    pub len: usize = 0
 }
 
 impl AVMem {
@@ -2,12 +2,15 @@
 mod audio_fifo;
 mod buffer;
 mod channel_layout;
+pub mod cpu;
 mod dict;
+mod display;
 mod error;
 mod file;
 mod frame;
 mod hwcontext;
 mod imgutils;
+pub mod log;
 mod media_type;
 mod mem;
 mod motion_vector;
@@ -23,6 +26,7 @@ pub use audio_fifo::*;
 pub use buffer::*;
 pub use channel_layout::*;
 pub use dict::*;
+pub use display::*;
 pub use error::*;
 pub use file::*;
 pub use frame::*;
@@ -1,10 +1,21 @@
 use crate::{
+    avcodec::AVCodecContext,
+    avfilter::{AVFilterContext, AVFilterContextMut},
+    avformat::{AVFormatContextInput, AVFormatContextOutput},
     error::Result,
     ffi,
     ffi::{AVPixelFormat, AVRational, AVSampleFormat},
-    shared::RetUpgrade,
+    shared::{PointerUpgrade, RetUpgrade},
+    swresample::SwrContext,
+    swscale::SwsContext,
+};
+use std::{
+    collections::VecDeque,
+    ffi::{c_char, c_double, c_int, c_void, CStr, CString},
+    iter::FusedIterator,
+    marker::PhantomData,
+    ptr,
 };
-use std::ffi::{c_double, c_int, c_void, CStr};
 
 /// - `name`: the name of the field to set
 /// - `val`: if the field is not of a string type, then the given string is parsed.
@@ -155,3 +166,289 @@ pub unsafe fn opt_set_video_rate(
     unsafe { ffi::av_opt_set_video_rate(obj, name.as_ptr(), val, search_flags) }.upgrade()?;
     Ok(())
 }
+
+/// Wrappers of structs whose first field is a pointer to an `AVClass`, their
+/// AVOptions can be listed with [`options_iter()`] and read with
+/// [`get_opt_str()`] and friends.
+///
+/// # Safety
+///
+/// [`Self::opt_obj()`] must return a pointer to such a struct, valid as long
+/// as `self`.
+pub unsafe trait OptObject {
+    fn opt_obj(&self) -> *mut c_void;
+}
+
+macro_rules! impl_opt_object {
+    ($($ty:ty),+ $(,)?) => {
+        $(
+            unsafe impl OptObject for $ty {
+                fn opt_obj(&self) -> *mut c_void {
+                    self.as_ptr() as *mut c_void
+                }
+            }
+        )+
+    };
+}
+
+impl_opt_object!(
+    AVCodecContext,
+    AVFilterContext,
+    AVFilterContextMut<'_>,
+    AVFormatContextInput,
+    AVFormatContextOutput,
+    SwsContext,
+    SwrContext,
+);
+
+/// Default value of an AVOption, depending on its type.
+#[derive(Debug, Clone, Copy)]
+pub enum AVOptionDefault<'a> {
+    /// Integer, flags, boolean, pixel or sample format, duration and
+    /// constant options.
+    Int(i64),
+    /// Double and float options.
+    Double(f64),
+    /// Rational options.
+    Rational(AVRational),
+    /// String, image size, video rate, color, channel layout and dictionary
+    /// options.
+    Str(Option<&'a CStr>),
+    /// Binary and array options.
+    None,
+}
+
+/// Description of an AVOption, yielded by [`options_iter()`].
+#[derive(Debug, Clone, Copy)]
+pub struct AVOptionInfo<'a> {
+    pub name: &'a CStr,
+    /// Short English help text.
+    pub help: Option<&'a CStr>,
+    /// `AV_OPT_TYPE_*`.
+    pub r#type: ffi::AVOptionType,
+    pub default: AVOptionDefault<'a>,
+    pub min: f64,
+    pub max: f64,
+    /// Combination of `AV_OPT_FLAG_*`.
+    pub flags: c_int,
+    /// Named constants of an option and the constants themselves share the
+    /// same unit.
+    pub unit: Option<&'a CStr>,
+}
+
+impl<'a> AVOptionInfo<'a> {
+    /// # Safety
+    ///
+    /// `option` must point to a valid `AVOption` living for `'a`.
+    unsafe fn from_raw(option: &'a ffi::AVOption) -> Self {
+        let cstr = |x: *const c_char| x.upgrade().map(|x| unsafe { CStr::from_ptr(x.as_ptr()) });
+        let r#type = option.type_;
+        let default = unsafe {
+            match r#type {
+                #[cfg(feature = "ffmpeg7")]
+                _ if r#type as u32 & ffi::AV_OPT_TYPE_FLAG_ARRAY as u32 != 0 => {
+                    AVOptionDefault::None
+                }
+                ffi::AV_OPT_TYPE_FLAGS
+                | ffi::AV_OPT_TYPE_INT
+                | ffi::AV_OPT_TYPE_INT64
+                | ffi::AV_OPT_TYPE_UINT64
+                | ffi::AV_OPT_TYPE_CONST
+                | ffi::AV_OPT_TYPE_PIXEL_FMT
+                | ffi::AV_OPT_TYPE_SAMPLE_FMT
+                | ffi::AV_OPT_TYPE_DURATION
+                | ffi::AV_OPT_TYPE_BOOL => AVOptionDefault::Int(option.default_val.i64_),
+                ffi::AV_OPT_TYPE_DOUBLE | ffi::AV_OPT_TYPE_FLOAT => {
+                    AVOptionDefault::Double(option.default_val.dbl)
+                }
+                ffi::AV_OPT_TYPE_RATIONAL => AVOptionDefault::Rational(option.default_val.q),
+                ffi::AV_OPT_TYPE_STRING
+                | ffi::AV_OPT_TYPE_IMAGE_SIZE
+                | ffi::AV_OPT_TYPE_VIDEO_RATE
+                | ffi::AV_OPT_TYPE_COLOR
+                | ffi::AV_OPT_TYPE_CHLAYOUT
+                | ffi::AV_OPT_TYPE_DICT => AVOptionDefault::Str(cstr(option.default_val.str_)),
+                _ => AVOptionDefault::None,
+            }
+        };
+        Self {
+            name: unsafe { CStr::from_ptr(option.name) },
+            help: cstr(option.help),
+            r#type,
+            default,
+            min: option.min,
+            max: option.max,
+            flags: option.flags,
+            unit: cstr(option.unit),
+        }
+    }
+
+    /// Return true if the option is a named constant of the options of the
+    /// same unit rather than an option.
+    pub fn is_const(&self) -> bool {
+        self.r#type == ffi::AV_OPT_TYPE_CONST
+    }
+}
+
+/// Iterator of the AVOptions of an object and of its children (e.g. the
+/// private options of a codec), created by [`options_iter()`].
+pub struct AVOptionIter<'a> {
+    obj: *mut c_void,
+    prev: *const ffi::AVOption,
+    children: VecDeque<*mut c_void>,
+    _marker: PhantomData<&'a ()>,
+}
+
+impl<'a> Iterator for AVOptionIter<'a> {
+    type Item = AVOptionInfo<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.obj.is_null() {
+                return None;
+            }
+            if let Some(option) = unsafe { ffi::av_opt_next(self.obj, self.prev).as_ref() } {
+                self.prev = option;
+                return Some(unsafe { AVOptionInfo::from_raw(option) });
+            }
+            let mut child = ptr::null_mut();
+            loop {
+                child = unsafe { ffi::av_opt_child_next(self.obj, child) };
+                if child.is_null() {
+                    break;
+                }
+                self.children.push_back(child);
+            }
+            self.obj = self.children.pop_front().unwrap_or_else(ptr::null_mut);
+            self.prev = ptr::null();
+        }
+    }
+}
+
+impl FusedIterator for AVOptionIter<'_> {}
+
+/// Iterate over the AVOptions of `obj`, then of its children, e.g. the
+/// private options of the codec of an [`AVCodecContext`]. The named
+/// constants of an option follow it, see [`option_consts()`].
+pub fn options_iter<T: OptObject + ?Sized>(obj: &T) -> AVOptionIter<'_> {
+    AVOptionIter {
+        obj: obj.opt_obj(),
+        prev: ptr::null(),
+        children: VecDeque::new(),
+        _marker: PhantomData,
+    }
+}
+
+/// Get the named constants of `unit`, e.g. the values of the `flags` option
+/// of an [`AVCodecContext`], or of the `preset` option of libx264.
+pub fn option_consts<'a, T: OptObject + ?Sized>(
+    obj: &'a T,
+    unit: &'a CStr,
+) -> impl Iterator<Item = AVOptionInfo<'a>> + 'a {
+    options_iter(obj).filter(move |x| x.is_const() && x.unit == Some(unit))
+}
+
+/// Get the value of option `name` of `obj` or of its children, converted to
+/// a string.
+pub fn get_opt_str<T: OptObject + ?Sized>(obj: &T, name: &CStr) -> Result<CString> {
+    let mut value = ptr::null_mut();
+    unsafe {
+        ffi::av_opt_get(
+            obj.opt_obj(),
+            name.as_ptr(),
+            ffi::AV_OPT_SEARCH_CHILDREN as c_int,
+            &mut value,
+        )
+    }
+    .upgrade()?;
+    let string = unsafe { CStr::from_ptr(value as *const _) }.to_owned();
+    unsafe { ffi::av_free(value.cast()) };
+    Ok(string)
+}
+
+/// Get the value of integer option `name` of `obj` or of its children.
+pub fn get_opt_int<T: OptObject + ?Sized>(obj: &T, name: &CStr) -> Result<i64> {
+    let mut value = 0;
+    unsafe {
+        ffi::av_opt_get_int(
+            obj.opt_obj(),
+            name.as_ptr(),
+            ffi::AV_OPT_SEARCH_CHILDREN as c_int,
+            &mut value,
+        )
+    }
+    .upgrade()?;
+    Ok(value)
+}
+
+/// Get the value of floating-point option `name` of `obj` or of its
+/// children.
+pub fn get_opt_double<T: OptObject + ?Sized>(obj: &T, name: &CStr) -> Result<f64> {
+    let mut value = 0.0;
+    unsafe {
+        ffi::av_opt_get_double(
+            obj.opt_obj(),
+            name.as_ptr(),
+            ffi::AV_OPT_SEARCH_CHILDREN as c_int,
+            &mut value,
+        )
+    }
+    .upgrade()?;
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        avcodec::AVCodec,
+        avfilter::{AVFilter, AVFilterGraph},
+    };
+    use cstr::cstr;
+
+    #[test]
+    fn test_filter_options() {
+        let filter_graph = AVFilterGraph::new();
+        let scale = AVFilter::get_by_name(cstr!("scale")).unwrap();
+        let scale_ctx = filter_graph
+            .create_filter_context(&scale, cstr!("scale"), Some(cstr!("w=32:h=24")))
+            .unwrap();
+        let options: Vec<_> = options_iter(&scale_ctx).collect();
+        for name in [cstr!("width"), cstr!("w")] {
+            let option = options.iter().find(|x| x.name == name).unwrap();
+            assert_eq!(option.r#type, ffi::AV_OPT_TYPE_STRING);
+            assert!(option.help.is_some());
+        }
+        assert_eq!(
+            get_opt_str(&scale_ctx, cstr!("w")).unwrap().as_c_str(),
+            cstr!("32")
+        );
+        assert!(get_opt_str(&scale_ctx, cstr!("no_such_option")).is_err());
+    }
+
+    #[test]
+    fn test_codec_options() {
+        let encoder = AVCodec::find_encoder(ffi::AV_CODEC_ID_MPEG4).unwrap();
+        let mut context = AVCodecContext::new(&encoder);
+        context.set_opt_int(cstr!("g"), 5).unwrap();
+        assert_eq!(get_opt_int(&context, cstr!("g")).unwrap(), 5);
+        assert_eq!(get_opt_double(&context, cstr!("g")).unwrap(), 5.0);
+
+        let gop_size = options_iter(&context)
+            .find(|x| x.name == cstr!("g"))
+            .unwrap();
+        assert!(matches!(gop_size.default, AVOptionDefault::Int(12)));
+        assert_eq!(gop_size.min, i32::MIN as f64);
+        // Private options of the encoder are listed after the generic ones.
+        assert!(options_iter(&context).any(|x| x.name == cstr!("data_partitioning")));
+
+        let flags = options_iter(&context)
+            .find(|x| x.name == cstr!("flags") && !x.is_const())
+            .unwrap();
+        assert_eq!(flags.unit, Some(cstr!("flags")));
+        let consts: Vec<_> = option_consts(&context, cstr!("flags"))
+            .map(|x| x.name)
+            .collect();
+        assert!(consts.contains(&cstr!("global_header")));
+    }
+}
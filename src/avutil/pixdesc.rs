@@ -1,6 +1,10 @@
-use crate::{ffi, shared::PointerUpgrade};
+use crate::{
+    avutil::AVPixelFormat,
+    ffi,
+    shared::{PointerUpgrade, RetUpgrade},
+};
 
-use std::{ffi::CStr, ops::Deref, ptr::NonNull};
+use std::{ffi::CStr, ops::Deref, os::raw::c_int, ptr::NonNull};
 
 pub use ffi::AVComponentDescriptor;
 
@@ -56,6 +60,229 @@ impl AVPixFmtDescriptorRef {
             .upgrade()
             .map(|x| unsafe { CStr::from_ptr(x.as_ptr()) })
     }
+
+    /// Number of components of a pixel, 1 to 4, e.g. 3 for YUV and 4 for
+    /// RGBA. Zero for the hardware formats.
+    pub fn nb_components(&self) -> usize {
+        self.nb_components as usize
+    }
+
+    /// Amount to shift the luma width right to get the chroma width, e.g. 1
+    /// for 4:2:0 and 4:2:2.
+    pub fn log2_chroma_w(&self) -> u8 {
+        self.log2_chroma_w
+    }
+
+    /// Amount to shift the luma height right to get the chroma height, e.g.
+    /// 1 for 4:2:0 and 0 for 4:2:2.
+    pub fn log2_chroma_h(&self) -> u8 {
+        self.log2_chroma_h
+    }
+
+    /// Get the flags of the pixel format, a combination of `AV_PIX_FMT_FLAG_*`.
+    pub fn flags(&self) -> u64 {
+        self.flags
+    }
+
+    fn has_flag(&self, flag: u32) -> bool {
+        self.flags & flag as u64 != 0
+    }
+
+    /// Return true if at least one component is in a separate plane, e.g.
+    /// yuv420p and nv12.
+    pub fn is_planar(&self) -> bool {
+        self.has_flag(ffi::AV_PIX_FMT_FLAG_PLANAR)
+    }
+
+    /// Return true if the pixel format has an alpha channel.
+    pub fn has_alpha(&self) -> bool {
+        self.has_flag(ffi::AV_PIX_FMT_FLAG_ALPHA)
+    }
+
+    /// Return true if the pixel format is an opaque hardware surface, its
+    /// data can't be accessed from the CPU.
+    pub fn is_hwaccel(&self) -> bool {
+        self.has_flag(ffi::AV_PIX_FMT_FLAG_HWACCEL)
+    }
+
+    /// Return true if the pixel format is RGB-like, false for YUV and gray.
+    pub fn is_rgb(&self) -> bool {
+        self.has_flag(ffi::AV_PIX_FMT_FLAG_RGB)
+    }
+
+    /// Return true if the pixel format has a palette in the plane after its
+    /// last one.
+    pub fn is_paletted(&self) -> bool {
+        self.has_flag(ffi::AV_PIX_FMT_FLAG_PAL)
+    }
+
+    /// Return true if the components are stored big-endian.
+    pub fn is_big_endian(&self) -> bool {
+        self.has_flag(ffi::AV_PIX_FMT_FLAG_BE)
+    }
+
+    /// Get the number of bits of `component`, e.g. 10 for the components of
+    /// yuv420p10le. Return `None` if the pixel format has no such component.
+    pub fn component_depth(&self, component: usize) -> Option<i32> {
+        (component < self.nb_components()).then(|| self.comp[component].depth)
+    }
+
+    /// Get the number of bits per pixel used by the pixel format, not
+    /// counting the padding bits, e.g. 12 for yuv420p.
+    pub fn bits_per_pixel(&self) -> i32 {
+        unsafe { ffi::av_get_bits_per_pixel(self.0.as_ptr()) }
+    }
+
+    /// Get the number of planes of the pixel format, not counting the
+    /// palette, e.g. 3 for yuv420p and 2 for nv12.
+    pub fn nb_planes(&self) -> usize {
+        self.comp[..self.nb_components()]
+            .iter()
+            .map(|comp| comp.plane as usize + 1)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Get the height of `plane` of an image of `height`: chroma planes
+    /// (1 and 2) are subsampled, rounding up.
+    pub fn plane_height(&self, plane: usize, height: i32) -> i32 {
+        if plane == 1 || plane == 2 {
+            -((-height) >> self.log2_chroma_h)
+        } else {
+            height
+        }
+    }
+}
+
+/// Return the short name of given pixel format, or `None` if pix_fmt is not
+/// recognized.
+///
+/// ```rust
+/// # use rsmpeg::avutil::get_pix_fmt_name;
+/// # use rsmpeg::ffi::AV_PIX_FMT_YUV420P;
+/// # use std::ffi::CString;
+/// # fn main() {
+/// assert_eq!(
+///     CString::new("yuv420p").ok().as_deref(),
+///     get_pix_fmt_name(AV_PIX_FMT_YUV420P)
+/// );
+/// # }
+/// ```
+pub fn get_pix_fmt_name(pix_fmt: AVPixelFormat) -> Option<&'static CStr> {
+    unsafe {
+        ffi::av_get_pix_fmt_name(pix_fmt)
+            .upgrade()
+            .map(|x| CStr::from_ptr(x.as_ptr()))
+    }
+}
+
+/// Return the pixel format corresponding to name(or alias), or `None` if it's
+/// not recognized. Names without endianness suffix, e.g. `rgb48`, resolve to the
+/// native endianness.
+///
+/// ```rust
+/// # use rsmpeg::avutil::get_pix_fmt;
+/// # use rsmpeg::ffi::AV_PIX_FMT_YUV420P;
+/// # use std::ffi::CString;
+/// # fn main() {
+/// assert_eq!(
+///     Some(AV_PIX_FMT_YUV420P),
+///     get_pix_fmt(&CString::new("yuv420p").unwrap())
+/// );
+/// # }
+/// ```
+pub fn get_pix_fmt(name: &CStr) -> Option<AVPixelFormat> {
+    let pix_fmt = unsafe { ffi::av_get_pix_fmt(name.as_ptr()) };
+    match pix_fmt {
+        ffi::AV_PIX_FMT_NONE => None,
+        _ => Some(pix_fmt),
+    }
+}
+
+/// Get the number of planes of `pix_fmt`, not counting the palette. Return
+/// `None` if the pixel format is unknown.
+pub fn pix_fmt_count_planes(pix_fmt: AVPixelFormat) -> Option<usize> {
+    unsafe { ffi::av_pix_fmt_count_planes(pix_fmt) }
+        .upgrade()
+        .ok()
+        .map(|x| x as usize)
+}
+
+/// Return the pixel format with swapped endianness, e.g. `rgb48le` =>
+/// `rgb48be`, or `None` if the format has no such counterpart.
+pub fn pix_fmt_swap_endianness(pix_fmt: AVPixelFormat) -> Option<AVPixelFormat> {
+    let pix_fmt = unsafe { ffi::av_pix_fmt_swap_endianness(pix_fmt) };
+    match pix_fmt {
+        ffi::AV_PIX_FMT_NONE => None,
+        _ => Some(pix_fmt),
+    }
+}
+
+/// Non exhaustive wrapper of the loss of a pixel format conversion, a
+/// combination of `FF_LOSS_*`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PixFmtLoss(pub c_int);
+
+impl PixFmtLoss {
+    /// Return true if all the bits of `flag` are set.
+    pub fn contains(&self, flag: u32) -> bool {
+        self.0 & flag as c_int == flag as c_int
+    }
+
+    /// Return true if the conversion loses nothing.
+    pub fn is_lossless(&self) -> bool {
+        self.0 == 0
+    }
+
+    /// Loss due to resolution change, including chroma subsampling.
+    pub fn is_resolution(&self) -> bool {
+        self.contains(ffi::FF_LOSS_RESOLUTION)
+    }
+
+    /// Loss due to color depth change.
+    pub fn is_depth(&self) -> bool {
+        self.contains(ffi::FF_LOSS_DEPTH)
+    }
+
+    /// Loss due to color space conversion.
+    pub fn is_colorspace(&self) -> bool {
+        self.contains(ffi::FF_LOSS_COLORSPACE)
+    }
+
+    /// Loss of alpha bits.
+    pub fn is_alpha(&self) -> bool {
+        self.contains(ffi::FF_LOSS_ALPHA)
+    }
+
+    /// Loss due to color quantization.
+    pub fn is_colorquant(&self) -> bool {
+        self.contains(ffi::FF_LOSS_COLORQUANT)
+    }
+
+    /// Loss of chroma, i.e. conversion to gray.
+    pub fn is_chroma(&self) -> bool {
+        self.contains(ffi::FF_LOSS_CHROMA)
+    }
+}
+
+/// Compute what kind of losses will occur when converting from `src` to `dst`.
+/// `has_alpha` tells whether the source alpha channel is used.
+pub fn get_pix_fmt_loss(dst: AVPixelFormat, src: AVPixelFormat, has_alpha: bool) -> PixFmtLoss {
+    PixFmtLoss(unsafe { ffi::av_get_pix_fmt_loss(dst, src, has_alpha as c_int) })
+}
+
+/// Pick the one of `dst1` and `dst2` that loses the least when converting
+/// from `src`, return it together with the loss of the conversion.
+pub fn find_best_pix_fmt_of_2(
+    dst1: AVPixelFormat,
+    dst2: AVPixelFormat,
+    src: AVPixelFormat,
+    has_alpha: bool,
+) -> (AVPixelFormat, PixFmtLoss) {
+    let mut loss = 0;
+    let pix_fmt =
+        unsafe { ffi::av_find_best_pix_fmt_of_2(dst1, dst2, src, has_alpha as c_int, &mut loss) };
+    (pix_fmt, PixFmtLoss(loss))
 }
 
 #[cfg(test)]
@@ -93,6 +320,47 @@ mod tests {
         assert_eq!(pix_fmt_desc.alias(), Some(cstr!("y9le")));
     }
 
+    #[test]
+    fn test_pix_fmt_desc_queries() {
+        let desc = AVPixFmtDescriptorRef::get(ffi::AV_PIX_FMT_YUV420P).unwrap();
+        assert_eq!(desc.nb_components(), 3);
+        assert_eq!(desc.nb_planes(), 3);
+        assert_eq!((desc.log2_chroma_w(), desc.log2_chroma_h()), (1, 1));
+        assert!(desc.is_planar());
+        assert!(!desc.has_alpha());
+        assert!(!desc.is_rgb());
+        assert_eq!(desc.component_depth(0), Some(8));
+        assert_eq!(desc.component_depth(3), None);
+        assert_eq!(desc.bits_per_pixel(), 12);
+        assert_eq!(desc.plane_height(0, 7), 7);
+        assert_eq!(desc.plane_height(1, 7), 4);
+        assert_eq!(pix_fmt_count_planes(ffi::AV_PIX_FMT_YUV420P), Some(3));
+
+        let desc = AVPixFmtDescriptorRef::get(ffi::AV_PIX_FMT_RGBA).unwrap();
+        assert_eq!(desc.nb_components(), 4);
+        assert_eq!(desc.nb_planes(), 1);
+        assert!(desc.has_alpha());
+        assert!(desc.is_rgb());
+        assert!(!desc.is_planar());
+        assert_eq!(desc.bits_per_pixel(), 32);
+
+        let desc = AVPixFmtDescriptorRef::get(ffi::AV_PIX_FMT_NV12).unwrap();
+        assert_eq!(desc.nb_planes(), 2);
+        assert_eq!(pix_fmt_count_planes(ffi::AV_PIX_FMT_NV12), Some(2));
+
+        let desc = AVPixFmtDescriptorRef::get(ffi::AV_PIX_FMT_YUV420P10BE).unwrap();
+        assert_eq!(desc.component_depth(2), Some(10));
+        assert!(desc.is_big_endian());
+
+        assert!(AVPixFmtDescriptorRef::get(ffi::AV_PIX_FMT_PAL8)
+            .unwrap()
+            .is_paletted());
+        let desc = AVPixFmtDescriptorRef::get(ffi::AV_PIX_FMT_VAAPI).unwrap();
+        assert!(desc.is_hwaccel());
+        assert_eq!(desc.nb_planes(), 0);
+        assert_eq!(pix_fmt_count_planes(ffi::AV_PIX_FMT_NONE), None);
+    }
+
     #[test]
     fn test_pix_fmt_desc_next() {
         let pix_fmt_desc = AVPixFmtDescriptorRef::get(ffi::AV_PIX_FMT_GRAYF32BE).unwrap();
@@ -104,6 +372,81 @@ mod tests {
         assert_eq!(next.alias(), Some(cstr!("yf32le")));
     }
 
+    #[test]
+    fn test_pix_fmt_name() {
+        for (pix_fmt, name) in [
+            (ffi::AV_PIX_FMT_YUV420P, cstr!("yuv420p")),
+            (ffi::AV_PIX_FMT_YUYV422, cstr!("yuyv422")),
+            (ffi::AV_PIX_FMT_RGB24, cstr!("rgb24")),
+            (ffi::AV_PIX_FMT_BGR24, cstr!("bgr24")),
+            (ffi::AV_PIX_FMT_YUV422P, cstr!("yuv422p")),
+            (ffi::AV_PIX_FMT_YUV444P, cstr!("yuv444p")),
+            (ffi::AV_PIX_FMT_GRAY8, cstr!("gray")),
+            (ffi::AV_PIX_FMT_YUVJ420P, cstr!("yuvj420p")),
+            (ffi::AV_PIX_FMT_NV12, cstr!("nv12")),
+            (ffi::AV_PIX_FMT_RGBA, cstr!("rgba")),
+            (ffi::AV_PIX_FMT_BGRA, cstr!("bgra")),
+            (ffi::AV_PIX_FMT_RGB48BE, cstr!("rgb48be")),
+            (ffi::AV_PIX_FMT_YUV420P10LE, cstr!("yuv420p10le")),
+            (ffi::AV_PIX_FMT_P010LE, cstr!("p010le")),
+        ] {
+            assert_eq!(get_pix_fmt_name(pix_fmt), Some(name));
+            assert_eq!(get_pix_fmt(name), Some(pix_fmt));
+        }
+        assert_eq!(get_pix_fmt_name(ffi::AV_PIX_FMT_NONE), None);
+        assert_eq!(get_pix_fmt(cstr!("not_a_format")), None);
+        // Aliases are accepted.
+        assert_eq!(get_pix_fmt(cstr!("y9le")), Some(ffi::AV_PIX_FMT_GRAY9LE));
+    }
+
+    #[test]
+    fn test_pix_fmt_swap_endianness() {
+        assert_eq!(
+            pix_fmt_swap_endianness(ffi::AV_PIX_FMT_RGB48LE),
+            Some(ffi::AV_PIX_FMT_RGB48BE)
+        );
+        assert_eq!(
+            pix_fmt_swap_endianness(ffi::AV_PIX_FMT_YUV420P10BE),
+            Some(ffi::AV_PIX_FMT_YUV420P10LE)
+        );
+        assert_eq!(pix_fmt_swap_endianness(ffi::AV_PIX_FMT_YUV420P), None);
+    }
+
+    #[test]
+    fn test_pix_fmt_loss() {
+        let loss = get_pix_fmt_loss(ffi::AV_PIX_FMT_YUV420P, ffi::AV_PIX_FMT_RGBA, true);
+        assert!(loss.is_alpha());
+        // Chroma subsampling is reported as resolution loss.
+        assert!(loss.is_resolution());
+        assert!(loss.is_colorspace());
+        assert!(!loss.is_chroma());
+        assert!(!loss.is_depth());
+
+        let loss = get_pix_fmt_loss(ffi::AV_PIX_FMT_GRAY8, ffi::AV_PIX_FMT_RGBA, true);
+        assert!(loss.is_alpha());
+        assert!(loss.is_chroma());
+
+        // Alpha is not lost if it's unused.
+        let loss = get_pix_fmt_loss(ffi::AV_PIX_FMT_YUV420P, ffi::AV_PIX_FMT_RGBA, false);
+        assert!(!loss.is_alpha());
+
+        let loss = get_pix_fmt_loss(ffi::AV_PIX_FMT_YUV420P, ffi::AV_PIX_FMT_YUV420P10LE, false);
+        assert!(loss.is_depth());
+        assert!(
+            get_pix_fmt_loss(ffi::AV_PIX_FMT_YUV444P, ffi::AV_PIX_FMT_YUV420P, false).is_lossless()
+        );
+
+        let (pix_fmt, loss) = find_best_pix_fmt_of_2(
+            ffi::AV_PIX_FMT_YUV420P,
+            ffi::AV_PIX_FMT_YUVA420P,
+            ffi::AV_PIX_FMT_RGBA,
+            true,
+        );
+        assert_eq!(pix_fmt, ffi::AV_PIX_FMT_YUVA420P);
+        assert!(!loss.is_alpha());
+        assert!(loss.is_resolution());
+    }
+
     #[test]
     fn test_pix_fmt_get_id() {
         let pix_fmt = ffi::AV_PIX_FMT_YUVA444P12LE;
@@ -1 +1,183 @@
 pub use crate::ffi::AVPixelFormat;
+
+use crate::{ffi, shared::PointerUpgrade};
+use std::ffi::CStr;
+
+/// Rust enum of a color property of `AVFrame`, with an `Unknown` fallback for
+/// the values this enum doesn't know, e.g. the ones of a newer FFmpeg.
+macro_rules! color_enum {
+    (
+        $(#[$meta:meta])*
+        $name:ident: $ffi_type:ty, $name_fn:ident {
+            $($(#[$variant_meta:meta])* $variant:ident = $value:path,)*
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy, Eq)]
+        pub enum $name {
+            $($(#[$variant_meta])* $variant,)*
+            /// A raw value not listed, passed through as is. It's equal to the
+            /// variant [`Self::from_raw()`] gives for the same value.
+            Unknown(i32),
+        }
+
+        impl PartialEq for $name {
+            fn eq(&self, other: &Self) -> bool {
+                self.into_raw() == other.into_raw()
+            }
+        }
+
+        impl $name {
+            /// Convert the raw value, `Unknown` is only given for the values
+            /// not listed.
+            pub fn from_raw(value: $ffi_type) -> Self {
+                $(
+                    if value == $value {
+                        return Self::$variant;
+                    }
+                )*
+                Self::Unknown(value as i32)
+            }
+
+            /// Get the raw value.
+            pub fn into_raw(self) -> $ffi_type {
+                match self {
+                    $(Self::$variant => $value,)*
+                    Self::Unknown(value) => value as $ffi_type,
+                }
+            }
+
+            /// Get the name FFmpeg gives to the value, e.g. in the options of
+            /// filters, `None` if it's out of range.
+            pub fn name(self) -> Option<&'static CStr> {
+                unsafe { ffi::$name_fn(self.into_raw()) }
+                    .upgrade()
+                    .map(|x| unsafe { CStr::from_ptr(x.as_ptr()) })
+            }
+        }
+    };
+}
+
+color_enum! {
+    /// Range of the values of the components, `AVColorRange`.
+    ColorRange: ffi::AVColorRange, av_color_range_name {
+        Unspecified = ffi::AVCOL_RANGE_UNSPECIFIED,
+        /// Limited range, e.g. 16-235 for 8 bits luma, usual for video.
+        Mpeg = ffi::AVCOL_RANGE_MPEG,
+        /// Full range, e.g. 0-255 for 8 bits, usual for images.
+        Jpeg = ffi::AVCOL_RANGE_JPEG,
+    }
+}
+
+color_enum! {
+    /// Chromaticity coordinates of the source primaries, `AVColorPrimaries`.
+    ColorPrimaries: ffi::AVColorPrimaries, av_color_primaries_name {
+        Bt709 = ffi::AVCOL_PRI_BT709,
+        Unspecified = ffi::AVCOL_PRI_UNSPECIFIED,
+        Bt470M = ffi::AVCOL_PRI_BT470M,
+        Bt470Bg = ffi::AVCOL_PRI_BT470BG,
+        Smpte170M = ffi::AVCOL_PRI_SMPTE170M,
+        Smpte240M = ffi::AVCOL_PRI_SMPTE240M,
+        Film = ffi::AVCOL_PRI_FILM,
+        Bt2020 = ffi::AVCOL_PRI_BT2020,
+        Smpte428 = ffi::AVCOL_PRI_SMPTE428,
+        Smpte431 = ffi::AVCOL_PRI_SMPTE431,
+        Smpte432 = ffi::AVCOL_PRI_SMPTE432,
+        Ebu3213 = ffi::AVCOL_PRI_EBU3213,
+    }
+}
+
+color_enum! {
+    /// Transfer characteristic, `AVColorTransferCharacteristic`.
+    ColorTransfer: ffi::AVColorTransferCharacteristic, av_color_transfer_name {
+        Bt709 = ffi::AVCOL_TRC_BT709,
+        Unspecified = ffi::AVCOL_TRC_UNSPECIFIED,
+        Gamma22 = ffi::AVCOL_TRC_GAMMA22,
+        Gamma28 = ffi::AVCOL_TRC_GAMMA28,
+        Smpte170M = ffi::AVCOL_TRC_SMPTE170M,
+        Smpte240M = ffi::AVCOL_TRC_SMPTE240M,
+        Linear = ffi::AVCOL_TRC_LINEAR,
+        Log = ffi::AVCOL_TRC_LOG,
+        LogSqrt = ffi::AVCOL_TRC_LOG_SQRT,
+        Iec61966_2_4 = ffi::AVCOL_TRC_IEC61966_2_4,
+        Bt1361Ecg = ffi::AVCOL_TRC_BT1361_ECG,
+        /// sRGB.
+        Iec61966_2_1 = ffi::AVCOL_TRC_IEC61966_2_1,
+        Bt2020_10 = ffi::AVCOL_TRC_BT2020_10,
+        Bt2020_12 = ffi::AVCOL_TRC_BT2020_12,
+        /// PQ of HDR10.
+        Smpte2084 = ffi::AVCOL_TRC_SMPTE2084,
+        Smpte428 = ffi::AVCOL_TRC_SMPTE428,
+        /// HLG.
+        AribStdB67 = ffi::AVCOL_TRC_ARIB_STD_B67,
+    }
+}
+
+color_enum! {
+    /// YUV colorspace, i.e. the matrix coefficients, `AVColorSpace`.
+    ColorSpace: ffi::AVColorSpace, av_color_space_name {
+        Rgb = ffi::AVCOL_SPC_RGB,
+        Bt709 = ffi::AVCOL_SPC_BT709,
+        Unspecified = ffi::AVCOL_SPC_UNSPECIFIED,
+        Fcc = ffi::AVCOL_SPC_FCC,
+        Bt470Bg = ffi::AVCOL_SPC_BT470BG,
+        Smpte170M = ffi::AVCOL_SPC_SMPTE170M,
+        Smpte240M = ffi::AVCOL_SPC_SMPTE240M,
+        YCgCo = ffi::AVCOL_SPC_YCGCO,
+        Bt2020Ncl = ffi::AVCOL_SPC_BT2020_NCL,
+        Bt2020Cl = ffi::AVCOL_SPC_BT2020_CL,
+        Smpte2085 = ffi::AVCOL_SPC_SMPTE2085,
+        ChromaDerivedNcl = ffi::AVCOL_SPC_CHROMA_DERIVED_NCL,
+        ChromaDerivedCl = ffi::AVCOL_SPC_CHROMA_DERIVED_CL,
+        ICtCp = ffi::AVCOL_SPC_ICTCP,
+    }
+}
+
+color_enum! {
+    /// Location of the chroma samples relative to the luma ones,
+    /// `AVChromaLocation`.
+    ChromaLocation: ffi::AVChromaLocation, av_chroma_location_name {
+        Unspecified = ffi::AVCHROMA_LOC_UNSPECIFIED,
+        /// MPEG-2/4 4:2:0, H.264 default for 4:2:0.
+        Left = ffi::AVCHROMA_LOC_LEFT,
+        /// MPEG-1 4:2:0, JPEG 4:2:0, H.263 4:2:0.
+        Center = ffi::AVCHROMA_LOC_CENTER,
+        /// ITU-R 601, SMPTE 274M 296M S314M(DV 4:1:1), mpeg2 4:2:2.
+        TopLeft = ffi::AVCHROMA_LOC_TOPLEFT,
+        Top = ffi::AVCHROMA_LOC_TOP,
+        BottomLeft = ffi::AVCHROMA_LOC_BOTTOMLEFT,
+        Bottom = ffi::AVCHROMA_LOC_BOTTOM,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cstr::cstr;
+
+    #[test]
+    fn test_color_enums() {
+        assert_eq!(
+            ColorRange::from_raw(ffi::AVCOL_RANGE_JPEG),
+            ColorRange::Jpeg
+        );
+        assert_eq!(ColorRange::Mpeg.into_raw(), ffi::AVCOL_RANGE_MPEG);
+        assert_eq!(ColorRange::Jpeg.name(), Some(cstr!("pc")));
+        assert_eq!(ColorPrimaries::Bt2020.name(), Some(cstr!("bt2020")));
+        assert_eq!(ColorTransfer::Smpte2084.name(), Some(cstr!("smpte2084")));
+        assert_eq!(ColorSpace::Bt709.name(), Some(cstr!("bt709")));
+        assert_eq!(ChromaLocation::Left.name(), Some(cstr!("left")));
+
+        // Out of range values round trip and have no name.
+        let unknown = ColorSpace::from_raw(1000 as ffi::AVColorSpace);
+        assert_eq!(unknown, ColorSpace::Unknown(1000));
+        assert_eq!(unknown.into_raw(), 1000 as ffi::AVColorSpace);
+        assert_eq!(unknown.name(), None);
+        assert_eq!(ColorRange::Unknown(-1).name(), None);
+        // Listed values are equal whichever variant holds them.
+        let bt709 = ColorSpace::Unknown(ffi::AVCOL_SPC_BT709 as i32);
+        assert_eq!(bt709, ColorSpace::Bt709);
+        assert_eq!(ColorSpace::from_raw(bt709.into_raw()), ColorSpace::Bt709);
+        assert_eq!(bt709.name(), Some(cstr!("bt709")));
+    }
+}
@@ -11,6 +11,74 @@ pub const fn ra(num: i32, den: i32) -> AVRational {
 
 pub use ffi::{av_cmp_q, av_inv_q, av_make_q, av_q2d};
 
+/// [`AVRational`] with arithmetic, e.g. for stream time bases and frame rates.
+/// Converts from and into [`AVRational`] for the APIs taking the raw one.
+#[derive(Clone, Copy, Debug)]
+#[repr(transparent)]
+pub struct Rational(pub AVRational);
+
+impl Rational {
+    /// return `Rational: num / den`;
+    pub const fn new(num: i32, den: i32) -> Self {
+        Self(ra(num, den))
+    }
+
+    pub const fn num(self) -> i32 {
+        self.0.num
+    }
+
+    pub const fn den(self) -> i32 {
+        self.0.den
+    }
+
+    /// Return `1 / self`.
+    pub fn inv(self) -> Self {
+        Self(av_inv_q(self.0))
+    }
+
+    /// Convert to a double, equivalent to [`av_q2d`]. `0/0` gives NaN.
+    pub fn to_f64(self) -> f64 {
+        av_q2d(self.0)
+    }
+}
+
+impl std::ops::Mul for Rational {
+    type Output = Self;
+
+    /// Multiply two rationals, reduced like [`av_mul_q`].
+    fn mul(self, rhs: Self) -> Self {
+        Self(av_mul_q(self.0, rhs.0))
+    }
+}
+
+impl std::ops::Div for Rational {
+    type Output = Self;
+
+    /// Divide one rational by another, reduced like [`av_div_q`].
+    fn div(self, rhs: Self) -> Self {
+        Self(av_div_q(self.0, rhs.0))
+    }
+}
+
+/// Compare the values, so `1/2 == 2/4`.
+impl PartialEq for Rational {
+    fn eq(&self, other: &Self) -> bool {
+        av_cmp_q(self.0, other.0) == 0
+    }
+}
+
+impl From<AVRational> for Rational {
+    fn from(value: AVRational) -> Self {
+        Self(value)
+    }
+}
+
+impl From<Rational> for AVRational {
+    fn from(value: Rational) -> Self {
+        value.0
+    }
+}
+
 #[inline]
 /// Convert a double precision floating point number to a rational.
 /// In case of infinity, the returned value is expressed as {1, 0} or {-1, 0} depending on the sign.
@@ -86,3 +154,25 @@ pub fn av_rescale_q(a: i64, bq: AVRational, cq: AVRational) -> i64 {
 pub fn av_rescale_q_rnd(a: i64, bq: AVRational, cq: AVRational, rnd: u32) -> i64 {
     unsafe { ffi::av_rescale_q_rnd(a, bq, cq, rnd as _) }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rational() {
+        let time_base = Rational::new(1, 90000);
+        let frame_rate = Rational::new(30000, 1001);
+        assert_eq!(time_base.inv(), Rational::new(90000, 1));
+        assert_eq!(frame_rate * Rational::new(2, 1), Rational::new(60000, 1001));
+        assert_eq!(frame_rate / frame_rate, Rational::new(1, 1));
+        assert_eq!(Rational::new(1, 2), Rational::new(2, 4));
+        assert_ne!(Rational::new(1, 2), Rational::new(1, 3));
+        let ticks = (frame_rate.inv() / time_base).to_f64();
+        assert!((ticks - 3003.0).abs() < 1e-9);
+        assert!(Rational::new(0, 0).to_f64().is_nan());
+        let raw: AVRational = frame_rate.into();
+        assert_eq!((raw.num, raw.den), (30000, 1001));
+        assert_eq!(Rational::from(raw).num(), 30000);
+    }
+}
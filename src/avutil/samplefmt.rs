@@ -1,8 +1,11 @@
-use crate::{ffi, shared::*};
+use crate::{error::*, ffi, shared::*};
 use std::{
-    ffi::CStr,
+    ffi::{CStr, CString},
+    fmt, mem,
     num::NonZeroI32,
     ptr::{self, NonNull},
+    slice,
+    str::FromStr,
 };
 
 pub type AVSampleFormat = ffi::AVSampleFormat;
@@ -108,6 +111,119 @@ pub fn sample_fmt_is_planar(sample_fmt: AVSampleFormat) -> bool {
     unsafe { ffi::av_sample_fmt_is_planar(sample_fmt) == 1 }
 }
 
+/// Non exhaustive wrapper of [`AVSampleFormat`], displayed as its name.
+///
+/// ```rust
+/// # use rsmpeg::avutil::SampleFormat;
+/// # use rsmpeg::ffi::{AV_SAMPLE_FMT_FLT, AV_SAMPLE_FMT_FLTP};
+/// # fn main() {
+/// let sample_fmt: SampleFormat = "fltp".parse().unwrap();
+/// assert_eq!(sample_fmt, SampleFormat(AV_SAMPLE_FMT_FLTP));
+/// assert_eq!(sample_fmt.packed(), Some(SampleFormat(AV_SAMPLE_FMT_FLT)));
+/// assert_eq!(sample_fmt.to_string(), "fltp");
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SampleFormat(pub AVSampleFormat);
+
+impl SampleFormat {
+    /// Return the sample format corresponding to name, or `None` if it's not
+    /// recognized.
+    pub fn from_name(name: &CStr) -> Option<Self> {
+        get_sample_fmt(name).map(Self)
+    }
+
+    /// Return the name of the sample format, or `None` if it's not recognized.
+    pub fn name(&self) -> Option<&'static CStr> {
+        get_sample_fmt_name(self.0)
+    }
+
+    /// Check if the sample format is planar.
+    pub fn is_planar(&self) -> bool {
+        sample_fmt_is_planar(self.0)
+    }
+
+    /// Get the packed alternative form, itself if it's already packed.
+    pub fn packed(&self) -> Option<Self> {
+        get_packed_sample_fmt(self.0).map(Self)
+    }
+
+    /// Get the planar alternative form, itself if it's already planar.
+    pub fn planar(&self) -> Option<Self> {
+        get_planar_sample_fmt(self.0).map(Self)
+    }
+
+    /// Return number of bytes per sample, `None` if the format is unknown.
+    pub fn bytes(&self) -> Option<usize> {
+        get_bytes_per_sample(self.0)
+    }
+}
+
+impl From<AVSampleFormat> for SampleFormat {
+    fn from(sample_fmt: AVSampleFormat) -> Self {
+        Self(sample_fmt)
+    }
+}
+
+impl From<SampleFormat> for AVSampleFormat {
+    fn from(sample_fmt: SampleFormat) -> Self {
+        sample_fmt.0
+    }
+}
+
+impl fmt::Display for SampleFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.name() {
+            Some(name) => f.write_str(&name.to_string_lossy()),
+            None => write!(f, "unknown({})", self.0),
+        }
+    }
+}
+
+impl FromStr for SampleFormat {
+    type Err = RsmpegError;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        CString::new(name)
+            .ok()
+            .and_then(|name| Self::from_name(&name))
+            .ok_or(RsmpegError::AVError(ffi::AVERROR(ffi::EINVAL)))
+    }
+}
+
+mod private {
+    pub trait Sealed {}
+}
+
+/// Rust types audio samples can be viewed as. Implemented for `i16`, `i32`,
+/// `f32` and `f64`, which correspond to the `S16`, `S32`, `FLT` and `DBL`
+/// sample formats (both packed and planar).
+pub trait Sample: private::Sealed + Copy {
+    /// Packed sample format storing samples of this type.
+    const PACKED: AVSampleFormat;
+    /// Planar sample format storing samples of this type.
+    const PLANAR: AVSampleFormat;
+}
+
+macro_rules! impl_sample {
+    ($($ty:ty => ($packed:ident, $planar:ident)),+ $(,)?) => {
+        $(
+            impl private::Sealed for $ty {}
+            impl Sample for $ty {
+                const PACKED: AVSampleFormat = ffi::$packed;
+                const PLANAR: AVSampleFormat = ffi::$planar;
+            }
+        )+
+    };
+}
+
+impl_sample! {
+    i16 => (AV_SAMPLE_FMT_S16, AV_SAMPLE_FMT_S16P),
+    i32 => (AV_SAMPLE_FMT_S32, AV_SAMPLE_FMT_S32P),
+    f32 => (AV_SAMPLE_FMT_FLT, AV_SAMPLE_FMT_FLTP),
+    f64 => (AV_SAMPLE_FMT_DBL, AV_SAMPLE_FMT_DBLP),
+}
+
 // The `nb_samples` of `AVSamples` is the capacity rather than length.
 // `nb_channels` and `audio_data.len()`(which is nb_planes) is only the same
 // when the audio sample format in planar.
@@ -122,15 +238,21 @@ pub fn sample_fmt_is_planar(sample_fmt: AVSampleFormat) -> bool {
 // This is the reason why `AVSamples` has a vector of channels for holding
 // audio data.
 wrap! {
+    !Send
     AVSamples: Box<[u8]>,
-    audio_data: Box<[*mut u8]> = Vec::new().into_boxed_slice(),
-    linesize: i32 = 0,
-    nb_channels: i32 = 0,
-    nb_samples: i32 = 0,
-    sample_fmt: AVSampleFormat = ffi::AV_SAMPLE_FMT_NONE,
-    align: i32 = 0,
+    pub audio_data: Box<[*mut u8]> = Vec::new().into_boxed_slice(),
+    pub linesize: i32 = 0,
+    pub nb_channels: i32 = 0,
+    pub nb_samples: i32 = 0,
+    pub sample_fmt: AVSampleFormat = ffi::AV_SAMPLE_FMT_NONE,
+    pub align: i32 = 0,
 }
 
+// SAFETY: The `audio_data` pointers point into the buffer owned by the
+// samples, which moves along with them, and the other attachments are plain
+// values.
+unsafe impl Send for AVSamples {}
+
 impl AVSamples {
     /// Get the required (linesize, buffer_size) for the given audio parameters,
     /// returns `None` when parameters are invalid.
@@ -222,8 +344,16 @@ impl AVSamples {
 
     /// Fill an audio buffer with silence.
     /// `offset` offset in samples at which to start filling.
-    /// `nb_samples` number of samples to fill.
+    /// `nb_samples` number of samples to fill, clamped to the end of the
+    /// buffer.
     pub fn set_silence(&mut self, offset: i32, nb_samples: i32) {
+        let Some(capacity) = self.capacity() else {
+            return;
+        };
+        if !(0..capacity).contains(&offset) {
+            return;
+        }
+        let nb_samples = nb_samples.clamp(0, capacity - offset);
         let x = unsafe {
             ffi::av_samples_set_silence(
                 self.audio_data.as_mut_ptr(),
@@ -238,6 +368,113 @@ impl AVSamples {
         // output. If this assert is triggered, please file an issue.
         debug_assert!(x == 0);
     }
+
+    /// Get the number of planes, `nb_channels` for planar sample formats, 1
+    /// for packed ones.
+    pub fn nb_planes(&self) -> usize {
+        self.audio_data.len()
+    }
+
+    /// Get the samples of plane `plane` as bytes, `None` if out of range. The
+    /// length is `nb_samples` samples of one channel for planar sample
+    /// formats, of all the channels interleaved for packed ones, without the
+    /// alignment padding.
+    ///
+    /// The fields being public, `None` is also returned if they describe
+    /// planes out of the buffer of the samples, e.g. `nb_samples` set above
+    /// the allocated count.
+    pub fn plane(&self, plane: usize) -> Option<&[u8]> {
+        let (data, len) = self.plane_raw(plane)?;
+        Some(unsafe { slice::from_raw_parts(data, len) })
+    }
+
+    /// Mutable version of [`Self::plane()`].
+    pub fn plane_mut(&mut self, plane: usize) -> Option<&mut [u8]> {
+        let (data, len) = self.plane_raw(plane)?;
+        Some(unsafe { slice::from_raw_parts_mut(data, len) })
+    }
+
+    /// Get the samples of plane `plane` as a typed slice, see
+    /// [`Self::plane()`] for its length.
+    ///
+    /// `T` must match the sample format, e.g. `f32` for `AV_SAMPLE_FMT_FLT`
+    /// and `AV_SAMPLE_FMT_FLTP`, or [`RsmpegError::SampleTypeMismatchError`]
+    /// is returned.
+    pub fn samples<T: Sample>(&self, plane: usize) -> Result<&[T]> {
+        let (data, len) = self.typed_plane::<T>(plane)?;
+        Ok(unsafe { slice::from_raw_parts(data, len) })
+    }
+
+    /// Mutable version of [`Self::samples()`].
+    pub fn samples_mut<T: Sample>(&mut self, plane: usize) -> Result<&mut [T]> {
+        let (data, len) = self.typed_plane::<T>(plane)?;
+        Ok(unsafe { slice::from_raw_parts_mut(data, len) })
+    }
+
+    /// Get the number of samples per channel the planes can hold before the
+    /// end of the buffer, which may be more than `nb_samples`. `None` if the
+    /// planes don't match the sample format and the channel count, or point
+    /// out of the buffer.
+    pub(crate) fn capacity(&self) -> Option<i32> {
+        let nb_channels = usize::try_from(self.nb_channels).ok().filter(|x| *x > 0)?;
+        let (nb_planes, nb_interleaved) = if sample_fmt_is_planar(self.sample_fmt) {
+            (nb_channels, 1)
+        } else {
+            (1, nb_channels)
+        };
+        if self.audio_data.len() != nb_planes {
+            return None;
+        }
+        let sample_size = get_bytes_per_sample(self.sample_fmt)? * nb_interleaved;
+        let buffer = unsafe { &**self.as_ptr() }.as_ptr_range();
+        let capacity = self
+            .audio_data
+            .iter()
+            .map(|&data| {
+                let data = data as *const u8;
+                (buffer.start <= data && data <= buffer.end)
+                    .then(|| (buffer.end as usize - data as usize) / sample_size)
+            })
+            .min()
+            .flatten()?;
+        Some(capacity.min(i32::MAX as usize) as i32)
+    }
+
+    /// Get the data pointer and the length in bytes of plane `plane`, checked
+    /// to fit in the buffer.
+    fn plane_raw(&self, plane: usize) -> Option<(*mut u8, usize)> {
+        let data = *self.audio_data.get(plane)?;
+        if !(0..=self.capacity()?).contains(&self.nb_samples) {
+            return None;
+        }
+        let nb_interleaved = if sample_fmt_is_planar(self.sample_fmt) {
+            1
+        } else {
+            self.nb_channels as usize
+        };
+        let len =
+            get_bytes_per_sample(self.sample_fmt)? * nb_interleaved * self.nb_samples as usize;
+        Some((data, len))
+    }
+
+    /// Validate the sample type and return the typed data pointer and the
+    /// number of samples of plane `plane`.
+    fn typed_plane<T: Sample>(&self, plane: usize) -> Result<(*mut T, usize)> {
+        if self.sample_fmt != T::PLANAR && self.sample_fmt != T::PACKED {
+            return Err(RsmpegError::SampleTypeMismatchError);
+        }
+        if plane >= self.audio_data.len() {
+            return Err(RsmpegError::ChannelIndexOutOfRangeError(plane));
+        }
+        let (data, len) = self
+            .plane_raw(plane)
+            .ok_or(RsmpegError::AVError(ffi::AVERROR(ffi::EINVAL)))?;
+        // The buffer is allocated as bytes.
+        if data as usize % mem::align_of::<T>() != 0 {
+            return Err(RsmpegError::AVError(ffi::AVERROR(ffi::EINVAL)));
+        }
+        Ok((data as *mut T, len / mem::size_of::<T>()))
+    }
 }
 
 impl Drop for AVSamples {
@@ -245,3 +482,96 @@ impl Drop for AVSamples {
         let _linear = unsafe { Box::from_raw(self.as_mut_ptr()) };
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sample_format_names() {
+        for sample_fmt in 0..ffi::AV_SAMPLE_FMT_NB {
+            let sample_fmt = SampleFormat(sample_fmt);
+            let name = sample_fmt.name().unwrap();
+            assert_eq!(SampleFormat::from_name(name), Some(sample_fmt));
+            assert_eq!(
+                sample_fmt.to_string().parse::<SampleFormat>(),
+                Ok(sample_fmt)
+            );
+        }
+        let none = SampleFormat(ffi::AV_SAMPLE_FMT_NONE);
+        assert_eq!(none.name(), None);
+        assert_eq!(none.to_string(), "unknown(-1)");
+        assert!("s17".parse::<SampleFormat>().is_err());
+    }
+
+    #[test]
+    fn test_samples_planes() {
+        let mut samples = AVSamples::new(2, 1024, ffi::AV_SAMPLE_FMT_FLTP, 1).unwrap();
+        let (linesize, buffer_size) =
+            AVSamples::get_buffer_size(2, 1024, ffi::AV_SAMPLE_FMT_FLTP, 1).unwrap();
+        assert_eq!(samples.nb_planes(), 2);
+        assert_eq!(samples.linesize, linesize);
+        assert_eq!(linesize * 2, buffer_size);
+        samples.samples_mut::<f32>(1).unwrap().fill(0.5);
+        samples.set_silence(0, 1024);
+        for plane in 0..2 {
+            assert_eq!(samples.plane(plane).unwrap().len(), linesize as usize);
+            let channel = samples.samples::<f32>(plane).unwrap();
+            assert_eq!(channel.len(), 1024);
+            assert!(channel.iter().all(|&x| x == 0.0));
+        }
+        assert!(samples.plane(2).is_none());
+        assert_eq!(
+            samples.samples::<i16>(0),
+            Err(RsmpegError::SampleTypeMismatchError)
+        );
+        assert_eq!(
+            samples.samples::<f32>(2),
+            Err(RsmpegError::ChannelIndexOutOfRangeError(2))
+        );
+
+        // The public fields can't make the planes outgrow the buffer.
+        samples.set_silence(1000, 100);
+        samples.nb_samples = 1025;
+        assert!(samples.plane(1).is_none());
+        assert!(samples.samples_mut::<f32>(0).is_err());
+        samples.nb_samples = 1024;
+        samples.nb_channels = 3;
+        assert!(samples.plane(0).is_none());
+        samples.nb_channels = 2;
+        samples.audio_data[1] = samples.audio_data[0].wrapping_sub(4);
+        assert!(samples.plane(0).is_none());
+
+        // Channels of packed formats are interleaved in a single plane, the
+        // default alignment pads it.
+        let samples = AVSamples::new(2, 1000, ffi::AV_SAMPLE_FMT_S16, 0).unwrap();
+        let (linesize, _) = AVSamples::get_buffer_size(2, 1000, ffi::AV_SAMPLE_FMT_S16, 0).unwrap();
+        assert_eq!(samples.nb_planes(), 1);
+        assert_eq!(samples.plane(0).unwrap().len(), 4000);
+        assert!(linesize >= 4000);
+        assert_eq!(samples.samples::<i16>(0).unwrap().len(), 2000);
+    }
+
+    #[test]
+    fn test_sample_format_pairs() {
+        for (packed, planar, bytes) in [
+            (ffi::AV_SAMPLE_FMT_U8, ffi::AV_SAMPLE_FMT_U8P, 1),
+            (ffi::AV_SAMPLE_FMT_S16, ffi::AV_SAMPLE_FMT_S16P, 2),
+            (ffi::AV_SAMPLE_FMT_S32, ffi::AV_SAMPLE_FMT_S32P, 4),
+            (ffi::AV_SAMPLE_FMT_FLT, ffi::AV_SAMPLE_FMT_FLTP, 4),
+            (ffi::AV_SAMPLE_FMT_DBL, ffi::AV_SAMPLE_FMT_DBLP, 8),
+            (ffi::AV_SAMPLE_FMT_S64, ffi::AV_SAMPLE_FMT_S64P, 8),
+        ] {
+            let (packed, planar) = (SampleFormat(packed), SampleFormat(planar));
+            assert!(!packed.is_planar());
+            assert!(planar.is_planar());
+            assert_eq!(packed.planar(), Some(planar));
+            assert_eq!(packed.packed(), Some(packed));
+            assert_eq!(planar.packed(), Some(packed));
+            assert_eq!(planar.planar(), Some(planar));
+            assert_eq!(packed.bytes(), Some(bytes));
+            assert_eq!(planar.bytes(), Some(bytes));
+        }
+        assert_eq!(SampleFormat(ffi::AV_SAMPLE_FMT_NONE).bytes(), None);
+    }
+}
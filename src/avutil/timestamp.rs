@@ -1,5 +1,5 @@
-use crate::ffi::{av_q2d, AVRational, AV_NOPTS_VALUE};
-use std::ffi::c_double;
+use crate::ffi::{self, av_q2d, AVRational, AV_NOPTS_VALUE};
+use std::{cmp::Ordering, ffi::c_double, fmt};
 
 /// Get a string containing a timestamp representation.
 pub fn ts2str(ts: i64) -> String {
@@ -18,3 +18,141 @@ pub fn ts2timestr(ts: i64, tb: AVRational) -> String {
         format!("{:.6}", av_q2d(tb) * ts as c_double)
     }
 }
+
+/// Compare timestamp `ts_a` in time base `tb_a` with `ts_b` in time base
+/// `tb_b`, without overflow. Timestamps must not be `AV_NOPTS_VALUE`.
+pub fn compare_ts(ts_a: i64, tb_a: AVRational, ts_b: i64, tb_b: AVRational) -> Ordering {
+    unsafe { ffi::av_compare_ts(ts_a, tb_a, ts_b, tb_b) }.cmp(&0)
+}
+
+/// A timestamp field of FFmpeg, which is `AV_NOPTS_VALUE` when unknown.
+///
+/// It's displayed like [`ts2str()`], e.g. in log messages. Timestamps of
+/// frames and packets are set from an `Option<i64>` with their `set_pts_opt()`
+/// and `set_dts_opt()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Timestamp(pub i64);
+
+impl Timestamp {
+    /// The unknown timestamp.
+    pub const NONE: Self = Self(AV_NOPTS_VALUE);
+
+    /// Get the timestamp, `None` if unknown.
+    pub fn get(self) -> Option<i64> {
+        (self.0 != AV_NOPTS_VALUE).then_some(self.0)
+    }
+
+    /// Rescale the timestamp from time base `from` to time base `to`,
+    /// rounding to the nearest and halfway away from zero like
+    /// [`av_rescale_q()`](crate::avutil::av_rescale_q). Unknown timestamps
+    /// stay unknown.
+    pub fn rescale(self, from: AVRational, to: AVRational) -> Self {
+        match self.get() {
+            Some(ts) => Self(unsafe { ffi::av_rescale_q(ts, from, to) }),
+            None => Self::NONE,
+        }
+    }
+
+    /// Same as [`Self::rescale()`] with the rounding `rnd`, a
+    /// `AV_ROUND_*` value, possibly combined with `AV_ROUND_PASS_MINMAX`.
+    pub fn rescale_rnd(self, from: AVRational, to: AVRational, rnd: u32) -> Self {
+        match self.get() {
+            Some(ts) => Self(unsafe { ffi::av_rescale_q_rnd(ts, from, to, rnd as _) }),
+            None => Self::NONE,
+        }
+    }
+
+    /// Get the timestamp in seconds in time base `tb`, formatted like
+    /// [`ts2timestr()`].
+    pub fn to_time_string(self, tb: AVRational) -> String {
+        ts2timestr(self.0, tb)
+    }
+}
+
+impl From<Option<i64>> for Timestamp {
+    fn from(ts: Option<i64>) -> Self {
+        ts.map_or(Self::NONE, Self)
+    }
+}
+
+impl From<Timestamp> for Option<i64> {
+    fn from(ts: Timestamp) -> Self {
+        ts.get()
+    }
+}
+
+impl fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&ts2str(self.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::avutil::{av_rescale_q, av_rescale_q_rnd, ra};
+
+    #[test]
+    fn test_timestamp() {
+        assert_eq!(Timestamp(42).get(), Some(42));
+        assert_eq!(Timestamp::NONE.get(), None);
+        assert_eq!(Timestamp::from(None), Timestamp::NONE);
+        assert_eq!(Option::<i64>::from(Timestamp(-1)), Some(-1));
+        assert_eq!(Timestamp(1024).to_string(), "1024");
+        assert_eq!(Timestamp::NONE.to_string(), "NOPTS");
+        assert_eq!(Timestamp(-6400).to_time_string(ra(1, 12800)), "-0.500000");
+        assert_eq!(
+            Timestamp::NONE.rescale(ra(1, 25), ra(1, 12800)),
+            Timestamp::NONE
+        );
+        assert_eq!(
+            Timestamp(3).rescale(ra(1, 25), ra(1, 12800)),
+            Timestamp(1536)
+        );
+    }
+
+    #[test]
+    fn test_rescale_negative() {
+        // Halfway cases round away from zero, symmetrically.
+        assert_eq!(av_rescale_q(1, ra(1, 2), ra(1, 1)), 1);
+        assert_eq!(av_rescale_q(-1, ra(1, 2), ra(1, 1)), -1);
+        assert_eq!(av_rescale_q(-3, ra(1, 4), ra(1, 1)), -1);
+        assert_eq!(av_rescale_q(-1, ra(1, 4), ra(1, 1)), 0);
+
+        // Rounding towards -inf and +inf, unlike an integer division which
+        // truncates towards zero.
+        let down = ffi::AV_ROUND_DOWN as u32;
+        let up = ffi::AV_ROUND_UP as u32;
+        let zero = ffi::AV_ROUND_ZERO as u32;
+        assert_eq!(av_rescale_q_rnd(-1, ra(1, 4), ra(1, 1), down), -1);
+        assert_eq!(av_rescale_q_rnd(-1, ra(1, 4), ra(1, 1), up), 0);
+        assert_eq!(av_rescale_q_rnd(-1, ra(1, 4), ra(1, 1), zero), 0);
+        assert_eq!(av_rescale_q_rnd(-5, ra(1, 4), ra(1, 1), down), -2);
+        assert_eq!(
+            Timestamp(-5).rescale_rnd(ra(1, 4), ra(1, 1), up),
+            Timestamp(-1)
+        );
+
+        // `AV_NOPTS_VALUE` is `i64::MIN`, it's kept as is with
+        // `AV_ROUND_PASS_MINMAX` instead of being rescaled.
+        let pass = ffi::AV_ROUND_PASS_MINMAX as u32;
+        assert_eq!(
+            av_rescale_q_rnd(AV_NOPTS_VALUE, ra(1, 25), ra(1, 12800), zero | pass),
+            AV_NOPTS_VALUE
+        );
+    }
+
+    #[test]
+    fn test_compare_ts() {
+        assert_eq!(compare_ts(1, ra(1, 2), 2, ra(1, 4)), Ordering::Equal);
+        assert_eq!(compare_ts(-1, ra(1, 2), -1, ra(1, 4)), Ordering::Less);
+        assert_eq!(
+            compare_ts(-1, ra(1, 25), -600, ra(1, 12800)),
+            Ordering::Greater
+        );
+        assert_eq!(
+            compare_ts(i64::MAX, ra(1, 1), 0, ra(1, 1000)),
+            Ordering::Greater
+        );
+    }
+}
@@ -1,6 +1,19 @@
 //! Errors of the rsmpeg.
+//!
+//! Every variant coming from a failed FFmpeg call keeps the raw `AVERROR`
+//! code, see [`RsmpegError::raw_error()`], and its message ends with the
+//! description of the code given by `av_strerror()`.
+//!
+//! # Migration
+//!
+//! The variant names are unchanged. Code matching on the displayed messages
+//! needs to account for the appended ``AVERROR(code): `description` `` part.
+//! Loops telling "try again" and "end of stream" apart per subsystem (e.g.
+//! `DecoderDrainError`, `BufferSinkDrainError` and `BitstreamDrainError`)
+//! can use [`RsmpegError::is_again()`] and [`RsmpegError::is_eof()`] instead.
 use std::{
     cmp::{Eq, PartialEq},
+    io,
     num::TryFromIntError,
     os::raw::c_int,
 };
@@ -12,23 +25,23 @@ use crate::{avutil::err2str, ffi, shared::AVERROR_EAGAIN};
 #[non_exhaustive]
 #[derive(Error, Debug, Eq, PartialEq)]
 pub enum RsmpegError {
-    #[error("AVERROR({0}): `{}`", err2str(*.0).unwrap_or_else(|| "Unknown error code.".to_string()))]
+    #[error("AVERROR({0}): `{}`", strerror(*.0))]
     AVError(c_int),
 
     // --------- Unstablized error type below ------
 
     // FFmpeg errors
-    #[error("Cannot open input file. ({0})")]
+    #[error("Cannot open input file. AVERROR({0}): `{}`", strerror(*.0))]
     OpenInputError(c_int),
-    #[error("Cannot find stream information. ({0})")]
+    #[error("Cannot find stream information. AVERROR({0}): `{}`", strerror(*.0))]
     FindStreamInfoError(c_int),
 
     // Decoder errors
-    #[error("Send packet to a codec context failed. ({0})")]
+    #[error("Send packet to a codec context failed. AVERROR({0}): `{}`", strerror(*.0))]
     SendPacketError(c_int),
     #[error("Decoder isn't accepting input, try to receive several frames and send again.")]
     DecoderFullError,
-    #[error("Receive frame from a codec context failed. ({0})")]
+    #[error("Receive frame from a codec context failed. AVERROR({0}): `{}`", strerror(*.0))]
     ReceiveFrameError(c_int),
     #[error("Decoder have no frame currently, Try send new input.")]
     DecoderDrainError,
@@ -36,11 +49,11 @@ pub enum RsmpegError {
     DecoderFlushedError,
 
     // Encoder errors
-    #[error("Send frame to a codec context failed. ({0})")]
+    #[error("Send frame to a codec context failed. AVERROR({0}): `{}`", strerror(*.0))]
     SendFrameError(c_int),
     #[error("Encoder isn't accepting input, try to receive several packets and send again.")]
     SendFrameAgainError,
-    #[error("Receive packet from a codec context failed. ({0})")]
+    #[error("Receive packet from a codec context failed. AVERROR({0}): `{}`", strerror(*.0))]
     ReceivePacketError(c_int),
     #[error("Encoder have no packet currently, Try send new input.")]
     EncoderDrainError,
@@ -54,12 +67,12 @@ pub enum RsmpegError {
     BitstreamDrainError,
     #[error("Bitstream filter is already flushed")]
     BitstreamFlushedError,
-    #[error("Send packet to a bitstream filter context failed. ({0})")]
+    #[error("Send packet to a bitstream filter context failed. AVERROR({0}): `{}`", strerror(*.0))]
     BitstreamSendPacketError(c_int),
-    #[error("Receive packet from a bitstream filter context failed. ({0})")]
+    #[error("Receive packet from a bitstream filter context failed. AVERROR({0}): `{}`", strerror(*.0))]
     BitstreamReceivePacketError(c_int),
 
-    #[error("Pulling filtered frame from filters failed ({0})")]
+    #[error("Pulling filtered frame from filters failed. AVERROR({0}): `{}`", strerror(*.0))]
     BufferSinkGetFrameError(c_int),
     #[error("No frames are available at this point")]
     BufferSinkDrainError,
@@ -68,9 +81,66 @@ pub enum RsmpegError {
 
     #[error("AVFrame buffer double allocating.")]
     AVFrameDoubleAllocatingError,
-    #[error("AVFrame buffer allocating with incorrect parameters. ({0})")]
+    #[error("AVFrame buffer allocating with incorrect parameters. AVERROR({0}): `{}`", strerror(*.0))]
     AVFrameInvalidAllocatingError(c_int),
 
+    #[error("Sample type doesn't match the sample format of the audio frame.")]
+    SampleTypeMismatchError,
+    #[error("Channel {0} of a packed audio frame cannot be accessed as a contiguous slice.")]
+    PackedChannelAccessError(usize),
+    #[error("Channel index {0} is out of range.")]
+    ChannelIndexOutOfRangeError(usize),
+
+    #[error("Input parameters of the resampler changed.")]
+    SwrInputChangedError,
+    #[error("Output parameters of the resampler changed.")]
+    SwrOutputChangedError,
+    #[error("Invalid image planes for scaling: {0}.")]
+    ScalePlanesError(String),
+    #[error("Pixel format `{0}` is a hardware format, transfer the frame to system memory first.")]
+    HwPixelFormatError(String),
+    #[error("Buffer of {0} bytes is too small, {1} bytes are needed.")]
+    BufferTooSmallError(usize, usize),
+
+    #[error("Failed to {0} the image: {1}")]
+    ImageError(&'static str, Box<RsmpegError>),
+
+    #[error("Codec `{0}` is not supported by container `{1}`.")]
+    CodecNotSupportedError(String, String),
+    #[error("Seeking by byte offset is not supported by the input.")]
+    ByteSeekUnsupportedError,
+    #[error("Seeking target is after the end of the input.")]
+    SeekBeyondEndError,
+    #[error("Listing the devices is not supported by the format.")]
+    DeviceListUnsupportedError,
+    #[error("Listing directories is not supported by the protocol.")]
+    DirListUnsupportedError,
+    #[error("Operation aborted by the interrupt callback.")]
+    Interrupted,
+
+    #[error("Pad `{1}` of filter `{0}` is not connected.")]
+    FilterPadNotConnectedError(String, String),
+    #[error("Filter graph has no filters.")]
+    EmptyFilterGraphError,
+    #[error("Filter `{0}` has no pad {1}.")]
+    FilterPadIndexOutOfRangeError(String, u32),
+
+    #[error("Failed to set option of the codec context. AVERROR({0}): `{}`", strerror(*.0))]
+    SetPropertyError(c_int),
+    #[error("Codec context is already opened.")]
+    CodecAlreadyOpenedError,
+    #[error("Codec context is not opened yet.")]
+    CodecNotOpenedError,
+
+    #[error("Output header is not written yet.")]
+    HeaderNotWrittenError,
+    #[error("Output header is already written.")]
+    HeaderAlreadyWrittenError,
+    #[error("Output is already finalized by the trailer.")]
+    AlreadyFinalizedError,
+    #[error("Non-monotonic dts {1}, previous dts is {0}.")]
+    NonMonotonicDtsError(i64, i64),
+
     #[error("{0}")]
     TryFromIntError(TryFromIntError),
 
@@ -79,7 +149,14 @@ pub enum RsmpegError {
     Unknown,
 }
 
+/// Description of `err`, e.g. `Resource temporarily unavailable`.
+fn strerror(err: c_int) -> String {
+    err2str(err).unwrap_or_else(|| "Unknown error code.".to_string())
+}
+
 impl RsmpegError {
+    /// Get the `AVERROR` code the error comes from, `None` if it's not from
+    /// FFmpeg.
     #[must_use]
     pub fn raw_error(&self) -> Option<c_int> {
         match self {
@@ -93,6 +170,7 @@ impl RsmpegError {
             | Self::BitstreamSendPacketError(err)
             | Self::BitstreamReceivePacketError(err)
             | Self::BufferSinkGetFrameError(err)
+            | Self::SetPropertyError(err)
             | Self::AVFrameInvalidAllocatingError(err) => Some(*err),
 
             Self::DecoderFullError
@@ -108,9 +186,51 @@ impl RsmpegError {
             | Self::EncoderFlushedError
             | Self::BitstreamFlushedError => Some(ffi::AVERROR_EOF),
 
-            Self::AVFrameDoubleAllocatingError | Self::TryFromIntError(_) | Self::Unknown => None,
+            Self::ImageError(_, err) => err.raw_error(),
+
+            Self::DeviceListUnsupportedError | Self::DirListUnsupportedError => {
+                Some(ffi::AVERROR(ffi::ENOSYS))
+            }
+            Self::Interrupted => Some(ffi::AVERROR_EXIT),
+
+            Self::SwrInputChangedError => Some(ffi::AVERROR_INPUT_CHANGED),
+            Self::SwrOutputChangedError => Some(ffi::AVERROR_OUTPUT_CHANGED),
+
+            Self::AVFrameDoubleAllocatingError
+            | Self::SampleTypeMismatchError
+            | Self::PackedChannelAccessError(_)
+            | Self::ChannelIndexOutOfRangeError(_)
+            | Self::ScalePlanesError(_)
+            | Self::HwPixelFormatError(_)
+            | Self::BufferTooSmallError(..)
+            | Self::CodecNotSupportedError(..)
+            | Self::FilterPadNotConnectedError(..)
+            | Self::EmptyFilterGraphError
+            | Self::FilterPadIndexOutOfRangeError(..)
+            | Self::ByteSeekUnsupportedError
+            | Self::SeekBeyondEndError
+            | Self::CodecAlreadyOpenedError
+            | Self::CodecNotOpenedError
+            | Self::HeaderNotWrittenError
+            | Self::HeaderAlreadyWrittenError
+            | Self::AlreadyFinalizedError
+            | Self::NonMonotonicDtsError(..)
+            | Self::TryFromIntError(_)
+            | Self::Unknown => None,
         }
     }
+
+    /// Return true if the error is `AVERROR(EAGAIN)`, whichever the
+    /// subsystem: more input is needed, or output has to be taken first.
+    pub fn is_again(&self) -> bool {
+        self.raw_error() == Some(AVERROR_EAGAIN)
+    }
+
+    /// Return true if the error is `AVERROR_EOF`, whichever the subsystem:
+    /// the end of stream is reached.
+    pub fn is_eof(&self) -> bool {
+        self.raw_error() == Some(ffi::AVERROR_EOF)
+    }
 }
 
 /// Overall result of Rsmpeg functions
@@ -130,3 +250,61 @@ impl From<TryFromIntError> for RsmpegError {
         Self::TryFromIntError(err)
     }
 }
+
+impl From<io::Error> for RsmpegError {
+    /// Convert to `AVERROR(errno)` like FFmpeg does, `AVERROR_EOF` on
+    /// unexpected EOF and `AVERROR_EXTERNAL` if no OS error code is available.
+    fn from(err: io::Error) -> Self {
+        if err.kind() == io::ErrorKind::UnexpectedEof {
+            return Self::AVError(ffi::AVERROR_EOF);
+        }
+        Self::AVError(
+            err.raw_os_error()
+                .map_or(ffi::AVERROR_EXTERNAL, ffi::AVERROR),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_raw_error_and_message() {
+        let err = RsmpegError::OpenInputError(ffi::AVERROR_INVALIDDATA);
+        assert_eq!(err.raw_error(), Some(ffi::AVERROR_INVALIDDATA));
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "Cannot open input file. AVERROR({}): `Invalid data found when processing input`",
+                ffi::AVERROR_INVALIDDATA
+            )
+        );
+        assert_eq!(RsmpegError::CodecNotOpenedError.raw_error(), None);
+    }
+
+    #[test]
+    fn test_again_and_eof() {
+        for err in [
+            RsmpegError::DecoderDrainError,
+            RsmpegError::EncoderDrainError,
+            RsmpegError::BitstreamDrainError,
+            RsmpegError::BufferSinkDrainError,
+            RsmpegError::AVError(AVERROR_EAGAIN),
+        ] {
+            assert!(err.is_again());
+            assert!(!err.is_eof());
+        }
+        for err in [
+            RsmpegError::DecoderFlushedError,
+            RsmpegError::EncoderFlushedError,
+            RsmpegError::BitstreamFlushedError,
+            RsmpegError::BufferSinkEofError,
+            RsmpegError::AVError(ffi::AVERROR_EOF),
+        ] {
+            assert!(err.is_eof());
+            assert!(!err.is_again());
+        }
+        assert!(!RsmpegError::SendPacketError(ffi::AVERROR_INVALIDDATA).is_again());
+    }
+}
@@ -0,0 +1,83 @@
+use crate::{
+    avformat::{AVFormatContextInput, AVIOContextContainer, AVIOContextCustom, AVInputFormat},
+    avutil::{AVDictionary, AVMem},
+    error::*,
+    ffi,
+};
+use std::{ffi::CStr, path::Path};
+
+/// Build an ffconcat list of `paths`, quoting each path so that special
+/// characters survive.
+fn ffconcat_list(paths: &[&Path]) -> Result<Vec<u8>> {
+    let mut list = String::from("ffconcat version 1.0\n");
+    for path in paths {
+        let path = path
+            .to_str()
+            .ok_or(RsmpegError::AVError(ffi::AVERROR(ffi::EINVAL)))?;
+        // In a quoted string only `'` needs escaping, by closing the quote.
+        list.push_str(&format!("file '{}'\n", path.replace('\'', r"'\''")));
+    }
+    Ok(list.into_bytes())
+}
+
+/// Join `paths` with the `concat` demuxer, packets are read from the files one
+/// after another without re-encoding, and timestamps of each file are offset
+/// by the duration of the previous ones.
+///
+/// All the files must have the same streams with the same codecs and codec
+/// parameters (and preferably the same time bases), the demuxer doesn't
+/// check this, mismatching files produce broken output.
+///
+/// The ffconcat list is kept in memory, relative paths are resolved against
+/// the current working directory. When `safe` is true, the demuxer rejects
+/// absolute paths, paths containing a protocol and paths with characters out
+/// of the portable set, pass `false` to accept any path.
+pub fn open_concat(paths: &[&Path], safe: bool) -> Result<AVFormatContextInput> {
+    if paths.is_empty() {
+        return Err(RsmpegError::AVError(ffi::AVERROR(ffi::EINVAL)));
+    }
+    let list = ffconcat_list(paths)?;
+    let mut current = 0;
+    let io_context = AVIOContextCustom::alloc_context(
+        AVMem::new(4096),
+        false,
+        list,
+        Some(Box::new(move |list, buf| {
+            let right = list.len().min(current + buf.len());
+            if right <= current {
+                return ffi::AVERROR_EOF;
+            }
+            let read_len = right - current;
+            buf[0..read_len].copy_from_slice(&list[current..right]);
+            current = right;
+            read_len as i32
+        })),
+        None,
+        None,
+    );
+
+    let concat = CStr::from_bytes_with_nul(b"concat\0").unwrap();
+    let concat =
+        AVInputFormat::find(concat).ok_or(RsmpegError::AVError(ffi::AVERROR_DEMUXER_NOT_FOUND))?;
+    let safe_key = CStr::from_bytes_with_nul(b"safe\0").unwrap();
+    let mut options = Some(AVDictionary::new_int(safe_key, safe as i64, 0));
+    AVFormatContextInput::from_io_context_with_format(
+        AVIOContextContainer::Custom(io_context),
+        Some(&*concat),
+        &mut options,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ffconcat_list() {
+        let list = ffconcat_list(&[Path::new("a.mp4"), Path::new("/tmp/bob's video.mp4")]).unwrap();
+        assert_eq!(
+            String::from_utf8(list).unwrap(),
+            "ffconcat version 1.0\nfile 'a.mp4'\nfile '/tmp/bob'\\''s video.mp4'\n"
+        );
+    }
+}
@@ -0,0 +1,171 @@
+use crate::{
+    avcodec::{AVCodecContext, AVPacket},
+    avformat::AVFormatContextInput,
+    avutil::AVFrame,
+    error::*,
+    ffi,
+};
+
+/// Parameters of decoded video frames, a change of them mid-stream is reported
+/// by [`DecodeEvent::StreamParametersChanged`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameParams {
+    pub width: i32,
+    pub height: i32,
+    pub format: i32,
+}
+
+impl FrameParams {
+    fn of_frame(frame: &AVFrame) -> Self {
+        Self {
+            width: frame.width,
+            height: frame.height,
+            format: frame.format,
+        }
+    }
+
+    /// Parameters the decoder is opened with, if they are already known.
+    fn of_context(context: &AVCodecContext) -> Option<Self> {
+        (context.width > 0 && context.pix_fmt != ffi::AV_PIX_FMT_NONE).then(|| Self {
+            width: context.width,
+            height: context.height,
+            format: context.pix_fmt,
+        })
+    }
+}
+
+/// Item of [`FrameDecoder`].
+#[derive(Debug)]
+pub enum DecodeEvent {
+    Frame(AVFrame),
+    /// Emitted right before the first frame with the `current` parameters.
+    StreamParametersChanged {
+        previous: FrameParams,
+        current: FrameParams,
+    },
+}
+
+/// Iterator decoding the video frames of one stream of an input.
+///
+/// By default frames are yielded as [`DecodeEvent::Frame`] only. With
+/// [`Self::detect_changes()`] enabled, it also:
+///
+/// - compares the width, height and pixel format of each frame with the
+///   previous one(or with the decoder for the first frame), and yields
+///   [`DecodeEvent::StreamParametersChanged`] before the frame on mismatch.
+/// - on a packet carrying `AV_PKT_DATA_NEW_EXTRADATA` side data(e.g. new
+///   SPS/PPS), drains the decoder and reconfigures it with the new extradata
+///   before sending the packet, see [`AVCodecContext::reconfigure()`].
+pub struct FrameDecoder<'a> {
+    input: &'a mut AVFormatContextInput,
+    decoder: &'a mut AVCodecContext,
+    stream_index: usize,
+    detect_changes: bool,
+    params: Option<FrameParams>,
+    /// Frame held back after yielding a parameter change.
+    pending: Option<AVFrame>,
+    /// Packet held back until the decoder is drained and reconfigured.
+    held: Option<AVPacket>,
+    finished: bool,
+}
+
+impl<'a> FrameDecoder<'a> {
+    /// `decoder` should be opened with the codec parameters of the stream.
+    pub fn new(
+        input: &'a mut AVFormatContextInput,
+        decoder: &'a mut AVCodecContext,
+        stream_index: usize,
+    ) -> Self {
+        let params = FrameParams::of_context(decoder);
+        Self {
+            input,
+            decoder,
+            stream_index,
+            detect_changes: false,
+            params,
+            pending: None,
+            held: None,
+            finished: false,
+        }
+    }
+
+    /// Detect mid-stream parameter changes, disabled by default.
+    pub fn detect_changes(mut self, detect_changes: bool) -> Self {
+        self.detect_changes = detect_changes;
+        self
+    }
+
+    /// Read packets of the stream until one is sent to the decoder.
+    fn feed(&mut self) -> Result<()> {
+        loop {
+            match self.input.read_packet()? {
+                Some(packet) if packet.stream_index as usize != self.stream_index => continue,
+                Some(packet) if self.detect_changes && new_extradata(&packet).is_some() => {
+                    self.held = Some(packet);
+                    self.decoder.send_packet(None)?;
+                }
+                Some(packet) => self.decoder.send_packet(Some(&packet))?,
+                None => self.decoder.send_packet(None)?,
+            }
+            return Ok(());
+        }
+    }
+
+    /// Reopen the drained decoder with the extradata of `packet`, then send it.
+    fn reconfigure(&mut self, packet: AVPacket) -> Result<()> {
+        let stream = &self.input.streams()[self.stream_index];
+        let mut codecpar = stream.codecpar().clone();
+        if let Some(extradata) = new_extradata(&packet) {
+            codecpar.set_extradata(extradata)?;
+        }
+        self.decoder.reconfigure(&codecpar, stream.time_base)?;
+        self.decoder.send_packet(Some(&packet))
+    }
+
+    fn on_frame(&mut self, frame: AVFrame) -> DecodeEvent {
+        let current = FrameParams::of_frame(&frame);
+        match self.params.replace(current) {
+            Some(previous) if self.detect_changes && previous != current => {
+                self.pending = Some(frame);
+                DecodeEvent::StreamParametersChanged { previous, current }
+            }
+            _ => DecodeEvent::Frame(frame),
+        }
+    }
+
+    fn next_event(&mut self) -> Result<Option<DecodeEvent>> {
+        loop {
+            if let Some(frame) = self.pending.take() {
+                return Ok(Some(DecodeEvent::Frame(frame)));
+            }
+            match self.decoder.receive_frame() {
+                Ok(frame) => return Ok(Some(self.on_frame(frame))),
+                Err(RsmpegError::DecoderDrainError) => self.feed()?,
+                Err(RsmpegError::DecoderFlushedError) => match self.held.take() {
+                    // Drained for the new extradata.
+                    Some(packet) => self.reconfigure(packet)?,
+                    None => return Ok(None),
+                },
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+impl Iterator for FrameDecoder<'_> {
+    type Item = Result<DecodeEvent>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+        let event = self.next_event().transpose();
+        // Stop on end of stream and on error.
+        self.finished = !matches!(event, Some(Ok(_)));
+        event
+    }
+}
+
+fn new_extradata(packet: &AVPacket) -> Option<&[u8]> {
+    packet.side_data(ffi::AV_PKT_DATA_NEW_EXTRADATA)
+}
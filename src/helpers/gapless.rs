@@ -0,0 +1,82 @@
+use crate::{
+    avutil::{av_rescale_q, ra, AVFrame, AVRational},
+    error::*,
+    ffi,
+    shared::*,
+};
+
+/// Trims the priming samples and the padding off decoded audio frames, for
+/// gapless playback.
+///
+/// Decoders already drop the samples signaled by
+/// [`AVPacket::skip_samples()`](crate::avcodec::AVPacket::skip_samples), e.g.
+/// from an MP4 edit list. This is for the padding known otherwise, e.g. from
+/// [`AVCodecParameters::initial_padding()`](crate::avcodec::AVCodecParameters::initial_padding)
+/// of raw streams, or a content length stored by the application.
+#[derive(Debug, Clone)]
+pub struct SampleTrimmer {
+    time_base: AVRational,
+    /// Samples still to be skipped.
+    skip: i64,
+    /// Samples still to be kept, unlimited if `None`.
+    remaining: Option<i64>,
+}
+
+impl SampleTrimmer {
+    /// Skip the first `skip` samples, then keep `length` samples and drop the
+    /// rest if `length` is given. `time_base` is the time base of the frame
+    /// timestamps, e.g. the `pkt_timebase` of the decoder.
+    pub fn new(time_base: AVRational, skip: i64, length: Option<i64>) -> Self {
+        Self {
+            time_base,
+            skip: skip.max(0),
+            remaining: length.map(|x| x.max(0)),
+        }
+    }
+
+    /// Trim `frame` in place, adjusting its `nb_samples`, `pts` and
+    /// `duration`. Return false if all of its samples are dropped, the frame
+    /// should be discarded then.
+    pub fn trim(&mut self, frame: &mut AVFrame) -> Result<bool> {
+        let nb_samples = frame.nb_samples as i64;
+        let start = self.skip.min(nb_samples);
+        let end = match self.remaining {
+            Some(remaining) => (start + remaining).min(nb_samples),
+            None => nb_samples,
+        };
+        self.skip -= start;
+        if let Some(remaining) = &mut self.remaining {
+            *remaining -= end - start;
+        }
+        if start == end {
+            return Ok(false);
+        }
+
+        let sample_time_base = ra(1, frame.sample_rate);
+        if start > 0 {
+            frame.make_writable()?;
+            unsafe {
+                ffi::av_samples_copy(
+                    frame.extended_data as _,
+                    frame.extended_data as _,
+                    0,
+                    start as i32,
+                    (end - start) as i32,
+                    frame.ch_layout.nb_channels,
+                    frame.format,
+                )
+            }
+            .upgrade()?;
+            if frame.pts != ffi::AV_NOPTS_VALUE {
+                frame.set_pts(frame.pts + av_rescale_q(start, sample_time_base, self.time_base));
+            }
+        }
+        if end - start != nb_samples {
+            frame.set_nb_samples((end - start) as i32);
+            if frame.duration > 0 {
+                frame.set_duration(av_rescale_q(end - start, sample_time_base, self.time_base));
+            }
+        }
+        Ok(true)
+    }
+}
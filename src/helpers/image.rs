@@ -0,0 +1,174 @@
+use crate::{
+    avcodec::{AVCodec, AVCodecContext, AVPacket},
+    avformat::AVFormatContextInput,
+    avutil::{ra, AVFrame, AVPixFmtDescriptorRef, AVPixelFormat},
+    error::*,
+    ffi,
+    swscale::{AspectPolicy, ScaleSpec, SwsFlags, SwsScaler},
+};
+use std::{ffi::CString, fs, path::Path};
+
+/// Image file format of [`save_frame()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageFormat {
+    Png,
+    /// `quality` ranges from 1(worst) to 100(best), values out of range are
+    /// clamped.
+    Jpeg {
+        quality: u8,
+    },
+}
+
+impl ImageFormat {
+    fn codec_id(&self) -> ffi::AVCodecID {
+        match self {
+            Self::Png => ffi::AV_CODEC_ID_PNG,
+            Self::Jpeg { .. } => ffi::AV_CODEC_ID_MJPEG,
+        }
+    }
+
+    /// Pixel format the frames of `src_pix_fmt` are converted to, the alpha
+    /// channel is kept in PNG and dropped in JPEG.
+    fn pix_fmt(&self, src_pix_fmt: AVPixelFormat) -> AVPixelFormat {
+        let has_alpha = AVPixFmtDescriptorRef::get(src_pix_fmt)
+            .map(|desc| desc.has_alpha())
+            .unwrap_or(false);
+        match self {
+            Self::Png if has_alpha => ffi::AV_PIX_FMT_RGBA,
+            Self::Png => ffi::AV_PIX_FMT_RGB24,
+            Self::Jpeg { .. } => ffi::AV_PIX_FMT_YUVJ420P,
+        }
+    }
+
+    /// Map quality 1..=100 linearly to qscale 31..=2, qscale 1 is avoided
+    /// since it bloats the output for little visual gain.
+    fn qscale(quality: u8) -> i32 {
+        let quality = quality.clamp(1, 100) as i32;
+        2 + (100 - quality) * 29 / 99
+    }
+}
+
+/// Wrap the error of `stage` of saving or loading an image into
+/// [`RsmpegError::ImageError`].
+fn stage(stage: &'static str) -> impl FnOnce(RsmpegError) -> RsmpegError {
+    move |err| RsmpegError::ImageError(stage, Box::new(err))
+}
+
+/// Encode a video frame into a single image file at `path`. The frame is
+/// converted to the pixel format the encoder expects, RGB24 (RGBA if the
+/// frame has an alpha channel) for PNG and YUVJ420P for JPEG. The encoded
+/// packet is the whole file content, so no muxer is involved.
+///
+/// Errors are [`RsmpegError::ImageError`], telling whether converting,
+/// encoding or writing failed.
+pub fn save_frame(frame: &AVFrame, path: &Path, format: ImageFormat) -> Result<()> {
+    let pix_fmt = format.pix_fmt(frame.format);
+    let converted;
+    let frame = if frame.format == pix_fmt {
+        frame
+    } else {
+        let spec = ScaleSpec {
+            width: frame.width,
+            height: frame.height,
+            format: pix_fmt,
+            flags: SwsFlags::BICUBIC,
+            policy: AspectPolicy::Stretch,
+        };
+        converted = SwsScaler::new()
+            .scale(frame, &spec)
+            .map_err(stage("convert"))?;
+        &converted
+    };
+    let packet = encode_image(frame, format).map_err(stage("encode"))?;
+    fs::write(path, packet.data().unwrap_or_default()).map_err(|e| stage("write")(e.into()))?;
+    Ok(())
+}
+
+fn encode_image(frame: &AVFrame, format: ImageFormat) -> Result<AVPacket> {
+    let encoder = AVCodec::find_encoder(format.codec_id())
+        .ok_or(RsmpegError::AVError(ffi::AVERROR_ENCODER_NOT_FOUND))?;
+    let mut encode_context = AVCodecContext::new(&encoder);
+    encode_context.try_set_width(frame.width)?;
+    encode_context.try_set_height(frame.height)?;
+    encode_context.try_set_pix_fmt(frame.format)?;
+    encode_context.set_time_base(ra(1, 25))?;
+
+    let mut frame = frame.clone();
+    frame.set_pts(0);
+    if let ImageFormat::Jpeg { quality } = format {
+        let quality = ImageFormat::qscale(quality) * ffi::FF_QP2LAMBDA as i32;
+        encode_context.try_set_flags(encode_context.flags | ffi::AV_CODEC_FLAG_QSCALE as i32)?;
+        encode_context.try_set_global_quality(quality)?;
+        frame.set_quality(quality);
+    }
+    encode_context.open(None)?;
+
+    encode_context.send_frame(Some(&frame))?;
+    encode_context.send_frame(None)?;
+    encode_context.receive_packet()
+}
+
+/// Decode the image file at `path`, e.g. a PNG or a JPEG, into a frame of
+/// the pixel format of the decoder, e.g. RGBA for a PNG with alpha.
+///
+/// Errors are [`RsmpegError::ImageError`], telling whether opening or
+/// decoding failed.
+pub fn load_frame(path: &Path) -> Result<AVFrame> {
+    let path = path
+        .to_str()
+        .and_then(|x| CString::new(x).ok())
+        .ok_or(RsmpegError::AVError(ffi::AVERROR(ffi::EINVAL)))
+        .map_err(stage("open"))?;
+    let mut input = AVFormatContextInput::open(&path, None, &mut None).map_err(stage("open"))?;
+    let (stream_index, decoder) = input
+        .find_best_stream(ffi::AVMEDIA_TYPE_VIDEO)
+        .and_then(|x| x.ok_or(RsmpegError::AVError(ffi::AVERROR_STREAM_NOT_FOUND)))
+        .map_err(stage("open"))?;
+    decode_image(&mut input, stream_index, &decoder).map_err(stage("decode"))
+}
+
+fn decode_image(
+    input: &mut AVFormatContextInput,
+    stream_index: usize,
+    decoder: &AVCodec,
+) -> Result<AVFrame> {
+    let mut decode_context = AVCodecContext::new(decoder);
+    decode_context.apply_codecpar(&input.streams()[stream_index].codecpar())?;
+    decode_context.open(None)?;
+    loop {
+        let packet = input.read_packet()?;
+        if let Some(packet) = &packet {
+            if packet.stream_index as usize != stream_index {
+                continue;
+            }
+        }
+        decode_context.send_packet(packet.as_ref())?;
+        match decode_context.receive_frame() {
+            Err(err) if err.is_again() => continue,
+            result => return result,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_qscale() {
+        assert_eq!(ImageFormat::qscale(100), 2);
+        assert_eq!(ImageFormat::qscale(1), 31);
+        assert_eq!(ImageFormat::qscale(0), 31);
+        assert!((2..=31).contains(&ImageFormat::qscale(75)));
+    }
+
+    #[test]
+    fn test_pix_fmt() {
+        let png = ImageFormat::Png;
+        let jpeg = ImageFormat::Jpeg { quality: 75 };
+        assert_eq!(png.pix_fmt(ffi::AV_PIX_FMT_YUV420P), ffi::AV_PIX_FMT_RGB24);
+        assert_eq!(png.pix_fmt(ffi::AV_PIX_FMT_YUVA420P), ffi::AV_PIX_FMT_RGBA);
+        assert_eq!(png.pix_fmt(ffi::AV_PIX_FMT_BGRA), ffi::AV_PIX_FMT_RGBA);
+        assert_eq!(jpeg.pix_fmt(ffi::AV_PIX_FMT_RGBA), ffi::AV_PIX_FMT_YUVJ420P);
+    }
+}
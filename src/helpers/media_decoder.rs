@@ -0,0 +1,214 @@
+use crate::{
+    avcodec::{AVCodec, AVCodecContext, AVSubtitle},
+    avformat::AVFormatContextInput,
+    avutil::{AVFrame, AVRational},
+    error::*,
+    ffi,
+};
+
+/// Streams decoded by [`MediaDecoder`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StreamSelection {
+    /// All the video, audio and subtitle streams having a decoder.
+    All,
+    /// The best video, audio and subtitle stream, see
+    /// [`AVFormatContextInput::find_best_stream()`]. Media types without such
+    /// a stream are left out.
+    Best,
+    /// Streams of the given indices, failing if any of them can't be decoded.
+    Indices(Vec<usize>),
+}
+
+/// Item of [`MediaDecoder::next_event()`], `stream` is the index of the
+/// stream decoded from.
+#[derive(Debug)]
+pub enum MediaEvent {
+    Video { stream: usize, frame: AVFrame },
+    Audio { stream: usize, frame: AVFrame },
+    Subtitle { stream: usize, sub: AVSubtitle },
+}
+
+struct StreamDecoder {
+    stream: usize,
+    media_type: ffi::AVMediaType,
+    context: AVCodecContext,
+    /// The end of stream is sent to the decoder.
+    flushing: bool,
+    /// All the frames are received after flushing.
+    flushed: bool,
+}
+
+/// Decodes the selected streams of an input at once, routing the packets to
+/// one decoder per stream and draining all of them at the end of the input.
+///
+/// Packets of the other streams are discarded by the demuxer. Timestamps of
+/// the events are in the time base of their stream, see
+/// [`Self::time_base()`].
+pub struct MediaDecoder<'a> {
+    input: &'a mut AVFormatContextInput,
+    decoders: Vec<StreamDecoder>,
+    /// Decoder of the stream the last packet was sent to, received from until
+    /// it needs more input.
+    current: Option<usize>,
+    /// All the packets are read.
+    eof: bool,
+}
+
+impl<'a> MediaDecoder<'a> {
+    /// Open a decoder for each stream of `input` in `selection`.
+    pub fn new(input: &'a mut AVFormatContextInput, selection: StreamSelection) -> Result<Self> {
+        let media_types = [
+            ffi::AVMEDIA_TYPE_VIDEO,
+            ffi::AVMEDIA_TYPE_AUDIO,
+            ffi::AVMEDIA_TYPE_SUBTITLE,
+        ];
+        let indices = match selection {
+            StreamSelection::All => input
+                .streams()
+                .iter()
+                .enumerate()
+                .filter(|(_, stream)| {
+                    let codecpar = stream.codecpar();
+                    media_types.contains(&codecpar.codec_type)
+                        && AVCodec::find_decoder(codecpar.codec_id).is_some()
+                })
+                .map(|(index, _)| index)
+                .collect(),
+            StreamSelection::Best => {
+                let mut indices = vec![];
+                for media_type in media_types {
+                    if let Some((index, _)) = input.find_best_stream(media_type)? {
+                        indices.push(index);
+                    }
+                }
+                indices
+            }
+            StreamSelection::Indices(indices) => indices,
+        };
+
+        let mut decoders = vec![];
+        for &index in &indices {
+            let stream = input
+                .streams()
+                .get(index)
+                .ok_or(RsmpegError::AVError(ffi::AVERROR_STREAM_NOT_FOUND))?;
+            let codecpar = stream.codecpar();
+            if !media_types.contains(&codecpar.codec_type) {
+                return Err(RsmpegError::AVError(ffi::AVERROR(ffi::EINVAL)));
+            }
+            let decoder = AVCodec::find_decoder(codecpar.codec_id)
+                .ok_or(RsmpegError::AVError(ffi::AVERROR_DECODER_NOT_FOUND))?;
+            let mut context = AVCodecContext::new(&decoder);
+            context.apply_codecpar(&codecpar)?;
+            context.try_set_pkt_timebase(stream.time_base)?;
+            context.open(None)?;
+            decoders.push(StreamDecoder {
+                stream: index,
+                media_type: codecpar.codec_type,
+                context,
+                flushing: false,
+                flushed: false,
+            });
+        }
+
+        for (index, stream) in input.streams_mut().iter_mut().enumerate() {
+            if !indices.contains(&index) {
+                stream.set_discard(ffi::AVDISCARD_ALL);
+            }
+        }
+
+        Ok(Self {
+            input,
+            decoders,
+            current: None,
+            eof: false,
+        })
+    }
+
+    /// Indices of the decoded streams.
+    pub fn streams(&self) -> Vec<usize> {
+        self.decoders.iter().map(|x| x.stream).collect()
+    }
+
+    /// Get the time base of the timestamps of the events of `stream`.
+    pub fn time_base(&self, stream: usize) -> Option<AVRational> {
+        self.input.streams().get(stream).map(|x| x.time_base)
+    }
+
+    /// Get the decoder of `stream`, `None` if the stream is not decoded.
+    pub fn decoder(&self, stream: usize) -> Option<&AVCodecContext> {
+        self.decoders
+            .iter()
+            .find(|x| x.stream == stream)
+            .map(|x| &x.context)
+    }
+
+    /// Get the next decoded frame or subtitle of the selected streams, `None`
+    /// once all of them are drained.
+    pub fn next_event(&mut self) -> Result<Option<MediaEvent>> {
+        loop {
+            if let Some(current) = self.current {
+                let decoder = &mut self.decoders[current];
+                match decoder.context.receive_frame() {
+                    Ok(frame) => return Ok(Some(decoder.event(frame))),
+                    Err(RsmpegError::DecoderDrainError) => self.current = None,
+                    Err(RsmpegError::DecoderFlushedError) => {
+                        decoder.flushed = true;
+                        self.current = None;
+                    }
+                    Err(e) => return Err(e),
+                }
+            } else if !self.eof {
+                let Some(mut packet) = self.input.read_packet()? else {
+                    self.eof = true;
+                    continue;
+                };
+                let stream = packet.stream_index as usize;
+                let Some(current) = self.decoders.iter().position(|x| x.stream == stream) else {
+                    continue;
+                };
+                let decoder = &mut self.decoders[current];
+                if decoder.media_type == ffi::AVMEDIA_TYPE_SUBTITLE {
+                    if let Some(sub) = decoder.context.decode_subtitle(Some(&mut packet))? {
+                        return Ok(Some(MediaEvent::Subtitle { stream, sub }));
+                    }
+                } else {
+                    decoder.context.send_packet(Some(&packet))?;
+                    self.current = Some(current);
+                }
+            } else {
+                // Drain the decoders one by one.
+                let Some(current) = self.decoders.iter().position(|x| !x.flushed) else {
+                    return Ok(None);
+                };
+                let decoder = &mut self.decoders[current];
+                if decoder.media_type == ffi::AVMEDIA_TYPE_SUBTITLE {
+                    match decoder.context.decode_subtitle(None)? {
+                        Some(sub) => {
+                            let stream = decoder.stream;
+                            return Ok(Some(MediaEvent::Subtitle { stream, sub }));
+                        }
+                        None => decoder.flushed = true,
+                    }
+                } else {
+                    if !decoder.flushing {
+                        decoder.context.send_packet(None)?;
+                        decoder.flushing = true;
+                    }
+                    self.current = Some(current);
+                }
+            }
+        }
+    }
+}
+
+impl StreamDecoder {
+    fn event(&self, frame: AVFrame) -> MediaEvent {
+        let stream = self.stream;
+        if self.media_type == ffi::AVMEDIA_TYPE_VIDEO {
+            MediaEvent::Video { stream, frame }
+        } else {
+            MediaEvent::Audio { stream, frame }
+        }
+    }
+}
@@ -0,0 +1,16 @@
+//! High level helpers built on top of the FFmpeg wrappers.
+mod concat;
+mod decode;
+mod gapless;
+mod image;
+mod media_decoder;
+mod seek;
+mod timestamp;
+
+pub use concat::*;
+pub use decode::*;
+pub use gapless::*;
+pub use image::*;
+pub use media_decoder::*;
+pub use seek::*;
+pub use timestamp::*;
@@ -0,0 +1,176 @@
+use crate::{
+    avcodec::{AVCodec, AVCodecContext},
+    avformat::{AVFormatContextInput, SeekTarget},
+    avutil::{av_rescale_q, ra, AVFrame},
+    error::*,
+    ffi,
+};
+
+/// Get the frame of stream `stream_index` displayed `timestamp_us`
+/// microseconds after the start of `input`, e.g. for a thumbnail.
+///
+/// A decoder is opened for each call, use [`FrameExtractor`] for extracting
+/// several frames of the same input.
+pub fn frame_at(
+    input: &mut AVFormatContextInput,
+    stream_index: usize,
+    timestamp_us: i64,
+) -> Result<AVFrame> {
+    FrameExtractor::new(input, stream_index)?.frame_at(input, timestamp_us)
+}
+
+/// Extracts the frames of a stream at given times, keeping its decoder
+/// between the calls. Each extraction seeks and flushes the decoder, so the
+/// calls are independent and can be in any order.
+pub struct FrameExtractor {
+    decode_context: AVCodecContext,
+    stream_index: usize,
+}
+
+impl FrameExtractor {
+    /// Open a decoder for stream `stream_index` of `input`.
+    pub fn new(input: &AVFormatContextInput, stream_index: usize) -> Result<Self> {
+        let stream = input
+            .stream(stream_index)
+            .ok_or(RsmpegError::AVError(ffi::AVERROR_STREAM_NOT_FOUND))?;
+        let codecpar = stream.codecpar();
+        let decoder = AVCodec::find_decoder(codecpar.codec_id)
+            .ok_or(RsmpegError::AVError(ffi::AVERROR_DECODER_NOT_FOUND))?;
+        let mut decode_context = AVCodecContext::new(&decoder);
+        decode_context.apply_codecpar(&codecpar)?;
+        decode_context.try_set_pkt_timebase(stream.time_base)?;
+        decode_context.open(None)?;
+        Ok(Self {
+            decode_context,
+            stream_index,
+        })
+    }
+
+    /// Get the frame displayed `timestamp_us` microseconds after the start of
+    /// `input`, the one the extractor is created for, see [`seek_exact()`].
+    /// Frames without `pts` get their `best_effort_timestamp`.
+    ///
+    /// Return [`RsmpegError::SeekBeyondEndError`] if `timestamp_us` is after
+    /// the end of the input or of the last frame, `AVERROR_STREAM_NOT_FOUND`
+    /// if `input` has no stream of the extractor's index.
+    pub fn frame_at(
+        &mut self,
+        input: &mut AVFormatContextInput,
+        timestamp_us: i64,
+    ) -> Result<AVFrame> {
+        if input.duration > 0 && timestamp_us >= input.duration {
+            return Err(RsmpegError::SeekBeyondEndError);
+        }
+        let start_time = if input.start_time == ffi::AV_NOPTS_VALUE {
+            0
+        } else {
+            input.start_time
+        };
+        let time_base = input
+            .stream(self.stream_index)
+            .ok_or(RsmpegError::AVError(ffi::AVERROR_STREAM_NOT_FOUND))?
+            .time_base;
+        let target = av_rescale_q(
+            start_time + timestamp_us,
+            ra(1, ffi::AV_TIME_BASE as i32),
+            time_base,
+        );
+        let mut frame = seek_exact(input, &mut self.decode_context, self.stream_index, target)?;
+        let pts = frame.best_effort_timestamp;
+        // `seek_exact()` falls back to the last frame.
+        if frame.duration > 0 && pts + frame.duration <= target {
+            return Err(RsmpegError::SeekBeyondEndError);
+        }
+        if frame.pts == ffi::AV_NOPTS_VALUE {
+            frame.set_pts(pts);
+        }
+        Ok(frame)
+    }
+}
+
+/// Seek to the frame of stream `stream_index` whose presentation interval
+/// covers `target`(in the time base of the stream), return the decoded frame.
+///
+/// See [`seek_exact_counted()`] for details.
+pub fn seek_exact(
+    input: &mut AVFormatContextInput,
+    decode_context: &mut AVCodecContext,
+    stream_index: usize,
+    target: i64,
+) -> Result<AVFrame> {
+    seek_exact_counted(input, decode_context, stream_index, target).map(|(frame, _)| frame)
+}
+
+/// Similar to [`seek_exact()`], while also returning the number of frames
+/// decoded and discarded before the returned one.
+///
+/// The input is seeked backward to the key frame before `target`, the decoder
+/// is flushed, then frames are decoded and discarded until one with
+/// `best_effort_timestamp + duration > target`. If `target` is before the
+/// first frame, the first frame is returned; if it's after the last frame, the
+/// last frame is returned.
+///
+/// Limitations: the backward seek relies on the demuxer index. For open-GOP
+/// content the frames right after the key frame may reference frames before
+/// it, decoders usually drop them or output them broken, so a target that falls
+/// on them may resolve to a later frame. Frames without duration(and no
+/// following frame to infer it) never match until EOF.
+pub fn seek_exact_counted(
+    input: &mut AVFormatContextInput,
+    decode_context: &mut AVCodecContext,
+    stream_index: usize,
+    target: i64,
+) -> Result<(AVFrame, usize)> {
+    input.seek(
+        Some(stream_index),
+        SeekTarget::Timestamp(target),
+        ffi::AVSEEK_FLAG_BACKWARD,
+    )?;
+    decode_context.flush_buffers();
+
+    // Number of frames decoded before the current one.
+    let mut decoded = 0;
+    // The previous frame and the number of frames decoded before it.
+    let mut prev: Option<(AVFrame, usize)> = None;
+    let mut flushing = false;
+    loop {
+        if !flushing {
+            match input.read_packet()? {
+                Some(packet) if packet.stream_index as usize != stream_index => continue,
+                Some(packet) => decode_context.send_packet(Some(&packet))?,
+                None => {
+                    decode_context.send_packet(None)?;
+                    flushing = true;
+                }
+            }
+        }
+        loop {
+            let frame = match decode_context.receive_frame() {
+                Ok(frame) => frame,
+                Err(RsmpegError::DecoderDrainError) => break,
+                // `target` is after the last frame.
+                Err(RsmpegError::DecoderFlushedError) => {
+                    return prev.ok_or(RsmpegError::AVError(ffi::AVERROR_EOF));
+                }
+                Err(e) => return Err(e),
+            };
+            let pts = frame.best_effort_timestamp;
+            if pts == ffi::AV_NOPTS_VALUE {
+                decoded += 1;
+                continue;
+            }
+            // Frames without duration last until the next one.
+            let prev_open_ended = matches!(&prev, Some((prev, _)) if prev.duration <= 0);
+            if prev_open_ended && pts > target {
+                return Ok(prev.unwrap());
+            }
+            // Also return the first frame after `target` when no frame covers
+            // it, e.g. `target` is before the first frame.
+            if pts > target || (frame.duration > 0 && pts + frame.duration > target) {
+                return Ok((frame, decoded));
+            }
+            prev = Some((frame, decoded));
+            decoded += 1;
+        }
+    }
+}
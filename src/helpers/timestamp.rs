@@ -0,0 +1,164 @@
+use crate::{
+    avcodec::AVPacket,
+    avutil::{av_rescale_q, log::log_message, ra, AVFrame, AVRational},
+    error::*,
+    ffi,
+};
+use std::ptr;
+
+/// Carries timestamps of one stream through a transcode pipeline: decoder →
+/// filter graph → encoder → muxer, each having its own time base.
+///
+/// Frames without a timestamp are placed right after the previous frame, so
+/// the encoder always gets increasing `pts`. Packets whose `dts` doesn't
+/// increase would be rejected by the muxer, they are reported as
+/// [`RsmpegError::NonMonotonicDtsError`] in strict mode, otherwise a warning
+/// is logged and the `dts` is bumped past the previous one.
+#[derive(Debug, Clone)]
+pub struct TimestampMapper {
+    decoder_time_base: AVRational,
+    filter_time_base: AVRational,
+    encoder_time_base: AVRational,
+    stream_time_base: AVRational,
+    strict: bool,
+    /// Expected `pts` of the next frame sent to the encoder, in the encoder
+    /// time base.
+    next_pts: Option<i64>,
+    /// `dts` of the last packet sent to the muxer, in the stream time base.
+    last_dts: Option<i64>,
+}
+
+impl TimestampMapper {
+    /// `decoder_time_base` is the `pkt_timebase` of the decoder (the input
+    /// stream time base), `filter_time_base` the time base of the buffer sink,
+    /// `encoder_time_base` and `stream_time_base` the time bases of the
+    /// encoder and of the output stream. Set the latter after writing the
+    /// header, muxers may change it.
+    pub fn new(
+        decoder_time_base: AVRational,
+        filter_time_base: AVRational,
+        encoder_time_base: AVRational,
+        stream_time_base: AVRational,
+    ) -> Self {
+        Self {
+            decoder_time_base,
+            filter_time_base,
+            encoder_time_base,
+            stream_time_base,
+            strict: false,
+            next_pts: None,
+            last_dts: None,
+        }
+    }
+
+    /// Fail on non-monotonic `dts` instead of fixing them.
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Set the output stream time base, e.g. once the header is written.
+    pub fn set_stream_time_base(&mut self, stream_time_base: AVRational) {
+        self.stream_time_base = stream_time_base;
+    }
+
+    /// Prepare a decoded `frame` for the filter graph: its `pts` is set to
+    /// the best effort timestamp, in the decoder time base.
+    pub fn frame_from_decoder(&self, frame: &mut AVFrame) {
+        frame.set_pts(frame.best_effort_timestamp);
+        frame.set_time_base(self.decoder_time_base);
+    }
+
+    /// Rescale the `pts` of a filtered `frame` to the encoder time base,
+    /// filling it from the previous frame if missing.
+    pub fn frame_to_encoder(&mut self, frame: &mut AVFrame) {
+        let pts = if frame.pts == ffi::AV_NOPTS_VALUE {
+            self.next_pts.unwrap_or(0)
+        } else {
+            av_rescale_q(frame.pts, self.filter_time_base, self.encoder_time_base)
+        };
+        let duration = if frame.duration > 0 {
+            av_rescale_q(
+                frame.duration,
+                self.filter_time_base,
+                self.encoder_time_base,
+            )
+        } else if frame.nb_samples > 0 && frame.sample_rate > 0 {
+            av_rescale_q(
+                frame.nb_samples as i64,
+                ra(1, frame.sample_rate),
+                self.encoder_time_base,
+            )
+        } else {
+            // Encoders of video take `1/framerate` as their time base.
+            1
+        };
+        self.next_pts = Some(pts + duration.max(1));
+        frame.set_pts(pts);
+        frame.set_time_base(self.encoder_time_base);
+    }
+
+    /// Rescale the timestamps of an encoded `packet` to the output stream
+    /// time base and check its `dts` increases.
+    pub fn packet_to_stream(&mut self, packet: &mut AVPacket) -> Result<()> {
+        packet.rescale_ts(self.encoder_time_base, self.stream_time_base);
+        if packet.dts == ffi::AV_NOPTS_VALUE {
+            return Ok(());
+        }
+        if let Some(last_dts) = self.last_dts {
+            if packet.dts <= last_dts {
+                if self.strict {
+                    return Err(RsmpegError::NonMonotonicDtsError(last_dts, packet.dts));
+                }
+                let dts = last_dts + 1;
+                let message = format!(
+                    "Non-monotonic dts {}, previous dts is {}, changing to {}",
+                    packet.dts, last_dts, dts
+                );
+                unsafe { log_message(ptr::null_mut(), ffi::AV_LOG_WARNING as i32, &message) };
+                if packet.pts != ffi::AV_NOPTS_VALUE && packet.pts < dts {
+                    packet.set_pts(dts);
+                }
+                packet.set_dts(dts);
+            }
+        }
+        self.last_dts = Some(packet.dts);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timestamp_mapper() {
+        let mut mapper = TimestampMapper::new(ra(1, 12800), ra(1, 12800), ra(1, 25), ra(1, 12800));
+        let mut frame = AVFrame::new();
+        frame.set_pts(1024);
+        mapper.frame_to_encoder(&mut frame);
+        assert_eq!(frame.pts, 2);
+        // Filled from the previous frame.
+        frame.set_pts(ffi::AV_NOPTS_VALUE);
+        mapper.frame_to_encoder(&mut frame);
+        assert_eq!(frame.pts, 3);
+
+        let mut packet = AVPacket::new();
+        packet.set_pts(3);
+        packet.set_dts(3);
+        mapper.packet_to_stream(&mut packet).unwrap();
+        assert_eq!((packet.pts, packet.dts), (1536, 1536));
+        packet.set_pts(3);
+        packet.set_dts(3);
+        mapper.packet_to_stream(&mut packet).unwrap();
+        assert_eq!((packet.pts, packet.dts), (1537, 1537));
+
+        let mut mapper = mapper.strict(true);
+        packet.set_pts(2);
+        packet.set_dts(2);
+        assert_eq!(
+            mapper.packet_to_stream(&mut packet),
+            Err(RsmpegError::NonMonotonicDtsError(1537, 1024))
+        );
+    }
+}
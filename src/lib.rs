@@ -11,11 +11,15 @@ mod macros;
 mod shared;
 
 pub mod avcodec;
+#[cfg(feature = "avdevice")]
+pub mod avdevice;
 pub mod avfilter;
 pub mod avformat;
 pub mod avutil;
+pub mod helpers;
 pub mod swresample;
 pub mod swscale;
+pub mod version;
 
 pub mod error;
 
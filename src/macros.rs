@@ -2,17 +2,37 @@
 //! type with some convenient functions
 
 /// Wrapping with XXX -> XXX mapping.
+///
+/// The wrapped type is `Send`: FFmpeg objects must only be used from one thread
+/// at a time, but they aren't bound to the thread creating them, so moving
+/// them between threads is sound. They are never `Sync`. Types that mustn't
+/// leave their owner's thread are wrapped with the `!Send` prefix.
 macro_rules! wrap_pure {
     (
         $(#[$meta:meta])*
         ($wrapped_type: ident): $ffi_type: ty
-        $(,$attach: ident: $attach_type: ty = $attach_default: expr)*
+        $(,$attach_vis: vis $attach: ident: $attach_type: ty = $attach_default: expr)*
+    ) => {
+        wrap_pure!(!Send $(#[$meta])* ($wrapped_type): $ffi_type $(,$attach_vis $attach: $attach_type = $attach_default)*);
+
+        // SAFETY: Moving is sound as said above, and the attachments are
+        // checked to be `Send` below. Types with other attachments use the
+        // `!Send` prefix and justify their own `Send` impl.
+        unsafe impl Send for $wrapped_type {}
+        const _: () = { $(crate::shared::assert_send::<$attach_type>();)* };
+    };
+    (
+        !Send
+        $(#[$meta:meta])*
+        ($wrapped_type: ident): $ffi_type: ty
+        $(,$attach_vis: vis $attach: ident: $attach_type: ty = $attach_default: expr)*
     ) => {
         $(#[$meta])*
         pub struct $wrapped_type {
             something_should_not_be_touched_directly: std::ptr::NonNull<$ffi_type>,
-            // Publicize the attachment, can be directly changed without deref_mut()
-            $(pub $attach: $attach_type,)*
+            // Attachments declared `pub` can be directly changed without
+            // deref_mut(), the ones FFmpeg keeps pointers to stay private.
+            $($attach_vis $attach: $attach_type,)*
         }
 
         impl $wrapped_type {
@@ -65,8 +85,6 @@ macro_rules! wrap_pure {
                 unsafe { self.something_should_not_be_touched_directly.as_mut() }
             }
         }
-
-        unsafe impl Send for $wrapped_type {}
     };
 }
 
@@ -158,10 +176,10 @@ macro_rules! wrap_ref_mut {
     (
         $(#[$meta:meta])*
         $name: ident: $ffi_type: ty
-        $(,$attach: ident: $attach_type: ty = $attach_default: expr)* $(,)?
+        $(,$attach_vis: vis $attach: ident: $attach_type: ty = $attach_default: expr)* $(,)?
     ) => {
         paste::paste! {
-            wrap_pure!($(#[$meta])* ($name): $ffi_type $(,$attach: $attach_type = $attach_default)*);
+            wrap_pure!($(#[$meta])* ($name): $ffi_type $(,$attach_vis $attach: $attach_type = $attach_default)*);
             wrap_ref_pure!(($name, [<$name Ref>]): $ffi_type);
             wrap_mut_pure!(($name, [<$name Mut>]): $ffi_type);
         }
@@ -173,10 +191,10 @@ macro_rules! wrap_ref {
     (
         $(#[$meta:meta])*
         $name: ident: $ffi_type: ty
-        $(,$attach: ident: $attach_type: ty = $attach_default: expr)* $(,)?
+        $(,$attach_vis: vis $attach: ident: $attach_type: ty = $attach_default: expr)* $(,)?
     ) => {
         paste::paste! {
-            wrap_pure!($(#[$meta])* ($name): $ffi_type $(,$attach: $attach_type = $attach_default)*);
+            wrap_pure!($(#[$meta])* ($name): $ffi_type $(,$attach_vis $attach: $attach_type = $attach_default)*);
             wrap_ref_pure!(($name, [<$name Ref>]): $ffi_type);
         }
     };
@@ -187,10 +205,21 @@ macro_rules! wrap_mut {
     (
         $(#[$meta:meta])*
         $name: ident: $ffi_type: ty
-        $(,$attach: ident: $attach_type: ty = $attach_default: expr)* $(,)?
+        $(,$attach_vis: vis $attach: ident: $attach_type: ty = $attach_default: expr)* $(,)?
+    ) => {
+        paste::paste! {
+            wrap_pure!($(#[$meta])* ($name): $ffi_type $(,$attach_vis $attach: $attach_type = $attach_default)*);
+            wrap_mut_pure!(($name, [<$name Mut>]): $ffi_type);
+        }
+    };
+    (
+        !Send
+        $(#[$meta:meta])*
+        $name: ident: $ffi_type: ty
+        $(,$attach_vis: vis $attach: ident: $attach_type: ty = $attach_default: expr)* $(,)?
     ) => {
         paste::paste! {
-            wrap_pure!($(#[$meta])* ($name): $ffi_type $(,$attach: $attach_type = $attach_default)*);
+            wrap_pure!(!Send $(#[$meta])* ($name): $ffi_type $(,$attach_vis $attach: $attach_type = $attach_default)*);
             wrap_mut_pure!(($name, [<$name Mut>]): $ffi_type);
         }
     };
@@ -201,15 +230,26 @@ macro_rules! wrap {
     (
         $(#[$meta:meta])*
         $name: ident: $ffi_type: ty
-        $(,$attach: ident: $attach_type: ty = $attach_default: expr)* $(,)?
+        $(,$attach_vis: vis $attach: ident: $attach_type: ty = $attach_default: expr)* $(,)?
     ) => {
         paste::paste! {
-            wrap_pure!($(#[$meta])* ($name): $ffi_type $(,$attach: $attach_type = $attach_default)*);
+            wrap_pure!($(#[$meta])* ($name): $ffi_type $(,$attach_vis $attach: $attach_type = $attach_default)*);
         }
     };
+    (
+        !Send
+        $(#[$meta:meta])*
+        $name: ident: $ffi_type: ty
+        $(,$attach_vis: vis $attach: ident: $attach_type: ty = $attach_default: expr)* $(,)?
+    ) => {
+        wrap_pure!(!Send $(#[$meta])* ($name): $ffi_type $(,$attach_vis $attach: $attach_type = $attach_default)*);
+    };
 }
 
 /// Autogen single set function.
+///
+/// The property type must implement `SettableField`, so fields owning memory
+/// (pointers, channel layouts...) fail to compile and need handwritten setters.
 macro_rules! set_fn {
     ($impl_type:ident {
         $(
@@ -217,13 +257,54 @@ macro_rules! set_fn {
         )+
     }) => {
         impl $impl_type {
-            $(pub fn $fn_name(&mut self, $property: $property_type) {
+            $(pub fn $fn_name(&mut self, $property: $property_type)
+            where
+                $property_type: crate::shared::SettableField,
+            {
                 unsafe {
                     self.deref_mut().$property = $property;
                 }
             })+
         }
-    }
+    };
+    // Setters calling `$check` first, fail if it fails. The setters named
+    // like the unchecked ones are kept as deprecated passthroughs logging the
+    // failure, for callers written before the checks.
+    ($impl_type:ident, $check:ident {
+        $(
+            ($try_fn_name:ident, $fn_name:ident, $property:ident, $property_type:path)
+        )+
+    }) => {
+        impl $impl_type {
+            $(pub fn $try_fn_name(&mut self, $property: $property_type) -> crate::error::Result<()>
+            where
+                $property_type: crate::shared::SettableField,
+            {
+                self.$check()?;
+                unsafe {
+                    self.deref_mut().$property = $property;
+                }
+                Ok(())
+            }
+
+            #[deprecated(note = "use the `try_set_*()` setter, which returns the error")]
+            pub fn $fn_name(&mut self, $property: $property_type)
+            where
+                $property_type: crate::shared::SettableField,
+            {
+                if let Err(e) = self.$try_fn_name($property) {
+                    let message = format!("{} ignored: {}", stringify!($fn_name), e);
+                    unsafe {
+                        crate::avutil::log::log_message(
+                            self.as_mut_ptr().cast(),
+                            crate::ffi::AV_LOG_WARNING as i32,
+                            &message,
+                        )
+                    };
+                }
+            })+
+        }
+    };
 }
 
 /// Autogen multiple set functions.
@@ -241,6 +322,19 @@ macro_rules! settable {
             });
         }
     };
+    ($impl_type:ident, $check:ident {
+        $(
+            $property:ident : $property_type:path
+        ),+ $(,)?
+    }) => {
+        paste::paste! {
+            set_fn!($impl_type, $check {
+                $(
+                    ([<try_set_ $property>], [<set_ $property>], $property, $property_type)
+                )+
+            });
+        }
+    };
 }
 
 #[cfg(test)]
@@ -249,7 +343,7 @@ mod test {
     use std::{ptr::NonNull, slice};
     #[test]
     fn test_attachment() {
-        wrap!(PinStr: u8, len: usize = 0, capacity: usize = 0);
+        wrap!(PinStr: u8, pub len: usize = 0, pub capacity: usize = 0);
 
         impl PinStr {
             fn new(s: &str) -> Self {
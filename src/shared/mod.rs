@@ -76,6 +76,22 @@ pub trait UnsafeDerefMut: Deref {
     unsafe fn deref_mut(&mut self) -> &mut Self::Target;
 }
 
+/// Plain-old-data field types `settable!` generates setters for. Overwriting
+/// such a field never leaks or frees anything, so pointers and types owning
+/// memory must not implement this trait.
+pub trait SettableField: Copy {}
+
+impl SettableField for i32 {}
+impl SettableField for i64 {}
+impl SettableField for u32 {}
+impl SettableField for u64 {}
+impl SettableField for f32 {}
+impl SettableField for f64 {}
+impl SettableField for ffi::AVRational {}
+
+/// Fails to compile if `T` isn't `Send`, `wrap!` checks attachments with it.
+pub const fn assert_send<T: Send>() {}
+
 /// Since ffi::AVERROR(ffi::EAGAIN) is often used in match arm, but RFC #2920
 /// ([tracking issue](https://github.com/rust-lang/rust/issues/76001)) haven't
 /// yet been implemented, we currently create a const value here as a workaround.
@@ -1,9 +1,86 @@
-use crate::{avutil::AVFrame, error::*, ffi, shared::*};
-use std::ptr::{self, NonNull};
+use crate::{
+    avutil::{av_rescale_q_rnd, ra, AVChannelLayout, AVFrame, AVSamples, SampleFormat},
+    error::*,
+    ffi,
+    shared::*,
+};
+use std::{
+    ffi::CStr,
+    mem,
+    ptr::{self, NonNull},
+};
 
-wrap!(SwrContext: ffi::SwrContext);
+wrap!(
+    SwrContext: ffi::SwrContext,
+    auto_reconfigure: bool = false,
+);
+
+/// Builder of [`SwrContext`], e.g. for resampling decoded audio to the
+/// parameters of an AAC encoder:
+///
+/// ```rust
+/// # use rsmpeg::{avutil::AVChannelLayout, ffi, swresample::SwrContext};
+/// # fn main() -> rsmpeg::error::Result<()> {
+/// let stereo = AVChannelLayout::from_nb_channels(2);
+/// let mut context = SwrContext::builder()
+///     .input(&stereo, ffi::AV_SAMPLE_FMT_S16, 44100)
+///     .output(&stereo, ffi::AV_SAMPLE_FMT_FLTP, 48000)
+///     .build()?;
+/// context.init()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Default)]
+pub struct SwrContextBuilder {
+    input: Option<(AVChannelLayout, SampleFormat, i32)>,
+    output: Option<(AVChannelLayout, SampleFormat, i32)>,
+}
+
+impl SwrContextBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the channel layout, sample format and sample rate of the input.
+    pub fn input(
+        mut self,
+        ch_layout: &AVChannelLayout,
+        sample_fmt: impl Into<SampleFormat>,
+        sample_rate: i32,
+    ) -> Self {
+        self.input = Some((ch_layout.clone(), sample_fmt.into(), sample_rate));
+        self
+    }
+
+    /// Set the channel layout, sample format and sample rate of the output.
+    pub fn output(
+        mut self,
+        ch_layout: &AVChannelLayout,
+        sample_fmt: impl Into<SampleFormat>,
+        sample_rate: i32,
+    ) -> Self {
+        self.output = Some((ch_layout.clone(), sample_fmt.into(), sample_rate));
+        self
+    }
+
+    /// Allocate the [`SwrContext`], it needs to be initialized with
+    /// [`SwrContext::init()`] after setting the other options.
+    ///
+    /// Returns `AVERROR(EINVAL)` if the input or the output is not set.
+    pub fn build(&self) -> Result<SwrContext> {
+        let (Some(input), Some(output)) = (&self.input, &self.output) else {
+            return Err(RsmpegError::AVError(ffi::AVERROR(ffi::EINVAL)));
+        };
+        SwrContext::new(&output.0, output.1, output.2, &input.0, input.1, input.2)
+    }
+}
 
 impl SwrContext {
+    /// Get a [`SwrContextBuilder`].
+    pub fn builder() -> SwrContextBuilder {
+        SwrContextBuilder::new()
+    }
+
     /// Check whether an swr context has been initialized or not.
     pub fn is_initialized(&self) -> bool {
         // should always be true
@@ -26,10 +103,10 @@ impl SwrContext {
     /// Returns None on invalid parameters or insufficient parameters.
     pub fn new(
         out_ch_layout: &ffi::AVChannelLayout,
-        out_sample_fmt: ffi::AVSampleFormat,
+        out_sample_fmt: impl Into<SampleFormat>,
         out_sample_rate: i32,
         in_ch_layout: &ffi::AVChannelLayout,
-        in_sample_fmt: ffi::AVSampleFormat,
+        in_sample_fmt: impl Into<SampleFormat>,
         in_sample_rate: i32,
     ) -> Result<Self> {
         let mut context = ptr::null_mut();
@@ -37,10 +114,10 @@ impl SwrContext {
             ffi::swr_alloc_set_opts2(
                 &mut context,
                 out_ch_layout,
-                out_sample_fmt,
+                out_sample_fmt.into().0,
                 out_sample_rate,
                 in_ch_layout,
-                in_sample_fmt,
+                in_sample_fmt.into().0,
                 in_sample_rate,
                 0,
                 ptr::null_mut(),
@@ -56,12 +133,35 @@ impl SwrContext {
         Ok(())
     }
 
+    /// Make [`Self::convert_frame()`] reconfigure the context automatically
+    /// when the input parameters change mid-stream, instead of returning
+    /// `AVERROR_INPUT_CHANGED`.
+    pub fn set_auto_reconfigure(&mut self, enabled: bool) {
+        self.auto_reconfigure = enabled;
+    }
+
+    /// Return true if [`Self::set_auto_reconfigure()`] is enabled.
+    pub fn auto_reconfigure(&self) -> bool {
+        self.auto_reconfigure
+    }
+
+    /// Reconfigure the context with the channel layout, sample rate and sample
+    /// format of `new_input`, and re-initialize it. Output parameters are kept.
+    ///
+    /// Samples buffered with the old configuration are dropped, flush them
+    /// with [`Self::convert_frame()`] before calling this if they are needed.
+    pub fn config_changed(&mut self, new_input: &AVFrame) -> Result<()> {
+        unsafe { ffi::swr_config_frame(self.as_mut_ptr(), ptr::null(), new_input.as_ptr()) }
+            .upgrade()?;
+        self.init()
+    }
+
     /// Find an upper bound on the number of samples that the next
-    /// [`SwrContext::convert`] call will output, if called with `in_samples` of
+    /// [`SwrContext::convert_samples`] call will output, if called with `in_samples` of
     /// input samples.
     ///
     /// This depends on the internal state, and anything changing the internal
-    /// state (like further [`SwrContext::convert`] calls) will may change the
+    /// state (like further [`SwrContext::convert_samples`] calls) will may change the
     /// number of samples current function returns for the same number of input
     /// samples.
     pub fn get_out_samples(&self, in_samples: i32) -> i32 {
@@ -97,7 +197,84 @@ impl SwrContext {
             .unwrap()
     }
 
-    /// Convert audio.
+    /// Convert `in_count` samples per channel of `in_data` into `out`, return
+    /// the number of samples output per channel.
+    ///
+    /// `in_data` can be `None` with an `in_count` of 0 to flush the last few
+    /// samples out at the end. If more input is provided than `out` can hold,
+    /// the input will be buffered, [`SwrContext::get_out_samples`] gives an
+    /// upper bound of the space needed.
+    ///
+    /// Returns `AVERROR(EINVAL)` if the sample formats and channel counts of
+    /// `in_data` and `out` don't match the ones the context is configured
+    /// with, or if `in_data` has fewer than `in_count` samples. At most
+    /// `out.nb_samples` samples are output, fewer if the buffer of `out`
+    /// can't hold them.
+    pub fn convert_samples(
+        &mut self,
+        out: &mut AVSamples,
+        in_data: Option<&AVSamples>,
+        in_count: i32,
+    ) -> Result<i32> {
+        let invalid = Err(RsmpegError::AVError(ffi::AVERROR(ffi::EINVAL)));
+        if self.get_params(b"out_sample_fmt\0", b"out_chlayout\0")?
+            != (out.sample_fmt, out.nb_channels)
+        {
+            return invalid;
+        }
+        // The public fields of `AVSamples` may not match their buffers.
+        let Some(out_capacity) = out.capacity() else {
+            return invalid;
+        };
+        let in_buffer = match in_data {
+            Some(in_data) => {
+                let Some(in_capacity) = in_data.capacity() else {
+                    return invalid;
+                };
+                if self.get_params(b"in_sample_fmt\0", b"in_chlayout\0")?
+                    != (in_data.sample_fmt, in_data.nb_channels)
+                    || !(0..=in_data.nb_samples.min(in_capacity)).contains(&in_count)
+                {
+                    return invalid;
+                }
+                in_data.audio_data.as_ptr() as *const *const u8
+            }
+            None if in_count == 0 => ptr::null(),
+            None => return invalid,
+        };
+        unsafe {
+            self.convert_raw(
+                out.audio_data.as_mut_ptr(),
+                out.nb_samples.clamp(0, out_capacity),
+                in_buffer,
+                in_count,
+            )
+        }
+    }
+
+    /// Get the sample format and the number of channels of the options
+    /// `sample_fmt` and `ch_layout`.
+    fn get_params(
+        &self,
+        sample_fmt: &[u8],
+        ch_layout: &[u8],
+    ) -> Result<(ffi::AVSampleFormat, i32)> {
+        let sample_fmt = CStr::from_bytes_with_nul(sample_fmt).unwrap();
+        let ch_layout = CStr::from_bytes_with_nul(ch_layout).unwrap();
+        let mut format = ffi::AV_SAMPLE_FMT_NONE;
+        unsafe {
+            ffi::av_opt_get_sample_fmt(self.as_ptr() as _, sample_fmt.as_ptr(), 0, &mut format)
+        }
+        .upgrade()?;
+        let mut layout: ffi::AVChannelLayout = unsafe { mem::zeroed() };
+        unsafe { ffi::av_opt_get_chlayout(self.as_ptr() as _, ch_layout.as_ptr(), 0, &mut layout) }
+            .upgrade()?;
+        let nb_channels = layout.nb_channels;
+        unsafe { ffi::av_channel_layout_uninit(&mut layout) };
+        Ok((format, nb_channels))
+    }
+
+    /// Convert audio, check [`Self::convert_samples()`] for the safe version.
     ///
     /// `in_buffer` and `in_count` can be set to 0 to flush the last few samples
     /// out at the end.  If more input is provided than output space, then the
@@ -116,7 +293,7 @@ impl SwrContext {
     /// # Safety
     ///
     /// Only safe when the `in_buffer` is valid.
-    pub unsafe fn convert(
+    pub unsafe fn convert_raw(
         &mut self,
         out_buffer: *mut *mut u8,
         out_count: i32,
@@ -143,6 +320,22 @@ impl SwrContext {
         Ok(ret)
     }
 
+    /// Renamed to [`Self::convert_raw()`], the safe version is
+    /// [`Self::convert_samples()`].
+    ///
+    /// # Safety
+    /// Same as [`Self::convert_raw()`].
+    #[deprecated(note = "use `convert_raw()` or the safe `convert_samples()`")]
+    pub unsafe fn convert(
+        &mut self,
+        out_buffer: *mut *mut u8,
+        out_count: i32,
+        in_buffer: *const *const u8,
+        in_count: i32,
+    ) -> Result<i32> {
+        unsafe { self.convert_raw(out_buffer, out_count, in_buffer, in_count) }
+    }
+
     /// Convert the samples in the input `AVFrame` and write them to the output
     /// `AVFrame`.
     ///
@@ -155,7 +348,7 @@ impl SwrContext {
     /// The output `AVFrame::nb_samples` can be 0 or have fewer allocated samples
     /// than required.  In this case, any remaining samples not written to the
     /// output will be added to an internal FIFO buffer, to be returned at the next
-    /// call to this function or to [`SwrContext::convert`].
+    /// call to this function or to [`SwrContext::convert_samples`].
     ///
     /// If converting sample rate, there may be data remaining in the internal
     /// resampling delay buffer. [`SwrContext::get_delay`] tells the number of remaining
@@ -163,16 +356,105 @@ impl SwrContext {
     /// with NULL input.
     ///
     /// If the `SwrContext` configuration does not match the output and input AVFrame
-    /// settings, the conversion does not take place and error is returned.
-    pub fn convert_frame(&self, input: Option<&AVFrame>, output: &mut AVFrame) -> Result<()> {
-        unsafe {
+    /// settings, the conversion does not take place and
+    /// [`RsmpegError::SwrInputChangedError`] or
+    /// [`RsmpegError::SwrOutputChangedError`] is returned, the former if both
+    /// changed.
+    /// When [`Self::set_auto_reconfigure()`] is enabled, input changes are handled
+    /// by flushing the samples buffered with the old configuration into
+    /// `output`, reconfiguring, and then converting `input`. In this case an
+    /// allocated `output` must have room for the bound of both:
+    /// [`Self::get_out_samples()`] of 0 plus `input.nb_samples` rescaled to
+    /// the output sample rate plus 3, or `AVERROR(EINVAL)` is returned before
+    /// anything is flushed. The context is borrowed mutably for reconfiguring
+    /// it.
+    pub fn convert_frame(&mut self, input: Option<&AVFrame>, output: &mut AVFrame) -> Result<()> {
+        match self.convert_frame_raw(input, output) {
+            Err(RsmpegError::SwrInputChangedError) if self.auto_reconfigure => {
+                // `input` must be some since only input frames are compared.
+                self.convert_frame_reconfigure(input.unwrap(), output)
+            }
+            x => x,
+        }
+    }
+
+    fn convert_frame_raw(&mut self, input: Option<&AVFrame>, output: &mut AVFrame) -> Result<()> {
+        match unsafe {
             ffi::swr_convert_frame(
-                self.as_ptr() as *mut _,
+                self.as_mut_ptr(),
                 output.as_mut_ptr(),
                 input.map(|x| x.as_ptr()).unwrap_or_else(ptr::null),
             )
         }
-        .upgrade()?;
+        .upgrade()
+        {
+            Ok(_) => Ok(()),
+            // Both changes are reported as `AVERROR_INPUT_CHANGED`, as the
+            // error codes are OR-ed.
+            Err(ffi::AVERROR_INPUT_CHANGED) => Err(RsmpegError::SwrInputChangedError),
+            Err(ffi::AVERROR_OUTPUT_CHANGED) => Err(RsmpegError::SwrOutputChangedError),
+            Err(x) => Err(RsmpegError::AVError(x)),
+        }
+    }
+
+    fn convert_frame_reconfigure(&mut self, input: &AVFrame, output: &mut AVFrame) -> Result<()> {
+        let template = || {
+            let mut frame = AVFrame::new();
+            frame.set_ch_layout(output.ch_layout().clone().into_inner());
+            frame.set_sample_rate(output.sample_rate);
+            frame.set_format(output.format);
+            frame
+        };
+
+        // Nothing is flushed or converted unless both fit, or the samples
+        // would be lost. The bound of the conversion is the one
+        // `swr_convert_frame()` allocates for a fresh context.
+        if output.is_allocated() {
+            let nb_flushed = self.get_out_samples(0);
+            if nb_flushed < 0 || input.sample_rate <= 0 || output.sample_rate <= 0 {
+                return Err(RsmpegError::AVError(ffi::AVERROR(ffi::EINVAL)));
+            }
+            let nb_converted = av_rescale_q_rnd(
+                input.nb_samples as i64,
+                ra(1, input.sample_rate),
+                ra(1, output.sample_rate),
+                ffi::AV_ROUND_UP as u32,
+            ) + 3;
+            if (output.nb_samples as i64) < nb_flushed as i64 + nb_converted {
+                return Err(RsmpegError::AVError(ffi::AVERROR(ffi::EINVAL)));
+            }
+        }
+
+        // Re-initialization drops buffered samples, flush them first.
+        let mut flushed = template();
+        self.convert_frame_raw(None, &mut flushed)?;
+        self.config_changed(input)?;
+        let mut converted = template();
+        self.convert_frame_raw(Some(input), &mut converted)?;
+
+        let nb_samples = flushed.nb_samples + converted.nb_samples;
+        if !output.is_allocated() {
+            output.set_nb_samples(nb_samples);
+            output.alloc_buffer()?;
+        } else if output.nb_samples < nb_samples {
+            return Err(RsmpegError::AVError(ffi::AVERROR(ffi::EINVAL)));
+        }
+        let nb_channels = output.ch_layout.nb_channels;
+        for (frame, offset) in [(&flushed, 0), (&converted, flushed.nb_samples)] {
+            unsafe {
+                ffi::av_samples_copy(
+                    output.extended_data as _,
+                    frame.extended_data as _,
+                    offset,
+                    0,
+                    frame.nb_samples,
+                    nb_channels,
+                    output.format,
+                )
+            }
+            .upgrade()?;
+        }
+        output.set_nb_samples(nb_samples);
         Ok(())
     }
 }
@@ -183,3 +465,210 @@ impl Drop for SwrContext {
         unsafe { ffi::swr_free(&mut ptr) }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn silent_frame(sample_rate: i32, nb_samples: i32) -> AVFrame {
+        let mut frame = AVFrame::new();
+        frame.set_ch_layout(AVChannelLayout::from_nb_channels(1));
+        frame.set_sample_rate(sample_rate);
+        frame.set_format(ffi::AV_SAMPLE_FMT_S16);
+        frame.set_nb_samples(nb_samples);
+        frame.alloc_buffer().unwrap();
+        frame.channel_samples_mut::<i16>(0).unwrap().fill(0);
+        frame
+    }
+
+    fn output_frame() -> AVFrame {
+        let mut frame = AVFrame::new();
+        frame.set_ch_layout(AVChannelLayout::from_nb_channels(1));
+        frame.set_sample_rate(48000);
+        frame.set_format(ffi::AV_SAMPLE_FMT_S16);
+        frame
+    }
+
+    #[test]
+    fn test_auto_reconfigure() {
+        let mono = AVChannelLayout::from_nb_channels(1);
+        let mut context = SwrContext::new(
+            &mono,
+            ffi::AV_SAMPLE_FMT_S16,
+            48000,
+            &mono,
+            ffi::AV_SAMPLE_FMT_S16,
+            8000,
+        )
+        .unwrap();
+        assert!(!context.auto_reconfigure());
+        context.set_auto_reconfigure(true);
+        assert!(context.auto_reconfigure());
+        context.init().unwrap();
+
+        let mut nb_output_samples = 0;
+        for sample_rate in [8000, 8000, 8000, 8000, 16000, 16000, 16000, 16000] {
+            let input = silent_frame(sample_rate, 1024);
+            let mut output = output_frame();
+            context.convert_frame(Some(&input), &mut output).unwrap();
+            nb_output_samples += output.nb_samples;
+        }
+        loop {
+            let mut output = output_frame();
+            context.convert_frame(None, &mut output).unwrap();
+            if output.nb_samples == 0 {
+                break;
+            }
+            nb_output_samples += output.nb_samples;
+        }
+
+        // 4 * 1024 samples at 8kHz and 4 * 1024 samples at 16kHz.
+        let expected = 4 * 1024 * 6 + 4 * 1024 * 3;
+        assert!((nb_output_samples - expected).abs() <= 16);
+    }
+
+    #[test]
+    fn test_auto_reconfigure_small_output() {
+        let mono = AVChannelLayout::from_nb_channels(1);
+        let mut context = SwrContext::new(
+            &mono,
+            ffi::AV_SAMPLE_FMT_S16,
+            48000,
+            &mono,
+            ffi::AV_SAMPLE_FMT_S16,
+            8000,
+        )
+        .unwrap();
+        context.set_auto_reconfigure(true);
+        context.init().unwrap();
+        let mut output = output_frame();
+        context
+            .convert_frame(Some(&silent_frame(8000, 1024)), &mut output)
+            .unwrap();
+        let nb_buffered = context.get_out_samples(0);
+
+        // Too small for the flushed and converted samples, nothing is lost.
+        let input = silent_frame(16000, 1024);
+        let mut output = output_frame();
+        output.set_nb_samples(16);
+        output.alloc_buffer().unwrap();
+        assert_eq!(
+            context.convert_frame(Some(&input), &mut output),
+            Err(RsmpegError::AVError(ffi::AVERROR(ffi::EINVAL)))
+        );
+        assert_eq!(context.get_out_samples(0), nb_buffered);
+
+        let mut output = output_frame();
+        output.set_nb_samples(nb_buffered + 1024 * 3 + 3);
+        output.alloc_buffer().unwrap();
+        context.convert_frame(Some(&input), &mut output).unwrap();
+        assert!(output.nb_samples >= 1024 * 3 - 16);
+    }
+
+    #[test]
+    fn test_input_changed_without_auto_reconfigure() {
+        let mono = AVChannelLayout::from_nb_channels(1);
+        let mut context = SwrContext::new(
+            &mono,
+            ffi::AV_SAMPLE_FMT_S16,
+            48000,
+            &mono,
+            ffi::AV_SAMPLE_FMT_S16,
+            8000,
+        )
+        .unwrap();
+        context.init().unwrap();
+
+        let input = silent_frame(16000, 1024);
+        let mut output = output_frame();
+        assert_eq!(
+            context.convert_frame(Some(&input), &mut output),
+            Err(RsmpegError::SwrInputChangedError)
+        );
+
+        context.config_changed(&input).unwrap();
+        context.convert_frame(Some(&input), &mut output).unwrap();
+    }
+
+    #[test]
+    fn test_output_changed() {
+        let mono = AVChannelLayout::from_nb_channels(1);
+        let s16: SampleFormat = "s16".parse().unwrap();
+        let mut context = SwrContext::new(&mono, s16, 48000, &mono, s16, 8000).unwrap();
+        context.init().unwrap();
+
+        let input = silent_frame(8000, 1024);
+        let mut output = output_frame();
+        output.set_sample_rate(44100);
+        assert_eq!(
+            context.convert_frame(Some(&input), &mut output),
+            Err(RsmpegError::SwrOutputChangedError)
+        );
+    }
+
+    #[test]
+    fn test_builder_and_convert() {
+        let stereo = AVChannelLayout::from_nb_channels(2);
+        let mut context = SwrContext::builder()
+            .input(&stereo, ffi::AV_SAMPLE_FMT_S16, 44100)
+            .output(&stereo, ffi::AV_SAMPLE_FMT_FLTP, 48000)
+            .build()
+            .unwrap();
+        context.init().unwrap();
+        assert!(SwrContext::builder()
+            .input(&stereo, ffi::AV_SAMPLE_FMT_S16, 44100)
+            .build()
+            .is_err());
+
+        // One second of input, 10 chunks of 4410 samples.
+        let mut input = AVSamples::new(2, 4410, ffi::AV_SAMPLE_FMT_S16, 0).unwrap();
+        input.set_silence(0, 4410);
+        let mut nb_output_samples = 0;
+        for _ in 0..10 {
+            let out_count = context.get_out_samples(4410);
+            let mut output = AVSamples::new(2, out_count, ffi::AV_SAMPLE_FMT_FLTP, 0).unwrap();
+            nb_output_samples += context
+                .convert_samples(&mut output, Some(&input), 4410)
+                .unwrap();
+        }
+        // Drain the samples buffered for resampling.
+        assert!(context.get_delay(48000) > 0);
+        let out_count = context.get_out_samples(0);
+        let mut output = AVSamples::new(2, out_count, ffi::AV_SAMPLE_FMT_FLTP, 0).unwrap();
+        nb_output_samples += context.convert_samples(&mut output, None, 0).unwrap();
+        assert!(
+            (nb_output_samples - 48000).abs() <= 32,
+            "{nb_output_samples}"
+        );
+
+        // Mismatching parameters are rejected.
+        let mut output = AVSamples::new(1, 4800, ffi::AV_SAMPLE_FMT_FLTP, 0).unwrap();
+        assert_eq!(
+            context.convert_samples(&mut output, Some(&input), 4410),
+            Err(RsmpegError::AVError(ffi::AVERROR(ffi::EINVAL)))
+        );
+        let mut output = AVSamples::new(2, 4800, ffi::AV_SAMPLE_FMT_FLTP, 0).unwrap();
+        assert_eq!(
+            context.convert_samples(&mut output, Some(&input), 4411),
+            Err(RsmpegError::AVError(ffi::AVERROR(ffi::EINVAL)))
+        );
+        assert_eq!(
+            context.convert_samples(&mut output, None, 1),
+            Err(RsmpegError::AVError(ffi::AVERROR(ffi::EINVAL)))
+        );
+
+        // Counts past the buffers are rejected or clamped.
+        input.nb_samples = 1 << 20;
+        assert_eq!(
+            context.convert_samples(&mut output, Some(&input), 1 << 20),
+            Err(RsmpegError::AVError(ffi::AVERROR(ffi::EINVAL)))
+        );
+        input.nb_samples = 4410;
+        let mut output = AVSamples::new(2, 16, ffi::AV_SAMPLE_FMT_FLTP, 1).unwrap();
+        output.nb_samples = 1 << 20;
+        let nb_samples = context
+            .convert_samples(&mut output, Some(&input), 4410)
+            .unwrap();
+        assert!(nb_samples <= 16, "{nb_samples}");
+    }
+}
@@ -0,0 +1,218 @@
+use crate::{
+    avutil::{av_image_fill_arrays, AVBufferPool, AVFrame, AVImage, AVPixelFormat},
+    error::*,
+    ffi,
+    shared::*,
+    swscale::{SwsContext, SwsFlags},
+};
+
+/// Alignment of the planes of the destination frames.
+const ALIGN: i32 = 32;
+
+/// A reusable conversion pipeline converting frames to a fixed destination
+/// size and pixel format.
+///
+/// The [`SwsContext`] is cached and only re-created when the properties of the
+/// source frames change. Destination frames are backed by buffers of an
+/// internal [`AVBufferPool`], the buffer of a converted frame goes back to the
+/// pool when the frame is dropped, so converting in a loop doesn't allocate.
+pub struct Converter {
+    context: Option<SwsContext>,
+    src_w: i32,
+    src_h: i32,
+    src_format: AVPixelFormat,
+    dst_w: i32,
+    dst_h: i32,
+    dst_format: AVPixelFormat,
+    flags: SwsFlags,
+    pool: AVBufferPool,
+}
+
+impl Converter {
+    /// Create a [`Converter`] producing frames of given size and pixel format.
+    /// `flags` can be set to [`SwsFlags::BILINEAR`] etc.
+    ///
+    /// Return `None` when destination parameters are invalid.
+    pub fn new(dst_w: i32, dst_h: i32, dst_format: AVPixelFormat, flags: SwsFlags) -> Option<Self> {
+        let size = AVImage::get_buffer_size(dst_format, dst_w, dst_h, ALIGN)?;
+        Some(Self {
+            context: None,
+            src_w: 0,
+            src_h: 0,
+            src_format: ffi::AV_PIX_FMT_NONE,
+            dst_w,
+            dst_h,
+            dst_format,
+            flags,
+            pool: AVBufferPool::new(size as usize),
+        })
+    }
+
+    /// Convert `src` into a new frame backed by a pooled buffer. Frame
+    /// properties(pts, etc.) are copied from `src`.
+    pub fn convert(&mut self, src: &AVFrame) -> Result<AVFrame> {
+        let mut dst = AVFrame::new();
+        dst.set_width(self.dst_w);
+        dst.set_height(self.dst_h);
+        dst.set_format(self.dst_format);
+        let buffer = self.pool.get();
+        let data = buffer.data;
+        unsafe {
+            // The frame takes the ownership of the buffer, it goes back to the
+            // pool in `av_frame_free()`.
+            dst.deref_mut().buf[0] = buffer.into_raw().as_ptr();
+            av_image_fill_arrays(
+                dst.data_raw_mut().as_mut_ptr(),
+                dst.linesize_mut().as_mut_ptr(),
+                data,
+                self.dst_format,
+                self.dst_w,
+                self.dst_h,
+                ALIGN,
+            )
+        }
+        .upgrade()?;
+        unsafe { ffi::av_frame_copy_props(dst.as_mut_ptr(), src.as_ptr()) }.upgrade()?;
+
+        self.context_for(src)?
+            .scale_frame(src, 0, src.height, &mut dst)?;
+        Ok(dst)
+    }
+
+    /// Get the buffer pool backing the converted frames, useful for checking
+    /// allocation statistics.
+    pub fn pool(&self) -> &AVBufferPool {
+        &self.pool
+    }
+
+    /// Get the cached [`SwsContext`], re-create it if properties of `src`
+    /// differ from the previous one.
+    fn context_for(&mut self, src: &AVFrame) -> Result<&mut SwsContext> {
+        let changed =
+            (src.width, src.height, src.format) != (self.src_w, self.src_h, self.src_format);
+        if changed || self.context.is_none() {
+            let context = match self.context.take() {
+                Some(context) => context.get_cached_context(
+                    src.width,
+                    src.height,
+                    src.format,
+                    self.dst_w,
+                    self.dst_h,
+                    self.dst_format,
+                    self.flags,
+                    None,
+                    None,
+                    None,
+                ),
+                None => SwsContext::get_context(
+                    src.width,
+                    src.height,
+                    src.format,
+                    self.dst_w,
+                    self.dst_h,
+                    self.dst_format,
+                    self.flags,
+                    None,
+                    None,
+                    None,
+                ),
+            };
+            let context = context.ok_or(RsmpegError::AVError(ffi::AVERROR(ffi::EINVAL)))?;
+            self.context = Some(context);
+            self.src_w = src.width;
+            self.src_h = src.height;
+            self.src_format = src.format;
+        }
+        Ok(self.context.as_mut().unwrap())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::slice;
+
+    fn rgb_frame(width: i32, height: i32) -> AVFrame {
+        let mut frame = AVFrame::new();
+        frame.set_width(width);
+        frame.set_height(height);
+        frame.set_format(ffi::AV_PIX_FMT_RGB24);
+        frame.alloc_buffer().unwrap();
+        for y in 0..height as usize {
+            let row = unsafe {
+                slice::from_raw_parts_mut(
+                    frame.data[0].add(y * frame.linesize[0] as usize),
+                    width as usize * 3,
+                )
+            };
+            for (x, byte) in row.iter_mut().enumerate() {
+                *byte = (x * 7 + y * 13) as u8;
+            }
+        }
+        frame
+    }
+
+    /// Visible bytes of a yuv420p plane.
+    fn plane(frame: &AVFrame, index: usize) -> Vec<u8> {
+        let (w, h) = if index == 0 {
+            (frame.width, frame.height)
+        } else {
+            ((frame.width + 1) / 2, (frame.height + 1) / 2)
+        };
+        (0..h as usize)
+            .flat_map(|y| unsafe {
+                slice::from_raw_parts(
+                    frame.data[index].add(y * frame.linesize[index] as usize),
+                    w as usize,
+                )
+            })
+            .copied()
+            .collect()
+    }
+
+    #[test]
+    fn test_converter() {
+        let src = rgb_frame(64, 48);
+
+        let mut naive = AVFrame::new();
+        naive.set_width(32);
+        naive.set_height(24);
+        naive.set_format(ffi::AV_PIX_FMT_YUV420P);
+        naive.alloc_buffer().unwrap();
+        SwsContext::get_context(
+            64,
+            48,
+            ffi::AV_PIX_FMT_RGB24,
+            32,
+            24,
+            ffi::AV_PIX_FMT_YUV420P,
+            SwsFlags::BILINEAR,
+            None,
+            None,
+            None,
+        )
+        .unwrap()
+        .scale_frame(&src, 0, 48, &mut naive)
+        .unwrap();
+
+        let mut converter =
+            Converter::new(32, 24, ffi::AV_PIX_FMT_YUV420P, SwsFlags::BILINEAR).unwrap();
+        for _ in 0..10 {
+            let dst = converter.convert(&src).unwrap();
+            assert_eq!(dst.width, 32);
+            assert_eq!(dst.height, 24);
+            for i in 0..3 {
+                assert_eq!(plane(&dst, i), plane(&naive, i));
+            }
+        }
+        // Each converted frame is dropped before the next conversion.
+        assert_eq!(converter.pool().nb_allocated(), 1);
+
+        // Source size change re-creates the context.
+        let src = rgb_frame(16, 16);
+        let dst = converter.convert(&src).unwrap();
+        assert_eq!(dst.width, 32);
+        assert_eq!(dst.height, 24);
+        assert_eq!(converter.pool().nb_allocated(), 1);
+    }
+}
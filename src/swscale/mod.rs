@@ -1,6 +1,10 @@
 //! Everything related to `libswscale`.
+mod converter;
+mod scaler;
 mod swscale;
 mod utils;
 
+pub use converter::*;
+pub use scaler::*;
 pub use swscale::*;
 pub use utils::*;
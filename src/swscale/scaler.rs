@@ -0,0 +1,406 @@
+use crate::{
+    avutil::{check_image_planes, log::log_message, AVFrame, AVPixFmtDescriptorRef, AVPixelFormat},
+    error::*,
+    ffi,
+    shared::*,
+    swscale::{SwsContext, SwsFlags},
+};
+use std::{os::raw::c_int, ptr};
+
+/// How to deal with aspect ratio difference between the source frame and the
+/// target size.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AspectPolicy {
+    /// Scale to the target size directly, the image may be distorted.
+    #[default]
+    Stretch,
+    /// Keep aspect ratio, fit the whole image in the target size and fill the
+    /// borders with black.
+    Pad,
+    /// Keep aspect ratio, fill the target size with the image and crop the
+    /// overflowing part of the source.
+    Crop,
+}
+
+/// Target specification of [`SwsScaler::scale()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScaleSpec {
+    pub width: i32,
+    pub height: i32,
+    pub format: AVPixelFormat,
+    /// Scaling flags, e.g. [`SwsFlags::BILINEAR`].
+    pub flags: SwsFlags,
+    pub policy: AspectPolicy,
+}
+
+/// Source and destination rectangles of a scaling operation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Layout {
+    src_x: i32,
+    src_y: i32,
+    src_w: i32,
+    src_h: i32,
+    dst_x: i32,
+    dst_y: i32,
+    dst_w: i32,
+    dst_h: i32,
+}
+
+impl Layout {
+    /// Return `AVERROR(EINVAL)` if a size is empty, the aspect ratios are
+    /// undefined then.
+    fn new(src_w: i32, src_h: i32, spec: &ScaleSpec) -> Result<Self> {
+        let (tw, th) = (spec.width, spec.height);
+        if src_w <= 0 || src_h <= 0 || tw <= 0 || th <= 0 {
+            return Err(RsmpegError::AVError(ffi::AVERROR(ffi::EINVAL)));
+        }
+        let mut layout = Self {
+            src_x: 0,
+            src_y: 0,
+            src_w,
+            src_h,
+            dst_x: 0,
+            dst_y: 0,
+            dst_w: tw,
+            dst_h: th,
+        };
+        // Compare src_w / src_h with tw / th without precision loss.
+        let src_is_wider = src_w as i64 * th as i64 > tw as i64 * src_h as i64;
+        match spec.policy {
+            AspectPolicy::Stretch => {}
+            AspectPolicy::Pad if src_is_wider => {
+                layout.dst_h = even((src_h as i64 * tw as i64 / src_w as i64) as i32).max(2);
+                layout.dst_y = even((th - layout.dst_h) / 2);
+            }
+            AspectPolicy::Pad => {
+                layout.dst_w = even((src_w as i64 * th as i64 / src_h as i64) as i32).max(2);
+                layout.dst_x = even((tw - layout.dst_w) / 2);
+            }
+            AspectPolicy::Crop if src_is_wider => {
+                layout.src_w = even((src_h as i64 * tw as i64 / th as i64) as i32).max(2);
+                layout.src_x = even((src_w - layout.src_w) / 2);
+            }
+            AspectPolicy::Crop => {
+                layout.src_h = even((src_w as i64 * th as i64 / tw as i64) as i32).max(2);
+                layout.src_y = even((src_h - layout.src_h) / 2);
+            }
+        }
+        Ok(layout)
+    }
+}
+
+/// Round down to a multiple of 2, so offsets stay aligned with subsampled
+/// chroma planes.
+fn even(x: i32) -> i32 {
+    x & !1
+}
+
+/// Offset the plane pointers of an image to the pixel at (`x`, `y`).
+///
+/// Return `AVERROR(EINVAL)` for a non-zero offset in a paletted or bitstream
+/// format, a pixel of them can't be addressed in each plane.
+fn offset_planes(
+    data: &[*mut u8; 8],
+    linesize: &[c_int; 8],
+    format: AVPixelFormat,
+    x: i32,
+    y: i32,
+) -> Result<[*mut u8; 8]> {
+    if (x, y) == (0, 0) {
+        return Ok(*data);
+    }
+    let desc = AVPixFmtDescriptorRef::get(format)
+        .ok_or(RsmpegError::AVError(ffi::AVERROR(ffi::EINVAL)))?;
+    let unaddressable = (ffi::AV_PIX_FMT_FLAG_PAL | ffi::AV_PIX_FMT_FLAG_BITSTREAM) as u64;
+    if desc.flags() & unaddressable != 0 {
+        return Err(RsmpegError::AVError(ffi::AVERROR(ffi::EINVAL)));
+    }
+    let mut max_pixsteps = [0; 4];
+    unsafe { ffi::av_image_fill_max_pixsteps(max_pixsteps.as_mut_ptr(), ptr::null_mut(), &*desc) };
+    let mut planes = *data;
+    for (i, plane) in planes.iter_mut().enumerate().take(4) {
+        if plane.is_null() {
+            continue;
+        }
+        // Planes 1 and 2 are the (possibly subsampled) chroma planes.
+        let (x, y) = if i == 1 || i == 2 {
+            (x >> desc.log2_chroma_w, y >> desc.log2_chroma_h)
+        } else {
+            (x, y)
+        };
+        let offset = y as isize * linesize[i] as isize + (x * max_pixsteps[i]) as isize;
+        *plane = unsafe { plane.offset(offset) };
+    }
+    Ok(planes)
+}
+
+/// A stateful scaler that caches its [`SwsContext`] and transparently
+/// re-creates it when the size or pixel format of the source frames changes,
+/// e.g. resolution switches between programs of a broadcast stream.
+#[derive(Default)]
+pub struct SwsScaler {
+    context: Option<SwsContext>,
+    config: Option<(Layout, AVPixelFormat, ScaleSpec)>,
+}
+
+impl SwsScaler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Scale `src` into a newly allocated frame described by `dst_spec`. Frame
+    /// properties(pts, etc.) are copied from `src`.
+    ///
+    /// Return an error if the planes of `src` don't fit in its buffers, or if
+    /// [`AspectPolicy::Pad`] or [`AspectPolicy::Crop`] needs to offset a
+    /// paletted or bitstream image, e.g. pal8 or monow.
+    pub fn scale(&mut self, src: &AVFrame, dst_spec: &ScaleSpec) -> Result<AVFrame> {
+        let layout = Layout::new(src.width, src.height, dst_spec)?;
+        check_image_planes(&src.linesize, src.format, src.width, src.height, |plane| {
+            src.plane_buffer_len(plane)
+        })?;
+        let context = self.context_for(layout, src.format, dst_spec)?;
+
+        let mut dst = AVFrame::new();
+        dst.set_width(dst_spec.width);
+        dst.set_height(dst_spec.height);
+        dst.set_format(dst_spec.format);
+        dst.alloc_buffer()?;
+        dst.copy_props_from(src)?;
+
+        if layout.dst_w != dst_spec.width || layout.dst_h != dst_spec.height {
+            let linesize = dst.linesize.map(|x| x as isize);
+            unsafe {
+                ffi::av_image_fill_black(
+                    dst.data.as_ptr(),
+                    linesize.as_ptr(),
+                    dst_spec.format,
+                    dst.color_range,
+                    dst_spec.width,
+                    dst_spec.height,
+                )
+            }
+            .upgrade()?;
+        }
+
+        let src_data = offset_planes(
+            &src.data,
+            &src.linesize,
+            src.format,
+            layout.src_x,
+            layout.src_y,
+        )?;
+        let dst_data = offset_planes(
+            &dst.data,
+            &dst.linesize,
+            dst_spec.format,
+            layout.dst_x,
+            layout.dst_y,
+        )?;
+        unsafe {
+            context.scale_raw(
+                src_data.as_ptr() as _,
+                src.linesize.as_ptr(),
+                0,
+                layout.src_h,
+                dst_data.as_ptr(),
+                dst.linesize.as_ptr(),
+            )
+        }?;
+        Ok(dst)
+    }
+
+    fn context_for(
+        &mut self,
+        layout: Layout,
+        src_format: AVPixelFormat,
+        spec: &ScaleSpec,
+    ) -> Result<&mut SwsContext> {
+        let config = (layout, src_format, *spec);
+        if self.config != Some(config) || self.context.is_none() {
+            if let Some((old, _, _)) = self.config {
+                let message = format!(
+                    "Source changed from {}x{} to {}x{}, re-creating scaling context.",
+                    old.src_w, old.src_h, layout.src_w, layout.src_h
+                );
+                unsafe { log_message(ptr::null_mut(), ffi::AV_LOG_VERBOSE as i32, &message) };
+            }
+            let context = match self.context.take() {
+                Some(context) => context.get_cached_context(
+                    layout.src_w,
+                    layout.src_h,
+                    src_format,
+                    layout.dst_w,
+                    layout.dst_h,
+                    spec.format,
+                    spec.flags,
+                    None,
+                    None,
+                    None,
+                ),
+                None => SwsContext::get_context(
+                    layout.src_w,
+                    layout.src_h,
+                    src_format,
+                    layout.dst_w,
+                    layout.dst_h,
+                    spec.format,
+                    spec.flags,
+                    None,
+                    None,
+                    None,
+                ),
+            };
+            // Reset the cached config first, so a failure leads to re-creation next time.
+            self.config = None;
+            let context = context.ok_or(RsmpegError::AVError(ffi::AVERROR(ffi::EINVAL)))?;
+            self.context = Some(context);
+            self.config = Some(config);
+        }
+        Ok(self.context.as_mut().unwrap())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::avutil::{ChromaLocation, ColorPrimaries, ColorRange, ColorSpace, ColorTransfer};
+    use std::slice;
+
+    fn yuv_frame(width: i32, height: i32) -> AVFrame {
+        let mut frame = AVFrame::new();
+        frame.set_width(width);
+        frame.set_height(height);
+        frame.set_format(ffi::AV_PIX_FMT_YUV420P);
+        frame.alloc_buffer().unwrap();
+        for (i, value) in [(0, 200), (1, 128), (2, 128)] {
+            let h = if i == 0 { height } else { height / 2 };
+            let len = frame.linesize[i] as usize * h as usize;
+            unsafe { slice::from_raw_parts_mut(frame.data[i], len) }.fill(value);
+        }
+        frame
+    }
+
+    fn rgb_pixel(frame: &AVFrame, x: usize, y: usize) -> [u8; 3] {
+        let row = unsafe { frame.data[0].add(y * frame.linesize[0] as usize) };
+        let pixel = unsafe { slice::from_raw_parts(row.add(x * 3), 3) };
+        pixel.try_into().unwrap()
+    }
+
+    #[test]
+    fn test_resolution_switch() {
+        for policy in [AspectPolicy::Stretch, AspectPolicy::Pad, AspectPolicy::Crop] {
+            let spec = ScaleSpec {
+                width: 200,
+                height: 100,
+                format: ffi::AV_PIX_FMT_RGB24,
+                flags: SwsFlags::BILINEAR,
+                policy,
+            };
+            let mut scaler = SwsScaler::new();
+            let frames = (0..8).map(|i| {
+                if i < 4 {
+                    yuv_frame(320, 240)
+                } else {
+                    yuv_frame(160, 120)
+                }
+            });
+            for src in frames {
+                let dst = scaler.scale(&src, &spec).unwrap();
+                assert_eq!(dst.width, 200);
+                assert_eq!(dst.height, 100);
+                assert_eq!(dst.format, ffi::AV_PIX_FMT_RGB24);
+
+                let border = rgb_pixel(&dst, 0, 50);
+                let center = rgb_pixel(&dst, 100, 50);
+                assert_ne!(center, [0, 0, 0]);
+                if policy == AspectPolicy::Pad {
+                    assert_eq!(border, [0, 0, 0]);
+                } else {
+                    assert_eq!(border, center);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_empty_size() {
+        let mut spec = ScaleSpec {
+            width: 0,
+            height: 100,
+            format: ffi::AV_PIX_FMT_RGB24,
+            flags: SwsFlags::BILINEAR,
+            policy: AspectPolicy::Pad,
+        };
+        let einval = RsmpegError::AVError(ffi::AVERROR(ffi::EINVAL));
+        let mut scaler = SwsScaler::new();
+        assert_eq!(scaler.scale(&yuv_frame(64, 48), &spec).unwrap_err(), einval);
+        spec.width = 200;
+        let mut src = yuv_frame(64, 48);
+        src.set_height(0);
+        assert_eq!(scaler.scale(&src, &spec).unwrap_err(), einval);
+    }
+
+    #[test]
+    fn test_invalid_source() {
+        let spec = ScaleSpec {
+            width: 32,
+            height: 32,
+            format: ffi::AV_PIX_FMT_RGB24,
+            flags: SwsFlags::BILINEAR,
+            policy: AspectPolicy::Pad,
+        };
+        let mut scaler = SwsScaler::new();
+        // The planes are checked against the buffers.
+        let mut src = yuv_frame(64, 48);
+        src.set_height(96);
+        assert!(matches!(
+            scaler.scale(&src, &spec),
+            Err(RsmpegError::BufferTooSmallError(..))
+        ));
+
+        // Paletted images can be scaled, but not cropped.
+        let spec = ScaleSpec {
+            policy: AspectPolicy::Crop,
+            ..spec
+        };
+        let mut src = AVFrame::new();
+        src.set_width(64);
+        src.set_height(48);
+        src.set_format(ffi::AV_PIX_FMT_PAL8);
+        src.alloc_buffer().unwrap();
+        assert_eq!(
+            scaler.scale(&src, &spec).unwrap_err(),
+            RsmpegError::AVError(ffi::AVERROR(ffi::EINVAL))
+        );
+        let spec = ScaleSpec {
+            policy: AspectPolicy::Stretch,
+            ..spec
+        };
+        assert!(scaler.scale(&src, &spec).is_ok());
+    }
+
+    #[test]
+    fn test_color_properties() {
+        let mut src = yuv_frame(64, 48);
+        src.set_color_range(ColorRange::Mpeg);
+        src.set_color_primaries(ColorPrimaries::Bt709);
+        src.set_color_trc(ColorTransfer::Bt709);
+        src.set_colorspace(ColorSpace::Bt709);
+        src.set_chroma_location(ChromaLocation::Left);
+        let spec = ScaleSpec {
+            width: 32,
+            height: 24,
+            format: ffi::AV_PIX_FMT_YUV420P,
+            flags: SwsFlags::BILINEAR,
+            policy: AspectPolicy::Stretch,
+        };
+        // Scaling doesn't convert colors, the properties still apply.
+        let dst = SwsScaler::new().scale(&src, &spec).unwrap();
+        assert_eq!(dst.color_range(), ColorRange::Mpeg);
+        assert_eq!(dst.color_primaries(), ColorPrimaries::Bt709);
+        assert_eq!(dst.color_trc(), ColorTransfer::Bt709);
+        assert_eq!(dst.colorspace(), ColorSpace::Bt709);
+        assert_eq!(dst.chroma_location(), ChromaLocation::Left);
+    }
+}
@@ -1,18 +1,133 @@
 use crate::{
-    avutil::{AVFrame, AVPixelFormat},
+    avutil::{check_image_planes, AVFrame, AVPixFmtDescriptorRef, AVPixelFormat},
     error::*,
     ffi,
     shared::*,
 };
-use std::ptr;
+use std::{
+    ffi::CStr,
+    ops::{BitAnd, BitOr},
+    os::raw::c_int,
+    ptr, slice,
+};
 wrap!(SwsContext: ffi::SwsContext);
 
+/// Flags of a [`SwsContext`]: one scaling algorithm, optionally combined with
+/// accuracy and debugging flags, e.g. `SwsFlags::BICUBIC |
+/// SwsFlags::ACCURATE_RND`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SwsFlags(u32);
+
+impl SwsFlags {
+    pub const FAST_BILINEAR: Self = Self(ffi::SWS_FAST_BILINEAR);
+    pub const BILINEAR: Self = Self(ffi::SWS_BILINEAR);
+    pub const BICUBIC: Self = Self(ffi::SWS_BICUBIC);
+    pub const X: Self = Self(ffi::SWS_X);
+    pub const POINT: Self = Self(ffi::SWS_POINT);
+    pub const AREA: Self = Self(ffi::SWS_AREA);
+    pub const BICUBLIN: Self = Self(ffi::SWS_BICUBLIN);
+    pub const GAUSS: Self = Self(ffi::SWS_GAUSS);
+    pub const SINC: Self = Self(ffi::SWS_SINC);
+    pub const LANCZOS: Self = Self(ffi::SWS_LANCZOS);
+    pub const SPLINE: Self = Self(ffi::SWS_SPLINE);
+    /// Log the chosen code paths at context creation.
+    pub const PRINT_INFO: Self = Self(ffi::SWS_PRINT_INFO);
+    /// Full chroma interpolation on output, slower but sharper RGB.
+    pub const FULL_CHR_H_INT: Self = Self(ffi::SWS_FULL_CHR_H_INT);
+    /// Full chroma interpolation on input.
+    pub const FULL_CHR_H_INP: Self = Self(ffi::SWS_FULL_CHR_H_INP);
+    pub const DIRECT_BGR: Self = Self(ffi::SWS_DIRECT_BGR);
+    /// Accurate rounding instead of the fastest code paths.
+    pub const ACCURATE_RND: Self = Self(ffi::SWS_ACCURATE_RND);
+    /// Same output on all CPUs.
+    pub const BITEXACT: Self = Self(ffi::SWS_BITEXACT);
+    /// Error diffusion dithering.
+    pub const ERROR_DIFFUSION: Self = Self(ffi::SWS_ERROR_DIFFUSION);
+
+    /// Wrap raw `SWS_*` flags, for the ones without a constant here.
+    pub const fn from_bits(bits: u32) -> Self {
+        Self(bits)
+    }
+
+    /// Get the raw `SWS_*` flags.
+    pub const fn bits(&self) -> u32 {
+        self.0
+    }
+
+    /// Return true if all the bits of `flags` are set.
+    pub fn contains(&self, flags: Self) -> bool {
+        self.0 & flags.0 == flags.0
+    }
+}
+
+impl BitOr for SwsFlags {
+    type Output = Self;
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl BitAnd for SwsFlags {
+    type Output = Self;
+    fn bitand(self, rhs: Self) -> Self {
+        Self(self.0 & rhs.0)
+    }
+}
+
+/// Get the YUV <-> RGB coefficients of `colorspace`, e.g.
+/// `ffi::SWS_CS_ITU709 as i32`, for [`ColorspaceDetails`]. Unknown colorspaces
+/// fall back to the ones of BT.601.
+pub fn get_coefficients(colorspace: c_int) -> [c_int; 4] {
+    // Points into a static table of FFmpeg.
+    let coefficients = unsafe { ffi::sws_getCoefficients(colorspace) };
+    unsafe { slice::from_raw_parts(coefficients, 4) }
+        .try_into()
+        .unwrap()
+}
+
+/// Colorspace conversion parameters of a [`SwsContext`], see
+/// [`SwsContext::set_colorspace_details()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ColorspaceDetails {
+    /// Coefficients of the source colorspace, from [`get_coefficients()`].
+    pub src_coefficients: [c_int; 4],
+    /// Whether the source is full range (JPEG), otherwise limited range
+    /// (MPEG).
+    pub src_full_range: bool,
+    /// Coefficients of the destination colorspace.
+    pub dst_coefficients: [c_int; 4],
+    pub dst_full_range: bool,
+    /// 16.16 fixed point, 0 by default.
+    pub brightness: c_int,
+    /// 16.16 fixed point, `1 << 16` by default.
+    pub contrast: c_int,
+    /// 16.16 fixed point, `1 << 16` by default.
+    pub saturation: c_int,
+}
+
+impl Default for ColorspaceDetails {
+    /// BT.601 in limited range on both sides, what contexts start with.
+    fn default() -> Self {
+        let coefficients = get_coefficients(ffi::SWS_CS_DEFAULT as c_int);
+        Self {
+            src_coefficients: coefficients,
+            src_full_range: false,
+            dst_coefficients: coefficients,
+            dst_full_range: false,
+            brightness: 0,
+            contrast: 1 << 16,
+            saturation: 1 << 16,
+        }
+    }
+}
+
 impl SwsContext {
     /// Allocate and return an [`SwsContext`]. You need it to perform
-    /// scaling/conversion operations using [`Self::scale()`].
+    /// scaling/conversion operations using [`Self::scale()`] or
+    /// [`Self::scale_frame()`].
     ///
     /// Return `None` when input is invalid. Parameter `flags` can be set to
-    /// `rsmpeg::ffi::SWS_FAST_BILINEAR` etc.
+    /// [`SwsFlags::FAST_BILINEAR`] etc.
     #[allow(clippy::too_many_arguments)]
     pub fn get_context(
         src_w: i32,
@@ -21,7 +136,7 @@ impl SwsContext {
         dst_w: i32,
         dst_h: i32,
         dst_format: AVPixelFormat,
-        flags: u32,
+        flags: SwsFlags,
         src_filter: Option<&ffi::SwsFilter>,
         dst_filter: Option<&ffi::SwsFilter>,
         param: Option<&[f64; 2]>,
@@ -34,7 +149,7 @@ impl SwsContext {
                 dst_w,
                 dst_h,
                 dst_format,
-                flags as i32,
+                flags.bits() as i32,
                 src_filter
                     .map(|x| x as *const _ as *mut _)
                     .unwrap_or_else(ptr::null_mut),
@@ -48,6 +163,51 @@ impl SwsContext {
         unsafe { Some(Self::from_raw(context)) }
     }
 
+    /// Convert `src` to a newly allocated frame of `dst_w`x`dst_h` in
+    /// `dst_format`, copying its properties(pts, etc.). Handy for one-off
+    /// conversions, [`SwsScaler`](crate::swscale::SwsScaler) and
+    /// [`Converter`](crate::swscale::Converter) reuse the context for
+    /// streams of frames.
+    ///
+    /// Return [`RsmpegError::HwPixelFormatError`] if either pixel format is a
+    /// hardware one, frames on a device need
+    /// [`AVFrame::hwframe_transfer_data()`] first. Return an error if the
+    /// planes of `src` don't fit in its buffers, e.g. after its height is
+    /// raised, see [`AVFrame::copy_image_to_buffer()`].
+    pub fn convert_frame(
+        src: &AVFrame,
+        dst_format: AVPixelFormat,
+        dst_w: i32,
+        dst_h: i32,
+        flags: SwsFlags,
+    ) -> Result<AVFrame> {
+        for format in [src.format, dst_format] {
+            let desc = AVPixFmtDescriptorRef::get(format)
+                .ok_or(RsmpegError::AVError(ffi::AVERROR(ffi::EINVAL)))?;
+            if desc.is_hwaccel() {
+                return Err(RsmpegError::HwPixelFormatError(
+                    desc.name().to_string_lossy().into_owned(),
+                ));
+            }
+        }
+        check_image_planes(&src.linesize, src.format, src.width, src.height, |plane| {
+            src.plane_buffer_len(plane)
+        })?;
+        let mut context = Self::get_context(
+            src.width, src.height, src.format, dst_w, dst_h, dst_format, flags, None, None, None,
+        )
+        .ok_or(RsmpegError::AVError(ffi::AVERROR(ffi::EINVAL)))?;
+
+        let mut dst = AVFrame::new();
+        dst.set_width(dst_w);
+        dst.set_height(dst_h);
+        dst.set_format(dst_format);
+        dst.alloc_buffer()?;
+        dst.copy_props_from(src)?;
+        context.scale_frame(src, 0, src.height, &mut dst)?;
+        Ok(dst)
+    }
+
     /// Check if context can be reused, otherwise reallocate a new one.
     ///
     /// Checks if the parameters are the ones already
@@ -68,7 +228,7 @@ impl SwsContext {
         dst_w: i32,
         dst_h: i32,
         dst_format: AVPixelFormat,
-        flags: u32,
+        flags: SwsFlags,
         src_filter: Option<&ffi::SwsFilter>,
         dst_filter: Option<&ffi::SwsFilter>,
         param: Option<&[f64; 2]>,
@@ -83,7 +243,7 @@ impl SwsContext {
                 dst_w,
                 dst_h,
                 dst_format,
-                flags as i32,
+                flags.bits() as i32,
                 src_filter
                     .map(|x| x as *const _ as *mut _)
                     .unwrap_or_else(ptr::null_mut),
@@ -105,10 +265,12 @@ impl SwsContext {
     /// top-bottom or bottom-top order. If slices are provided in
     /// non-sequential order the behavior of the function is undefined.
     ///
+    /// Check [`Self::scale()`] for the safe version.
+    ///
     /// # Safety
     /// The `src_slice` should be valid with the `src_stride`, `src_slice_y` and
     /// `src_slice_h`. The `dst` should be valid with the `dst_stride`.
-    pub unsafe fn scale(
+    pub unsafe fn scale_raw(
         &mut self,
         src_slice: *const *const u8,
         src_stride: *const i32,
@@ -135,7 +297,162 @@ impl SwsContext {
         Ok(())
     }
 
-    /// A wrapper of [`Self::scale`], check it's documentation.
+    /// Scale the rows `src_slice_y..src_slice_y + src_slice_h` of the source
+    /// image, given in `src_slices` from the first row of the slice, to the
+    /// whole destination image in `dst_slices`. Both have one slice and one
+    /// stride per plane of the pixel formats the context is configured with,
+    /// paletted formats take the palette as an extra plane.
+    ///
+    /// Slices have to be provided in sequential order, either in
+    /// top-bottom or bottom-top order.
+    ///
+    /// Returns [`RsmpegError::ScalePlanesError`] if the planes don't match the
+    /// configured sizes and pixel formats, e.g. a missing plane, a stride
+    /// shorter than a row or a slice shorter than its rows.
+    pub fn scale(
+        &mut self,
+        src_slices: &[&[u8]],
+        src_strides: &[i32],
+        src_slice_y: i32,
+        src_slice_h: i32,
+        dst_slices: &mut [&mut [u8]],
+        dst_strides: &[i32],
+    ) -> Result<()> {
+        let src_w = self.get_int_opt(b"srcw\0")?;
+        let src_h = self.get_int_opt(b"srch\0")?;
+        let src_format = self.get_int_opt(b"src_format\0")?;
+        let dst_w = self.get_int_opt(b"dstw\0")?;
+        let dst_h = self.get_int_opt(b"dsth\0")?;
+        let dst_format = self.get_int_opt(b"dst_format\0")?;
+
+        if src_slice_y < 0 || src_slice_h <= 0 || src_slice_y + src_slice_h > src_h {
+            return Err(RsmpegError::ScalePlanesError(format!(
+                "rows {}..{} are out of the source height {}",
+                src_slice_y,
+                src_slice_y + src_slice_h,
+                src_h
+            )));
+        }
+        let src_lens: Vec<usize> = src_slices.iter().map(|x| x.len()).collect();
+        check_planes(
+            "source",
+            &src_lens,
+            src_strides,
+            src_format,
+            src_w,
+            src_slice_y,
+            src_slice_h,
+        )?;
+        let dst_lens: Vec<usize> = dst_slices.iter().map(|x| x.len()).collect();
+        check_planes(
+            "destination",
+            &dst_lens,
+            dst_strides,
+            dst_format,
+            dst_w,
+            0,
+            dst_h,
+        )?;
+
+        let mut src = [ptr::null(); 4];
+        let mut src_stride = [0; 4];
+        for (i, (slice, stride)) in src_slices.iter().zip(src_strides).enumerate() {
+            src[i] = slice.as_ptr();
+            src_stride[i] = *stride;
+        }
+        let mut dst = [ptr::null_mut(); 4];
+        let mut dst_stride = [0; 4];
+        for (i, (slice, stride)) in dst_slices.iter_mut().zip(dst_strides).enumerate() {
+            dst[i] = slice.as_mut_ptr();
+            dst_stride[i] = *stride;
+        }
+        unsafe {
+            self.scale_raw(
+                src.as_ptr(),
+                src_stride.as_ptr(),
+                src_slice_y,
+                src_slice_h,
+                dst.as_ptr(),
+                dst_stride.as_ptr(),
+            )
+        }
+    }
+
+    /// Set the YUV coefficients and the ranges of the source and the
+    /// destination, and the brightness, contrast and saturation adjustments
+    /// of the conversion. E.g. HD sources need the BT.709 coefficients, the
+    /// BT.601 ones are used by default.
+    ///
+    /// Return `AVERROR(EINVAL)` if FFmpeg doesn't support the details for the
+    /// formats of the context.
+    pub fn set_colorspace_details(&mut self, details: &ColorspaceDetails) -> Result<()> {
+        let ret = unsafe {
+            ffi::sws_setColorspaceDetails(
+                self.as_mut_ptr(),
+                details.src_coefficients.as_ptr(),
+                details.src_full_range as c_int,
+                details.dst_coefficients.as_ptr(),
+                details.dst_full_range as c_int,
+                details.brightness,
+                details.contrast,
+                details.saturation,
+            )
+        };
+        // FFmpeg returns -1 instead of an `AVERROR`.
+        if ret < 0 {
+            return Err(RsmpegError::AVError(ffi::AVERROR(ffi::EINVAL)));
+        }
+        Ok(())
+    }
+
+    /// Get the colorspaces, ranges and color adjustments of the conversion.
+    pub fn colorspace_details(&self) -> Result<ColorspaceDetails> {
+        let mut src_coefficients = ptr::null_mut();
+        let mut src_range = 0;
+        let mut dst_coefficients = ptr::null_mut();
+        let mut dst_range = 0;
+        let (mut brightness, mut contrast, mut saturation) = (0, 0, 0);
+        let ret = unsafe {
+            ffi::sws_getColorspaceDetails(
+                self.as_ptr() as *mut _,
+                &mut src_coefficients,
+                &mut src_range,
+                &mut dst_coefficients,
+                &mut dst_range,
+                &mut brightness,
+                &mut contrast,
+                &mut saturation,
+            )
+        };
+        if ret < 0 {
+            return Err(RsmpegError::AVError(ffi::AVERROR(ffi::EINVAL)));
+        }
+        // Both point to arrays of 4 in the context.
+        let coefficients = |x: *mut c_int| -> [c_int; 4] {
+            unsafe { slice::from_raw_parts(x, 4) }.try_into().unwrap()
+        };
+        Ok(ColorspaceDetails {
+            src_coefficients: coefficients(src_coefficients),
+            src_full_range: src_range != 0,
+            dst_coefficients: coefficients(dst_coefficients),
+            dst_full_range: dst_range != 0,
+            brightness,
+            contrast,
+            saturation,
+        })
+    }
+
+    /// Get the integer option `name` of the context, e.g. the configured
+    /// width or pixel format.
+    fn get_int_opt(&self, name: &[u8]) -> Result<i32> {
+        let name = CStr::from_bytes_with_nul(name).unwrap();
+        let mut value = 0;
+        unsafe { ffi::av_opt_get_int(self.as_ptr() as _, name.as_ptr(), 0, &mut value) }
+            .upgrade()?;
+        Ok(value as i32)
+    }
+
+    /// A wrapper of [`Self::scale_raw`], check it's documentation.
     pub fn scale_frame(
         &mut self,
         src_frame: &AVFrame,
@@ -144,7 +461,7 @@ impl SwsContext {
         dst_frame: &mut AVFrame,
     ) -> Result<()> {
         unsafe {
-            self.scale(
+            self.scale_raw(
                 src_frame.data.as_ptr() as _,
                 src_frame.linesize.as_ptr(),
                 src_slice_y,
@@ -156,6 +473,70 @@ impl SwsContext {
     }
 }
 
+/// Check the planes of the rows `y..y + h` of an image of `width` in `format`
+/// have the length of `lens` and the strides of `strides`.
+fn check_planes(
+    what: &str,
+    lens: &[usize],
+    strides: &[i32],
+    format: AVPixelFormat,
+    width: i32,
+    y: i32,
+    h: i32,
+) -> Result<()> {
+    let invalid = |message: String| Err(RsmpegError::ScalePlanesError(format!("{what} {message}")));
+    let Some(desc) = AVPixFmtDescriptorRef::get(format) else {
+        return invalid(format!("pixel format {format} is unknown"));
+    };
+    if desc.is_hwaccel() {
+        return invalid(format!("pixel format {format} is a hardware format"));
+    }
+    let nb_planes = desc.nb_planes();
+    let paletted = desc.is_paletted();
+    let expected = if paletted { nb_planes + 1 } else { nb_planes };
+    if lens.len() != expected || strides.len() != expected {
+        return invalid(format!(
+            "has {} slices and {} strides, {} planes are expected",
+            lens.len(),
+            strides.len(),
+            expected
+        ));
+    }
+    if paletted && lens[nb_planes] < ffi::AVPALETTE_SIZE as usize {
+        return invalid(format!(
+            "palette has {} bytes, {} are expected",
+            lens[nb_planes],
+            ffi::AVPALETTE_SIZE
+        ));
+    }
+
+    let mut linesizes: [c_int; 4] = [0; 4];
+    unsafe { ffi::av_image_fill_linesizes(linesizes.as_mut_ptr(), format, width) }.upgrade()?;
+    for i in 0..nb_planes {
+        let (stride, linesize) = (strides[i], linesizes[i]);
+        if stride < linesize {
+            return invalid(format!(
+                "stride {stride} of plane {i} is shorter than its rows of {linesize} bytes"
+            ));
+        }
+        // Planes 1 and 2 are the (possibly subsampled) chroma planes.
+        let rows = if i == 1 || i == 2 {
+            let shift = desc.log2_chroma_h;
+            (-((-(y + h)) >> shift)) - (y >> shift)
+        } else {
+            h
+        };
+        let needed = (rows as usize - 1) * stride as usize + linesize as usize;
+        if lens[i] < needed {
+            return invalid(format!(
+                "plane {i} has {} bytes, {needed} are expected for {rows} rows",
+                lens[i]
+            ));
+        }
+    }
+    Ok(())
+}
+
 impl Drop for SwsContext {
     fn drop(&mut self) {
         unsafe { ffi::sws_freeContext(self.as_mut_ptr()) }
@@ -165,7 +546,7 @@ impl Drop for SwsContext {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::ffi::{AV_PIX_FMT_RGB24, SWS_BICUBIC, SWS_FULL_CHR_H_INT, SWS_PARAM_DEFAULT};
+    use crate::ffi::{AV_PIX_FMT_RGB24, AV_PIX_FMT_YUV420P, SWS_PARAM_DEFAULT};
 
     #[test]
     fn test_cached_sws_context() {
@@ -176,7 +557,7 @@ mod tests {
             10,
             10,
             AV_PIX_FMT_RGB24,
-            SWS_FULL_CHR_H_INT | SWS_BICUBIC,
+            SwsFlags::FULL_CHR_H_INT | SwsFlags::BICUBIC,
             None,
             None,
             Some(&[SWS_PARAM_DEFAULT as f64, SWS_PARAM_DEFAULT as f64]),
@@ -191,7 +572,7 @@ mod tests {
                 10,
                 10,
                 AV_PIX_FMT_RGB24,
-                SWS_FULL_CHR_H_INT | SWS_BICUBIC,
+                SwsFlags::FULL_CHR_H_INT | SwsFlags::BICUBIC,
                 None,
                 None,
                 None,
@@ -200,4 +581,186 @@ mod tests {
         let new_ptr = context.as_ptr();
         assert_eq!(old_ptr, new_ptr);
     }
+
+    fn yuv420p_to_rgb24(width: i32, height: i32) -> SwsContext {
+        SwsContext::get_context(
+            width,
+            height,
+            AV_PIX_FMT_YUV420P,
+            width,
+            height,
+            AV_PIX_FMT_RGB24,
+            SwsFlags::BILINEAR | SwsFlags::FULL_CHR_H_INT,
+            None,
+            None,
+            None,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_scale_slices() {
+        // 16x16, white top half and black bottom half, in limited range.
+        let (width, height) = (16, 16);
+        let mut y = vec![235u8; 16 * 16];
+        y[16 * 8..].fill(16);
+        let u = vec![128u8; 8 * 8];
+        let v = vec![128u8; 8 * 8];
+        let mut rgb = vec![0u8; 16 * 16 * 3];
+
+        let mut context = yuv420p_to_rgb24(width, height);
+        context
+            .scale(
+                &[&y, &u, &v],
+                &[16, 8, 8],
+                0,
+                height,
+                &mut [&mut rgb],
+                &[16 * 3],
+            )
+            .unwrap();
+        let pixel = |x: usize, y: usize| &rgb[y * 16 * 3 + x * 3..][..3];
+        for (x, y, value) in [(0, 0, 255), (15, 3, 255), (4, 12, 0), (15, 15, 0)] {
+            for component in pixel(x, y) {
+                assert!(
+                    (*component as i32 - value).abs() <= 2,
+                    "({x}, {y}): {component} != {value}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_colorspace_details() {
+        // A saturated color, whose RGB depends on the coefficients.
+        let (width, height) = (16, 16);
+        let y = vec![128u8; 16 * 16];
+        let u = vec![64u8; 8 * 8];
+        let v = vec![200u8; 8 * 8];
+        let convert = |colorspace: u32| {
+            let mut context = yuv420p_to_rgb24(width, height);
+            let details = ColorspaceDetails {
+                src_coefficients: get_coefficients(colorspace as c_int),
+                ..context.colorspace_details().unwrap()
+            };
+            context.set_colorspace_details(&details).unwrap();
+            assert_eq!(context.colorspace_details().unwrap(), details);
+            let mut rgb = vec![0u8; 16 * 16 * 3];
+            context
+                .scale(
+                    &[&y, &u, &v],
+                    &[16, 8, 8],
+                    0,
+                    height,
+                    &mut [&mut rgb],
+                    &[16 * 3],
+                )
+                .unwrap();
+            rgb
+        };
+        let bt601 = convert(ffi::SWS_CS_ITU601);
+        let bt709 = convert(ffi::SWS_CS_ITU709);
+        assert_eq!(bt601, convert(ffi::SWS_CS_DEFAULT));
+        assert_ne!(bt601, bt709);
+
+        let context = yuv420p_to_rgb24(width, height);
+        let details = context.colorspace_details().unwrap();
+        let default = ColorspaceDetails::default();
+        assert_eq!(details.src_coefficients, default.src_coefficients);
+        assert!(!details.src_full_range);
+        assert_eq!(
+            (details.brightness, details.contrast, details.saturation),
+            (0, 1 << 16, 1 << 16)
+        );
+    }
+
+    #[test]
+    fn test_sws_flags() {
+        let flags = SwsFlags::BICUBIC | SwsFlags::ACCURATE_RND;
+        assert_eq!(flags.bits(), ffi::SWS_BICUBIC | ffi::SWS_ACCURATE_RND);
+        assert!(flags.contains(SwsFlags::BICUBIC));
+        assert!(!flags.contains(SwsFlags::LANCZOS));
+        assert_eq!(flags & SwsFlags::ACCURATE_RND, SwsFlags::ACCURATE_RND);
+        assert_eq!(SwsFlags::from_bits(ffi::SWS_POINT), SwsFlags::POINT);
+    }
+
+    #[test]
+    fn test_convert_frame() {
+        let mut src = AVFrame::new();
+        src.set_width(16);
+        src.set_height(16);
+        src.set_format(AV_PIX_FMT_YUV420P);
+        src.alloc_buffer().unwrap();
+        src.set_pts(42);
+        for (plane, value) in [(0, 235), (1, 128), (2, 128)] {
+            let linesize = src.linesize[plane] as usize;
+            let rows = if plane == 0 { 16 } else { 8 };
+            unsafe { std::slice::from_raw_parts_mut(src.data[plane], linesize * rows) }.fill(value);
+        }
+
+        let dst = SwsContext::convert_frame(&src, AV_PIX_FMT_RGB24, 8, 8, SwsFlags::AREA).unwrap();
+        assert_eq!(
+            (dst.width, dst.height, dst.format),
+            (8, 8, AV_PIX_FMT_RGB24)
+        );
+        assert_eq!(dst.pts, 42);
+        let rgb = unsafe { std::slice::from_raw_parts(dst.data[0], 8 * 3) };
+        assert!(rgb.iter().all(|&x| x >= 253));
+
+        assert!(matches!(
+            SwsContext::convert_frame(&src, ffi::AV_PIX_FMT_CUDA, 8, 8, SwsFlags::BILINEAR),
+            Err(RsmpegError::HwPixelFormatError(name)) if name == "cuda"
+        ));
+        let mut hw = AVFrame::new();
+        hw.set_width(16);
+        hw.set_height(16);
+        hw.set_format(ffi::AV_PIX_FMT_VAAPI);
+        assert!(matches!(
+            SwsContext::convert_frame(&hw, AV_PIX_FMT_RGB24, 8, 8, SwsFlags::BILINEAR),
+            Err(RsmpegError::HwPixelFormatError(name)) if name == "vaapi"
+        ));
+
+        // The source planes are checked against its buffers.
+        src.set_height(32);
+        assert!(matches!(
+            SwsContext::convert_frame(&src, AV_PIX_FMT_RGB24, 8, 8, SwsFlags::AREA),
+            Err(RsmpegError::BufferTooSmallError(..))
+        ));
+        let mut unallocated = AVFrame::new();
+        unallocated.set_width(16);
+        unallocated.set_height(16);
+        unallocated.set_format(AV_PIX_FMT_YUV420P);
+        assert!(
+            SwsContext::convert_frame(&unallocated, AV_PIX_FMT_RGB24, 8, 8, SwsFlags::AREA)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_scale_slices_invalid() {
+        let (width, height) = (16, 16);
+        let y = vec![16u8; 16 * 16];
+        let u = vec![128u8; 8 * 8];
+        let v = vec![128u8; 8 * 8];
+        let mut rgb = vec![0u8; 16 * 16 * 3];
+        let mut context = yuv420p_to_rgb24(width, height);
+
+        let mut scale = |src: &[&[u8]], src_strides: &[i32], row, rows, dst_stride| {
+            let result = context.scale(src, src_strides, row, rows, &mut [&mut rgb], &[dst_stride]);
+            assert!(
+                matches!(result, Err(RsmpegError::ScalePlanesError(_))),
+                "{result:?}"
+            );
+        };
+        // Missing chroma plane.
+        scale(&[&y, &u], &[16, 8], 0, 16, 48);
+        // Stride shorter than the rows.
+        scale(&[&y, &u, &v], &[8, 8, 8], 0, 16, 48);
+        // Chroma plane too short for the stride.
+        scale(&[&y, &u, &v], &[16, 16, 8], 0, 16, 48);
+        // Rows out of the image.
+        scale(&[&y, &u, &v], &[16, 8, 8], 8, 16, 48);
+        // Destination too small.
+        scale(&[&y, &u, &v], &[16, 8, 8], 0, 16, 64);
+    }
 }
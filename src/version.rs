@@ -0,0 +1,149 @@
+//! Versions and build configuration of the linked FFmpeg libraries, for
+//! adapting to them at runtime and printing diagnostics.
+use crate::ffi;
+use std::{ffi::CStr, fmt, os::raw::c_char};
+
+/// Version of a FFmpeg library, e.g. `61.3.100`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Version {
+    pub major: u32,
+    pub minor: u32,
+    pub micro: u32,
+}
+
+impl Version {
+    /// Decode a version packed by `AV_VERSION_INT`.
+    pub const fn from_packed(version: u32) -> Self {
+        Self {
+            major: version >> 16,
+            minor: (version >> 8) & 0xff,
+            micro: version & 0xff,
+        }
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.micro)
+    }
+}
+
+/// The FFmpeg libraries used by rsmpeg.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Library {
+    AVUtil,
+    AVCodec,
+    AVFormat,
+    AVFilter,
+    SwScale,
+    SwResample,
+}
+
+/// Versions of the FFmpeg libraries linked at runtime, which can be newer
+/// than the ones rsmpeg is built against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Versions {
+    pub avutil: Version,
+    pub avcodec: Version,
+    pub avformat: Version,
+    pub avfilter: Version,
+    pub swscale: Version,
+    pub swresample: Version,
+}
+
+impl Versions {
+    /// Get the versions of the linked libraries.
+    pub fn linked() -> Self {
+        unsafe {
+            Self {
+                avutil: Version::from_packed(ffi::avutil_version()),
+                avcodec: Version::from_packed(ffi::avcodec_version()),
+                avformat: Version::from_packed(ffi::avformat_version()),
+                avfilter: Version::from_packed(ffi::avfilter_version()),
+                swscale: Version::from_packed(ffi::swscale_version()),
+                swresample: Version::from_packed(ffi::swresample_version()),
+            }
+        }
+    }
+
+    /// Get the version of `library`.
+    pub fn get(&self, library: Library) -> Version {
+        match library {
+            Library::AVUtil => self.avutil,
+            Library::AVCodec => self.avcodec,
+            Library::AVFormat => self.avformat,
+            Library::AVFilter => self.avfilter,
+            Library::SwScale => self.swscale,
+            Library::SwResample => self.swresample,
+        }
+    }
+
+    /// Return true if `library` is at least of version `major.minor`, e.g.
+    /// `Versions::linked().at_least(Library::AVUtil, 57, 24)` for the
+    /// `AVChannelLayout` API.
+    pub fn at_least(&self, library: Library, major: u32, minor: u32) -> bool {
+        let version = self.get(library);
+        (version.major, version.minor) >= (major, minor)
+    }
+}
+
+fn to_string(s: *const c_char) -> String {
+    if s.is_null() {
+        return String::new();
+    }
+    unsafe { CStr::from_ptr(s) }.to_string_lossy().into_owned()
+}
+
+/// Get the FFmpeg release or git revision, e.g. `7.0.1` or `N-115000-g...`.
+pub fn version_info() -> String {
+    to_string(unsafe { ffi::av_version_info() })
+}
+
+/// Get the `configure` arguments FFmpeg is built with.
+pub fn configuration() -> String {
+    to_string(unsafe { ffi::avutil_configuration() })
+}
+
+/// Get the license of the FFmpeg build, e.g. `LGPL version 2.1 or later`.
+pub fn license() -> String {
+    to_string(unsafe { ffi::avutil_license() })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_version_from_packed() {
+        let version = Version::from_packed((61 << 16) | (3 << 8) | 100);
+        assert_eq!(
+            version,
+            Version {
+                major: 61,
+                minor: 3,
+                micro: 100
+            }
+        );
+        assert_eq!(version.to_string(), "61.3.100");
+        assert!(version > Version::from_packed((60 << 16) | (31 << 8) | 102));
+    }
+
+    #[test]
+    fn test_linked_versions() {
+        let versions = Versions::linked();
+        // The major versions are ABI boundaries, they match the headers.
+        assert_eq!(versions.avutil.major, ffi::LIBAVUTIL_VERSION_MAJOR);
+        assert_eq!(versions.avcodec.major, ffi::LIBAVCODEC_VERSION_MAJOR);
+        assert_eq!(versions.avformat.major, ffi::LIBAVFORMAT_VERSION_MAJOR);
+        assert_eq!(versions.avfilter.major, ffi::LIBAVFILTER_VERSION_MAJOR);
+        assert_eq!(versions.swscale.major, ffi::LIBSWSCALE_VERSION_MAJOR);
+        assert_eq!(versions.swresample.major, ffi::LIBSWRESAMPLE_VERSION_MAJOR);
+        assert!(versions.at_least(Library::AVUtil, 57, 24));
+        assert!(versions.at_least(Library::AVCodec, versions.avcodec.major, 0));
+        assert!(!versions.at_least(Library::AVFormat, versions.avformat.major + 1, 0));
+
+        assert!(!version_info().is_empty());
+        assert!(!configuration().is_empty());
+        assert!(!license().is_empty());
+    }
+}
@@ -0,0 +1,17 @@
+//! Force the CPU flags of FFmpeg. The flags are process-global, so this test
+//! has its own binary instead of running alongside the other tests.
+use rsmpeg::avutil::cpu::{self, CpuFlags};
+
+#[test]
+fn test_force_cpu_flags() {
+    let detected = cpu::flags();
+    // Only ever a subset of the detected flags.
+    unsafe { cpu::force_flags(CpuFlags::empty()) };
+    assert!(cpu::flags().is_empty());
+    unsafe { cpu::force_flags(detected) };
+    assert_eq!(cpu::flags(), detected);
+    unsafe { cpu::force_flags(detected & !CpuFlags::AVX2) };
+    assert!(!cpu::flags().contains(CpuFlags::AVX2));
+    cpu::reset_flags();
+    assert_eq!(cpu::flags(), detected);
+}
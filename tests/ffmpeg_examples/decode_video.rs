@@ -11,20 +11,17 @@ use rusty_ffmpeg::ffi::AV_INPUT_BUFFER_PADDING_SIZE;
 use std::{
     fs::{self, File},
     io::prelude::*,
-    slice,
 };
 
 /// Save a `AVFrame` as pgm file.
 fn pgm_save(frame: &AVFrame, filename: &str) -> Result<()> {
     // Here we only capture the first layer of frame.
-    let data = frame.data[0];
+    let buffer = frame.data(0).unwrap();
     let linesize = frame.linesize[0] as usize;
 
     let width = frame.width as usize;
     let height = frame.height as usize;
 
-    let buffer = unsafe { slice::from_raw_parts(data, linesize * height) };
-
     // Create pgm file
     let mut pgm_file = fs::File::create(filename)?;
 
@@ -16,36 +16,54 @@ use std::{
 const WIDTH: usize = 352;
 const HEIGHT: usize = 288;
 
+/// What went out of the encoder.
+#[derive(Debug, Default)]
+struct EncodeStats {
+    nb_packets: usize,
+    nb_bytes: usize,
+    last_dts: Option<i64>,
+}
+
 fn encode(
     encode_context: &mut AVCodecContext,
     frame: Option<&AVFrame>,
     file: &mut BufWriter<File>,
+    stats: &mut EncodeStats,
 ) -> Result<()> {
     encode_context.send_frame(frame)?;
     loop {
         let packet = match encode_context.receive_packet() {
             Ok(packet) => packet,
-            Err(RsmpegError::EncoderDrainError) | Err(RsmpegError::EncoderFlushedError) => break,
+            // Wants more input, which never comes once draining.
+            Err(RsmpegError::EncoderDrainError) if frame.is_some() => break,
+            Err(RsmpegError::EncoderFlushedError) => break,
             Err(e) => return Err(e.into()),
         };
+        if let Some(last_dts) = stats.last_dts {
+            if packet.dts <= last_dts {
+                return Err(anyhow!("dts {} after {}", packet.dts, last_dts));
+            }
+        }
+        stats.last_dts = Some(packet.dts);
+        stats.nb_packets += 1;
+        stats.nb_bytes += packet.size as usize;
         let data = unsafe { std::slice::from_raw_parts(packet.data, packet.size as usize) };
         file.write_all(data)?;
     }
     Ok(())
 }
 
-fn encode_video(codec_name: &CStr, file_name: &str) -> Result<()> {
+fn encode_video(codec_name: &CStr, file_name: &str) -> Result<EncodeStats> {
     let encoder =
         AVCodec::find_encoder_by_name(codec_name).context("Failed to find encoder codec")?;
     let mut encode_context = AVCodecContext::new(&encoder);
-    encode_context.set_bit_rate(400000);
-    encode_context.set_width(WIDTH as i32);
-    encode_context.set_height(HEIGHT as i32);
-    encode_context.set_time_base(ra(1, 25));
-    encode_context.set_framerate(ra(25, 1));
-    encode_context.set_gop_size(10);
-    encode_context.set_max_b_frames(1);
-    encode_context.set_pix_fmt(ffi::AV_PIX_FMT_YUV420P);
+    encode_context.try_set_bit_rate(400000)?;
+    encode_context.try_set_width(WIDTH as i32)?;
+    encode_context.try_set_height(HEIGHT as i32)?;
+    encode_context.set_time_base_from_framerate(ra(25, 1))?;
+    encode_context.try_set_gop_size(10)?;
+    encode_context.try_set_max_b_frames(1)?;
+    encode_context.try_set_pix_fmt(ffi::AV_PIX_FMT_YUV420P)?;
     if encoder.id == ffi::AV_CODEC_ID_H264 {
         unsafe { opt_set(encode_context.priv_data, cstr!("preset"), cstr!("slow"), 0) }
             .context("Set preset failed.")?;
@@ -62,6 +80,7 @@ fn encode_video(codec_name: &CStr, file_name: &str) -> Result<()> {
 
     let file = File::create(file_name).with_context(|| anyhow!("Could not open: {}", file_name))?;
     let mut writer = BufWriter::new(file);
+    let mut stats = EncodeStats::default();
 
     for i in 0..25 {
         frame
@@ -96,18 +115,27 @@ fn encode_video(codec_name: &CStr, file_name: &str) -> Result<()> {
 
         frame.set_pts(i as i64);
 
-        encode(&mut encode_context, Some(&frame), &mut writer)?;
+        encode(&mut encode_context, Some(&frame), &mut writer, &mut stats)?;
     }
-    encode(&mut encode_context, None, &mut writer)?;
+    // flush the encoder, it returns the delayed packets until EOF
+    encode(&mut encode_context, None, &mut writer, &mut stats)?;
 
     let endcode: [u8; 4] = [0, 0, 1, 0xb7];
     writer.write_all(&endcode).context("Write endcode failed")?;
 
-    writer.flush().context("Flush file failed.")
+    writer.flush().context("Flush file failed.")?;
+    Ok(stats)
 }
 
 #[test]
 fn encode_video_test() {
     fs::create_dir_all("tests/output/encode_video/").unwrap();
-    encode_video(cstr!("mpeg4"), "tests/output/encode_video/output.mp4").unwrap();
+    let stats = encode_video(cstr!("mpeg4"), "tests/output/encode_video/output.mp4").unwrap();
+    // One packet per frame, the B-frame delayed one included.
+    assert_eq!(stats.nb_packets, 25);
+    assert!(stats.nb_bytes > 0);
+    let size = fs::metadata("tests/output/encode_video/output.mp4")
+        .unwrap()
+        .len();
+    assert_eq!(size as usize, stats.nb_bytes + 4);
 }
@@ -0,0 +1,104 @@
+//! RIIR: https://github.com/FFmpeg/FFmpeg/blob/master/doc/examples/hw_decode.c
+use anyhow::{Context, Result};
+use cstr::cstr;
+use rsmpeg::{
+    avcodec::{AVCodecContext, AVPacket},
+    avformat::AVFormatContextInput,
+    avutil::{AVFrame, AVHWDeviceContext},
+    error::RsmpegError,
+    ffi,
+};
+use std::{
+    ffi::CStr,
+    fs::{self, File},
+};
+
+/// Send `packet` to the decoder, download the decoded frames from the device
+/// and write them raw to `output`.
+fn decode_write(
+    decoder_ctx: &mut AVCodecContext,
+    packet: Option<&AVPacket>,
+    hw_pix_fmt: ffi::AVPixelFormat,
+    output: &mut File,
+) -> Result<usize> {
+    decoder_ctx
+        .send_packet(packet)
+        .context("Error during decoding")?;
+    let mut nb_frames = 0;
+    loop {
+        let frame = match decoder_ctx.receive_frame() {
+            Ok(frame) => frame,
+            Err(RsmpegError::DecoderDrainError) | Err(RsmpegError::DecoderFlushedError) => break,
+            Err(e) => Err(e).context("Error while decoding")?,
+        };
+        let frame = if frame.format == hw_pix_fmt {
+            // retrieve data from GPU to CPU
+            let mut sw_frame = AVFrame::new();
+            sw_frame
+                .hwframe_transfer_data(&frame)
+                .context("Error transferring the data to system memory")?;
+            sw_frame
+        } else {
+            // The decoder fell back to software decoding.
+            frame
+        };
+        frame
+            .write_raw_to(output)
+            .context("Failed to dump raw data")?;
+        nb_frames += 1;
+    }
+    Ok(nb_frames)
+}
+
+/// Decode the video stream of `input` on the first hardware device of the
+/// decoder that can be created, return the number of decoded frames.
+fn hw_decode(input: &CStr, output: &str) -> Result<usize> {
+    let mut input_ctx =
+        AVFormatContextInput::open(input, None, &mut None).context("Cannot open input file")?;
+    let (video_stream, decoder) = input_ctx
+        .find_best_stream(ffi::AVMEDIA_TYPE_VIDEO)?
+        .context("Cannot find a video stream in the input file")?;
+
+    let (hw_device_ctx, hw_pix_fmt) = decoder
+        .hw_configs()
+        .filter(|config| config.methods & ffi::AV_CODEC_HW_CONFIG_METHOD_HW_DEVICE_CTX as i32 != 0)
+        .find_map(|config| {
+            let hw_device_ctx =
+                AVHWDeviceContext::create(config.device_type, None, None, 0).ok()?;
+            Some((hw_device_ctx, config.pix_fmt))
+        })
+        .context("No hardware device available")?;
+
+    let mut decoder_ctx = AVCodecContext::new(&decoder);
+    decoder_ctx.apply_codecpar(&input_ctx.streams()[video_stream].codecpar())?;
+    decoder_ctx.set_hw_device_ctx(&hw_device_ctx)?;
+    // The decoder context keeps its own reference to the device.
+    drop(hw_device_ctx);
+    decoder_ctx
+        .open(None)
+        .context("Failed to open codec for stream")?;
+
+    let mut output = File::create(output).context("Cannot open output file")?;
+
+    let mut nb_frames = 0;
+    while let Some(packet) = input_ctx.read_packet()? {
+        if packet.stream_index as usize == video_stream {
+            nb_frames += decode_write(&mut decoder_ctx, Some(&packet), hw_pix_fmt, &mut output)?;
+        }
+    }
+    // flush the decoder
+    nb_frames += decode_write(&mut decoder_ctx, None, hw_pix_fmt, &mut output)?;
+
+    Ok(nb_frames)
+}
+
+#[test]
+fn hw_decode_test() {
+    fs::create_dir_all("tests/output/hw_decode/").unwrap();
+    let nb_frames = hw_decode(
+        cstr!("tests/assets/vids/bear.mp4"),
+        "tests/output/hw_decode/bear.yuv",
+    )
+    .unwrap();
+    assert!(nb_frames > 0);
+}
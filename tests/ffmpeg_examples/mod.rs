@@ -2,6 +2,8 @@ mod decode_audio;
 mod decode_video;
 mod encode_video;
 mod extract_mvs;
+#[cfg(feature = "hwaccel")]
+mod hw_decode;
 mod remux;
 mod transcode;
 mod transcode_aac;
@@ -4,8 +4,8 @@ use cstr::cstr;
 use rsmpeg::{
     avcodec::AVPacket,
     avformat::{AVFormatContextInput, AVFormatContextOutput},
-    avutil::{ts2str, ts2timestr},
-    ffi::AVRational,
+    avutil::{ts2str, ts2timestr, AVDictionary},
+    ffi::{self, AVRational},
 };
 use std::ffi::CStr;
 
@@ -41,9 +41,12 @@ fn remux(input_path: &CStr, output_path: &CStr) -> Result<()> {
                 if !codec_type.is_video() && !codec_type.is_audio() && !codec_type.is_subtitle() {
                     None
                 } else {
-                    output_format_context
-                        .new_stream()
-                        .set_codecpar(stream.codecpar().clone());
+                    let mut out_stream = output_format_context.new_stream();
+                    out_stream.set_codecpar(stream.codecpar().clone());
+                    out_stream.set_time_base(stream.time_base);
+                    out_stream.set_avg_frame_rate(stream.avg_frame_rate);
+                    out_stream.set_disposition(stream.disposition);
+                    out_stream.set_metadata(stream.metadata().as_deref().cloned());
                     stream_index += 1;
                     Some(stream_index - 1)
                 }
@@ -94,3 +97,96 @@ fn remux_test0() {
     )
     .unwrap();
 }
+
+/// Configure an output stream entirely through `AVStreamMut`, read the
+/// settings back from the remuxed file.
+#[test]
+fn remux_test1() {
+    std::fs::create_dir_all("tests/output/remux/").unwrap();
+    let output_path = cstr!("tests/output/remux/bear_rotated.mov");
+    let mut ifmt_ctx =
+        AVFormatContextInput::open(cstr!("tests/assets/vids/bear.mp4"), None, &mut None).unwrap();
+    let (video_index, _) = ifmt_ctx
+        .find_best_stream(ffi::AVMEDIA_TYPE_VIDEO)
+        .unwrap()
+        .unwrap();
+    let in_stream = &ifmt_ctx.streams()[video_index];
+    let in_time_base = in_stream.time_base;
+
+    // 90 degrees rotation, 16.16 fixed point with 2.30 in the last column.
+    let matrix: [i32; 9] = [0, 1 << 16, 0, -(1 << 16), 0, 0, 0, 0, 1 << 30];
+    let matrix: Vec<u8> = matrix.iter().flat_map(|x| x.to_ne_bytes()).collect();
+
+    let mut ofmt_ctx = AVFormatContextOutput::create(output_path, None).unwrap();
+    ofmt_ctx
+        .new_stream()
+        .set_codecpar(in_stream.codecpar().clone());
+    {
+        let out_stream = &mut ofmt_ctx.streams_mut()[0];
+        out_stream.set_id(1);
+        out_stream.set_time_base(in_time_base);
+        out_stream.set_avg_frame_rate(in_stream.avg_frame_rate);
+        out_stream.set_disposition(ffi::AV_DISPOSITION_DEFAULT as i32);
+        out_stream.set_metadata(Some(AVDictionary::new(cstr!("language"), cstr!("fra"), 0)));
+        out_stream
+            .add_side_data(ffi::AV_PKT_DATA_DISPLAYMATRIX, &matrix)
+            .unwrap();
+        assert_eq!(
+            out_stream.side_data(ffi::AV_PKT_DATA_DISPLAYMATRIX),
+            Some(&matrix[..])
+        );
+    }
+    ofmt_ctx.write_header(&mut None).unwrap();
+    while let Some(mut packet) = ifmt_ctx.read_packet().unwrap() {
+        if packet.stream_index as usize != video_index {
+            continue;
+        }
+        packet.rescale_ts(in_time_base, ofmt_ctx.streams()[0].time_base);
+        packet.set_stream_index(0);
+        packet.set_pos(-1);
+        ofmt_ctx.interleaved_write_frame(&mut packet).unwrap();
+    }
+    ofmt_ctx.write_trailer().unwrap();
+
+    let ifmt_ctx = AVFormatContextInput::open(output_path, None, &mut None).unwrap();
+    let stream = &ifmt_ctx.streams()[0];
+    assert_eq!(stream.id, 1);
+    assert!(stream.disposition & ffi::AV_DISPOSITION_DEFAULT as i32 != 0);
+    assert_eq!(
+        stream.metadata().unwrap().get_all(cstr!("language")),
+        [cstr!("fra")]
+    );
+    assert_eq!(
+        stream.side_data(ffi::AV_PKT_DATA_DISPLAYMATRIX),
+        Some(&matrix[..])
+    );
+}
+
+/// Remux MPEG-PS to MP4 without re-encoding, the streams and their packets
+/// are kept.
+#[test]
+fn remux_test2() {
+    std::fs::create_dir_all("tests/output/remux/").unwrap();
+    let input_path = cstr!("tests/assets/vids/centaur.mpg");
+    let output_path = cstr!("tests/output/remux/centaur.mp4");
+    remux(input_path, output_path).unwrap();
+
+    let count_packets = |path: &CStr| {
+        let mut ifmt_ctx = AVFormatContextInput::open(path, None, &mut None).unwrap();
+        let codec_ids: Vec<_> = ifmt_ctx
+            .streams()
+            .iter()
+            .map(|x| x.codecpar().codec_id)
+            .collect();
+        let mut nb_packets = 0;
+        while ifmt_ctx.read_packet().unwrap().is_some() {
+            nb_packets += 1;
+        }
+        (codec_ids, nb_packets)
+    };
+    let (input_codec_ids, input_packets) = count_packets(input_path);
+    let (output_codec_ids, output_packets) = count_packets(output_path);
+    assert_eq!(output_codec_ids, input_codec_ids);
+    assert!(output_packets > 0);
+    assert_eq!(output_packets, input_packets);
+}
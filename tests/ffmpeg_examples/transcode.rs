@@ -2,14 +2,15 @@
 use anyhow::{anyhow, bail, Context, Result};
 use cstr::cstr;
 use rsmpeg::{
-    avcodec::{AVCodec, AVCodecContext},
+    avcodec::{AVCodec, AVCodecContext, Compliance},
     avfilter::{AVFilter, AVFilterContextMut, AVFilterGraph, AVFilterInOut},
-    avformat::{AVFormatContextInput, AVFormatContextOutput},
+    avformat::{AVFormatContextInput, AVFormatContextOutput, AVStreamMut},
     avutil::{
         av_inv_q, av_rescale_q, get_sample_fmt_name, ra, AVChannelLayout, AVDictionary, AVFrame,
     },
     error::RsmpegError,
     ffi,
+    helpers::TimestampMapper,
 };
 use std::ffi::{CStr, CString};
 
@@ -19,12 +20,16 @@ struct FilteringContext<'graph> {
     stream_index: usize,
     buffersrc_ctx: AVFilterContextMut<'graph>,
     buffersink_ctx: AVFilterContextMut<'graph>,
+    ts_mapper: TimestampMapper,
 }
 
 struct StreamContext {
     dec_ctx: AVCodecContext,
     enc_ctx: AVCodecContext,
     stream_index: usize,
+    /// Whether the encoder takes another pixel or sample format, or sample
+    /// rate, than the decoder outputs, so the filter graph converts to it.
+    needs_conversion: bool,
 }
 
 struct FilterContext<'graph> {
@@ -54,10 +59,10 @@ fn open_input_file(filename: &CStr) -> Result<(Vec<Option<AVCodecContext>>, AVFo
                     i
                 )
             })?;
-            dec_ctx.set_pkt_timebase(input_stream.time_base);
+            dec_ctx.try_set_pkt_timebase(input_stream.time_base)?;
             if codec_type.is_video() {
                 if let Some(framerate) = input_stream.guess_framerate() {
-                    dec_ctx.set_framerate(framerate);
+                    dec_ctx.set_framerate(framerate)?;
                 }
             }
             dec_ctx
@@ -77,10 +82,13 @@ fn open_input_file(filename: &CStr) -> Result<(Vec<Option<AVCodecContext>>, AVFo
 /// Accepts a output filename, attach `encode_context` to the corresponding
 /// `decode_context` and wrap them into a `stream_context`. `stream_context` is
 /// None when the given `decode_context` in the same index is None.
+///
+/// The encoders are left unopened, the parameters set here are the ones
+/// requested from the filter graph, [`open_encoder()`] applies the negotiated
+/// ones.
 fn open_output_file(
     filename: &CStr,
     dec_ctx: Vec<Option<AVCodecContext>>,
-    dict: &mut Option<AVDictionary>,
 ) -> Result<(Vec<Option<StreamContext>>, AVFormatContextOutput)> {
     let mut ofmt_ctx = AVFormatContextOutput::create(filename, None)?;
     let mut stream_ctx = vec![];
@@ -92,22 +100,30 @@ fn open_output_file(
         };
         let encoder = AVCodec::find_encoder(dec_ctx.codec_id)
             .with_context(|| anyhow!("encoder({}) not found.", dec_ctx.codec_id))?;
+        ofmt_ctx
+            .oformat()
+            .check_codec(encoder.id, Compliance::Normal)?;
 
         let mut enc_ctx = AVCodecContext::new(&encoder);
+        let needs_conversion;
 
         if dec_ctx.codec_type == ffi::AVMEDIA_TYPE_VIDEO {
-            enc_ctx.set_height(dec_ctx.height);
-            enc_ctx.set_width(dec_ctx.width);
-            enc_ctx.set_sample_aspect_ratio(dec_ctx.sample_aspect_ratio);
-            // take first format from list of supported formats
-            enc_ctx.set_pix_fmt(encoder.pix_fmts().unwrap()[0]);
-            enc_ctx.set_time_base(av_inv_q(dec_ctx.framerate));
+            enc_ctx.try_set_height(dec_ctx.height)?;
+            enc_ctx.try_set_width(dec_ctx.width)?;
+            enc_ctx.try_set_sample_aspect_ratio(dec_ctx.sample_aspect_ratio)?;
+            // take the supported format closest to the decoded one
+            let pix_fmt = enc_ctx.pick_best_pix_fmt(dec_ctx.pix_fmt)?;
+            enc_ctx.try_set_pix_fmt(pix_fmt)?;
+            needs_conversion = pix_fmt != dec_ctx.pix_fmt;
         } else if dec_ctx.codec_type == ffi::AVMEDIA_TYPE_AUDIO {
-            enc_ctx.set_sample_rate(dec_ctx.sample_rate);
-            enc_ctx.set_ch_layout(dec_ctx.ch_layout().clone().into_inner());
-            // take first format from list of supported formats
-            enc_ctx.set_sample_fmt(encoder.sample_fmts().unwrap()[0]);
-            enc_ctx.set_time_base(ra(1, dec_ctx.sample_rate));
+            // take the supported rate and format closest to the decoded ones
+            let sample_rate = enc_ctx.pick_sample_rate(dec_ctx.sample_rate)?;
+            let sample_fmt = enc_ctx.pick_sample_fmt(dec_ctx.sample_fmt)?;
+            enc_ctx.try_set_sample_rate(sample_rate)?;
+            enc_ctx.try_set_ch_layout(dec_ctx.ch_layout().clone().into_inner())?;
+            enc_ctx.try_set_sample_fmt(sample_fmt)?;
+            needs_conversion =
+                sample_rate != dec_ctx.sample_rate || sample_fmt != dec_ctx.sample_fmt;
         } else {
             bail!(
                 "Elementary stream #{} is of unknown type, cannot proceed",
@@ -116,37 +132,64 @@ fn open_output_file(
         }
 
         // Some formats want stream headers to be separate.
-        if ofmt_ctx.oformat().flags & ffi::AVFMT_GLOBALHEADER as i32 != 0 {
-            enc_ctx.set_flags(enc_ctx.flags | ffi::AV_CODEC_FLAG_GLOBAL_HEADER as i32);
+        if ofmt_ctx.oformat().flags().is_globalheader() {
+            enc_ctx.try_set_flags(enc_ctx.flags | ffi::AV_CODEC_FLAG_GLOBAL_HEADER as i32)?;
         }
 
-        enc_ctx.open(None).with_context(|| {
-            anyhow!(
-                "Cannot open {} encoder for stream #{}",
-                encoder.name().to_str().unwrap(),
-                i
-            )
-        })?;
-
-        let mut out_stream = ofmt_ctx.new_stream();
-        out_stream.set_codecpar(enc_ctx.extract_codecpar());
-        out_stream.set_time_base(enc_ctx.time_base);
+        let out_stream = ofmt_ctx.new_stream();
 
         stream_ctx.push(Some(StreamContext {
             enc_ctx,
             dec_ctx,
             stream_index: out_stream.index as usize,
+            needs_conversion,
         }));
     }
 
-    ofmt_ctx.dump(0, filename)?;
-    ofmt_ctx
-        .write_header(dict)
-        .context("Error occurred when opening output file")?;
-
     Ok((stream_ctx, ofmt_ctx))
 }
 
+/// Configure `enc_ctx` with the output negotiated by the configured filter
+/// graph of `buffersink_ctx`, open it and describe the encoded stream in
+/// `out_stream`.
+fn open_encoder(
+    enc_ctx: &mut AVCodecContext,
+    buffersink_ctx: &mut AVFilterContextMut,
+    out_stream: &mut AVStreamMut,
+) -> Result<()> {
+    if enc_ctx.codec_type == ffi::AVMEDIA_TYPE_VIDEO {
+        enc_ctx.try_set_pix_fmt(buffersink_ctx.get_format())?;
+        enc_ctx.try_set_width(buffersink_ctx.get_w())?;
+        enc_ctx.try_set_height(buffersink_ctx.get_h())?;
+        enc_ctx.try_set_sample_aspect_ratio(buffersink_ctx.get_sample_aspect_ratio())?;
+        enc_ctx.set_time_base_from_framerate(buffersink_ctx.get_frame_rate())?;
+    } else {
+        enc_ctx.try_set_sample_fmt(buffersink_ctx.get_format())?;
+        enc_ctx.try_set_sample_rate(buffersink_ctx.get_sample_rate())?;
+        enc_ctx.try_set_ch_layout(buffersink_ctx.get_ch_layout().into_inner())?;
+    }
+
+    enc_ctx.open(None).with_context(|| {
+        anyhow!(
+            "Cannot open {} encoder for stream #{}",
+            enc_ctx.codec().name().to_str().unwrap(),
+            out_stream.index
+        )
+    })?;
+
+    // Encoders like AAC take frames of a fixed size, known once opened.
+    if enc_ctx.codec_type == ffi::AVMEDIA_TYPE_AUDIO
+        && enc_ctx.frame_size > 0
+        && enc_ctx.codec().capabilities & ffi::AV_CODEC_CAP_VARIABLE_FRAME_SIZE as i32 == 0
+    {
+        buffersink_ctx.buffersink_set_frame_size(enc_ctx.frame_size as u32);
+    }
+
+    out_stream.set_codecpar(enc_ctx.extract_codecpar());
+    out_stream.set_time_base(enc_ctx.time_base);
+    Ok(())
+}
+
 /// Init a filter between a `decode_context` and a `encode_context`
 /// corresponds to the given `filter_spec`.
 fn init_filter<'graph>(
@@ -155,12 +198,12 @@ fn init_filter<'graph>(
     enc_ctx: &mut AVCodecContext,
     filter_spec: &CStr,
 ) -> Result<FilterContext<'graph>> {
-    let (mut buffersrc_ctx, mut buffersink_ctx) = if dec_ctx.codec_type == ffi::AVMEDIA_TYPE_VIDEO {
+    let (buffersrc_ctx, buffersink_ctx) = if dec_ctx.codec_type == ffi::AVMEDIA_TYPE_VIDEO {
         let buffersrc = AVFilter::get_by_name(cstr!("buffer")).unwrap();
         let buffersink = AVFilter::get_by_name(cstr!("buffersink")).unwrap();
 
         let args = format!(
-            "video_size={}x{}:pix_fmt={}:time_base={}/{}:pixel_aspect={}/{}",
+            "video_size={}x{}:pix_fmt={}:time_base={}/{}:pixel_aspect={}/{}:frame_rate={}/{}",
             dec_ctx.width,
             dec_ctx.height,
             dec_ctx.pix_fmt,
@@ -168,6 +211,8 @@ fn init_filter<'graph>(
             dec_ctx.pkt_timebase.den,
             dec_ctx.sample_aspect_ratio.num,
             dec_ctx.sample_aspect_ratio.den,
+            dec_ctx.framerate.num,
+            dec_ctx.framerate.den,
         );
 
         let args = &CString::new(args).unwrap();
@@ -189,11 +234,12 @@ fn init_filter<'graph>(
         let buffersrc = AVFilter::get_by_name(cstr!("abuffer")).unwrap();
         let buffersink = AVFilter::get_by_name(cstr!("abuffersink")).unwrap();
 
-        if dec_ctx.ch_layout.order == ffi::AV_CHANNEL_ORDER_UNSPEC {
-            dec_ctx.set_ch_layout(
-                AVChannelLayout::from_nb_channels(dec_ctx.ch_layout.nb_channels).into_inner(),
-            );
-        }
+        // The decoder is opened, so describe a default layout locally.
+        let ch_layout = if dec_ctx.ch_layout.order == ffi::AV_CHANNEL_ORDER_UNSPEC {
+            AVChannelLayout::from_nb_channels(dec_ctx.ch_layout.nb_channels)
+        } else {
+            dec_ctx.ch_layout().clone()
+        };
 
         let args = format!(
             "time_base={}/{}:sample_rate={}:sample_fmt={}:channel_layout={}",
@@ -205,7 +251,7 @@ fn init_filter<'graph>(
             get_sample_fmt_name(dec_ctx.sample_fmt)
                 .unwrap()
                 .to_string_lossy(),
-            dec_ctx.ch_layout().describe().unwrap().to_string_lossy(),
+            ch_layout.describe().unwrap().to_string_lossy(),
         );
         let args = &CString::new(args).unwrap();
 
@@ -237,8 +283,8 @@ fn init_filter<'graph>(
     // Endpoints for the filter graph
     //
     // Yes the outputs' name is `in` -_-b
-    let outputs = AVFilterInOut::new(cstr!("in"), &mut buffersrc_ctx, 0);
-    let inputs = AVFilterInOut::new(cstr!("out"), &mut buffersink_ctx, 0);
+    let outputs = AVFilterInOut::new(cstr!("in"), &buffersrc_ctx, 0);
+    let inputs = AVFilterInOut::new(cstr!("out"), &buffersink_ctx, 0);
 
     let (_inputs, _outputs) = filter_graph.parse_ptr(filter_spec, Some(inputs), Some(outputs))?;
 
@@ -252,17 +298,21 @@ fn init_filter<'graph>(
 
 /// Create transcoding context corresponding to the given `stream_contexts`, the
 /// added filter contexts is mutable reference to objects stored in
-/// `filter_graphs`.
-fn init_filters(
-    filter_graphs: &mut [AVFilterGraph],
+/// `filter_graphs`. The encoders are opened with the filter outputs and the
+/// header of `ofmt_ctx` is written.
+fn init_filters<'graph>(
+    filter_graphs: &'graph mut [AVFilterGraph],
     stream_contexts: Vec<Option<StreamContext>>,
-) -> Result<Vec<Option<FilteringContext>>> {
-    let mut filter_ctx = Vec::with_capacity(stream_contexts.len());
+    ofmt_ctx: &mut AVFormatContextOutput,
+    filename: &CStr,
+    dict: &mut Option<AVDictionary>,
+) -> Result<Vec<Option<FilteringContext<'graph>>>> {
+    let mut opened = Vec::with_capacity(stream_contexts.len());
 
     for (filter_graph, stream_context) in filter_graphs.iter_mut().zip(stream_contexts.into_iter())
     {
         let Some(stream_context) = stream_context else {
-            filter_ctx.push(None);
+            opened.push(None);
             continue;
         };
 
@@ -270,6 +320,7 @@ fn init_filters(
             mut dec_ctx,
             mut enc_ctx,
             stream_index,
+            needs_conversion,
         } = stream_context;
 
         // dummy filter
@@ -281,18 +332,67 @@ fn init_filters(
 
         let FilterContext {
             buffersrc_ctx,
-            buffersink_ctx,
+            mut buffersink_ctx,
         } = init_filter(filter_graph, &mut dec_ctx, &mut enc_ctx, filter_spec)?;
 
-        filter_ctx.push(Some(FilteringContext {
-            enc_ctx,
-            dec_ctx,
-            stream_index,
-            buffersrc_ctx,
-            buffersink_ctx,
-        }));
+        open_encoder(
+            &mut enc_ctx,
+            &mut buffersink_ctx,
+            &mut ofmt_ctx.streams_mut()[stream_index],
+        )?;
+
+        opened.push(Some((
+            StreamContext {
+                dec_ctx,
+                enc_ctx,
+                stream_index,
+                needs_conversion,
+            },
+            FilterContext {
+                buffersrc_ctx,
+                buffersink_ctx,
+            },
+        )));
     }
 
+    ofmt_ctx.dump(0, filename)?;
+    ofmt_ctx
+        .write_header(dict)
+        .context("Error occurred when opening output file")?;
+
+    // The output stream time bases are final once the header is written.
+    let filter_ctx = opened
+        .into_iter()
+        .map(|opened| {
+            opened.map(|(stream_context, filter_context)| {
+                let StreamContext {
+                    dec_ctx,
+                    enc_ctx,
+                    stream_index,
+                    needs_conversion: _,
+                } = stream_context;
+                let FilterContext {
+                    buffersrc_ctx,
+                    buffersink_ctx,
+                } = filter_context;
+                let ts_mapper = TimestampMapper::new(
+                    dec_ctx.pkt_timebase,
+                    buffersink_ctx.get_time_base(),
+                    enc_ctx.time_base,
+                    ofmt_ctx.streams()[stream_index].time_base,
+                );
+                FilteringContext {
+                    enc_ctx,
+                    dec_ctx,
+                    stream_index,
+                    buffersrc_ctx,
+                    buffersink_ctx,
+                    ts_mapper,
+                }
+            })
+        })
+        .collect();
+
     Ok(filter_ctx)
 }
 
@@ -302,15 +402,10 @@ fn encode_write_frame(
     enc_ctx: &mut AVCodecContext,
     ofmt_ctx: &mut AVFormatContextOutput,
     stream_index: usize,
+    ts_mapper: &mut TimestampMapper,
 ) -> Result<()> {
     if let Some(filt_frame) = filt_frame.as_mut() {
-        if filt_frame.pts != ffi::AV_NOPTS_VALUE {
-            filt_frame.set_pts(av_rescale_q(
-                filt_frame.pts,
-                filt_frame.time_base,
-                enc_ctx.time_base,
-            ));
-        }
+        ts_mapper.frame_to_encoder(filt_frame);
     }
 
     enc_ctx
@@ -325,10 +420,7 @@ fn encode_write_frame(
         };
 
         enc_pkt.set_stream_index(stream_index as i32);
-        enc_pkt.rescale_ts(
-            enc_ctx.time_base,
-            ofmt_ctx.streams()[stream_index].time_base,
-        );
+        ts_mapper.packet_to_stream(&mut enc_pkt)?;
 
         ofmt_ctx
             .interleaved_write_frame(&mut enc_pkt)
@@ -346,6 +438,7 @@ fn filter_encode_write_frame(
     enc_ctx: &mut AVCodecContext,
     ofmt_ctx: &mut AVFormatContextOutput,
     stream_index: usize,
+    ts_mapper: &mut TimestampMapper,
 ) -> Result<()> {
     buffersrc_ctx
         .buffersrc_add_frame(frame, None)
@@ -357,10 +450,15 @@ fn filter_encode_write_frame(
             Err(_) => bail!("Get frame from buffer sink failed."),
         };
 
-        filtered_frame.set_time_base(buffersink_ctx.get_time_base());
         filtered_frame.set_pict_type(ffi::AV_PICTURE_TYPE_NONE);
 
-        encode_write_frame(Some(filtered_frame), enc_ctx, ofmt_ctx, stream_index)?;
+        encode_write_frame(
+            Some(filtered_frame),
+            enc_ctx,
+            ofmt_ctx,
+            stream_index,
+            ts_mapper,
+        )?;
     }
     Ok(())
 }
@@ -370,26 +468,39 @@ fn flush_encoder(
     enc_ctx: &mut AVCodecContext,
     ofmt_ctx: &mut AVFormatContextOutput,
     stream_index: usize,
+    ts_mapper: &mut TimestampMapper,
 ) -> Result<()> {
     if enc_ctx.codec().capabilities & ffi::AV_CODEC_CAP_DELAY as i32 == 0 {
         return Ok(());
     }
-    encode_write_frame(None, enc_ctx, ofmt_ctx, stream_index)?;
+    encode_write_frame(None, enc_ctx, ofmt_ctx, stream_index, ts_mapper)?;
     Ok(())
 }
 
 /// Transcoding audio and video stream in a multi media file.
+/// Returns whether each input stream needs a conversion stage before its
+/// encoder, `None` for the streams which are not transcoded.
 pub fn transcode(
     input_file: &CStr,
     output_file: &CStr,
     dict: &mut Option<AVDictionary>,
-) -> Result<()> {
+) -> Result<Vec<Option<bool>>> {
     let (dec_ctx, mut ifmt_ctx) = open_input_file(input_file)?;
-    let (stream_ctx, mut ofmt_ctx) = open_output_file(output_file, dec_ctx, dict)?;
+    let (stream_ctx, mut ofmt_ctx) = open_output_file(output_file, dec_ctx)?;
+    let needs_conversion = stream_ctx
+        .iter()
+        .map(|stream_ctx| stream_ctx.as_ref().map(|x| x.needs_conversion))
+        .collect();
     let mut filter_graphs: Vec<_> = (0..stream_ctx.len())
         .map(|_| AVFilterGraph::new())
         .collect();
-    let mut filter_ctx = init_filters(&mut filter_graphs, stream_ctx)?;
+    let mut filter_ctx = init_filters(
+        &mut filter_graphs,
+        stream_ctx,
+        &mut ofmt_ctx,
+        output_file,
+        dict,
+    )?;
 
     loop {
         let packet = match ifmt_ctx.read_packet() {
@@ -407,6 +518,7 @@ pub fn transcode(
             stream_index,
             buffersrc_ctx,
             buffersink_ctx,
+            ts_mapper,
         }) = filter_ctx[in_stream_index].as_mut()
         {
             decode_context.send_packet(Some(&packet)).unwrap();
@@ -420,7 +532,7 @@ pub fn transcode(
                     Err(e) => bail!(e),
                 };
 
-                frame.set_pts(frame.best_effort_timestamp);
+                ts_mapper.frame_from_decoder(&mut frame);
                 filter_encode_write_frame(
                     Some(frame),
                     buffersrc_ctx,
@@ -428,6 +540,7 @@ pub fn transcode(
                     encode_context,
                     &mut ofmt_ctx,
                     *stream_index,
+                    ts_mapper,
                 )?;
             }
         }
@@ -443,6 +556,7 @@ pub fn transcode(
                 stream_index,
                 buffersrc_ctx,
                 buffersink_ctx,
+                ts_mapper,
             }) => {
                 filter_encode_write_frame(
                     None,
@@ -451,15 +565,87 @@ pub fn transcode(
                     enc_ctx,
                     &mut ofmt_ctx,
                     *stream_index,
+                    ts_mapper,
                 )
                 .context("Flushing filter failed")?;
-                flush_encoder(enc_ctx, &mut ofmt_ctx, *stream_index)
+                flush_encoder(enc_ctx, &mut ofmt_ctx, *stream_index, ts_mapper)
                     .context("Flushing encoder failed")?;
             }
             None => (),
         }
     }
     ofmt_ctx.write_trailer()?;
+    Ok(needs_conversion)
+}
+
+/// Assert the video packets of `output_file` last one frame of the input
+/// frame rate, as the encoder time base is `1/framerate`.
+fn assert_video_durations(input_file: &CStr, output_file: &CStr) -> Result<()> {
+    let ifmt_ctx = AVFormatContextInput::open(input_file, None, &mut None)?;
+    let (video_index, _) = ifmt_ctx
+        .find_best_stream(ffi::AVMEDIA_TYPE_VIDEO)?
+        .context("Failed to find video stream")?;
+    let framerate = ifmt_ctx.streams()[video_index]
+        .guess_framerate()
+        .context("Failed to guess framerate")?;
+
+    let mut ofmt_ctx = AVFormatContextInput::open(output_file, None, &mut None)?;
+    let (video_index, _) = ofmt_ctx
+        .find_best_stream(ffi::AVMEDIA_TYPE_VIDEO)?
+        .context("Failed to find video stream")?;
+    let time_base = ofmt_ctx.streams()[video_index].time_base;
+    let expected = av_rescale_q(1, av_inv_q(framerate), time_base);
+    let mut durations = vec![];
+    while let Some(packet) = ofmt_ctx.read_packet()? {
+        if packet.stream_index as usize == video_index {
+            durations.push(packet.duration);
+        }
+    }
+    // The last packet may be cut short.
+    durations.pop();
+    assert!(!durations.is_empty());
+    assert!(durations.iter().all(|&duration| duration == expected));
+    Ok(())
+}
+
+/// Assert the dts of each stream of `output_file` increase, and its video
+/// stream lasts as long as the one of `input_file` within one frame.
+fn assert_timestamps(input_file: &CStr, output_file: &CStr) -> Result<()> {
+    let microseconds = ra(1, 1_000_000);
+    // Return the span of the video stream and the duration of one frame.
+    let video_span = |path: &CStr| -> Result<(i64, i64)> {
+        let mut fmt_ctx = AVFormatContextInput::open(path, None, &mut None)?;
+        let (video_index, _) = fmt_ctx
+            .find_best_stream(ffi::AVMEDIA_TYPE_VIDEO)?
+            .context("Failed to find video stream")?;
+        let stream = &fmt_ctx.streams()[video_index];
+        let time_base = stream.time_base;
+        let framerate = stream
+            .guess_framerate()
+            .context("Failed to guess framerate")?;
+        let mut last_dts = vec![None; fmt_ctx.nb_streams as usize];
+        let (mut start, mut end) = (i64::MAX, i64::MIN);
+        while let Some(packet) = fmt_ctx.read_packet()? {
+            let index = packet.stream_index as usize;
+            if packet.dts != ffi::AV_NOPTS_VALUE {
+                if let Some(last_dts) = last_dts[index] {
+                    assert!(packet.dts > last_dts);
+                }
+                last_dts[index] = Some(packet.dts);
+            }
+            if index == video_index && packet.pts != ffi::AV_NOPTS_VALUE {
+                start = start.min(packet.pts);
+                end = end.max(packet.pts + packet.duration);
+            }
+        }
+        Ok((
+            av_rescale_q(end - start, time_base, microseconds),
+            av_rescale_q(1, av_inv_q(framerate), microseconds),
+        ))
+    };
+    let (input_span, frame_duration) = video_span(input_file)?;
+    let (output_span, _) = video_span(output_file)?;
+    assert!((output_span - input_span).abs() <= frame_duration);
     Ok(())
 }
 
@@ -494,17 +680,29 @@ fn transcode_test2() {
         &mut None,
     )
     .unwrap();
+    assert_timestamps(
+        cstr!("tests/assets/vids/bear.mp4"),
+        cstr!("tests/output/transcode/bear.mp4"),
+    )
+    .unwrap();
+    assert_video_durations(
+        cstr!("tests/assets/vids/bear.mp4"),
+        cstr!("tests/output/transcode/bear.mp4"),
+    )
+    .unwrap();
 }
 
 #[test]
 fn transcode_test3() {
     std::fs::create_dir_all("tests/output/transcode/").unwrap();
-    transcode(
+    let needs_conversion = transcode(
         cstr!("tests/assets/vids/vp8.mp4"),
         cstr!("tests/output/transcode/vp8.webm"),
         &mut None,
     )
     .unwrap();
+    // The decoded yuv420p frames are taken by the vp8 encoder as is.
+    assert_eq!(needs_conversion, vec![Some(false)]);
 }
 
 #[test]
@@ -516,6 +714,11 @@ fn transcode_test4() {
         &mut None,
     )
     .unwrap();
+    assert_timestamps(
+        cstr!("tests/assets/vids/big_buck_bunny.mp4"),
+        cstr!("tests/output/transcode/big_buck_bunny.mp4"),
+    )
+    .unwrap();
 }
 
 #[test]
@@ -29,10 +29,10 @@ fn open_input_file(input_file: &CStr) -> Result<(AVFormatContextInput, AVCodecCo
     let stream = &input_format_context.streams()[audio_index];
     let mut decode_context = AVCodecContext::new(&decoder);
     decode_context.apply_codecpar(&stream.codecpar())?;
+    decode_context.try_set_pkt_timebase(stream.time_base)?;
     decode_context
         .open(None)
         .context("Could not open input codec")?;
-    decode_context.set_pkt_timebase(stream.time_base);
     Ok((input_format_context, decode_context, audio_index))
 }
 
@@ -52,10 +52,10 @@ fn open_output_file(
 
     // Set the basic encoder parameters.
     // The input file's sample rate is used to avoid a sample rate conversion.
-    encode_context.set_ch_layout(AVChannelLayout::from_nb_channels(OUTPUT_CHANNELS).into_inner());
-    encode_context.set_sample_rate(decode_context.sample_rate);
-    encode_context.set_sample_fmt(encode_codec.sample_fmts().unwrap()[0]);
-    encode_context.set_bit_rate(OUTPUT_BIT_RATE);
+    encode_context.try_set_ch_layout(AVChannelLayout::from_nb_channels(OUTPUT_CHANNELS))?;
+    encode_context.try_set_sample_rate(decode_context.sample_rate)?;
+    encode_context.try_set_sample_fmt(encode_codec.sample_fmts().unwrap()[0])?;
+    encode_context.try_set_bit_rate(OUTPUT_BIT_RATE)?;
 
     // Open the encoder for the audio stream to use it later.
     encode_context.open(None)?;
@@ -95,8 +95,7 @@ fn add_samples_to_fifo(
     samples_buffer: &AVSamples,
     frame_size: i32,
 ) -> Result<()> {
-    fifo.realloc(fifo.size() + frame_size);
-    unsafe { fifo.write(samples_buffer.audio_data.as_ptr(), frame_size) }
+    fifo.write_samples(samples_buffer, frame_size)
         .context("Could not write data to FIFO")?;
     Ok(())
 }
@@ -161,7 +160,7 @@ fn load_encode_and_write(
         encode_context.sample_fmt,
         encode_context.sample_rate,
     )?;
-    if unsafe { fifo.read(frame.data_mut().as_mut_ptr(), frame_size)? } < frame_size {
+    if fifo.read_frame(&mut frame)? < frame_size {
         bail!("Could not read data from FIFO");
     }
     encode_audio_frame(Some(frame), output_format_context, encode_context)?;
@@ -240,7 +239,7 @@ fn transcode_aac(input_file: &CStr, output_file: &CStr) -> Result<()> {
 
                 unsafe {
                     resample_context
-                        .convert(
+                        .convert_raw(
                             output_samples.audio_data.as_mut_ptr(),
                             output_samples.nb_samples,
                             frame.extended_data as *const _,
@@ -85,12 +85,11 @@ fn hw_encode(
 
     let mut avctx = AVCodecContext::new(&codec);
 
-    avctx.set_width(width);
-    avctx.set_height(height);
-    avctx.set_time_base(ra(1, 25));
-    avctx.set_framerate(ra(25, 1));
-    avctx.set_sample_aspect_ratio(ra(1, 1));
-    avctx.set_pix_fmt(hw_format);
+    avctx.try_set_width(width)?;
+    avctx.try_set_height(height)?;
+    avctx.set_time_base_from_framerate(ra(25, 1))?;
+    avctx.try_set_sample_aspect_ratio(ra(1, 1))?;
+    avctx.try_set_pix_fmt(hw_format)?;
 
     set_hwframe_ctx(
         &mut avctx,
@@ -115,13 +114,13 @@ fn hw_encode(
         sw_frame.set_format(sw_format);
         sw_frame.get_buffer(0).context("Get buffer failed.")?;
 
-        let y = unsafe { slice::from_raw_parts_mut(sw_frame.data_mut()[0], size) };
+        let y = &mut sw_frame.data_mut(0).unwrap()[..size];
         match fin.read_exact(y) {
             Ok(()) => {}
             Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
             e @ Err(_) => e.context("Read Y failed.")?,
         }
-        let uv = unsafe { slice::from_raw_parts_mut(sw_frame.data_mut()[1], size / 2) };
+        let uv = &mut sw_frame.data_mut(1).unwrap()[..size / 2];
         match fin.read_exact(uv) {
             Ok(()) => {}
             Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
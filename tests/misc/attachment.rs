@@ -0,0 +1,94 @@
+//! Embed a file into Matroska with `AVFormatContextOutput::add_attachment` and
+//! extract it with `AVFormatContextInput::attachments`.
+use anyhow::{Context, Result};
+use cstr::cstr;
+use rsmpeg::{
+    avformat::{AVFormatContextInput, AVFormatContextOutput},
+    error::RsmpegError,
+    ffi, UnsafeDerefMut,
+};
+use std::{ffi::CStr, fs};
+
+/// Remux the video stream of `input_path` into `output_path` with `data`
+/// attached.
+fn remux_with_attachment(input_path: &CStr, output_path: &CStr, data: &[u8]) -> Result<()> {
+    let mut ifmt_ctx = AVFormatContextInput::open(input_path, None, &mut None)?;
+    let (video_index, _) = ifmt_ctx
+        .find_best_stream(ffi::AVMEDIA_TYPE_VIDEO)?
+        .context("Failed to find video stream")?;
+
+    let mut ofmt_ctx = AVFormatContextOutput::create(output_path, None)?;
+    {
+        let in_stream = &ifmt_ctx.streams()[video_index];
+        let mut out_stream = ofmt_ctx.new_stream();
+        let mut codecpar = in_stream.codecpar().clone();
+        // Let the muxer pick the tag.
+        unsafe { codecpar.deref_mut() }.codec_tag = 0;
+        out_stream.set_codecpar(codecpar);
+        out_stream.set_time_base(in_stream.time_base);
+    }
+    ofmt_ctx.add_attachment(cstr!("blob.bin"), cstr!("application/octet-stream"), data)?;
+    ofmt_ctx.write_header(&mut None)?;
+
+    while let Some(mut packet) = ifmt_ctx.read_packet()? {
+        if packet.stream_index as usize != video_index {
+            continue;
+        }
+        packet.rescale_ts(
+            ifmt_ctx.streams()[video_index].time_base,
+            ofmt_ctx.streams()[0].time_base,
+        );
+        packet.set_stream_index(0);
+        packet.set_pos(-1);
+        ofmt_ctx.interleaved_write_frame(&mut packet)?;
+    }
+    ofmt_ctx.write_trailer()?;
+    Ok(())
+}
+
+#[test]
+fn test_attachment0() {
+    fs::create_dir_all("tests/output/attachment").unwrap();
+    let output_path = cstr!("tests/output/attachment/bear.mkv");
+    // Every byte value, including zeros.
+    let data: Vec<u8> = (0..=255).cycle().take(1000).collect();
+    remux_with_attachment(cstr!("tests/assets/vids/bear.mp4"), output_path, &data).unwrap();
+
+    let ifmt_ctx = AVFormatContextInput::open(output_path, None, &mut None).unwrap();
+    let attachments = ifmt_ctx.attachments();
+    assert_eq!(attachments.len(), 1);
+    let attachment = attachments[0];
+    assert_eq!(attachment.filename, Some(cstr!("blob.bin")));
+    assert_eq!(attachment.mimetype, Some(cstr!("application/octet-stream")));
+    assert_eq!(attachment.data, &data[..]);
+}
+
+#[test]
+fn test_attachment1() {
+    let ifmt_ctx =
+        AVFormatContextInput::open(cstr!("tests/assets/vids/bear.mp4"), None, &mut None).unwrap();
+    assert!(ifmt_ctx.attachments().is_empty());
+}
+
+#[test]
+fn test_attachment2() {
+    fs::create_dir_all("tests/output/attachment").unwrap();
+    let mut ofmt_ctx =
+        AVFormatContextOutput::create(cstr!("tests/output/attachment/late.mkv"), None).unwrap();
+    let ifmt_ctx =
+        AVFormatContextInput::open(cstr!("tests/assets/vids/bear.mp4"), None, &mut None).unwrap();
+    ofmt_ctx
+        .new_stream_copy_from(&ifmt_ctx.streams()[0], false)
+        .unwrap();
+    ofmt_ctx.write_header(&mut None).unwrap();
+    assert_eq!(
+        ofmt_ctx.add_attachment(
+            cstr!("late.bin"),
+            cstr!("application/octet-stream"),
+            &[0; 4]
+        ),
+        Err(RsmpegError::HeaderAlreadyWrittenError)
+    );
+    assert_eq!(ofmt_ctx.nb_streams, 1);
+    ofmt_ctx.write_trailer().unwrap();
+}
@@ -55,7 +55,7 @@ fn av_spliter(file_path: &CStr, out_video: &str, out_audio: &CStr) -> Result<()>
         if packet_stream_index == video_index {
             bsf_context.send_packet(Some(&mut packet))?;
             loop {
-                match bsf_context.receive_packet(&mut packet) {
+                match bsf_context.receive_packet_into(&mut packet) {
                     Ok(()) => {
                         let data = unsafe {
                             std::slice::from_raw_parts(packet.data, packet.size as usize)
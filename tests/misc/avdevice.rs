@@ -0,0 +1,68 @@
+//! Enumerate the capture devices and open one, requires the `avdevice`
+//! feature. Machines without devices, e.g. CI, only have the virtual ones.
+use cstr::cstr;
+use rsmpeg::{
+    avdevice::{self, DeviceInfo},
+    avformat::{AVFormatContextInput, AVInputFormat},
+    error::RsmpegError,
+};
+
+#[test]
+fn avdevice_list_test() {
+    avdevice::register_all();
+    let formats: Vec<_> = avdevice::input_video_devices()
+        .chain(avdevice::input_audio_devices())
+        .collect();
+    for format in formats {
+        match avdevice::list_input_sources(&format, None) {
+            Ok(devices) => {
+                assert!(devices.iter().filter(|x| x.is_default).count() <= 1);
+                for DeviceInfo { name, .. } in devices {
+                    assert!(!name.is_empty());
+                }
+            }
+            // Devices that can't enumerate, or failing to, e.g. without a
+            // display or sound server.
+            Err(RsmpegError::DeviceListUnsupportedError) | Err(RsmpegError::AVError(_)) => {}
+            Err(e) => panic!("{:?}: {}", format.name(), e),
+        }
+    }
+
+    // Demuxers aren't devices.
+    let mp4 = AVInputFormat::find(cstr!("mp4")).unwrap();
+    assert!(matches!(
+        avdevice::list_input_sources(&mp4, None),
+        Err(RsmpegError::AVError(_))
+    ));
+}
+
+#[test]
+fn avdevice_open_test() {
+    avdevice::register_all();
+    // A virtual device, available wherever libavfilter is.
+    let Some(lavfi) = AVInputFormat::find(cstr!("lavfi")) else {
+        eprintln!("No lavfi device, skipped.");
+        return;
+    };
+    assert!(avdevice::input_video_devices().any(|x| x.name() == lavfi.name()));
+    assert!(matches!(
+        avdevice::list_input_sources(&lavfi, None),
+        Err(RsmpegError::DeviceListUnsupportedError)
+    ));
+
+    let mut input = AVFormatContextInput::open(
+        cstr!("testsrc=size=64x48:rate=25:duration=1"),
+        Some(&*lavfi),
+        &mut None,
+    )
+    .unwrap();
+    assert_eq!(input.iformat().name(), cstr!("lavfi"));
+    let codecpar = input.streams()[0].codecpar();
+    assert_eq!((codecpar.width, codecpar.height), (64, 48));
+    drop(codecpar);
+    let mut nb_packets = 0;
+    while input.read_packet().unwrap().is_some() {
+        nb_packets += 1;
+    }
+    assert_eq!(nb_packets, 25);
+}
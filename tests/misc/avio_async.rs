@@ -0,0 +1,91 @@
+//! Demux and decode a file read through `tokio::fs::File` with
+//! `AVIOContextAsync`, requires the `async-io` feature.
+use anyhow::{Context, Result};
+use cstr::cstr;
+use rsmpeg::{
+    avcodec::AVCodecContext,
+    avformat::{
+        AVFormatContextInput, AVFormatContextOutput, AVIOContextAsync, AVIOContextContainer,
+    },
+    error::RsmpegError,
+    ffi,
+};
+use tokio::runtime::Handle;
+
+/// Return the number of decoded video frames.
+fn decode_all(io_context: AVIOContextAsync) -> Result<usize> {
+    let mut ifmt_ctx =
+        AVFormatContextInput::from_io_context(AVIOContextContainer::Async(io_context))?;
+    let (video_index, decoder) = ifmt_ctx
+        .find_best_stream(ffi::AVMEDIA_TYPE_VIDEO)?
+        .context("Failed to find video stream")?;
+    let mut dec_ctx = AVCodecContext::new(&decoder);
+    dec_ctx.apply_codecpar(&ifmt_ctx.streams()[video_index].codecpar())?;
+    dec_ctx.open(None)?;
+
+    let mut nb_frames = 0;
+    loop {
+        let packet = ifmt_ctx.read_packet()?;
+        if let Some(packet) = &packet {
+            if packet.stream_index as usize != video_index {
+                continue;
+            }
+        }
+        dec_ctx.send_packet(packet.as_ref())?;
+        loop {
+            match dec_ctx.receive_frame() {
+                Ok(_) => nb_frames += 1,
+                Err(RsmpegError::DecoderDrainError) => break,
+                Err(RsmpegError::DecoderFlushedError) => return Ok(nb_frames),
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_avio_async0() {
+    let file = tokio::fs::File::open("tests/assets/vids/bear.mp4")
+        .await
+        .unwrap();
+    let io_context = AVIOContextAsync::from_async_read(file, &Handle::current());
+    // FFmpeg calls block, keep them off the runtime threads.
+    let nb_frames = tokio::task::spawn_blocking(move || decode_all(io_context))
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(nb_frames > 0);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_avio_async1() {
+    // Dropping the context before the reader is exhausted doesn't hang.
+    let file = tokio::fs::File::open("tests/assets/vids/bear.mp4")
+        .await
+        .unwrap();
+    let io_context = AVIOContextAsync::from_async_read(file, &Handle::current());
+    tokio::task::spawn_blocking(move || {
+        let mut ifmt_ctx =
+            AVFormatContextInput::from_io_context(AVIOContextContainer::Async(io_context)).unwrap();
+        ifmt_ctx.read_packet().unwrap().unwrap();
+    })
+    .await
+    .unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_avio_async2() {
+    // A read-only context would silently drop the muxed data.
+    let file = tokio::fs::File::open("tests/assets/vids/bear.mp4")
+        .await
+        .unwrap();
+    let io_context = AVIOContextAsync::from_async_read(file, &Handle::current());
+    let result = AVFormatContextOutput::create(
+        cstr!("bear.mkv"),
+        Some(AVIOContextContainer::Async(io_context)),
+    );
+    assert!(matches!(
+        result,
+        Err(RsmpegError::AVError(x)) if x == ffi::AVERROR(ffi::EINVAL)
+    ));
+}
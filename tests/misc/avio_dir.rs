@@ -0,0 +1,67 @@
+//! List a local directory through the `file` protocol.
+use cstr::cstr;
+use rsmpeg::{
+    avformat::{AVIOContextURL, AVIODirIterator, DirEntryType},
+    error::RsmpegError,
+    ffi,
+};
+use std::{
+    fs,
+    time::{Duration, UNIX_EPOCH},
+};
+
+#[test]
+fn test_avio_dir() {
+    let entries = AVIODirIterator::open(cstr!("file:tests/assets/vids"), &mut None)
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    let centaur = entries
+        .iter()
+        .find(|entry| entry.name() == cstr!("centaur.mpg"))
+        .unwrap();
+    let metadata = fs::metadata("tests/assets/vids/centaur.mpg").unwrap();
+    assert_eq!(centaur.entry_type(), DirEntryType::File);
+    assert_eq!(centaur.size(), Some(metadata.len()));
+    let modified =
+        UNIX_EPOCH + Duration::from_micros(centaur.modification_time_us().unwrap() as u64);
+    // Truncated to seconds by some file systems.
+    let delta = metadata
+        .modified()
+        .unwrap()
+        .duration_since(modified)
+        .unwrap();
+    assert!(delta < Duration::from_secs(1));
+
+    let entries = AVIODirIterator::open(cstr!("tests/assets"), &mut None)
+        .unwrap()
+        .map(Result::unwrap)
+        .collect::<Vec<_>>();
+    let vids = entries
+        .iter()
+        .find(|entry| entry.name() == cstr!("vids"))
+        .unwrap();
+    assert_eq!(vids.entry_type(), DirEntryType::Directory);
+
+    assert!(matches!(
+        AVIODirIterator::open(cstr!("pipe:0"), &mut None),
+        Err(RsmpegError::DirListUnsupportedError)
+    ));
+    assert!(AVIODirIterator::open(cstr!("tests/assets/nonexistent"), &mut None).is_err());
+}
+
+#[test]
+fn test_avio_context_url() {
+    fs::create_dir_all("tests/output/avio_dir/").unwrap();
+    let mut io_context = AVIOContextURL::open(
+        cstr!("tests/output/avio_dir/hello.txt"),
+        ffi::AVIO_FLAG_WRITE,
+    )
+    .unwrap();
+    unsafe { ffi::avio_write(io_context.as_mut_ptr(), b"hello".as_ptr(), 5) };
+    io_context.close().unwrap();
+    assert_eq!(
+        fs::read("tests/output/avio_dir/hello.txt").unwrap(),
+        b"hello"
+    );
+}
@@ -0,0 +1,148 @@
+//! Open an MP4 with the `moov` atom at the end from memory with
+//! `AVIOContextCustom::from_read_seek`, write one with
+//! `AVIOContextCustom::from_write_seek`.
+use anyhow::Result;
+use cstr::cstr;
+use rsmpeg::{
+    avcodec::AVCodecContext,
+    avformat::{
+        AVFormatContextInput, AVFormatContextOutput, AVIOContextContainer, AVIOContextCustom,
+    },
+    avutil::AVMem,
+    error::RsmpegError,
+    ffi, UnsafeDerefMut,
+};
+use std::{ffi::CStr, fs, io::Cursor};
+
+use super::utils::copy_packets;
+
+/// Remux `input_path` into `output_path`, the mp4 muxer writes the `moov` atom
+/// after the media data unless `faststart` is set.
+fn remux(input_path: &CStr, output_path: &CStr) -> Result<()> {
+    let mut ifmt_ctx = AVFormatContextInput::open(input_path, None, &mut None)?;
+    let mut ofmt_ctx = AVFormatContextOutput::create(output_path, None)?;
+    for in_stream in ifmt_ctx.streams() {
+        let mut codecpar = in_stream.codecpar().clone();
+        unsafe { codecpar.deref_mut() }.codec_tag = 0;
+        let mut out_stream = ofmt_ctx.new_stream();
+        out_stream.set_codecpar(codecpar);
+        out_stream.set_time_base(in_stream.time_base);
+    }
+    ofmt_ctx.write_header(&mut None)?;
+    copy_packets(&mut ifmt_ctx, &mut ofmt_ctx)?;
+    ofmt_ctx.write_trailer()?;
+    Ok(())
+}
+
+#[test]
+fn test_avio_read_seek0() {
+    fs::create_dir_all("tests/output/avio_read_seek").unwrap();
+    let input_path = cstr!("tests/assets/vids/bear.mp4");
+    let output_path = cstr!("tests/output/avio_read_seek/bear.mp4");
+    remux(input_path, output_path).unwrap();
+
+    let data = fs::read(output_path.to_str().unwrap()).unwrap();
+    let position = |atom: &[u8]| data.windows(4).position(|x| x == atom).unwrap();
+    assert!(position(b"moov") > position(b"mdat"));
+    let len = data.len();
+
+    let io_context = AVIOContextCustom::from_read_seek(Cursor::new(data));
+    let mut ifmt_ctx =
+        AVFormatContextInput::from_io_context(AVIOContextContainer::Custom(io_context)).unwrap();
+    let expected = AVFormatContextInput::open(input_path, None, &mut None)
+        .unwrap()
+        .duration;
+    assert!(ifmt_ctx.duration > 0);
+    assert!((ifmt_ctx.duration - expected).abs() < ffi::AV_TIME_BASE as i64 / 10);
+    // `AVSEEK_SIZE` is answered.
+    assert_eq!(unsafe { ffi::avio_size(ifmt_ctx.pb) }, len as i64);
+    assert!(ifmt_ctx.read_packet().unwrap().is_some());
+}
+
+/// Decode the video stream of `ifmt_ctx`, return the number of frames.
+fn count_frames(mut ifmt_ctx: AVFormatContextInput) -> usize {
+    let (video_index, decoder) = ifmt_ctx
+        .find_best_stream(ffi::AVMEDIA_TYPE_VIDEO)
+        .unwrap()
+        .unwrap();
+    let mut dec_ctx = AVCodecContext::new(&decoder);
+    dec_ctx
+        .apply_codecpar(&ifmt_ctx.streams()[video_index].codecpar())
+        .unwrap();
+    dec_ctx.open(None).unwrap();
+    let mut nb_frames = 0;
+    loop {
+        let packet = ifmt_ctx.read_packet().unwrap();
+        if let Some(packet) = &packet {
+            if packet.stream_index as usize != video_index {
+                continue;
+            }
+        }
+        dec_ctx.send_packet(packet.as_ref()).unwrap();
+        loop {
+            match dec_ctx.receive_frame() {
+                Ok(_) => nb_frames += 1,
+                Err(RsmpegError::DecoderDrainError) => break,
+                Err(RsmpegError::DecoderFlushedError) => return nb_frames,
+                Err(e) => panic!("{}", e),
+            }
+        }
+    }
+}
+
+#[test]
+fn test_avio_read_seek1() {
+    let path = cstr!("tests/assets/vids/centaur.mpg");
+    let expected = count_frames(AVFormatContextInput::open(path, None, &mut None).unwrap());
+
+    let data = fs::read(path.to_str().unwrap()).unwrap();
+    let io_context = AVIOContextCustom::from_read_seek(Cursor::new(data));
+    let ifmt_ctx =
+        AVFormatContextInput::from_io_context(AVIOContextContainer::Custom(io_context)).unwrap();
+    assert!(expected > 0);
+    assert_eq!(count_frames(ifmt_ctx), expected);
+}
+
+#[test]
+fn test_avio_write_seek0() {
+    // The mp4 muxer seeks back to patch the `mdat` size.
+    fs::create_dir_all("tests/output/avio_read_seek").unwrap();
+    let input_path = cstr!("tests/assets/vids/bear.mp4");
+    let output_path = cstr!("tests/output/avio_read_seek/bear_write_seek.mp4");
+    let mut ifmt_ctx = AVFormatContextInput::open(input_path, None, &mut None).unwrap();
+    let file = fs::File::create(output_path.to_str().unwrap()).unwrap();
+    let io_context = AVIOContextCustom::from_write_seek(file);
+    let mut ofmt_ctx =
+        AVFormatContextOutput::create(output_path, Some(AVIOContextContainer::Custom(io_context)))
+            .unwrap();
+    for in_stream in ifmt_ctx.streams() {
+        ofmt_ctx.new_stream_copy_from(in_stream, true).unwrap();
+    }
+    ofmt_ctx.write_header(&mut None).unwrap();
+    copy_packets(&mut ifmt_ctx, &mut ofmt_ctx).unwrap();
+    ofmt_ctx.write_trailer().unwrap();
+    drop(ofmt_ctx);
+
+    let expected = count_frames(AVFormatContextInput::open(input_path, None, &mut None).unwrap());
+    let ifmt_ctx = AVFormatContextInput::open(output_path, None, &mut None).unwrap();
+    assert_eq!(count_frames(ifmt_ctx), expected);
+}
+
+#[test]
+fn test_avio_callback_panic0() {
+    // A panicking callback fails the FFmpeg call instead of unwinding through
+    // it.
+    let io_context = AVIOContextCustom::alloc_context(
+        AVMem::new(4096),
+        false,
+        vec![],
+        Some(Box::new(|_: &mut Vec<u8>, _: &mut [u8]| -> i32 {
+            panic!("read failed")
+        })),
+        None,
+        None,
+    );
+    assert!(
+        AVFormatContextInput::from_io_context(AVIOContextContainer::Custom(io_context)).is_err()
+    );
+}
@@ -35,7 +35,7 @@ fn open_input_file(filename: &CStr) -> Result<(usize, AVFormatContextInput, AVCo
         let mut decode_context = AVCodecContext::new(&decoder);
         decode_context.apply_codecpar(&input_stream.codecpar())?;
         if let Some(framerate) = input_stream.guess_framerate() {
-            decode_context.set_framerate(framerate);
+            decode_context.set_framerate(framerate)?;
         }
         decode_context.open(None)?;
         decode_context
@@ -90,25 +90,26 @@ fn open_output_file(
         .with_context(|| anyhow!("encoder({}) not found.", AVCodecID_AV_CODEC_ID_H264))?;
 
     let mut encode_context = AVCodecContext::new(&encoder);
-    encode_context.set_height(decode_context.height);
-    encode_context.set_width(decode_context.width);
-    encode_context.set_sample_aspect_ratio(decode_context.sample_aspect_ratio);
-    encode_context.set_pix_fmt(if let Some(pix_fmts) = encoder.pix_fmts() {
+    encode_context.try_set_height(decode_context.height)?;
+    encode_context.try_set_width(decode_context.width)?;
+    encode_context.try_set_sample_aspect_ratio(decode_context.sample_aspect_ratio)?;
+    encode_context.try_set_pix_fmt(if let Some(pix_fmts) = encoder.pix_fmts() {
         pix_fmts[0]
     } else {
         decode_context.pix_fmt
-    });
+    })?;
     encode_context.set_time_base(av_inv_q(av_mul_q(
         decode_context.framerate,
         AVRational {
             num: decode_context.ticks_per_frame,
             den: 1,
         },
-    )));
+    )))?;
 
     // Some formats want stream headers to be separate.
-    if output_format_context.oformat().flags & ffi::AVFMT_GLOBALHEADER as i32 != 0 {
-        encode_context.set_flags(encode_context.flags | ffi::AV_CODEC_FLAG_GLOBAL_HEADER as i32);
+    if output_format_context.oformat().flags().is_globalheader() {
+        encode_context
+            .try_set_flags(encode_context.flags | ffi::AV_CODEC_FLAG_GLOBAL_HEADER as i32)?;
     }
 
     encode_context.open(None)?;
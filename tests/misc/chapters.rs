@@ -0,0 +1,91 @@
+//! Write chapters with `AVFormatContextOutput::add_chapter`, read chapters and
+//! programs back.
+use anyhow::Result;
+use cstr::cstr;
+use rsmpeg::{
+    avformat::{AVFormatContextInput, AVFormatContextOutput},
+    avutil::{av_rescale_q, ra},
+    error::RsmpegError,
+};
+use std::{ffi::CStr, fs};
+
+use super::utils::copy_packets;
+
+/// Remux all the streams of `input_path` into `output_path`, with chapters of
+/// `(start, end, title)` in milliseconds.
+fn remux(input_path: &CStr, output_path: &CStr, chapters: &[(i64, i64, &CStr)]) -> Result<()> {
+    let mut ifmt_ctx = AVFormatContextInput::open(input_path, None, &mut None)?;
+    let mut ofmt_ctx = AVFormatContextOutput::create(output_path, None)?;
+    for in_stream in ifmt_ctx.streams() {
+        ofmt_ctx.new_stream_copy_from(in_stream, false)?;
+    }
+    for (id, &(start, end, title)) in chapters.iter().enumerate() {
+        ofmt_ctx.add_chapter(id as i64, ra(1, 1000), start, end, Some(title))?;
+    }
+    ofmt_ctx.write_header(&mut None)?;
+    copy_packets(&mut ifmt_ctx, &mut ofmt_ctx)?;
+    ofmt_ctx.write_trailer()?;
+    Ok(())
+}
+
+#[test]
+fn test_chapters0() {
+    fs::create_dir_all("tests/output/chapters/").unwrap();
+    let output_path = cstr!("tests/output/chapters/bear.mkv");
+    let chapters = [(0, 400, cstr!("Intro")), (400, 800, cstr!("Bear"))];
+    remux(cstr!("tests/assets/vids/bear.mp4"), output_path, &chapters).unwrap();
+
+    let input = AVFormatContextInput::open(output_path, None, &mut None).unwrap();
+    assert_eq!(input.chapters().len(), 2);
+    for (chapter, (start, end, title)) in input.chapters().iter().zip(chapters) {
+        assert_eq!(chapter.title(), Some(title));
+        assert_eq!(
+            chapter
+                .metadata()
+                .unwrap()
+                .get(cstr!("title"), None, 0)
+                .unwrap()
+                .value(),
+            title
+        );
+        let ms = |ts| av_rescale_q(ts, chapter.time_base(), ra(1, 1000));
+        assert_eq!((ms(chapter.start()), ms(chapter.end())), (start, end));
+    }
+    assert_ne!(input.chapters()[0].id(), input.chapters()[1].id());
+    // Matroska has no programs.
+    assert!(input.programs().is_empty());
+}
+
+#[test]
+fn test_chapters1() {
+    fs::create_dir_all("tests/output/chapters/").unwrap();
+    let output_path = cstr!("tests/output/chapters/bear.ts");
+    remux(cstr!("tests/assets/vids/bear.mp4"), output_path, &[]).unwrap();
+
+    let input = AVFormatContextInput::open(output_path, None, &mut None).unwrap();
+    assert!(input.chapters().is_empty());
+    // A single service, with both the video and the audio.
+    assert_eq!(input.programs().len(), 1);
+    let program = &input.programs()[0];
+    let mut stream_indexes = program.stream_indexes().to_vec();
+    stream_indexes.sort();
+    assert_eq!(stream_indexes, [0, 1]);
+}
+
+#[test]
+fn test_chapters2() {
+    fs::create_dir_all("tests/output/chapters/").unwrap();
+    let mut ofmt_ctx =
+        AVFormatContextOutput::create(cstr!("tests/output/chapters/late.mkv"), None).unwrap();
+    let ifmt_ctx =
+        AVFormatContextInput::open(cstr!("tests/assets/vids/bear.mp4"), None, &mut None).unwrap();
+    ofmt_ctx
+        .new_stream_copy_from(&ifmt_ctx.streams()[0], false)
+        .unwrap();
+    ofmt_ctx.write_header(&mut None).unwrap();
+    assert_eq!(
+        ofmt_ctx.add_chapter(0, ra(1, 1000), 0, 1000, None),
+        Err(RsmpegError::HeaderAlreadyWrittenError)
+    );
+    ofmt_ctx.write_trailer().unwrap();
+}
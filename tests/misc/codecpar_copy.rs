@@ -0,0 +1,76 @@
+//! Remux with owned `AVCodecParameters`, copied from the input streams or
+//! filled from a codec context.
+use anyhow::Result;
+use cstr::cstr;
+use rsmpeg::{
+    avcodec::{AVCodecContext, AVCodecParameters},
+    avformat::{AVFormatContextInput, AVFormatContextOutput},
+    ffi, UnsafeDerefMut,
+};
+use std::{ffi::CStr, fs};
+
+use super::utils::copy_packets;
+
+/// Remux all the streams of `input_path` into `output_path`, copying the
+/// codec parameters of each stream.
+fn remux(input_path: &CStr, output_path: &CStr) -> Result<()> {
+    let mut ifmt_ctx = AVFormatContextInput::open(input_path, None, &mut None)?;
+    let mut ofmt_ctx = AVFormatContextOutput::create(output_path, None)?;
+    for in_stream in ifmt_ctx.streams() {
+        let mut parameters = AVCodecParameters::new();
+        parameters.copy_from(&in_stream.codecpar());
+        // Tags of MP4 aren't valid in Matroska.
+        unsafe { parameters.deref_mut() }.codec_tag = 0;
+        let mut out_stream = ofmt_ctx.new_stream();
+        out_stream.set_codecpar(parameters);
+        out_stream.set_time_base(in_stream.time_base);
+    }
+    ofmt_ctx.write_header(&mut None)?;
+    copy_packets(&mut ifmt_ctx, &mut ofmt_ctx)?;
+    ofmt_ctx.write_trailer()?;
+    Ok(())
+}
+
+#[test]
+fn test_codecpar_copy0() {
+    fs::create_dir_all("tests/output/codecpar_copy/").unwrap();
+    let input_path = cstr!("tests/assets/vids/bear.mp4");
+    let output_path = cstr!("tests/output/codecpar_copy/bear.mkv");
+    remux(input_path, output_path).unwrap();
+
+    let input = AVFormatContextInput::open(input_path, None, &mut None).unwrap();
+    let output = AVFormatContextInput::open(output_path, None, &mut None).unwrap();
+    assert_eq!(output.streams().len(), input.streams().len());
+    for (in_stream, out_stream) in input.streams().iter().zip(output.streams()) {
+        let (a, b) = (in_stream.codecpar(), out_stream.codecpar());
+        assert_eq!(b.codec_type, a.codec_type);
+        assert_eq!(b.codec_id(), a.codec_id());
+        assert_eq!((b.width(), b.height()), (a.width(), a.height()));
+        assert_eq!(b.sample_rate(), a.sample_rate());
+        assert_eq!(b.ch_layout().nb_channels, a.ch_layout().nb_channels);
+        assert_eq!(b.extradata(), a.extradata());
+    }
+}
+
+#[test]
+fn test_codecpar_copy1() {
+    let input =
+        AVFormatContextInput::open(cstr!("tests/assets/vids/bear.mp4"), None, &mut None).unwrap();
+    let (video_index, decoder) = input
+        .find_best_stream(ffi::AVMEDIA_TYPE_VIDEO)
+        .unwrap()
+        .unwrap();
+    let codecpar = input.streams()[video_index].codecpar();
+    let mut dec_ctx = AVCodecContext::new(&decoder);
+    dec_ctx.apply_codecpar(&codecpar).unwrap();
+
+    let parameters = AVCodecParameters::from_codec_context(&dec_ctx);
+    assert_eq!(parameters.codec_id(), codecpar.codec_id());
+    assert!(parameters.codec_type().is_video());
+    assert_eq!(
+        (parameters.width(), parameters.height()),
+        (codecpar.width(), codecpar.height())
+    );
+    assert_eq!(parameters.extradata(), codecpar.extradata());
+    assert_eq!(parameters.sample_rate(), 0);
+}
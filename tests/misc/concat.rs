@@ -0,0 +1,45 @@
+//! Join files with `rsmpeg::helpers::open_concat` and check the timestamps
+//! continue across file boundaries.
+use anyhow::Result;
+use rsmpeg::{avformat::AVFormatContextInput, avutil::av_q2d, helpers::open_concat};
+use std::path::Path;
+
+/// Read all the packets, return the end time in seconds of the longest stream
+/// and assert the dts of each stream is monotonic.
+fn read_all(mut ifmt_ctx: AVFormatContextInput) -> Result<f64> {
+    let nb_streams = ifmt_ctx.nb_streams as usize;
+    let mut last_dts = vec![i64::MIN; nb_streams];
+    let mut end_time = 0f64;
+    while let Some(packet) = ifmt_ctx.read_packet()? {
+        let index = packet.stream_index as usize;
+        let time_base = ifmt_ctx.streams()[index].time_base;
+        if packet.dts != rsmpeg::ffi::AV_NOPTS_VALUE {
+            assert!(packet.dts >= last_dts[index]);
+            last_dts[index] = packet.dts;
+        }
+        if packet.pts != rsmpeg::ffi::AV_NOPTS_VALUE {
+            end_time = end_time.max((packet.pts + packet.duration) as f64 * av_q2d(time_base));
+        }
+    }
+    Ok(end_time)
+}
+
+#[test]
+fn test_concat0() {
+    let path = Path::new("tests/assets/vids/bear.mp4");
+    let single = read_all(open_concat(&[path], true).unwrap()).unwrap();
+    let double = read_all(open_concat(&[path, path], true).unwrap()).unwrap();
+    assert!(single > 0.);
+    assert!((double / single - 2.).abs() < 0.1);
+}
+
+#[test]
+fn test_concat1() {
+    // Absolute paths are only accepted with `safe` set to false.
+    let path = std::env::current_dir()
+        .unwrap()
+        .join("tests/assets/vids/bear.mp4");
+    let path = path.as_path();
+    assert!(open_concat(&[path, path], true).is_err());
+    read_all(open_concat(&[path, path], false).unwrap()).unwrap();
+}
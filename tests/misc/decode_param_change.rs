@@ -0,0 +1,90 @@
+//! Decode a concatenation of two MPEG-4 elementary streams with different
+//! resolutions with `rsmpeg::helpers::FrameDecoder`.
+use anyhow::{Context, Result};
+use rsmpeg::{
+    avcodec::{AVCodec, AVCodecContext},
+    avutil::{ra, AVFrame},
+    error::RsmpegError,
+    ffi,
+    helpers::{open_concat, DecodeEvent, FrameDecoder, FrameParams},
+};
+use std::{fs, io::Write, path::Path, slice};
+
+/// Encode `nb_frames` gray frames into a raw MPEG-4 elementary stream.
+fn write_segment(path: &Path, width: i32, height: i32, nb_frames: i64) -> Result<()> {
+    let encoder = AVCodec::find_encoder(ffi::AV_CODEC_ID_MPEG4).context("Encoder not found")?;
+    let mut enc_ctx = AVCodecContext::new(&encoder);
+    enc_ctx.try_set_width(width)?;
+    enc_ctx.try_set_height(height)?;
+    enc_ctx.try_set_pix_fmt(ffi::AV_PIX_FMT_YUV420P)?;
+    enc_ctx.set_time_base(ra(1, 25))?;
+    enc_ctx.open(None)?;
+
+    let size = (width * height * 3 / 2) as usize;
+    let mut file = fs::File::create(path)?;
+    for pts in 0..=nb_frames {
+        let frame = if pts < nb_frames {
+            let data = vec![128; size];
+            let mut frame =
+                AVFrame::read_raw_from(&mut &data[..], width, height, ffi::AV_PIX_FMT_YUV420P)?;
+            frame.set_pts(pts);
+            Some(frame)
+        } else {
+            None
+        };
+        enc_ctx.send_frame(frame.as_ref())?;
+        loop {
+            let packet = match enc_ctx.receive_packet() {
+                Ok(packet) => packet,
+                Err(RsmpegError::EncoderDrainError) | Err(RsmpegError::EncoderFlushedError) => {
+                    break
+                }
+                Err(e) => return Err(e.into()),
+            };
+            file.write_all(unsafe { slice::from_raw_parts(packet.data, packet.size as usize) })?;
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn test_decode_param_change0() {
+    let dir = Path::new("tests/output/decode_param_change");
+    fs::create_dir_all(dir).unwrap();
+    let large = dir.join("large.m4v");
+    let small = dir.join("small.m4v");
+    write_segment(&large, 64, 48, 5).unwrap();
+    write_segment(&small, 32, 24, 5).unwrap();
+
+    let mut ifmt_ctx = open_concat(&[large.as_path(), small.as_path()], true).unwrap();
+    let (stream_index, decoder) = ifmt_ctx
+        .find_best_stream(ffi::AVMEDIA_TYPE_VIDEO)
+        .unwrap()
+        .unwrap();
+    let mut dec_ctx = AVCodecContext::new(&decoder);
+    dec_ctx
+        .apply_codecpar(&ifmt_ctx.streams()[stream_index].codecpar())
+        .unwrap();
+    dec_ctx.open(None).unwrap();
+
+    let mut sizes = vec![];
+    let mut changes = vec![];
+    for event in FrameDecoder::new(&mut ifmt_ctx, &mut dec_ctx, stream_index).detect_changes(true) {
+        match event.unwrap() {
+            DecodeEvent::Frame(frame) => sizes.push((frame.width, frame.height)),
+            DecodeEvent::StreamParametersChanged { previous, current } => {
+                changes.push((sizes.len(), previous, current))
+            }
+        }
+    }
+
+    let mut expected = vec![(64, 48); 5];
+    expected.extend([(32, 24); 5]);
+    assert_eq!(sizes, expected);
+    let params = |width, height| FrameParams {
+        width,
+        height,
+        format: ffi::AV_PIX_FMT_YUV420P,
+    };
+    assert_eq!(changes, [(5, params(64, 48), params(32, 24))]);
+}
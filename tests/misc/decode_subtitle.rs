@@ -0,0 +1,49 @@
+//! Decode the text of a SubRip subtitle file.
+use cstr::cstr;
+use rsmpeg::{
+    avcodec::{AVCodecContext, SubtitleRect},
+    avformat::AVFormatContextInput,
+    avutil::{av_rescale_q, ra},
+    ffi,
+};
+
+#[test]
+fn test_decode_subtitle() {
+    let mut input =
+        AVFormatContextInput::open(cstr!("tests/assets/subs/sample.srt"), None, &mut None).unwrap();
+    let (stream_index, decoder) = input
+        .find_best_stream(ffi::AVMEDIA_TYPE_SUBTITLE)
+        .unwrap()
+        .unwrap();
+    let time_base = input.streams()[stream_index].time_base;
+    let mut decode_context = AVCodecContext::new(&decoder);
+    decode_context
+        .apply_codecpar(&input.streams()[stream_index].codecpar())
+        .unwrap();
+    decode_context.try_set_pkt_timebase(time_base).unwrap();
+    decode_context.open(None).unwrap();
+
+    let mut events = vec![];
+    while let Some(mut packet) = input.read_packet().unwrap() {
+        let start = av_rescale_q(packet.pts, time_base, ra(1, 1000));
+        let Some(subtitle) = decode_context.decode_subtitle(Some(&mut packet)).unwrap() else {
+            continue;
+        };
+        for rect in subtitle.rects() {
+            // The SubRip decoder converts the events to ASS.
+            assert!(matches!(rect, SubtitleRect::Ass(_)));
+            let text = rect.text().unwrap().to_str().unwrap().to_string();
+            events.push((start, text));
+        }
+    }
+    assert!(decode_context.decode_subtitle(None).unwrap().is_none());
+
+    assert_eq!(
+        events,
+        [
+            (500, "Hello, world!".to_string()),
+            (2500, "Second line\\Nwith a break".to_string()),
+            (5000, "{\\i1}Italic{\\i0} text".to_string()),
+        ]
+    );
+}
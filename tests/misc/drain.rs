@@ -0,0 +1,140 @@
+//! Transcode a video to the end: the decoder, the filter graph and the encoder
+//! are drained at EOF, so the frames they still hold aren't dropped.
+use anyhow::{Context, Result};
+use cstr::cstr;
+use rsmpeg::{
+    avcodec::{AVCodec, AVCodecContext},
+    avfilter::{AVFilterContextMut, AVFilterGraph},
+    avformat::AVFormatContextInput,
+    avutil::AVFrame,
+    error::RsmpegError,
+    ffi,
+};
+use std::ffi::{CStr, CString};
+
+/// Number of frames or packets that went through each stage.
+#[derive(Debug, Default)]
+struct Counts {
+    packets: usize,
+    decoded: usize,
+    filtered: usize,
+    encoded: usize,
+}
+
+/// Send a decoded `frame` to the filter graph, `None` at EOF.
+fn send_to_filter(filter_graph: &mut AVFilterGraph, frame: Option<AVFrame>) -> Result<()> {
+    let mut buffersrc_ctx = filter_graph.get_filter(cstr!("buffer@in")).unwrap();
+    buffersrc_ctx.buffersrc_add_frame(frame, None)?;
+    Ok(())
+}
+
+/// Pull the frames available in the sink and send them to the encoder,
+/// return true once the sink reaches EOF.
+fn filter_encode(
+    buffersink_ctx: &mut AVFilterContextMut,
+    enc_ctx: &mut AVCodecContext,
+    counts: &mut Counts,
+) -> Result<bool> {
+    loop {
+        let mut frame = match buffersink_ctx.buffersink_get_frame(None) {
+            Ok(frame) => frame,
+            Err(RsmpegError::BufferSinkDrainError) => return Ok(false),
+            Err(RsmpegError::BufferSinkEofError) => return Ok(true),
+            Err(e) => return Err(e.into()),
+        };
+        counts.filtered += 1;
+        frame.set_pict_type(ffi::AV_PICTURE_TYPE_NONE);
+        enc_ctx.send_frame(Some(&frame))?;
+        loop {
+            match enc_ctx.receive_packet() {
+                Ok(_) => counts.encoded += 1,
+                Err(e) if e.is_again() => break,
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+}
+
+fn transcode(path: &CStr) -> Result<Counts> {
+    let mut ifmt_ctx = AVFormatContextInput::open(path, None, &mut None)?;
+    let (video_index, decoder) = ifmt_ctx
+        .find_best_stream(ffi::AVMEDIA_TYPE_VIDEO)?
+        .context("Failed to find video stream")?;
+    let stream = &ifmt_ctx.streams()[video_index];
+    let time_base = stream.time_base;
+    let framerate = stream.guess_framerate().context("Unknown framerate")?;
+    let mut dec_ctx = AVCodecContext::new(&decoder);
+    dec_ctx.apply_codecpar(&stream.codecpar())?;
+    dec_ctx.try_set_pkt_timebase(time_base)?;
+    dec_ctx.open(None)?;
+
+    let mut filter_graph = AVFilterGraph::new();
+    let spec = CString::new(format!(
+        "buffer@in=video_size={}x{}:pix_fmt={}:time_base={}/{}:pixel_aspect=1/1,\
+         null,buffersink@out",
+        dec_ctx.width, dec_ctx.height, dec_ctx.pix_fmt, time_base.num, time_base.den,
+    ))?;
+    filter_graph.parse_full(&spec)?;
+    filter_graph.config()?;
+
+    let encoder = AVCodec::find_encoder(ffi::AV_CODEC_ID_MPEG4).context("No mpeg4 encoder")?;
+    let mut enc_ctx = AVCodecContext::new(&encoder);
+    enc_ctx.try_set_width(dec_ctx.width)?;
+    enc_ctx.try_set_height(dec_ctx.height)?;
+    enc_ctx.try_set_pix_fmt(dec_ctx.pix_fmt)?;
+    enc_ctx.set_time_base(time_base)?;
+    enc_ctx.set_framerate(framerate)?;
+    // B-frames make the encoder hold frames until it's drained.
+    enc_ctx.try_set_max_b_frames(2)?;
+    enc_ctx.open(None)?;
+
+    let mut counts = Counts::default();
+    while let Some(packet) = ifmt_ctx.read_packet()? {
+        if packet.stream_index as usize != video_index {
+            continue;
+        }
+        counts.packets += 1;
+        dec_ctx.send_packet(Some(&packet))?;
+        loop {
+            let mut frame = match dec_ctx.receive_frame() {
+                Ok(frame) => frame,
+                Err(e) if e.is_again() => break,
+                Err(e) => return Err(e.into()),
+            };
+            counts.decoded += 1;
+            frame.set_pts(frame.best_effort_timestamp);
+            send_to_filter(&mut filter_graph, Some(frame))?;
+        }
+        let mut buffersink_ctx = filter_graph.get_filter(cstr!("buffersink@out")).unwrap();
+        filter_encode(&mut buffersink_ctx, &mut enc_ctx, &mut counts)?;
+    }
+
+    // EOF: drain the decoder, then the filter graph, then the encoder.
+    for mut frame in dec_ctx.flush_decoder()? {
+        counts.decoded += 1;
+        frame.set_pts(frame.best_effort_timestamp);
+        send_to_filter(&mut filter_graph, Some(frame))?;
+    }
+    send_to_filter(&mut filter_graph, None)?;
+    let mut buffersink_ctx = filter_graph.get_filter(cstr!("buffersink@out")).unwrap();
+    assert!(filter_encode(
+        &mut buffersink_ctx,
+        &mut enc_ctx,
+        &mut counts
+    )?);
+    counts.encoded += enc_ctx.flush_encoder()?.len();
+
+    // Draining again yields nothing.
+    assert!(dec_ctx.flush_decoder()?.is_empty());
+    assert!(enc_ctx.flush_encoder()?.is_empty());
+    Ok(counts)
+}
+
+#[test]
+fn drain_test() {
+    let counts = transcode(cstr!("tests/assets/vids/bear.mp4")).unwrap();
+    assert!(counts.packets > 0);
+    assert_eq!(counts.decoded, counts.packets);
+    assert_eq!(counts.filtered, counts.decoded);
+    assert_eq!(counts.encoded, counts.filtered);
+}
@@ -0,0 +1,63 @@
+//! Reconfigure a running filter graph with commands.
+use cstr::cstr;
+use rsmpeg::{
+    avfilter::AVFilterGraph,
+    avutil::{AVChannelLayout, AVFrame},
+    error::RsmpegError,
+    ffi,
+};
+
+/// Push a mono frame of 1024 samples of 0.5 at `pts` through the graph, return
+/// the first output sample.
+fn filter_frame(filter_graph: &mut AVFilterGraph, pts: i64) -> f32 {
+    let mut frame = AVFrame::new();
+    frame.set_format(ffi::AV_SAMPLE_FMT_FLT);
+    frame.set_ch_layout(AVChannelLayout::from_nb_channels(1));
+    frame.set_sample_rate(48000);
+    frame.set_nb_samples(1024);
+    frame.set_pts(pts);
+    frame.alloc_buffer().unwrap();
+    frame.channel_samples_mut::<f32>(0).unwrap().fill(0.5);
+
+    let mut buffersrc_ctx = filter_graph.get_filter(cstr!("abuffer@in")).unwrap();
+    buffersrc_ctx
+        .buffersrc_add_frame(Some(frame), None)
+        .unwrap();
+    let mut buffersink_ctx = filter_graph.get_filter(cstr!("abuffersink@out")).unwrap();
+    let frame = buffersink_ctx.buffersink_get_frame(None).unwrap();
+    assert_eq!(frame.pts, pts);
+    frame.channel_samples::<f32>(0).unwrap()[0]
+}
+
+#[test]
+fn test_filter_command0() {
+    let mut filter_graph = AVFilterGraph::new();
+    filter_graph
+        .parse_full(cstr!(
+            "abuffer@in=sample_rate=48000:sample_fmt=flt:channel_layout=mono:time_base=1/48000,\
+             volume@vol=volume=1.0:precision=float,\
+             aformat=sample_fmts=flt,abuffersink@out"
+        ))
+        .unwrap();
+    filter_graph.config().unwrap();
+
+    assert_eq!(filter_frame(&mut filter_graph, 0), 0.5);
+
+    filter_graph
+        .send_command(cstr!("volume@vol"), cstr!("volume"), cstr!("0.5"), 0)
+        .unwrap();
+    assert_eq!(filter_frame(&mut filter_graph, 1024), 0.25);
+
+    // Applied once a frame reaches 1s.
+    filter_graph
+        .queue_command(cstr!("volume@vol"), cstr!("volume"), cstr!("2.0"), 0, 1.0)
+        .unwrap();
+    assert_eq!(filter_frame(&mut filter_graph, 2048), 0.25);
+    assert_eq!(filter_frame(&mut filter_graph, 48000), 1.0);
+
+    // No such filter.
+    assert_eq!(
+        filter_graph.send_command(cstr!("volume@none"), cstr!("volume"), cstr!("0.5"), 0),
+        Err(RsmpegError::AVError(ffi::AVERROR(ffi::ENOSYS)))
+    );
+}
@@ -0,0 +1,33 @@
+//! Dump filter graphs for debugging.
+use cstr::cstr;
+use rsmpeg::{avfilter::AVFilterGraph, error::RsmpegError};
+
+#[test]
+fn test_filter_dump0() {
+    let filter_graph = AVFilterGraph::new();
+    assert_eq!(filter_graph.dump(), Err(RsmpegError::EmptyFilterGraphError));
+    assert_eq!(filter_graph.to_string(), "");
+
+    filter_graph
+        .parse_full(cstr!(
+            "buffer@in=video_size=64x48:pix_fmt=yuv420p:time_base=1/25:pixel_aspect=1/1,\
+             scale@scale=32:24,buffersink@out"
+        ))
+        .unwrap();
+    let dump = filter_graph.dump().unwrap();
+    for name in ["buffer@in", "scale@scale", "buffersink@out"] {
+        assert!(dump.contains(name), "{}", dump);
+    }
+
+    // The negotiated formats show up once configured.
+    filter_graph.config().unwrap();
+    let dump = filter_graph.dump().unwrap();
+    assert!(dump.contains("64x48"), "{}", dump);
+    assert!(dump.contains("32x24"), "{}", dump);
+    assert!(dump.contains("yuv420p"), "{}", dump);
+    assert_eq!(filter_graph.to_string(), dump);
+
+    let debug = format!("{:?}", filter_graph);
+    assert!(debug.starts_with("AVFilterGraph { nb_filters: "));
+    assert!(debug.ends_with(&dump));
+}
@@ -0,0 +1,67 @@
+//! Run a filter graph fully described by its spec with
+//! `AVFilterGraph::parse_full`.
+use cstr::cstr;
+use rsmpeg::{avfilter::AVFilterGraph, avutil::AVFrame, error::RsmpegError, ffi};
+
+#[test]
+fn test_filter_parse_full0() {
+    let mut filter_graph = AVFilterGraph::new();
+    filter_graph
+        .parse_full(cstr!(
+            "buffer@in=video_size=64x48:pix_fmt=yuv420p:time_base=1/25:pixel_aspect=1/1,\
+             scale=32:24,buffersink@out"
+        ))
+        .unwrap();
+    filter_graph.config().unwrap();
+
+    for pts in 0..5 {
+        let mut frame = AVFrame::new();
+        frame.set_format(ffi::AV_PIX_FMT_YUV420P);
+        frame.set_width(64);
+        frame.set_height(48);
+        frame.set_pts(pts);
+        frame.alloc_buffer().unwrap();
+        let mut buffersrc_ctx = filter_graph.get_filter(cstr!("buffer@in")).unwrap();
+        buffersrc_ctx
+            .buffersrc_add_frame(Some(frame), None)
+            .unwrap();
+    }
+    let mut buffersrc_ctx = filter_graph.get_filter(cstr!("buffer@in")).unwrap();
+    buffersrc_ctx.buffersrc_add_frame(None, None).unwrap();
+
+    let mut buffersink_ctx = filter_graph.get_filter(cstr!("buffersink@out")).unwrap();
+    let mut pts = vec![];
+    loop {
+        match buffersink_ctx.buffersink_get_frame(None) {
+            Ok(frame) => {
+                assert_eq!((frame.width, frame.height), (32, 24));
+                pts.push(frame.pts);
+            }
+            Err(RsmpegError::BufferSinkEofError) => break,
+            Err(e) => panic!("{}", e),
+        }
+    }
+    assert_eq!(pts, vec![0, 1, 2, 3, 4]);
+}
+
+#[test]
+fn test_filter_parse_full1() {
+    // The output of the scale filter is left open.
+    let filter_graph = AVFilterGraph::new();
+    filter_graph
+        .parse_full(cstr!(
+            "buffer=video_size=64x48:pix_fmt=yuv420p:time_base=1/25,scale@last=32:24"
+        ))
+        .unwrap();
+    assert_eq!(
+        filter_graph.config(),
+        Err(RsmpegError::FilterPadNotConnectedError(
+            "scale@last".to_string(),
+            "default".to_string()
+        ))
+    );
+
+    assert!(AVFilterGraph::new()
+        .parse_full(cstr!("no_such_filter"))
+        .is_err());
+}
@@ -0,0 +1,131 @@
+//! Run filter graphs built by `avfilter::specs` to check FFmpeg accepts the
+//! rendered specs.
+use cstr::cstr;
+use rsmpeg::{
+    avfilter::{
+        specs::{Atempo, Crop, Filter, FilterChain, Fps, Overlay, Scale, ScaleAlgorithm, Volume},
+        AVFilterGraph,
+    },
+    avutil::{AVFrame, AVRational},
+    error::RsmpegError,
+    ffi,
+};
+use std::ffi::{CStr, CString};
+
+/// Run the source-only graph `filter_spec` to its end, return the frames of
+/// `buffersink@out`.
+fn run(filter_spec: &CStr) -> Vec<AVFrame> {
+    let filter_graph = AVFilterGraph::new();
+    filter_graph.parse_full(filter_spec).unwrap();
+    filter_graph.config().unwrap();
+    let mut buffersink_ctx = filter_graph.get_filter(cstr!("buffersink@out")).unwrap();
+    let mut frames = vec![];
+    loop {
+        match buffersink_ctx.buffersink_get_frame(None) {
+            Ok(frame) => frames.push(frame),
+            Err(RsmpegError::BufferSinkEofError) => return frames,
+            Err(e) => panic!("{}", e),
+        }
+    }
+}
+
+fn testsrc(rate: i32) -> Filter {
+    Filter::new("testsrc")
+        .option("duration", 1)
+        .option("size", "64x48")
+        .option("rate", rate)
+}
+
+fn sink() -> Filter {
+    Filter::new("buffersink").instance("out")
+}
+
+#[test]
+fn test_filter_specs0() {
+    let chain = FilterChain::new()
+        .filter(testsrc(25))
+        .filter(
+            Scale::new()
+                .width(32)
+                .height(-2)
+                .flags(ScaleAlgorithm::Bicubic),
+        )
+        .filter(Fps::new(AVRational { num: 10, den: 1 }))
+        .filter(sink());
+    let frames = run(&chain.build());
+    assert_eq!(frames.len(), 10);
+    assert!(frames.iter().all(|x| (x.width, x.height) == (32, 24)));
+
+    // The comma of the expressions is escaped.
+    let chain = FilterChain::new()
+        .filter(testsrc(25))
+        .filter(Crop::new("min(iw,ih)", "min(iw,ih)").at(0, 0))
+        .filter(sink());
+    let frames = run(&chain.build());
+    assert!(frames.iter().all(|x| (x.width, x.height) == (48, 48)));
+}
+
+#[test]
+fn test_filter_specs1() {
+    let graph = FilterChain::join(&[
+        FilterChain::new().filter(testsrc(25)).output("main"),
+        FilterChain::new()
+            .filter(testsrc(25))
+            .filter(Scale::new().width(16).height(16))
+            .output("logo"),
+        FilterChain::new()
+            .input("main")
+            .input("logo")
+            .filter(Overlay::at("main_w-overlay_w", 0).shortest(true))
+            .filter(sink()),
+    ]);
+    let frames = run(&graph);
+    assert_eq!(frames.len(), 25);
+    assert!(frames.iter().all(|x| (x.width, x.height) == (64, 48)));
+}
+
+#[test]
+fn test_filter_specs2() {
+    let source = Filter::new("sine")
+        .option("frequency", 440)
+        .option("sample_rate", 44100)
+        .option("duration", 1);
+    let nb_samples = |frames: Vec<AVFrame>| frames.iter().map(|x| x.nb_samples).sum::<i32>();
+    let chain = FilterChain::new()
+        .filter(source.clone())
+        .filter(Volume::db(-3.5))
+        .filter(Volume::factor(0.5))
+        .filter(sink());
+    assert_eq!(nb_samples(run(&chain.build())), 44100);
+
+    let chain = FilterChain::new()
+        .filter(source)
+        .filter(Atempo::new(2.0))
+        .filter(sink());
+    let nb_samples = nb_samples(run(&chain.build()));
+    assert!((nb_samples - 22050).abs() < 1024);
+}
+
+#[test]
+fn test_filter_specs3() {
+    // Option and graph separators, quotes and surrounding spaces survive.
+    let value = " it's a:b, [c]; d\\e ";
+    let chain = FilterChain::new()
+        .filter(testsrc(25))
+        .filter(
+            Filter::new("metadata")
+                .option("mode", "add")
+                .option("key", "title")
+                .option("value", value),
+        )
+        .filter(sink());
+    let frames = run(&chain.build());
+    assert!(!frames.is_empty());
+    for frame in frames {
+        let key = CString::new("title").unwrap();
+        let entry = unsafe { ffi::av_dict_get(frame.metadata, key.as_ptr(), std::ptr::null(), 0) };
+        assert!(!entry.is_null());
+        let title = unsafe { CStr::from_ptr((*entry).value) };
+        assert_eq!(title.to_str().unwrap(), value);
+    }
+}
@@ -0,0 +1,167 @@
+//! Decode and filter into reused frames, compare with the allocating API.
+use anyhow::{Context, Result};
+use cstr::cstr;
+use rsmpeg::{
+    avcodec::AVCodecContext,
+    avfilter::{AVFilter, AVFilterContextMut, AVFilterGraph, AVFilterInOut, BufferSinkFlag},
+    avformat::AVFormatContextInput,
+    avutil::{AVFrame, FramePool},
+    error::RsmpegError,
+    ffi,
+};
+use std::ffi::CStr;
+
+/// pts and raw image of a video frame.
+fn summarize(frame: &AVFrame) -> (i64, Vec<u8>) {
+    let mut data = vec![];
+    frame.write_raw_to(&mut data).unwrap();
+    (frame.pts, data)
+}
+
+/// Decode the video stream of `path`, call `receive` to pull each frame.
+fn decode(
+    path: &CStr,
+    mut receive: impl FnMut(&mut AVCodecContext) -> Result<(i64, Vec<u8>), RsmpegError>,
+) -> Result<Vec<(i64, Vec<u8>)>> {
+    let mut ifmt_ctx = AVFormatContextInput::open(path, None, &mut None)?;
+    let (video_index, decoder) = ifmt_ctx
+        .find_best_stream(ffi::AVMEDIA_TYPE_VIDEO)?
+        .context("Failed to find video stream")?;
+    let mut dec_ctx = AVCodecContext::new(&decoder);
+    dec_ctx.apply_codecpar(&ifmt_ctx.streams()[video_index].codecpar())?;
+    dec_ctx.open(None)?;
+
+    let mut frames = vec![];
+    loop {
+        let packet = ifmt_ctx.read_packet()?;
+        if let Some(packet) = &packet {
+            if packet.stream_index as usize != video_index {
+                continue;
+            }
+        }
+        dec_ctx.send_packet(packet.as_ref())?;
+        loop {
+            match receive(&mut dec_ctx) {
+                Ok(frame) => frames.push(frame),
+                Err(RsmpegError::DecoderDrainError) => break,
+                Err(RsmpegError::DecoderFlushedError) => return Ok(frames),
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+}
+
+/// Run a source-only filter graph described by `filter_spec`, call `receive`
+/// to pull each frame from the sink.
+fn filter(
+    filter_spec: &CStr,
+    mut receive: impl FnMut(&mut AVFilterContextMut) -> Result<(i64, Vec<u8>), RsmpegError>,
+) -> Result<Vec<(i64, Vec<u8>)>> {
+    let filter_graph = AVFilterGraph::new();
+    let buffersink = AVFilter::get_by_name(cstr!("buffersink")).unwrap();
+    let mut buffersink_ctx = filter_graph.create_filter_context(&buffersink, cstr!("out"), None)?;
+    let inputs = AVFilterInOut::new(cstr!("out"), &buffersink_ctx, 0);
+    filter_graph.parse_ptr(filter_spec, Some(inputs), None)?;
+    filter_graph.config()?;
+
+    let mut frames = vec![];
+    loop {
+        match receive(&mut buffersink_ctx) {
+            Ok(frame) => frames.push(frame),
+            Err(RsmpegError::BufferSinkEofError) => return Ok(frames),
+            Err(e) => return Err(e.into()),
+        }
+    }
+}
+
+#[test]
+fn test_receive_frame_into0() {
+    let path = cstr!("tests/assets/vids/bear.mp4");
+    let expected = decode(path, |dec_ctx| {
+        dec_ctx.receive_frame().map(|frame| summarize(&frame))
+    })
+    .unwrap();
+
+    let mut frame = AVFrame::new();
+    let frames = decode(path, |dec_ctx| {
+        dec_ctx.receive_frame_into(&mut frame)?;
+        Ok(summarize(&frame))
+    })
+    .unwrap();
+    assert!(!expected.is_empty());
+    assert_eq!(frames, expected);
+    // Left blank by the final error.
+    assert!(!frame.is_allocated());
+}
+
+#[test]
+fn test_buffersink_get_frame_into0() {
+    let filter_spec = cstr!("testsrc=duration=1:size=64x48:rate=25");
+    let expected = filter(filter_spec, |buffersink_ctx| {
+        buffersink_ctx
+            .buffersink_get_frame(None)
+            .map(|frame| summarize(&frame))
+    })
+    .unwrap();
+
+    let mut frame = AVFrame::new();
+    let frames = filter(filter_spec, |buffersink_ctx| {
+        buffersink_ctx.buffersink_get_frame_into(&mut frame)?;
+        Ok(summarize(&frame))
+    })
+    .unwrap();
+    assert_eq!(expected.len(), 25);
+    assert_eq!(frames, expected);
+    assert!(!frame.is_allocated());
+}
+
+#[test]
+fn test_frame_pool0() {
+    let path = cstr!("tests/assets/vids/bear.mp4");
+    let mut pool = FramePool::new();
+    assert!(pool.is_empty());
+    let mut frame_ptrs = vec![];
+    let frames = decode(path, |dec_ctx| {
+        let mut frame = pool.get();
+        frame_ptrs.push(frame.as_ptr());
+        let result = dec_ctx
+            .receive_frame_into(&mut frame)
+            .map(|_| summarize(&frame));
+        pool.put(frame);
+        result
+    })
+    .unwrap();
+    assert!(!frames.is_empty());
+    // A single frame object is handed out over and over.
+    assert_eq!(pool.len(), 1);
+    assert!(frame_ptrs.iter().all(|x| *x == frame_ptrs[0]));
+}
+
+#[test]
+fn test_buffersink_get_frame_flags_into0() {
+    let filter_spec = cstr!("testsrc=duration=1:size=64x48:rate=25");
+    let mut peeked = AVFrame::new();
+    let mut frame = AVFrame::new();
+    let frames = filter(filter_spec, |buffersink_ctx| {
+        // Nothing is buffered before the first request.
+        if peeked.pts == ffi::AV_NOPTS_VALUE {
+            assert!(matches!(
+                buffersink_ctx
+                    .buffersink_get_frame_flags_into(&mut frame, &[BufferSinkFlag::NoRequest]),
+                Err(RsmpegError::BufferSinkDrainError)
+            ));
+        }
+        buffersink_ctx.buffersink_get_frame_flags_into(&mut peeked, &[BufferSinkFlag::Peek])?;
+        // Peeking again doesn't consume the frame either.
+        buffersink_ctx.buffersink_get_frame_flags_into(
+            &mut peeked,
+            &[BufferSinkFlag::Peek, BufferSinkFlag::NoRequest],
+        )?;
+        buffersink_ctx.buffersink_get_frame_into(&mut frame)?;
+        assert_eq!(frame.pts, peeked.pts);
+        Ok(summarize(&frame))
+    })
+    .unwrap();
+    let pts: Vec<_> = frames.iter().map(|(pts, _)| *pts).collect();
+    assert_eq!(pts, (0..25).collect::<Vec<_>>());
+}
@@ -0,0 +1,145 @@
+//! Encode a known number of samples to AAC and decode them back exactly with
+//! `SampleTrimmer`.
+use rsmpeg::{
+    avcodec::{AVCodec, AVCodecContext, AVPacket, SkipSamples},
+    avutil::{ra, AVChannelLayout, AVFrame},
+    error::RsmpegError,
+    ffi,
+    helpers::SampleTrimmer,
+};
+use std::f32::consts::PI;
+
+const SAMPLE_RATE: i32 = 44100;
+const NB_SAMPLES: i64 = 10000;
+
+/// Encode `NB_SAMPLES` samples of a stereo sine wave to AAC.
+fn encode() -> (AVCodecContext, Vec<AVPacket>) {
+    let encoder = AVCodec::find_encoder(ffi::AV_CODEC_ID_AAC).unwrap();
+    let mut enc_ctx = AVCodecContext::new(&encoder);
+    enc_ctx.try_set_sample_fmt(ffi::AV_SAMPLE_FMT_FLTP).unwrap();
+    enc_ctx.try_set_sample_rate(SAMPLE_RATE).unwrap();
+    enc_ctx
+        .try_set_ch_layout(AVChannelLayout::from_nb_channels(2))
+        .unwrap();
+    enc_ctx.try_set_bit_rate(128000).unwrap();
+    enc_ctx.set_time_base(ra(1, SAMPLE_RATE)).unwrap();
+    enc_ctx.open(None).unwrap();
+
+    let mut packets = vec![];
+    let mut receive = |enc_ctx: &mut AVCodecContext| loop {
+        match enc_ctx.receive_packet() {
+            Ok(packet) => packets.push(packet),
+            Err(RsmpegError::EncoderDrainError) | Err(RsmpegError::EncoderFlushedError) => break,
+            Err(e) => panic!("{}", e),
+        }
+    };
+    let mut pts = 0;
+    while pts < NB_SAMPLES {
+        let nb_samples = (NB_SAMPLES - pts).min(enc_ctx.frame_size as i64);
+        let mut frame = AVFrame::new();
+        frame.set_format(ffi::AV_SAMPLE_FMT_FLTP);
+        frame.set_nb_samples(nb_samples as i32);
+        frame.set_ch_layout(AVChannelLayout::from_nb_channels(2));
+        frame.set_sample_rate(SAMPLE_RATE);
+        frame.set_pts(pts);
+        frame.alloc_buffer().unwrap();
+        for channel in 0..2 {
+            let data = unsafe {
+                std::slice::from_raw_parts_mut(frame.data[channel] as *mut f32, nb_samples as usize)
+            };
+            for (i, sample) in data.iter_mut().enumerate() {
+                let t = (pts + i as i64) as f32 / SAMPLE_RATE as f32;
+                *sample = (2. * PI * 440. * t).sin() * 0.5;
+            }
+        }
+        enc_ctx.send_frame(Some(&frame)).unwrap();
+        receive(&mut enc_ctx);
+        pts += nb_samples;
+    }
+    enc_ctx.send_frame(None).unwrap();
+    receive(&mut enc_ctx);
+    (enc_ctx, packets)
+}
+
+/// Decode `packets`, passing the frames through `trimmer` if given. Return
+/// the number of samples and the pts of the first frame.
+fn decode(
+    enc_ctx: &AVCodecContext,
+    packets: &[AVPacket],
+    mut trimmer: Option<SampleTrimmer>,
+) -> (i64, i64) {
+    let decoder = AVCodec::find_decoder(ffi::AV_CODEC_ID_AAC).unwrap();
+    let mut dec_ctx = AVCodecContext::new(&decoder);
+    dec_ctx.apply_codecpar(&enc_ctx.extract_codecpar()).unwrap();
+    dec_ctx.try_set_pkt_timebase(ra(1, SAMPLE_RATE)).unwrap();
+    dec_ctx.open(None).unwrap();
+
+    let mut nb_samples = 0;
+    let mut first_pts = None;
+    for packet in packets.iter().map(Some).chain([None]) {
+        dec_ctx.send_packet(packet).unwrap();
+        loop {
+            let mut frame = match dec_ctx.receive_frame() {
+                Ok(frame) => frame,
+                Err(RsmpegError::DecoderDrainError) | Err(RsmpegError::DecoderFlushedError) => {
+                    break
+                }
+                Err(e) => panic!("{}", e),
+            };
+            if let Some(trimmer) = &mut trimmer {
+                if !trimmer.trim(&mut frame).unwrap() {
+                    continue;
+                }
+            }
+            first_pts.get_or_insert(frame.pts);
+            nb_samples += frame.nb_samples as i64;
+        }
+    }
+    (nb_samples, first_pts.unwrap())
+}
+
+#[test]
+fn test_gapless0() {
+    let (enc_ctx, packets) = encode();
+    let initial_padding = enc_ctx.extract_codecpar().initial_padding();
+    assert_eq!(initial_padding, enc_ctx.initial_padding);
+    assert!(initial_padding > 0);
+    // Priming samples are before the timestamp 0.
+    assert_eq!(packets[0].pts, -initial_padding as i64);
+    assert!(packets.iter().all(|x| x.skip_samples().is_none()));
+
+    let (nb_samples, first_pts) = decode(&enc_ctx, &packets, None);
+    assert!(nb_samples >= NB_SAMPLES + initial_padding as i64);
+    assert_eq!(first_pts, -initial_padding as i64);
+
+    let trimmer = SampleTrimmer::new(ra(1, SAMPLE_RATE), initial_padding as i64, Some(NB_SAMPLES));
+    let (nb_samples, first_pts) = decode(&enc_ctx, &packets, Some(trimmer));
+    assert_eq!(nb_samples, NB_SAMPLES);
+    assert_eq!(first_pts, 0);
+}
+
+#[test]
+fn test_skip_samples0() {
+    let mut packet = AVPacket::new();
+    assert_eq!(packet.skip_samples(), None);
+    let data = [0x40, 0x04, 0, 0, 0x10, 0, 0, 0, 0, 0];
+    unsafe {
+        let side_data = ffi::av_packet_new_side_data(
+            packet.as_mut_ptr(),
+            ffi::AV_PKT_DATA_SKIP_SAMPLES,
+            data.len() as _,
+        );
+        std::ptr::copy_nonoverlapping(data.as_ptr(), side_data, data.len());
+    }
+    assert_eq!(
+        packet.side_data(ffi::AV_PKT_DATA_SKIP_SAMPLES),
+        Some(&data[..])
+    );
+    assert_eq!(
+        packet.skip_samples(),
+        Some(SkipSamples {
+            start: 1088,
+            end: 16
+        })
+    );
+}
@@ -0,0 +1,170 @@
+//! Decode into buffers allocated by `AVCodecContext::set_get_buffer2`.
+use cstr::cstr;
+use rsmpeg::{
+    avcodec::{AVCodecContext, BufferRequest},
+    avformat::AVFormatContextInput,
+    avutil::{cpu, AVBufferRef, AVFrame},
+    error::RsmpegError,
+    ffi,
+};
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+/// Decode the video stream of bear.mp4, return the pts and raw image of each
+/// frame.
+fn decode(configure: impl FnOnce(&mut AVCodecContext)) -> Vec<(i64, Vec<u8>)> {
+    let mut ifmt_ctx =
+        AVFormatContextInput::open(cstr!("tests/assets/vids/bear.mp4"), None, &mut None).unwrap();
+    let (video_index, decoder) = ifmt_ctx
+        .find_best_stream(ffi::AVMEDIA_TYPE_VIDEO)
+        .unwrap()
+        .unwrap();
+    let mut dec_ctx = AVCodecContext::new(&decoder);
+    dec_ctx
+        .apply_codecpar(&ifmt_ctx.streams()[video_index].codecpar())
+        .unwrap();
+    configure(&mut dec_ctx);
+    dec_ctx.open(None).unwrap();
+
+    let mut frames = vec![];
+    loop {
+        let packet = ifmt_ctx.read_packet().unwrap();
+        if let Some(packet) = &packet {
+            if packet.stream_index as usize != video_index {
+                continue;
+            }
+        }
+        dec_ctx.send_packet(packet.as_ref()).unwrap();
+        loop {
+            match dec_ctx.receive_frame() {
+                Ok(frame) => {
+                    let mut data = vec![];
+                    frame.write_raw_to(&mut data).unwrap();
+                    frames.push((frame.pts, data));
+                }
+                Err(RsmpegError::DecoderDrainError) => break,
+                Err(RsmpegError::DecoderFlushedError) => return frames,
+                Err(e) => panic!("{}", e),
+            }
+        }
+    }
+}
+
+/// Allocate one buffer per plane for the requested image.
+fn allocate(frame: &mut AVFrame, request: BufferRequest) -> Result<(), RsmpegError> {
+    let mut linesizes = [0; 4];
+    let ret = unsafe {
+        ffi::av_image_fill_linesizes(
+            linesizes.as_mut_ptr(),
+            request.format,
+            request.aligned_width,
+        )
+    };
+    if ret < 0 {
+        return Err(RsmpegError::AVError(ret));
+    }
+    let max_align = cpu::max_align();
+    for (linesize, align) in linesizes.iter_mut().zip(request.linesize_align) {
+        let align = align.max(max_align as i32);
+        *linesize = (*linesize + align - 1) / align * align;
+    }
+    let mut sizes = [0; 4];
+    let ret = unsafe {
+        ffi::av_image_fill_plane_sizes(
+            sizes.as_mut_ptr(),
+            request.format,
+            request.aligned_height,
+            linesizes.map(|x| x as isize).as_ptr(),
+        )
+    };
+    if ret < 0 {
+        return Err(RsmpegError::AVError(ret));
+    }
+    for (i, (size, linesize)) in sizes.into_iter().zip(linesizes).enumerate() {
+        if size > 0 {
+            frame.set_buffer(i, AVBufferRef::new(size + 16 + max_align - 1), linesize)?;
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn test_get_buffer0() {
+    let expected = decode(|_| {});
+    assert!(!expected.is_empty());
+
+    let nb_allocated = Arc::new(AtomicUsize::new(0));
+    let frames = decode(|dec_ctx| {
+        dec_ctx.try_set_thread_count(4).unwrap();
+        let nb_allocated = nb_allocated.clone();
+        // `allocate()` sizes the planes as requested.
+        unsafe {
+            dec_ctx.set_get_buffer2(move |frame, request| {
+                nb_allocated.fetch_add(1, Ordering::Relaxed);
+                allocate(frame, request)
+            })
+        }
+        .unwrap();
+    });
+    assert_eq!(frames, expected);
+    assert!(nb_allocated.load(Ordering::Relaxed) >= expected.len());
+}
+
+#[test]
+fn test_get_buffer1() {
+    // Failures and panics fall back to the default allocator.
+    let expected = decode(|_| {});
+    let nb_calls = Arc::new(AtomicUsize::new(0));
+    let frames = decode(|dec_ctx| {
+        let nb_calls = nb_calls.clone();
+        // `allocate()` sizes the planes as requested.
+        unsafe {
+            dec_ctx.set_get_buffer2(move |frame, request| {
+                match nb_calls.fetch_add(1, Ordering::Relaxed) % 3 {
+                    0 => panic!("allocation panicked"),
+                    1 => {
+                        // Half done allocations are dropped.
+                        frame.set_buffer(0, AVBufferRef::new(64), 64)?;
+                        Err(RsmpegError::AVError(ffi::AVERROR(ffi::ENOMEM)))
+                    }
+                    _ => allocate(frame, request),
+                }
+            })
+        }
+        .unwrap();
+    });
+    assert_eq!(frames, expected);
+    assert!(nb_calls.load(Ordering::Relaxed) >= expected.len());
+}
+
+#[test]
+fn test_get_buffer2() {
+    decode(|dec_ctx| {
+        unsafe { dec_ctx.set_get_buffer2(allocate) }.unwrap();
+        // Replacing drops the previous callback.
+        unsafe { dec_ctx.set_get_buffer2(allocate) }.unwrap();
+    });
+    let ifmt_ctx =
+        AVFormatContextInput::open(cstr!("tests/assets/vids/bear.mp4"), None, &mut None).unwrap();
+    let (video_index, decoder) = ifmt_ctx
+        .find_best_stream(ffi::AVMEDIA_TYPE_VIDEO)
+        .unwrap()
+        .unwrap();
+    let mut dec_ctx = AVCodecContext::new(&decoder);
+    dec_ctx
+        .apply_codecpar(&ifmt_ctx.streams()[video_index].codecpar())
+        .unwrap();
+    dec_ctx.open(None).unwrap();
+    assert_eq!(
+        unsafe { dec_ctx.set_get_buffer2(allocate) },
+        Err(RsmpegError::CodecAlreadyOpenedError)
+    );
+
+    let mut frame = AVFrame::new();
+    assert_eq!(
+        frame.set_buffer(8, AVBufferRef::new(64), 64),
+        Err(RsmpegError::AVError(ffi::AVERROR(ffi::EINVAL)))
+    );
+}
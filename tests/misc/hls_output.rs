@@ -0,0 +1,133 @@
+//! Transcode the video stream of a file into HLS segments with
+//! `AVFormatContextOutput::open_hls`.
+use anyhow::{Context, Result};
+use cstr::cstr;
+use rsmpeg::{
+    avcodec::{AVCodec, AVCodecContext},
+    avformat::{AVFormatContextInput, AVFormatContextOutput, HlsOutputOptions},
+    avutil::AVFrame,
+    error::RsmpegError,
+    ffi,
+};
+use std::{
+    ffi::{CStr, CString},
+    fs,
+};
+
+fn encode_write_frame(
+    frame: Option<&AVFrame>,
+    enc_ctx: &mut AVCodecContext,
+    ofmt_ctx: &mut AVFormatContextOutput,
+) -> Result<()> {
+    enc_ctx.send_frame(frame)?;
+    loop {
+        let mut packet = match enc_ctx.receive_packet() {
+            Ok(packet) => packet,
+            Err(RsmpegError::EncoderDrainError) | Err(RsmpegError::EncoderFlushedError) => break,
+            Err(e) => return Err(e.into()),
+        };
+        packet.set_stream_index(0);
+        packet.rescale_ts(enc_ctx.time_base, ofmt_ctx.streams()[0].time_base);
+        ofmt_ctx.interleaved_write_frame(&mut packet)?;
+    }
+    Ok(())
+}
+
+fn hls_output(input_path: &CStr, playlist_path: &CStr, segment_pattern: &CStr) -> Result<()> {
+    let mut ifmt_ctx = AVFormatContextInput::open(input_path, None, &mut None)?;
+    let (video_index, decoder) = ifmt_ctx
+        .find_best_stream(ffi::AVMEDIA_TYPE_VIDEO)?
+        .context("Failed to find video stream")?;
+
+    let mut dec_ctx = AVCodecContext::new(&decoder);
+    {
+        let stream = &ifmt_ctx.streams()[video_index];
+        dec_ctx.apply_codecpar(&stream.codecpar())?;
+        dec_ctx.try_set_pkt_timebase(stream.time_base)?;
+        if let Some(framerate) = stream.guess_framerate() {
+            dec_ctx.set_framerate(framerate)?;
+        }
+    }
+    dec_ctx.open(None)?;
+
+    let opts = HlsOutputOptions::new()
+        .hls_time(2.)
+        .hls_list_size(0)
+        .hls_segment_filename(segment_pattern);
+    let mut ofmt_ctx = AVFormatContextOutput::open_hls(playlist_path, opts)?;
+
+    let encoder = AVCodec::find_encoder(ffi::AV_CODEC_ID_MPEG4).context("Encoder not found")?;
+    let mut enc_ctx = AVCodecContext::new(&encoder);
+    enc_ctx.try_set_width(dec_ctx.width)?;
+    enc_ctx.try_set_height(dec_ctx.height)?;
+    enc_ctx.try_set_pix_fmt(ffi::AV_PIX_FMT_YUV420P)?;
+    enc_ctx.set_time_base_from_framerate(dec_ctx.framerate)?;
+    // Frequent key frames, so segments can be cut close to `hls_time`.
+    enc_ctx.try_set_gop_size(12)?;
+    if ofmt_ctx.oformat().flags().is_globalheader() {
+        enc_ctx.try_set_flags(enc_ctx.flags | ffi::AV_CODEC_FLAG_GLOBAL_HEADER as i32)?;
+    }
+    enc_ctx.open(None)?;
+    {
+        let mut out_stream = ofmt_ctx.new_stream();
+        out_stream.set_codecpar(enc_ctx.extract_codecpar());
+        out_stream.set_time_base(enc_ctx.time_base);
+    }
+    ofmt_ctx.write_header(&mut None)?;
+
+    let mut pts = 0;
+    loop {
+        let packet = ifmt_ctx.read_packet()?;
+        if let Some(packet) = &packet {
+            if packet.stream_index as usize != video_index {
+                continue;
+            }
+        }
+        dec_ctx.send_packet(packet.as_ref())?;
+        loop {
+            let mut frame = match dec_ctx.receive_frame() {
+                Ok(frame) => frame,
+                Err(RsmpegError::DecoderDrainError) | Err(RsmpegError::DecoderFlushedError) => {
+                    break
+                }
+                Err(e) => return Err(e.into()),
+            };
+            frame.set_pts(pts);
+            frame.set_pict_type(ffi::AV_PICTURE_TYPE_NONE);
+            pts += 1;
+            encode_write_frame(Some(&frame), &mut enc_ctx, &mut ofmt_ctx)?;
+        }
+        if packet.is_none() {
+            break;
+        }
+    }
+    encode_write_frame(None, &mut enc_ctx, &mut ofmt_ctx)?;
+    ofmt_ctx.write_trailer()?;
+    Ok(())
+}
+
+#[test]
+fn test_hls_output0() {
+    let dir = "tests/output/hls_output";
+    let _ = fs::remove_dir_all(dir);
+    fs::create_dir_all(dir).unwrap();
+    hls_output(
+        cstr!("tests/assets/vids/big_buck_bunny.mp4"),
+        cstr!("tests/output/hls_output/playlist.m3u8"),
+        cstr!("tests/output/hls_output/segment_%03d.ts"),
+    )
+    .unwrap();
+
+    let playlist = fs::read_to_string(format!("{}/playlist.m3u8", dir)).unwrap();
+    assert!(playlist.contains("#EXT-X-ENDLIST"));
+    let segments: Vec<_> = playlist
+        .lines()
+        .filter(|line| line.ends_with(".ts"))
+        .collect();
+    assert!(segments.len() >= 2);
+    for segment in segments {
+        let path = CString::new(format!("{}/{}", dir, segment)).unwrap();
+        let ifmt_ctx = AVFormatContextInput::open(&path, None, &mut None).unwrap();
+        assert_eq!(ifmt_ctx.nb_streams, 1);
+    }
+}
@@ -0,0 +1,91 @@
+//! Copy a decoded frame into a packed RGB24 buffer, e.g. for GUI toolkits.
+use anyhow::{Context, Result};
+use cstr::cstr;
+use rsmpeg::{
+    avcodec::AVCodecContext,
+    avformat::AVFormatContextInput,
+    avutil::{AVFrame, AVImage},
+    error::RsmpegError,
+    ffi,
+    swscale::{Converter, SwsFlags},
+};
+use std::ffi::CStr;
+
+/// Decode the first video frame of the file.
+fn first_frame(path: &CStr) -> Result<AVFrame> {
+    let mut ifmt_ctx = AVFormatContextInput::open(path, None, &mut None)?;
+    let (video_index, decoder) = ifmt_ctx
+        .find_best_stream(ffi::AVMEDIA_TYPE_VIDEO)?
+        .context("Failed to find video stream")?;
+    let mut dec_ctx = AVCodecContext::new(&decoder);
+    dec_ctx.apply_codecpar(&ifmt_ctx.streams()[video_index].codecpar())?;
+    dec_ctx.open(None)?;
+
+    loop {
+        let packet = ifmt_ctx.read_packet()?;
+        if let Some(packet) = &packet {
+            if packet.stream_index as usize != video_index {
+                continue;
+            }
+        }
+        dec_ctx.send_packet(packet.as_ref())?;
+        match dec_ctx.receive_frame() {
+            Ok(frame) => return Ok(frame),
+            Err(RsmpegError::DecoderDrainError) => continue,
+            Err(e) => return Err(e.into()),
+        }
+    }
+}
+
+/// Convert `frame` to RGB24 and copy it into a tightly packed buffer.
+fn to_packed_rgb24(frame: &AVFrame) -> Result<Vec<u8>> {
+    let mut converter = Converter::new(
+        frame.width,
+        frame.height,
+        ffi::AV_PIX_FMT_RGB24,
+        SwsFlags::BILINEAR,
+    )
+    .context("Invalid destination parameters")?;
+    let rgb = converter.convert(frame)?;
+    let size = AVImage::get_buffer_size(rgb.format, rgb.width, rgb.height, 1)
+        .context("Invalid image parameters")?;
+    let mut buffer = vec![0; size as usize];
+    let written = rgb.copy_image_to_buffer(&mut buffer, 1)?;
+    buffer.truncate(written);
+    Ok(buffer)
+}
+
+#[test]
+fn image_buffer_test() {
+    let frame = first_frame(cstr!("tests/assets/vids/bear.mp4")).unwrap();
+    let buffer = to_packed_rgb24(&frame).unwrap();
+    assert_eq!(buffer.len(), (frame.width * frame.height * 3) as usize);
+
+    // Going back: the planes of a yuv420p image split the buffer.
+    let size = AVImage::get_buffer_size(frame.format, frame.width, frame.height, 1).unwrap();
+    let mut yuv = vec![0; size as usize];
+    assert_eq!(
+        frame.copy_image_to_buffer(&mut yuv, 1).unwrap(),
+        size as usize
+    );
+    let (data, linesizes) =
+        AVImage::fill_arrays(&mut yuv, frame.format, frame.width, frame.height, 1).unwrap();
+    assert_eq!(data[0], yuv.as_mut_ptr());
+    assert_eq!(linesizes[0], frame.width);
+}
+
+#[test]
+fn image_buffer_too_small_test() {
+    let frame = first_frame(cstr!("tests/assets/vids/bear.mp4")).unwrap();
+    let size =
+        AVImage::get_buffer_size(frame.format, frame.width, frame.height, 1).unwrap() as usize;
+    let mut buffer = vec![0; size - 1];
+    assert_eq!(
+        frame.copy_image_to_buffer(&mut buffer, 1),
+        Err(RsmpegError::BufferTooSmallError(size - 1, size))
+    );
+    assert!(matches!(
+        AVImage::fill_arrays(&mut buffer, frame.format, frame.width, frame.height, 1),
+        Err(RsmpegError::BufferTooSmallError(..))
+    ));
+}
@@ -0,0 +1,113 @@
+//! Encode the first frames of a video to a numbered PNG sequence with the
+//! `image2` muxer, then read the sequence back with the `image2` demuxer.
+use anyhow::{Context, Result};
+use cstr::cstr;
+use rsmpeg::{
+    avcodec::{AVCodec, AVCodecContext},
+    avformat::{AVFormatContextInput, AVFormatContextOutput, AVInputFormat},
+    avutil::ra,
+    error::RsmpegError,
+    ffi,
+    swscale::{Converter, SwsFlags},
+};
+use std::{ffi::CStr, fs, path::Path};
+
+fn write_image_sequence(input_path: &CStr, pattern: &CStr, nb_frames: i64) -> Result<()> {
+    let mut ifmt_ctx = AVFormatContextInput::open(input_path, None, &mut None)?;
+    let (video_index, decoder) = ifmt_ctx
+        .find_best_stream(ffi::AVMEDIA_TYPE_VIDEO)?
+        .context("Failed to find video stream")?;
+    let mut dec_ctx = AVCodecContext::new(&decoder);
+    dec_ctx.apply_codecpar(&ifmt_ctx.streams()[video_index].codecpar())?;
+    dec_ctx.open(None)?;
+
+    let encoder = AVCodec::find_encoder(ffi::AV_CODEC_ID_PNG).context("Encoder not found")?;
+    let mut enc_ctx = AVCodecContext::new(&encoder);
+    enc_ctx.try_set_width(dec_ctx.width)?;
+    enc_ctx.try_set_height(dec_ctx.height)?;
+    enc_ctx.try_set_pix_fmt(ffi::AV_PIX_FMT_RGB24)?;
+    enc_ctx.set_time_base(ra(1, 25))?;
+    enc_ctx.open(None)?;
+
+    let mut converter = Converter::new(
+        dec_ctx.width,
+        dec_ctx.height,
+        ffi::AV_PIX_FMT_RGB24,
+        SwsFlags::BILINEAR,
+    )
+    .context("Failed to create converter")?;
+
+    // `image2` is an `AVFMT_NOFILE` muxer, it opens a file for each image.
+    let mut ofmt_ctx =
+        AVFormatContextOutput::create_with_format(pattern, Some(cstr!("image2")), None)?;
+    {
+        let mut out_stream = ofmt_ctx.new_stream();
+        out_stream.set_codecpar(enc_ctx.extract_codecpar());
+        out_stream.set_time_base(enc_ctx.time_base);
+    }
+    ofmt_ctx.write_header(&mut None)?;
+
+    let mut pts = 0;
+    while pts < nb_frames {
+        let Some(packet) = ifmt_ctx.read_packet()? else {
+            break;
+        };
+        if packet.stream_index as usize != video_index {
+            continue;
+        }
+        dec_ctx.send_packet(Some(&packet))?;
+        while pts < nb_frames {
+            let frame = match dec_ctx.receive_frame() {
+                Ok(frame) => frame,
+                Err(RsmpegError::DecoderDrainError) | Err(RsmpegError::DecoderFlushedError) => {
+                    break
+                }
+                Err(e) => return Err(e.into()),
+            };
+            let mut frame = converter.convert(&frame)?;
+            frame.set_pts(pts);
+            pts += 1;
+            enc_ctx.send_frame(Some(&frame))?;
+            let mut packet = enc_ctx.receive_packet()?;
+            packet.set_stream_index(0);
+            packet.rescale_ts(enc_ctx.time_base, ofmt_ctx.streams()[0].time_base);
+            ofmt_ctx.write_frame(&mut packet)?;
+        }
+    }
+    ofmt_ctx.write_trailer()?;
+    Ok(())
+}
+
+fn count_image_sequence(pattern: &CStr) -> Result<usize> {
+    let image2 = AVInputFormat::find(cstr!("image2")).context("image2 not found")?;
+    let mut ifmt_ctx = AVFormatContextInput::open(pattern, Some(&*image2), &mut None)?;
+    let (video_index, decoder) = ifmt_ctx
+        .find_best_stream(ffi::AVMEDIA_TYPE_VIDEO)?
+        .context("Failed to find video stream")?;
+    let mut dec_ctx = AVCodecContext::new(&decoder);
+    dec_ctx.apply_codecpar(&ifmt_ctx.streams()[video_index].codecpar())?;
+    dec_ctx.open(None)?;
+
+    let mut count = 0;
+    while let Some(packet) = ifmt_ctx.read_packet()? {
+        dec_ctx.send_packet(Some(&packet))?;
+        let frame = dec_ctx.receive_frame()?;
+        assert_eq!(frame.format, ffi::AV_PIX_FMT_RGB24);
+        count += 1;
+    }
+    Ok(count)
+}
+
+#[test]
+fn test_image_sequence0() {
+    let dir = "tests/output/image_sequence";
+    let _ = fs::remove_dir_all(dir);
+    fs::create_dir_all(dir).unwrap();
+    let pattern = cstr!("tests/output/image_sequence/frame_%04d.png");
+    write_image_sequence(cstr!("tests/assets/vids/bear.mp4"), pattern, 5).unwrap();
+    for i in 1..=5 {
+        assert!(Path::new(&format!("{}/frame_{:04}.png", dir, i)).exists());
+    }
+    assert!(!Path::new(&format!("{}/frame_0006.png", dir)).exists());
+    assert_eq!(count_image_sequence(pattern).unwrap(), 5);
+}
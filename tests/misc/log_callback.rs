@@ -0,0 +1,95 @@
+//! Route FFmpeg's log messages to a Rust closure.
+use cstr::cstr;
+use rsmpeg::{avformat::AVFormatContextInput, avutil::log, ffi};
+use std::{
+    fs, ptr,
+    sync::{Arc, Mutex},
+};
+
+#[test]
+fn test_log_callback() {
+    let messages = Arc::new(Mutex::new(Vec::<(i32, String)>::new()));
+    {
+        let messages = messages.clone();
+        log::set_callback(move |level, message| {
+            messages.lock().unwrap().push((level, message.to_string()));
+        });
+    }
+    let level = log::get_level();
+    log::set_level(level);
+    assert_eq!(log::get_level(), level);
+
+    // A line logged in two parts comes in one call.
+    unsafe {
+        ffi::av_log(
+            ptr::null_mut(),
+            ffi::AV_LOG_WARNING as i32,
+            cstr!("rsmpeg log test %d, ").as_ptr(),
+            42,
+        );
+        ffi::av_log(
+            ptr::null_mut(),
+            ffi::AV_LOG_WARNING as i32,
+            cstr!("%s\n").as_ptr(),
+            cstr!("second part").as_ptr(),
+        );
+        // Dropped as less important than the level.
+        ffi::av_log(
+            ptr::null_mut(),
+            ffi::AV_LOG_TRACE as i32,
+            cstr!("rsmpeg log test trace\n").as_ptr(),
+        );
+    }
+
+    // A warning of the demuxer, prefixed by its name.
+    fs::create_dir_all("tests/output/log_callback/").unwrap();
+    let data = fs::read("tests/assets/vids/bear.mp4").unwrap();
+    fs::write(
+        "tests/output/log_callback/truncated.mp4",
+        &data[..data.len() / 2],
+    )
+    .unwrap();
+    if let Ok(mut input) = AVFormatContextInput::open(
+        cstr!("tests/output/log_callback/truncated.mp4"),
+        None,
+        &mut None,
+    ) {
+        while let Ok(Some(_)) = input.read_packet() {}
+    }
+
+    log::reset_callback();
+    let messages = messages.lock().unwrap();
+    // Other tests log concurrently.
+    assert!(messages.contains(&(
+        ffi::AV_LOG_WARNING as i32,
+        "rsmpeg log test 42, second part".to_string()
+    )));
+    assert!(!messages
+        .iter()
+        .any(|(_, x)| x.contains("rsmpeg log test trace")));
+    assert!(messages
+        .iter()
+        .any(|(level, x)| *level <= ffi::AV_LOG_WARNING as i32 && x.contains("[mov,mp4")));
+    drop(messages);
+
+    // The callback isn't called under the lock, so it can replace itself.
+    let reset = Arc::new(Mutex::new(false));
+    {
+        let reset = reset.clone();
+        log::set_callback(move |_, message| {
+            if message.contains("rsmpeg log test reset") {
+                log::reset_callback();
+                *reset.lock().unwrap() = true;
+            }
+        });
+    }
+    unsafe {
+        ffi::av_log(
+            ptr::null_mut(),
+            ffi::AV_LOG_WARNING as i32,
+            cstr!("rsmpeg log test reset\n").as_ptr(),
+        );
+    }
+    log::reset_callback();
+    assert!(*reset.lock().unwrap());
+}
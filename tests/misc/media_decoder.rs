@@ -0,0 +1,75 @@
+//! Decode several streams of an input with `MediaDecoder`.
+use cstr::cstr;
+use rsmpeg::{
+    avformat::AVFormatContextInput,
+    ffi,
+    helpers::{MediaDecoder, MediaEvent, StreamSelection},
+};
+use std::collections::BTreeMap;
+
+/// Decode bear.mp4 with `selection`, return the number of frames per stream.
+fn decode(selection: StreamSelection) -> BTreeMap<usize, usize> {
+    let mut ifmt_ctx =
+        AVFormatContextInput::open(cstr!("tests/assets/vids/bear.mp4"), None, &mut None).unwrap();
+    let time_bases: Vec<_> = ifmt_ctx
+        .streams()
+        .iter()
+        .map(|x| (x.time_base.num, x.time_base.den))
+        .collect();
+    let mut decoder = MediaDecoder::new(&mut ifmt_ctx, selection).unwrap();
+    for stream in decoder.streams() {
+        let time_base = decoder.time_base(stream).unwrap();
+        assert_eq!((time_base.num, time_base.den), time_bases[stream]);
+        assert!(decoder.decoder(stream).unwrap().is_open());
+    }
+
+    let mut counts = BTreeMap::new();
+    while let Some(event) = decoder.next_event().unwrap() {
+        let stream = match event {
+            MediaEvent::Video { stream, frame } => {
+                assert!(frame.width > 0);
+                stream
+            }
+            MediaEvent::Audio { stream, frame } => {
+                assert!(frame.nb_samples > 0);
+                stream
+            }
+            MediaEvent::Subtitle { stream, .. } => stream,
+        };
+        *counts.entry(stream).or_insert(0) += 1;
+    }
+    // Drained for good.
+    assert!(decoder.next_event().unwrap().is_none());
+    counts
+}
+
+#[test]
+fn test_media_decoder0() {
+    let counts = decode(StreamSelection::All);
+    // bear.mp4 has a video and an audio stream.
+    assert_eq!(counts.len(), 2);
+    assert!(counts.values().all(|x| *x > 0));
+    assert_eq!(decode(StreamSelection::All), counts);
+    assert_eq!(decode(StreamSelection::Best), counts);
+}
+
+#[test]
+fn test_media_decoder1() {
+    let ifmt_ctx =
+        AVFormatContextInput::open(cstr!("tests/assets/vids/bear.mp4"), None, &mut None).unwrap();
+    let (audio_index, _) = ifmt_ctx
+        .find_best_stream(ffi::AVMEDIA_TYPE_AUDIO)
+        .unwrap()
+        .unwrap();
+    let all = decode(StreamSelection::All);
+    let counts = decode(StreamSelection::Indices(vec![audio_index]));
+    assert_eq!(counts.len(), 1);
+    assert_eq!(counts[&audio_index], all[&audio_index]);
+}
+
+#[test]
+fn test_media_decoder2() {
+    let mut ifmt_ctx =
+        AVFormatContextInput::open(cstr!("tests/assets/vids/bear.mp4"), None, &mut None).unwrap();
+    assert!(MediaDecoder::new(&mut ifmt_ctx, StreamSelection::Indices(vec![42])).is_err());
+}
@@ -1,5 +1,12 @@
 use anyhow::{Context, Result};
-use rsmpeg::{avcodec::AVCodecContext, avformat::AVFormatContextInput, avutil::av_q2d, ffi};
+use cstr::cstr;
+use rsmpeg::{
+    avcodec::AVCodecContext,
+    avformat::{AVFormatContextInput, AVFormatContextOutput},
+    avutil::{av_q2d, AVDictionary},
+    error::RsmpegError,
+    ffi,
+};
 use std::ffi::CString;
 
 /// Get metadata key-value pair form a video file.
@@ -84,3 +91,73 @@ fn metadata_test1() {
         ]
     );
 }
+
+#[test]
+fn metadata_prefix_test() {
+    let input =
+        AVFormatContextInput::open(cstr!("tests/assets/vids/bear.mp4"), None, &mut None).unwrap();
+    let metadata = input.metadata().unwrap();
+    assert_eq!(
+        metadata.get(cstr!("major_brand"), None, 0).unwrap().value(),
+        cstr!("isom")
+    );
+    let keys: Vec<_> = metadata
+        .get_prefixed(cstr!("m"))
+        .map(|x| x.key().to_str().unwrap().to_string())
+        .collect();
+    assert_eq!(keys, ["major_brand", "minor_version"]);
+
+    let stream_metadata = input.streams()[0].metadata().unwrap();
+    assert!(stream_metadata
+        .get(cstr!("handler_name"), None, 0)
+        .is_some());
+}
+
+#[test]
+fn metadata_write_test() {
+    std::fs::create_dir_all("tests/output/metadata/").unwrap();
+    let output_path = cstr!("tests/output/metadata/bear.mkv");
+    {
+        let mut input =
+            AVFormatContextInput::open(cstr!("tests/assets/vids/bear.mp4"), None, &mut None)
+                .unwrap();
+        let mut output = AVFormatContextOutput::create(output_path, None).unwrap();
+        output
+            .set_metadata(Some(
+                AVDictionary::new(cstr!("title"), cstr!("Bear"), 0).set(
+                    cstr!("artist"),
+                    cstr!("rsmpeg"),
+                    0,
+                ),
+            ))
+            .unwrap();
+        for stream in input.streams() {
+            output.new_stream_copy_from(stream, true).unwrap();
+        }
+        output.write_header(&mut None).unwrap();
+        assert_eq!(
+            output.set_metadata(None),
+            Err(RsmpegError::HeaderAlreadyWrittenError)
+        );
+        while let Some(mut packet) = input.read_packet().unwrap() {
+            let index = packet.stream_index as usize;
+            packet.rescale_ts(
+                input.streams()[index].time_base,
+                output.streams()[index].time_base,
+            );
+            output.interleaved_write_frame(&mut packet).unwrap();
+        }
+        output.write_trailer().unwrap();
+    }
+
+    let input = AVFormatContextInput::open(output_path, None, &mut None).unwrap();
+    let metadata = input.metadata().unwrap();
+    assert_eq!(
+        metadata.get(cstr!("title"), None, 0).unwrap().value(),
+        cstr!("Bear")
+    );
+    assert_eq!(
+        metadata.get(cstr!("artist"), None, 0).unwrap().value(),
+        cstr!("rsmpeg")
+    );
+}
@@ -1,7 +1,42 @@
+mod attachment;
 mod av_spliter;
+#[cfg(feature = "avdevice")]
+mod avdevice;
+#[cfg(feature = "async-io")]
+mod avio_async;
+mod avio_dir;
+mod avio_read_seek;
 mod avio_reading;
 mod avio_writing;
+mod chapters;
+mod codecpar_copy;
+mod concat;
+mod decode_param_change;
+mod decode_subtitle;
+mod drain;
+mod filter_command;
+mod filter_dump;
+mod filter_parse_full;
+mod filter_specs;
+mod frame_reuse;
+mod gapless;
+mod get_buffer;
+mod hls_output;
+mod image_buffer;
 mod image_dump;
+mod image_sequence;
+mod log_callback;
+mod media_decoder;
 mod metadata;
+mod network;
+mod opaque;
+mod output_state;
+mod pipeline_threads;
+mod rotation;
+mod save_frame;
+mod seek;
+mod stream_copy;
 mod thumbnail;
-mod tutorial01;
\ No newline at end of file
+mod tutorial01;
+mod uncoded_frame;
+mod utils;
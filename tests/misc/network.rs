@@ -0,0 +1,114 @@
+//! Open an HTTP url served from a local socket with
+//! `AVFormatContextInput::open_network`, abort opening a server that never
+//! answers with an interrupt callback.
+use cstr::cstr;
+use rsmpeg::{
+    avformat::{
+        avio_check, avio_find_protocol_name, avio_protocols, network_deinit, network_init,
+        AVFormatContextInput, NetworkOptions, RtspTransport,
+    },
+    error::RsmpegError,
+    ffi,
+};
+use std::{
+    ffi::CString,
+    fs,
+    io::{BufRead, BufReader, Write},
+    net::TcpListener,
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Serve `body` to every request until the process exits, return the port.
+fn serve(body: Vec<u8>) -> u16 {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            // Skip the request headers.
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut line = String::new();
+            while reader.read_line(&mut line).unwrap() > 2 {
+                line.clear();
+            }
+            let header = format!(
+                "HTTP/1.0 200 OK\r\nContent-Type: video/mp4\r\nContent-Length: {}\r\n\r\n",
+                body.len()
+            );
+            // The demuxer may close the connection early to seek.
+            let _ = stream
+                .write_all(header.as_bytes())
+                .and_then(|_| stream.write_all(&body));
+        }
+    });
+    port
+}
+
+#[test]
+#[ignore = "requires FFmpeg built with network protocols"]
+fn test_network0() {
+    let port = serve(fs::read("tests/assets/vids/bear.mp4").unwrap());
+    let url = CString::new(format!("http://127.0.0.1:{}/bear.mp4", port)).unwrap();
+    let options = NetworkOptions::new()
+        .rw_timeout(Duration::from_secs(5))
+        .timeout(Duration::from_secs(5))
+        .reconnect(true)
+        // Not an HTTP option, dropped with a warning.
+        .rtsp_transport(RtspTransport::Tcp);
+    let mut ifmt_ctx = AVFormatContextInput::open_network(&url, &options).unwrap();
+    assert!(ifmt_ctx.nb_streams > 0);
+    assert!(ifmt_ctx.read_packet().unwrap().is_some());
+}
+
+#[test]
+fn test_network_interrupt() {
+    if !avio_protocols(false).any(|x| x == cstr!("http")) {
+        eprintln!("No http protocol, skipped.");
+        return;
+    }
+    network_init();
+    // Accepts connections and never answers.
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    thread::spawn(move || {
+        let _streams: Vec<_> = listener.incoming().collect();
+    });
+    let url = CString::new(format!("http://127.0.0.1:{}/never.mp4", port)).unwrap();
+
+    let start = Instant::now();
+    let deadline = start + Duration::from_millis(300);
+    let result = AVFormatContextInput::open_with_interrupt(&url, None, &mut None, move || {
+        Instant::now() > deadline
+    });
+    assert!(matches!(result, Err(RsmpegError::Interrupted)));
+    assert!(start.elapsed() < Duration::from_secs(10));
+
+    // Panics don't unwind into FFmpeg, they abort the operation as well.
+    let result = AVFormatContextInput::open_with_interrupt(&url, None, &mut None, || {
+        panic!("interrupt callback panicked")
+    });
+    assert!(matches!(result, Err(RsmpegError::Interrupted)));
+    network_deinit();
+}
+
+#[test]
+fn test_protocols() {
+    let inputs: Vec<_> = avio_protocols(false).collect();
+    let outputs: Vec<_> = avio_protocols(true).collect();
+    assert!(inputs.contains(&cstr!("file")));
+    assert!(outputs.contains(&cstr!("file")));
+
+    assert_eq!(
+        avio_find_protocol_name(cstr!("tests/assets/vids/bear.mp4")),
+        Some(cstr!("file"))
+    );
+    assert_eq!(avio_find_protocol_name(cstr!("nonexistent://a")), None);
+    let access = avio_check(cstr!("tests/assets/vids/bear.mp4"), ffi::AVIO_FLAG_READ).unwrap();
+    assert_eq!(access & ffi::AVIO_FLAG_READ, ffi::AVIO_FLAG_READ);
+    assert!(avio_check(
+        cstr!("tests/assets/vids/nonexistent.mp4"),
+        ffi::AVIO_FLAG_READ
+    )
+    .is_err());
+}
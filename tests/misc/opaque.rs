@@ -0,0 +1,103 @@
+//! Attach user data to packets and read it back from the decoded frames.
+use cstr::cstr;
+use rsmpeg::{
+    avcodec::AVCodecContext,
+    avformat::AVFormatContextInput,
+    avutil::{AVBufferRef, AVFrame},
+    error::RsmpegError,
+    ffi, UnsafeDerefMut,
+};
+use std::sync::Arc;
+
+#[derive(Debug)]
+struct Capture {
+    pts: i64,
+    camera: &'static str,
+    /// Counts the living values.
+    _token: Arc<()>,
+}
+
+#[test]
+fn test_opaque0() {
+    let token = Arc::new(());
+    let mut ifmt_ctx =
+        AVFormatContextInput::open(cstr!("tests/assets/vids/bear.mp4"), None, &mut None).unwrap();
+    let (video_index, decoder) = ifmt_ctx
+        .find_best_stream(ffi::AVMEDIA_TYPE_VIDEO)
+        .unwrap()
+        .unwrap();
+    let mut dec_ctx = AVCodecContext::new(&decoder);
+    dec_ctx
+        .apply_codecpar(&ifmt_ctx.streams()[video_index].codecpar())
+        .unwrap();
+    dec_ctx.set_copy_opaque(true).unwrap();
+    dec_ctx.open(None).unwrap();
+
+    let mut frames: Vec<AVFrame> = vec![];
+    let mut nb_packets = 0;
+    loop {
+        let mut packet = ifmt_ctx.read_packet().unwrap();
+        if let Some(packet) = &mut packet {
+            if packet.stream_index as usize != video_index {
+                continue;
+            }
+            packet.set_opaque(Capture {
+                pts: packet.pts,
+                camera: "front",
+                _token: token.clone(),
+            });
+            nb_packets += 1;
+        }
+        dec_ctx.send_packet(packet.as_ref()).unwrap();
+        loop {
+            match dec_ctx.receive_frame() {
+                Ok(frame) => frames.push(frame),
+                Err(RsmpegError::DecoderDrainError) => break,
+                Err(RsmpegError::DecoderFlushedError) => break,
+                Err(e) => panic!("{}", e),
+            }
+        }
+        if packet.is_none() {
+            break;
+        }
+    }
+    drop(dec_ctx);
+
+    assert_eq!(frames.len(), nb_packets);
+    for frame in &frames {
+        let capture = frame.opaque_ref::<Capture>().unwrap();
+        assert_eq!(capture.pts, frame.pts);
+        assert_eq!(capture.camera, "front");
+        // Values of other types are never handed out.
+        assert!(frame.opaque_ref::<i64>().is_none());
+    }
+    // Only the frames hold the values now.
+    assert_eq!(Arc::strong_count(&token), frames.len() + 1);
+    drop(frames);
+    assert_eq!(Arc::strong_count(&token), 1);
+}
+
+#[test]
+fn test_opaque1() {
+    let mut frame = AVFrame::new();
+    assert!(frame.opaque_ref::<u32>().is_none());
+    frame.set_opaque(42u32);
+    assert_eq!(frame.opaque_ref::<u32>(), Some(&42));
+    assert!(frame.opaque_ref::<i32>().is_none());
+    frame.set_opaque(String::from("replaced"));
+    assert_eq!(frame.opaque_ref::<String>().unwrap(), "replaced");
+    assert!(frame.opaque_ref::<u32>().is_none());
+
+    // Shared with the new reference.
+    let cloned = frame.clone();
+    assert_eq!(cloned.opaque_ref::<String>().unwrap(), "replaced");
+
+    // Buffers not holding a value are never interpreted.
+    let buffer = AVBufferRef::zeroed(64);
+    unsafe {
+        let frame = frame.deref_mut();
+        ffi::av_buffer_unref(&mut frame.opaque_ref);
+        frame.opaque_ref = buffer.into_raw().as_ptr();
+    }
+    assert!(frame.opaque_ref::<u32>().is_none());
+}
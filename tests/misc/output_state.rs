@@ -0,0 +1,102 @@
+//! Header and trailer ordering of `AVFormatContextOutput`.
+use cstr::cstr;
+use rsmpeg::{
+    avformat::{AVFormatContextInput, AVFormatContextOutput, OutputState},
+    error::RsmpegError,
+    ffi,
+};
+use std::ffi::CStr;
+
+/// Create an output with the video stream of bear.mp4, return the input for
+/// reading packets.
+fn create_output(output_path: &CStr) -> (AVFormatContextInput, AVFormatContextOutput) {
+    std::fs::create_dir_all("tests/output/output_state/").unwrap();
+    let ifmt_ctx =
+        AVFormatContextInput::open(cstr!("tests/assets/vids/bear.mp4"), None, &mut None).unwrap();
+    let mut ofmt_ctx = AVFormatContextOutput::create(output_path, None).unwrap();
+    {
+        let in_stream = ifmt_ctx
+            .streams()
+            .iter()
+            .find(|x| x.codecpar().codec_type == ffi::AVMEDIA_TYPE_VIDEO)
+            .unwrap();
+        let mut out_stream = ofmt_ctx.new_stream();
+        out_stream.set_codecpar(in_stream.codecpar().clone());
+        out_stream.set_time_base(in_stream.time_base);
+    }
+    (ifmt_ctx, ofmt_ctx)
+}
+
+/// Copy the video packets of `ifmt_ctx` into stream 0 of `ofmt_ctx`.
+fn copy_packets(ifmt_ctx: &mut AVFormatContextInput, ofmt_ctx: &mut AVFormatContextOutput) {
+    while let Some(mut packet) = ifmt_ctx.read_packet().unwrap() {
+        let in_stream = &ifmt_ctx.streams()[packet.stream_index as usize];
+        if in_stream.codecpar().codec_type != ffi::AVMEDIA_TYPE_VIDEO {
+            continue;
+        }
+        packet.rescale_ts(in_stream.time_base, ofmt_ctx.streams()[0].time_base);
+        packet.set_stream_index(0);
+        packet.set_pos(-1);
+        ofmt_ctx.interleaved_write_frame(&mut packet).unwrap();
+    }
+}
+
+#[test]
+fn test_output_state0() {
+    let output_path = cstr!("tests/output/output_state/misordering.mp4");
+    let (mut ifmt_ctx, mut ofmt_ctx) = create_output(output_path);
+    let mut packet = ifmt_ctx.read_packet().unwrap().unwrap();
+    packet.set_stream_index(0);
+
+    assert_eq!(ofmt_ctx.state(), OutputState::Unstarted);
+    assert_eq!(
+        ofmt_ctx.write_frame(&mut packet),
+        Err(RsmpegError::HeaderNotWrittenError)
+    );
+    assert_eq!(
+        ofmt_ctx.interleaved_write_frame(&mut packet),
+        Err(RsmpegError::HeaderNotWrittenError)
+    );
+    assert_eq!(
+        ofmt_ctx.write_trailer(),
+        Err(RsmpegError::HeaderNotWrittenError)
+    );
+
+    ofmt_ctx.write_header(&mut None).unwrap();
+    assert_eq!(ofmt_ctx.state(), OutputState::Writing);
+    assert_eq!(
+        ofmt_ctx.write_header(&mut None),
+        Err(RsmpegError::HeaderAlreadyWrittenError)
+    );
+
+    copy_packets(&mut ifmt_ctx, &mut ofmt_ctx);
+    ofmt_ctx.write_trailer().unwrap();
+    assert_eq!(ofmt_ctx.state(), OutputState::Finalized);
+    assert_eq!(
+        ofmt_ctx.interleaved_write_frame(&mut packet),
+        Err(RsmpegError::AlreadyFinalizedError)
+    );
+    assert_eq!(
+        ofmt_ctx.write_trailer(),
+        Err(RsmpegError::AlreadyFinalizedError)
+    );
+    assert_eq!(
+        ofmt_ctx.write_header(&mut None),
+        Err(RsmpegError::AlreadyFinalizedError)
+    );
+}
+
+#[test]
+fn test_output_state1() {
+    // The mp4 index is written by the trailer, dropping the output after the
+    // header finalizes it.
+    let output_path = cstr!("tests/output/output_state/dropped.mp4");
+    {
+        let (mut ifmt_ctx, mut ofmt_ctx) = create_output(output_path);
+        ofmt_ctx.write_header(&mut None).unwrap();
+        copy_packets(&mut ifmt_ctx, &mut ofmt_ctx);
+    }
+    let mut ifmt_ctx = AVFormatContextInput::open(output_path, None, &mut None).unwrap();
+    assert!(ifmt_ctx.duration > 0);
+    assert!(ifmt_ctx.read_packet().unwrap().is_some());
+}
@@ -0,0 +1,238 @@
+//! Demux, decode, filter and encode on their own threads, with the contexts
+//! moved into the threads.
+use cstr::cstr;
+use rsmpeg::{
+    avcodec::{AVCodec, AVCodecContext, AVPacket},
+    avfilter::{AVBufferSrcParameters, AVFilter, AVFilterGraph, AVFilterInOut},
+    avformat::{AVFormatContextInput, AVFormatContextOutput},
+    avutil::{ra, AVFrame},
+    error::RsmpegError,
+    ffi,
+    swresample::SwrContext,
+    swscale::{Converter, SwsContext, SwsFlags},
+};
+use std::{sync::mpsc, thread};
+
+#[test]
+fn test_send() {
+    fn assert_send<T: Send>() {}
+    assert_send::<AVFormatContextInput>();
+    assert_send::<AVFormatContextOutput>();
+    assert_send::<AVCodecContext>();
+    assert_send::<AVFilterGraph>();
+    assert_send::<AVPacket>();
+    assert_send::<AVFrame>();
+    assert_send::<SwsContext>();
+    assert_send::<SwrContext>();
+    assert_send::<Converter>();
+}
+
+/// Send all the frames `dec_ctx` has to `frame_tx`, return the number of
+/// frames sent.
+fn drain_decoder(dec_ctx: &mut AVCodecContext, frame_tx: &mpsc::Sender<AVFrame>) -> usize {
+    let mut nb_frames = 0;
+    loop {
+        match dec_ctx.receive_frame() {
+            Ok(frame) => frame_tx.send(frame).unwrap(),
+            Err(RsmpegError::DecoderDrainError) | Err(RsmpegError::DecoderFlushedError) => {
+                return nb_frames
+            }
+            Err(e) => panic!("{}", e),
+        }
+        nb_frames += 1;
+    }
+}
+
+/// Write all the packets `enc_ctx` has to `ofmt_ctx`.
+fn drain_encoder(enc_ctx: &mut AVCodecContext, ofmt_ctx: &mut AVFormatContextOutput) {
+    loop {
+        let mut packet = match enc_ctx.receive_packet() {
+            Ok(packet) => packet,
+            Err(RsmpegError::EncoderDrainError) | Err(RsmpegError::EncoderFlushedError) => return,
+            Err(e) => panic!("{}", e),
+        };
+        packet.rescale_ts(enc_ctx.time_base, ofmt_ctx.streams()[0].time_base);
+        packet.set_stream_index(0);
+        ofmt_ctx.interleaved_write_frame(&mut packet).unwrap();
+    }
+}
+
+#[test]
+fn test_pipeline_threads0() {
+    std::fs::create_dir_all("tests/output/pipeline_threads/").unwrap();
+    let output_path = cstr!("tests/output/pipeline_threads/bear.mp4");
+
+    // Everything is set up on the main thread, then moved.
+    let ifmt_ctx =
+        AVFormatContextInput::open(cstr!("tests/assets/vids/bear.mp4"), None, &mut None).unwrap();
+    let (video_index, decoder) = ifmt_ctx
+        .find_best_stream(ffi::AVMEDIA_TYPE_VIDEO)
+        .unwrap()
+        .unwrap();
+    let stream = &ifmt_ctx.streams()[video_index];
+    let time_base = stream.time_base;
+    let framerate = stream.guess_framerate().unwrap();
+    let mut dec_ctx = AVCodecContext::new(&decoder);
+    dec_ctx.apply_codecpar(&stream.codecpar()).unwrap();
+    dec_ctx.try_set_pkt_timebase(time_base).unwrap();
+    dec_ctx.open(None).unwrap();
+
+    let filter_graph = AVFilterGraph::new();
+    {
+        let mut parameters =
+            AVBufferSrcParameters::video(dec_ctx.width, dec_ctx.height, dec_ctx.pix_fmt, time_base);
+        parameters.set_sample_aspect_ratio(ra(1, 1));
+        let buffer = AVFilter::get_by_name(cstr!("buffer")).unwrap();
+        let buffersink = AVFilter::get_by_name(cstr!("buffersink")).unwrap();
+        let mut buffersrc_ctx = filter_graph
+            .alloc_filter_context(&buffer, cstr!("in"))
+            .unwrap();
+        buffersrc_ctx.buffersrc_parameters_set(&parameters).unwrap();
+        buffersrc_ctx.init(None).unwrap();
+        let buffersink_ctx = filter_graph
+            .create_filter_context(&buffersink, cstr!("out"), None)
+            .unwrap();
+        let outputs = AVFilterInOut::new(cstr!("in"), &buffersrc_ctx, 0);
+        let inputs = AVFilterInOut::new(cstr!("out"), &buffersink_ctx, 0);
+        filter_graph
+            .parse_ptr(cstr!("hflip"), Some(inputs), Some(outputs))
+            .unwrap();
+        filter_graph.config().unwrap();
+    }
+
+    let encoder = AVCodec::find_encoder(ffi::AV_CODEC_ID_MPEG4).unwrap();
+    let mut enc_ctx = AVCodecContext::new(&encoder);
+    enc_ctx.try_set_width(dec_ctx.width).unwrap();
+    enc_ctx.try_set_height(dec_ctx.height).unwrap();
+    enc_ctx.try_set_pix_fmt(dec_ctx.pix_fmt).unwrap();
+    enc_ctx.set_time_base_from_framerate(framerate).unwrap();
+    enc_ctx
+        .try_set_flags(enc_ctx.flags | ffi::AV_CODEC_FLAG_GLOBAL_HEADER as i32)
+        .unwrap();
+    enc_ctx.open(None).unwrap();
+    let mut ofmt_ctx = AVFormatContextOutput::create(output_path, None).unwrap();
+    {
+        let mut out_stream = ofmt_ctx.new_stream();
+        out_stream.set_codecpar(enc_ctx.extract_codecpar());
+        out_stream.set_time_base(enc_ctx.time_base);
+    }
+    ofmt_ctx.write_header(&mut None).unwrap();
+
+    let (packet_tx, packet_rx) = mpsc::channel::<AVPacket>();
+    let (frame_tx, frame_rx) = mpsc::channel::<AVFrame>();
+    let (filtered_tx, filtered_rx) = mpsc::channel::<AVFrame>();
+
+    let demux = thread::spawn(move || {
+        let mut ifmt_ctx = ifmt_ctx;
+        while let Some(packet) = ifmt_ctx.read_packet().unwrap() {
+            if packet.stream_index as usize == video_index {
+                packet_tx.send(packet).unwrap();
+            }
+        }
+    });
+
+    let decode = thread::spawn(move || {
+        let mut nb_frames = 0;
+        for packet in packet_rx {
+            dec_ctx.send_packet(Some(&packet)).unwrap();
+            nb_frames += drain_decoder(&mut dec_ctx, &frame_tx);
+        }
+        dec_ctx.send_packet(None).unwrap();
+        nb_frames + drain_decoder(&mut dec_ctx, &frame_tx)
+    });
+
+    let filter = thread::spawn(move || {
+        let mut filter_graph = filter_graph;
+        let pull = |filter_graph: &mut AVFilterGraph| loop {
+            let mut buffersink_ctx = filter_graph.get_filter(cstr!("out")).unwrap();
+            match buffersink_ctx.buffersink_get_frame(None) {
+                Ok(frame) => filtered_tx.send(frame).unwrap(),
+                Err(RsmpegError::BufferSinkDrainError) | Err(RsmpegError::BufferSinkEofError) => {
+                    break
+                }
+                Err(e) => panic!("{}", e),
+            }
+        };
+        for frame in frame_rx {
+            let mut buffersrc_ctx = filter_graph.get_filter(cstr!("in")).unwrap();
+            buffersrc_ctx
+                .buffersrc_add_frame(Some(frame), None)
+                .unwrap();
+            pull(&mut filter_graph);
+        }
+        let mut buffersrc_ctx = filter_graph.get_filter(cstr!("in")).unwrap();
+        buffersrc_ctx.buffersrc_add_frame(None, None).unwrap();
+        pull(&mut filter_graph);
+    });
+
+    let encode = thread::spawn(move || {
+        let mut nb_frames = 0;
+        for mut frame in filtered_rx {
+            frame.set_pts(nb_frames);
+            frame.set_pict_type(ffi::AV_PICTURE_TYPE_NONE);
+            enc_ctx.send_frame(Some(&frame)).unwrap();
+            drain_encoder(&mut enc_ctx, &mut ofmt_ctx);
+            nb_frames += 1;
+        }
+        enc_ctx.send_frame(None).unwrap();
+        drain_encoder(&mut enc_ctx, &mut ofmt_ctx);
+        ofmt_ctx.write_trailer().unwrap();
+        nb_frames
+    });
+
+    demux.join().unwrap();
+    let nb_decoded = decode.join().unwrap();
+    filter.join().unwrap();
+    let nb_encoded = encode.join().unwrap();
+    assert!(nb_decoded > 0);
+    assert_eq!(nb_encoded as usize, nb_decoded);
+
+    let mut ifmt_ctx = AVFormatContextInput::open(output_path, None, &mut None).unwrap();
+    let mut nb_packets = 0;
+    while ifmt_ctx.read_packet().unwrap().is_some() {
+        nb_packets += 1;
+    }
+    assert_eq!(nb_packets, nb_decoded);
+}
+
+#[test]
+fn test_pipeline_threads1() {
+    // Decode on a worker thread, scale the frames on the current one.
+    let (frame_tx, frame_rx) = mpsc::channel::<AVFrame>();
+    let decode = thread::spawn(move || {
+        let mut ifmt_ctx =
+            AVFormatContextInput::open(cstr!("tests/assets/vids/bear.mp4"), None, &mut None)
+                .unwrap();
+        let (video_index, decoder) = ifmt_ctx
+            .find_best_stream(ffi::AVMEDIA_TYPE_VIDEO)
+            .unwrap()
+            .unwrap();
+        let mut dec_ctx = AVCodecContext::new(&decoder);
+        dec_ctx
+            .apply_codecpar(&ifmt_ctx.streams()[video_index].codecpar())
+            .unwrap();
+        dec_ctx.open(None).unwrap();
+        let mut nb_frames = 0;
+        while let Some(packet) = ifmt_ctx.read_packet().unwrap() {
+            if packet.stream_index as usize == video_index {
+                dec_ctx.send_packet(Some(&packet)).unwrap();
+                nb_frames += drain_decoder(&mut dec_ctx, &frame_tx);
+            }
+        }
+        dec_ctx.send_packet(None).unwrap();
+        nb_frames + drain_decoder(&mut dec_ctx, &frame_tx)
+    });
+
+    let mut converter = Converter::new(64, 48, ffi::AV_PIX_FMT_RGB24, SwsFlags::BILINEAR).unwrap();
+    let mut nb_scaled = 0;
+    for frame in frame_rx {
+        let rgb = converter.convert(&frame).unwrap();
+        assert_eq!((rgb.width, rgb.height), (64, 48));
+        assert_eq!(rgb.format, ffi::AV_PIX_FMT_RGB24);
+        assert_eq!(rgb.pts, frame.pts);
+        nb_scaled += 1;
+    }
+    let nb_decoded = decode.join().unwrap();
+    assert!(nb_decoded > 0);
+    assert_eq!(nb_scaled, nb_decoded);
+}
@@ -0,0 +1,70 @@
+//! Write the rotation of a video stream and read it back, with both MP4 and
+//! Matroska.
+use cstr::cstr;
+use rsmpeg::{
+    avformat::{AVFormatContextInput, AVFormatContextOutput},
+    ffi,
+};
+use std::ffi::CStr;
+
+/// Remux the video stream of bear.mp4 into `output_path` with `degrees` of
+/// rotation.
+fn remux_rotated(output_path: &CStr, degrees: f64) {
+    let mut ifmt_ctx =
+        AVFormatContextInput::open(cstr!("tests/assets/vids/bear.mp4"), None, &mut None).unwrap();
+    let (video_index, _) = ifmt_ctx
+        .find_best_stream(ffi::AVMEDIA_TYPE_VIDEO)
+        .unwrap()
+        .unwrap();
+    let mut ofmt_ctx = AVFormatContextOutput::create(output_path, None).unwrap();
+    let index = ofmt_ctx
+        .new_stream_copy_from(&ifmt_ctx.streams()[video_index], false)
+        .unwrap();
+    {
+        let out_stream = &mut ofmt_ctx.streams_mut()[index];
+        out_stream.set_rotation(degrees).unwrap();
+        assert!((out_stream.rotation().unwrap() - degrees).abs() < 1e-6);
+    }
+    ofmt_ctx.write_header(&mut None).unwrap();
+    while let Some(mut packet) = ifmt_ctx.read_packet().unwrap() {
+        if packet.stream_index as usize != video_index {
+            continue;
+        }
+        packet.rescale_ts(
+            ifmt_ctx.streams()[video_index].time_base,
+            ofmt_ctx.streams()[index].time_base,
+        );
+        packet.set_stream_index(index as i32);
+        packet.set_pos(-1);
+        ofmt_ctx.interleaved_write_frame(&mut packet).unwrap();
+    }
+    ofmt_ctx.write_trailer().unwrap();
+}
+
+fn rotation(path: &CStr) -> Option<f64> {
+    let ifmt_ctx = AVFormatContextInput::open(path, None, &mut None).unwrap();
+    ifmt_ctx.streams()[0].rotation()
+}
+
+#[test]
+fn test_rotation0() {
+    std::fs::create_dir_all("tests/output/rotation/").unwrap();
+    assert_eq!(rotation(cstr!("tests/assets/vids/bear.mp4")), None);
+    for output_path in [
+        cstr!("tests/output/rotation/bear.mp4"),
+        cstr!("tests/output/rotation/bear.mkv"),
+    ] {
+        remux_rotated(output_path, 90.);
+        let rotation = rotation(output_path).unwrap();
+        assert!((rotation - 90.).abs() < 1e-6, "{}", rotation);
+    }
+}
+
+#[test]
+fn test_rotation1() {
+    std::fs::create_dir_all("tests/output/rotation/").unwrap();
+    let output_path = cstr!("tests/output/rotation/bear_180.mp4");
+    remux_rotated(output_path, -180.);
+    let rotation = rotation(output_path).unwrap();
+    assert!((rotation.abs() - 180.).abs() < 1e-6, "{}", rotation);
+}
@@ -0,0 +1,125 @@
+//! Save a decoded frame as PNG and JPEG with `rsmpeg::helpers::save_frame`,
+//! and load it back with `rsmpeg::helpers::load_frame`.
+use anyhow::{Context, Result};
+use cstr::cstr;
+use rsmpeg::{
+    avcodec::AVCodecContext,
+    avformat::AVFormatContextInput,
+    avutil::AVFrame,
+    error::RsmpegError,
+    ffi,
+    helpers::{load_frame, save_frame, ImageFormat},
+    swscale::{AspectPolicy, ScaleSpec, SwsFlags, SwsScaler},
+};
+use std::{
+    ffi::{CStr, CString},
+    fs,
+    path::Path,
+};
+
+/// Decode the first video frame of the file.
+fn first_frame(path: &CStr) -> Result<AVFrame> {
+    let mut ifmt_ctx = AVFormatContextInput::open(path, None, &mut None)?;
+    let (video_index, decoder) = ifmt_ctx
+        .find_best_stream(ffi::AVMEDIA_TYPE_VIDEO)?
+        .context("Failed to find video stream")?;
+    let mut dec_ctx = AVCodecContext::new(&decoder);
+    dec_ctx.apply_codecpar(&ifmt_ctx.streams()[video_index].codecpar())?;
+    dec_ctx.open(None)?;
+
+    loop {
+        let packet = ifmt_ctx.read_packet()?;
+        if let Some(packet) = &packet {
+            if packet.stream_index as usize != video_index {
+                continue;
+            }
+        }
+        dec_ctx.send_packet(packet.as_ref())?;
+        match dec_ctx.receive_frame() {
+            Ok(frame) => return Ok(frame),
+            Err(RsmpegError::DecoderDrainError) => continue,
+            Err(e) => return Err(e.into()),
+        }
+    }
+}
+
+#[test]
+fn test_save_frame0() {
+    let dir = Path::new("tests/output/save_frame");
+    fs::create_dir_all(dir).unwrap();
+    let frame = first_frame(cstr!("tests/assets/vids/bear.mp4")).unwrap();
+
+    for (name, format) in [
+        ("bear.png", ImageFormat::Png),
+        ("bear.jpg", ImageFormat::Jpeg { quality: 90 }),
+    ] {
+        let path = dir.join(name);
+        save_frame(&frame, &path, format).unwrap();
+        let path = CString::new(path.to_str().unwrap()).unwrap();
+        let image = first_frame(&path).unwrap();
+        assert_eq!(image.width, frame.width);
+        assert_eq!(image.height, frame.height);
+    }
+
+    // Lower quality leads to smaller file.
+    let low = dir.join("bear_low.jpg");
+    save_frame(&frame, &low, ImageFormat::Jpeg { quality: 10 }).unwrap();
+    let high = dir.join("bear.jpg");
+    assert!(fs::metadata(low).unwrap().len() < fs::metadata(high).unwrap().len());
+}
+
+#[test]
+fn test_save_frame1() {
+    let dir = Path::new("tests/output/save_frame");
+    fs::create_dir_all(dir).unwrap();
+    let frame = first_frame(cstr!("tests/assets/vids/bear.mp4")).unwrap();
+
+    let path = dir.join("bear_round_trip.png");
+    save_frame(&frame, &path, ImageFormat::Png).unwrap();
+    let image = load_frame(&path).unwrap();
+    assert_eq!((image.width, image.height), (frame.width, frame.height));
+    assert_eq!(image.format, ffi::AV_PIX_FMT_RGB24);
+
+    // Odd dimensions, with alpha.
+    let spec = ScaleSpec {
+        width: 63,
+        height: 47,
+        format: ffi::AV_PIX_FMT_RGBA,
+        flags: SwsFlags::BILINEAR,
+        policy: AspectPolicy::Stretch,
+    };
+    let mut frame = SwsScaler::new().scale(&frame, &spec).unwrap();
+    let linesize = frame.linesize[0] as usize;
+    let data = frame.data_mut(0).unwrap();
+    for y in 0..47 {
+        for x in 0..63 {
+            data[y * linesize + x * 4 + 3] = (x * 4) as u8;
+        }
+    }
+
+    // PNG is lossless, alpha included.
+    let path = dir.join("bear_alpha.png");
+    save_frame(&frame, &path, ImageFormat::Png).unwrap();
+    let image = load_frame(&path).unwrap();
+    assert_eq!((image.width, image.height), (63, 47));
+    assert_eq!(image.format, ffi::AV_PIX_FMT_RGBA);
+    let size = 63 * 47 * 4;
+    let (mut expected, mut loaded) = (vec![0; size], vec![0; size]);
+    frame.copy_image_to_buffer(&mut expected, 1).unwrap();
+    image.copy_image_to_buffer(&mut loaded, 1).unwrap();
+    assert_eq!(loaded, expected);
+
+    // Chroma of JPEG is subsampled, the alpha channel is dropped.
+    let path = dir.join("bear_odd.jpg");
+    save_frame(&frame, &path, ImageFormat::Jpeg { quality: 90 }).unwrap();
+    let image = load_frame(&path).unwrap();
+    assert_eq!((image.width, image.height), (63, 47));
+    assert_eq!(image.format, ffi::AV_PIX_FMT_YUVJ420P);
+}
+
+#[test]
+fn test_load_frame_error() {
+    let err = load_frame(Path::new("tests/assets/pics/not_exist.png")).unwrap_err();
+    assert!(matches!(err, RsmpegError::ImageError("open", _)));
+    assert_eq!(err.raw_error(), Some(ffi::AVERROR(ffi::ENOENT)));
+}
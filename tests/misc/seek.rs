@@ -0,0 +1,293 @@
+//! Seek inputs by byte offset, by timestamp and to an exact frame.
+use cstr::cstr;
+use rsmpeg::{
+    avcodec::AVCodecContext,
+    avformat::{AVFormatContextInput, AVFormatContextOutput, SeekTarget},
+    avutil::{av_q2d, av_rescale_q, ra},
+    error::RsmpegError,
+    ffi,
+    helpers::{frame_at, seek_exact_counted, FrameExtractor},
+};
+
+#[test]
+fn test_seek_bytes0() {
+    let path = cstr!("tests/assets/vids/centaur.mpg");
+    let mut ifmt_ctx = AVFormatContextInput::open(path, None, &mut None).unwrap();
+    assert!(ifmt_ctx.supports_byte_seek());
+    // MPEG-PS has discontinuous timestamps.
+    assert!(ifmt_ctx.prefers_byte_seek());
+
+    let size = unsafe { ffi::avio_size(ifmt_ctx.pb) };
+    assert!(size > 0);
+    let middle = size / 2;
+    ifmt_ctx
+        .seek(None, SeekTarget::Bytes(middle as u64), 0)
+        .unwrap();
+
+    // Packets resume after the offset with timestamps in the middle of the
+    // file.
+    let duration = ifmt_ctx.duration as f64 / ffi::AV_TIME_BASE as f64;
+    let start_time = ifmt_ctx.start_time as f64 / ffi::AV_TIME_BASE as f64;
+    let mut nb_packets = 0;
+    while let Some(packet) = ifmt_ctx.read_packet().unwrap() {
+        if nb_packets == 0 {
+            assert!(packet.pos >= middle);
+        }
+        if packet.pts != ffi::AV_NOPTS_VALUE && nb_packets < 10 {
+            let time_base = ifmt_ctx.streams()[packet.stream_index as usize].time_base;
+            let time = packet.pts as f64 * av_q2d(time_base) - start_time;
+            assert!(time > duration * 0.1 && time < duration * 0.9);
+        }
+        nb_packets += 1;
+    }
+    assert!(nb_packets > 0);
+}
+
+#[test]
+fn test_seek_timestamp0() {
+    let path = cstr!("tests/assets/vids/bear.mp4");
+    let mut ifmt_ctx = AVFormatContextInput::open(path, None, &mut None).unwrap();
+    let target = ifmt_ctx.duration / 2;
+    ifmt_ctx
+        .seek(
+            None,
+            SeekTarget::Timestamp(target),
+            ffi::AVSEEK_FLAG_BACKWARD,
+        )
+        .unwrap();
+    let packet = ifmt_ctx.read_packet().unwrap().unwrap();
+    let time_base = ifmt_ctx.streams()[packet.stream_index as usize].time_base;
+    let time = packet.pts as f64 * av_q2d(time_base);
+    // Backward seeking lands on a key frame before the target.
+    assert!(time <= target as f64 / ffi::AV_TIME_BASE as f64 + 0.1);
+}
+
+#[test]
+fn test_seek_exact0() {
+    let path = cstr!("tests/assets/vids/bear.mp4");
+    let mut ifmt_ctx = AVFormatContextInput::open(path, None, &mut None).unwrap();
+    let (stream_index, decoder) = ifmt_ctx
+        .find_best_stream(ffi::AVMEDIA_TYPE_VIDEO)
+        .unwrap()
+        .unwrap();
+    let mut dec_ctx = AVCodecContext::new(&decoder);
+    dec_ctx
+        .apply_codecpar(&ifmt_ctx.streams()[stream_index].codecpar())
+        .unwrap();
+    dec_ctx.open(None).unwrap();
+
+    let stream = &ifmt_ctx.streams()[stream_index];
+    let duration = av_rescale_q(
+        ifmt_ctx.duration,
+        ra(1, ffi::AV_TIME_BASE as i32),
+        stream.time_base,
+    );
+    let start = stream.start_time.max(0);
+    // Out of order on purpose.
+    for ratio in [0.5, 0.1, 0.9, 0.33, 0.0] {
+        let target = start + (duration as f64 * ratio) as i64;
+        let (frame, discarded) =
+            seek_exact_counted(&mut ifmt_ctx, &mut dec_ctx, stream_index, target).unwrap();
+        let pts = frame.best_effort_timestamp;
+        assert!(pts <= target, "{} > {}", pts, target);
+        assert!(
+            pts + frame.duration > target,
+            "{} + {} <= {}",
+            pts,
+            frame.duration,
+            target
+        );
+        if ratio == 0.0 {
+            assert_eq!(discarded, 0);
+        }
+    }
+}
+
+#[test]
+fn test_seek_timestamp1() {
+    // Seek into the middle of an MPEG-PS and decode from the next key frame.
+    let path = cstr!("tests/assets/vids/centaur.mpg");
+    let mut ifmt_ctx = AVFormatContextInput::open(path, None, &mut None).unwrap();
+    let (stream_index, decoder) = ifmt_ctx
+        .find_best_stream(ffi::AVMEDIA_TYPE_VIDEO)
+        .unwrap()
+        .unwrap();
+    let mut dec_ctx = AVCodecContext::new(&decoder);
+    dec_ctx
+        .apply_codecpar(&ifmt_ctx.streams()[stream_index].codecpar())
+        .unwrap();
+    dec_ctx.open(None).unwrap();
+
+    let start_time = ifmt_ctx.start_time.max(0);
+    let target = start_time + ifmt_ctx.duration / 2;
+    ifmt_ctx
+        .seek(
+            None,
+            SeekTarget::Timestamp(target),
+            ffi::AVSEEK_FLAG_BACKWARD,
+        )
+        .unwrap();
+    dec_ctx.flush_buffers();
+
+    let time_base = ifmt_ctx.streams()[stream_index].time_base;
+    let mut key_frame_found = false;
+    let frame = loop {
+        let packet = ifmt_ctx.read_packet().unwrap().unwrap();
+        if packet.stream_index as usize != stream_index {
+            continue;
+        }
+        key_frame_found |= packet.flags & ffi::AV_PKT_FLAG_KEY as i32 != 0;
+        if !key_frame_found {
+            continue;
+        }
+        dec_ctx.send_packet(Some(&packet)).unwrap();
+        if let Ok(frame) = dec_ctx.receive_frame() {
+            break frame;
+        }
+    };
+    let time = av_rescale_q(
+        frame.best_effort_timestamp,
+        time_base,
+        ra(1, ffi::AV_TIME_BASE as i32),
+    );
+    assert!((time - target).abs() < ifmt_ctx.duration / 4);
+}
+
+#[test]
+fn test_seek_beyond_end0() {
+    let path = cstr!("tests/assets/vids/bear.mp4");
+    let mut ifmt_ctx = AVFormatContextInput::open(path, None, &mut None).unwrap();
+    let target = ifmt_ctx.duration * 2;
+    assert_eq!(
+        ifmt_ctx.seek(None, SeekTarget::Timestamp(target), 0),
+        Err(RsmpegError::SeekBeyondEndError)
+    );
+    // Landing on the last key frame instead.
+    ifmt_ctx
+        .seek(
+            None,
+            SeekTarget::Timestamp(target),
+            ffi::AVSEEK_FLAG_BACKWARD,
+        )
+        .unwrap();
+    assert!(ifmt_ctx.read_packet().unwrap().is_some());
+    assert_eq!(
+        ifmt_ctx.seek(Some(2), SeekTarget::Timestamp(0), 0),
+        Err(RsmpegError::AVError(ffi::AVERROR_STREAM_NOT_FOUND))
+    );
+}
+
+#[test]
+fn test_seek_raw0() {
+    // Raw H.264 has neither an index nor timestamps in the file.
+    std::fs::create_dir_all("tests/output/seek/").unwrap();
+    let output_path = cstr!("tests/output/seek/bear.h264");
+    {
+        let mut ifmt_ctx =
+            AVFormatContextInput::open(cstr!("tests/assets/vids/bear.mp4"), None, &mut None)
+                .unwrap();
+        let (video_index, _) = ifmt_ctx
+            .find_best_stream(ffi::AVMEDIA_TYPE_VIDEO)
+            .unwrap()
+            .unwrap();
+        let mut ofmt_ctx = AVFormatContextOutput::create(output_path, None).unwrap();
+        ofmt_ctx
+            .new_stream_copy_from(&ifmt_ctx.streams()[video_index], true)
+            .unwrap();
+        ofmt_ctx.write_header(&mut None).unwrap();
+        while let Some(mut packet) = ifmt_ctx.read_packet().unwrap() {
+            if packet.stream_index as usize != video_index {
+                continue;
+            }
+            packet.rescale_ts(
+                ifmt_ctx.streams()[video_index].time_base,
+                ofmt_ctx.streams()[0].time_base,
+            );
+            packet.set_stream_index(0);
+            ofmt_ctx.interleaved_write_frame(&mut packet).unwrap();
+        }
+        ofmt_ctx.write_trailer().unwrap();
+    }
+
+    let mut ifmt_ctx = AVFormatContextInput::open(output_path, None, &mut None).unwrap();
+    let nb_packets = std::iter::from_fn(|| ifmt_ctx.read_packet().unwrap()).count();
+    assert!(nb_packets > 0);
+    // Back to the start after reading everything, then by byte offset.
+    ifmt_ctx
+        .seek(None, SeekTarget::Timestamp(0), ffi::AVSEEK_FLAG_BACKWARD)
+        .unwrap();
+    let nb_packets_again = std::iter::from_fn(|| ifmt_ctx.read_packet().unwrap()).count();
+    assert_eq!(nb_packets_again, nb_packets);
+    ifmt_ctx.seek(None, SeekTarget::Bytes(0), 0).unwrap();
+    assert!(ifmt_ctx.read_packet().unwrap().is_some());
+}
+
+#[test]
+fn test_frame_at0() {
+    let path = cstr!("tests/assets/vids/bear.mp4");
+    let mut ifmt_ctx = AVFormatContextInput::open(path, None, &mut None).unwrap();
+    let (stream_index, _) = ifmt_ctx.first_video().unwrap();
+    let time_base = ifmt_ctx.streams()[stream_index].time_base;
+    let start_time = ifmt_ctx.start_time.max(0);
+    let duration = ifmt_ctx.duration;
+    let mut extractor = FrameExtractor::new(&ifmt_ctx, stream_index).unwrap();
+
+    let first = extractor.frame_at(&mut ifmt_ctx, 0).unwrap();
+    let first_pts = first.pts;
+    assert_ne!(first_pts, ffi::AV_NOPTS_VALUE);
+
+    // Past the end, then the middle: nothing is left from the failed call.
+    assert!(matches!(
+        extractor.frame_at(&mut ifmt_ctx, duration + 1_000_000),
+        Err(RsmpegError::SeekBeyondEndError)
+    ));
+    let middle = duration / 2;
+    let frame = extractor.frame_at(&mut ifmt_ctx, middle).unwrap();
+    let target = av_rescale_q(
+        start_time + middle,
+        ra(1, ffi::AV_TIME_BASE as i32),
+        time_base,
+    );
+    assert!(frame.pts <= target && frame.pts + frame.duration > target);
+    assert!(frame.pts > first_pts);
+
+    assert_eq!(extractor.frame_at(&mut ifmt_ctx, 0).unwrap().pts, first_pts);
+    assert_eq!(
+        frame_at(&mut ifmt_ctx, stream_index, 0).unwrap().pts,
+        first_pts
+    );
+    assert!(frame_at(&mut ifmt_ctx, 2, 0).is_err());
+
+    // An input without the stream of the extractor is rejected.
+    let mut extractor = FrameExtractor::new(&ifmt_ctx, 1).unwrap();
+    let mut audio_ctx =
+        AVFormatContextInput::open(cstr!("tests/assets/audios/sample1.aac"), None, &mut None)
+            .unwrap();
+    assert_eq!(audio_ctx.nb_streams, 1);
+    assert!(matches!(
+        extractor.frame_at(&mut audio_ctx, 0),
+        Err(RsmpegError::AVError(ffi::AVERROR_STREAM_NOT_FOUND))
+    ));
+}
+
+#[test]
+fn test_frame_at1() {
+    let path = cstr!("tests/assets/vids/centaur.mpg");
+    let mut ifmt_ctx = AVFormatContextInput::open(path, None, &mut None).unwrap();
+    let (stream_index, _) = ifmt_ctx.first_video().unwrap();
+    let time_base = ifmt_ctx.streams()[stream_index].time_base;
+    let start_time = ifmt_ctx.start_time.max(0);
+    let duration = ifmt_ctx.duration;
+    let mut extractor = FrameExtractor::new(&ifmt_ctx, stream_index).unwrap();
+
+    for position in [0, duration / 2, duration - duration / 20] {
+        let frame = extractor.frame_at(&mut ifmt_ctx, position).unwrap();
+        let time = av_rescale_q(frame.pts, time_base, ra(1, ffi::AV_TIME_BASE as i32));
+        // Within a second of the target.
+        assert!((time - (start_time + position)).abs() < 1_000_000);
+    }
+    assert!(matches!(
+        extractor.frame_at(&mut ifmt_ctx, duration),
+        Err(RsmpegError::SeekBeyondEndError)
+    ));
+}
@@ -0,0 +1,125 @@
+//! Remux between MP4 and Matroska with
+//! `AVFormatContextOutput::new_stream_copy_from`.
+use anyhow::{Context, Result};
+use cstr::cstr;
+use rsmpeg::{
+    avcodec::AVCodecContext,
+    avformat::{AVFormatContextInput, AVFormatContextOutput},
+    avutil::AVDictionary,
+    error::RsmpegError,
+    ffi,
+};
+use std::ffi::CStr;
+
+use super::utils::copy_packets;
+
+/// Remux all the streams of `input_path` into `output_path`, override the
+/// `language` tag of the output streams if `language` is given.
+fn remux(input_path: &CStr, output_path: &CStr, language: Option<&CStr>) -> Result<()> {
+    let mut ifmt_ctx = AVFormatContextInput::open(input_path, None, &mut None)?;
+    let mut ofmt_ctx = AVFormatContextOutput::create(output_path, None)?;
+    for in_stream in ifmt_ctx.streams() {
+        let index = ofmt_ctx.new_stream_copy_from(in_stream, false)?;
+        if let Some(language) = language {
+            let metadata = AVDictionary::new(cstr!("language"), language, 0);
+            ofmt_ctx.streams_mut()[index].set_metadata(Some(metadata));
+        }
+    }
+    ofmt_ctx.write_header(&mut None)?;
+    copy_packets(&mut ifmt_ctx, &mut ofmt_ctx)?;
+    ofmt_ctx.write_trailer()?;
+    Ok(())
+}
+
+/// Return the `language` tags of all the streams of `path`.
+fn languages(path: &CStr) -> Vec<String> {
+    let ifmt_ctx = AVFormatContextInput::open(path, None, &mut None).unwrap();
+    ifmt_ctx
+        .streams()
+        .iter()
+        .map(|stream| {
+            let metadata = stream.metadata().unwrap();
+            let entry = metadata.get(cstr!("language"), None, 0).unwrap();
+            entry.value().to_string_lossy().into_owned()
+        })
+        .collect()
+}
+
+/// Decode the video stream of `path`, return the number of frames.
+fn decode_video(path: &CStr) -> Result<usize> {
+    let mut ifmt_ctx = AVFormatContextInput::open(path, None, &mut None)?;
+    let (video_index, decoder) = ifmt_ctx
+        .find_best_stream(ffi::AVMEDIA_TYPE_VIDEO)?
+        .context("Failed to find video stream")?;
+    let mut dec_ctx = AVCodecContext::new(&decoder);
+    dec_ctx.apply_codecpar(&ifmt_ctx.streams()[video_index].codecpar())?;
+    dec_ctx.open(None)?;
+
+    let mut nb_frames = 0;
+    loop {
+        let packet = ifmt_ctx.read_packet()?;
+        if let Some(packet) = &packet {
+            if packet.stream_index as usize != video_index {
+                continue;
+            }
+        }
+        dec_ctx.send_packet(packet.as_ref())?;
+        loop {
+            match dec_ctx.receive_frame() {
+                Ok(_) => nb_frames += 1,
+                Err(RsmpegError::DecoderDrainError) => break,
+                Err(RsmpegError::DecoderFlushedError) => return Ok(nb_frames),
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+}
+
+#[test]
+fn test_stream_copy0() {
+    std::fs::create_dir_all("tests/output/stream_copy/").unwrap();
+    let input_path = cstr!("tests/assets/vids/bear.mp4");
+    let tagged_path = cstr!("tests/output/stream_copy/tagged.mp4");
+    let mkv_path = cstr!("tests/output/stream_copy/bear.mkv");
+    let mp4_path = cstr!("tests/output/stream_copy/bear.mp4");
+
+    remux(input_path, tagged_path, Some(cstr!("fra"))).unwrap();
+    // MP4 -> MKV, then back.
+    remux(tagged_path, mkv_path, None).unwrap();
+    remux(mkv_path, mp4_path, None).unwrap();
+
+    let nb_streams = AVFormatContextInput::open(input_path, None, &mut None)
+        .unwrap()
+        .nb_streams as usize;
+    let nb_frames = decode_video(input_path).unwrap();
+    assert!(nb_frames > 0);
+    for path in [tagged_path, mkv_path, mp4_path] {
+        assert_eq!(languages(path), vec!["fra"; nb_streams]);
+        assert_eq!(decode_video(path).unwrap(), nb_frames);
+    }
+}
+
+#[test]
+fn test_stream_copy1() {
+    std::fs::create_dir_all("tests/output/stream_copy/").unwrap();
+    let ifmt_ctx =
+        AVFormatContextInput::open(cstr!("tests/assets/vids/bear.mp4"), None, &mut None).unwrap();
+    let in_stream = &ifmt_ctx.streams()[0];
+    assert_ne!(in_stream.codecpar().codec_tag, 0);
+
+    let mut ofmt_ctx =
+        AVFormatContextOutput::create(cstr!("tests/output/stream_copy/reset.mkv"), None).unwrap();
+    assert_eq!(ofmt_ctx.new_stream_copy_from(in_stream, true), Ok(0));
+    assert_eq!(ofmt_ctx.streams()[0].codecpar().codec_tag, 0);
+    assert_eq!(ofmt_ctx.new_stream_copy_from(in_stream, false), Ok(1));
+    // Matroska maps `avc1` to H.264 too.
+    assert_eq!(
+        ofmt_ctx.streams()[1].codecpar().codec_tag,
+        in_stream.codecpar().codec_tag
+    );
+    ofmt_ctx.write_header(&mut None).unwrap();
+    assert_eq!(
+        ofmt_ctx.new_stream_copy_from(in_stream, false),
+        Err(RsmpegError::HeaderAlreadyWrittenError)
+    );
+}
@@ -56,19 +56,18 @@ fn thumbnail(
     println!("Cover frame info: {:#?}", cover_frame);
 
     let mut encode_context = {
-        let encoder =
-            AVCodec::find_encoder(ffi::AV_CODEC_ID_MJPEG).context("Encoder not found")?;
+        let encoder = AVCodec::find_encoder(ffi::AV_CODEC_ID_MJPEG).context("Encoder not found")?;
         let mut encode_context = AVCodecContext::new(&encoder);
 
-        encode_context.set_bit_rate(decode_context.bit_rate);
-        encode_context.set_width(width.unwrap_or(decode_context.width));
-        encode_context.set_height(height.unwrap_or(decode_context.height));
-        encode_context.set_time_base(av_inv_q(decode_context.framerate));
-        encode_context.set_pix_fmt(if let Some(pix_fmts) = encoder.pix_fmts() {
+        encode_context.try_set_bit_rate(decode_context.bit_rate)?;
+        encode_context.try_set_width(width.unwrap_or(decode_context.width))?;
+        encode_context.try_set_height(height.unwrap_or(decode_context.height))?;
+        encode_context.set_time_base(av_inv_q(decode_context.framerate))?;
+        encode_context.try_set_pix_fmt(if let Some(pix_fmts) = encoder.pix_fmts() {
             pix_fmts[0]
         } else {
             decode_context.pix_fmt
-        });
+        })?;
         encode_context.open(None)?;
 
         encode_context
@@ -82,7 +81,7 @@ fn thumbnail(
             encode_context.width,
             encode_context.height,
             encode_context.pix_fmt,
-            ffi::SWS_FAST_BILINEAR | ffi::SWS_PRINT_INFO,
+            SwsFlags::FAST_BILINEAR | SwsFlags::PRINT_INFO,
             None,
             None,
             None,
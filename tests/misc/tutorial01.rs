@@ -8,40 +8,27 @@ use cstr::cstr;
 use rsmpeg::{
     avcodec::{AVCodec, AVCodecContext},
     avformat::AVFormatContextInput,
-    avutil::{AVFrame, AVFrameWithImage, AVImage},
+    avutil::{AVFrameWithImage, AVImage},
     error::RsmpegError,
     ffi,
-    swscale::SwsContext,
+    swscale::{SwsContext, SwsFlags},
 };
 use std::{
     ffi::CStr,
     fs::{self, File},
     io::prelude::*,
-    slice,
 };
 
-/// Save a `AVFrame` as *colorful* pgm file.
-fn pgm_save(frame: &AVFrame, filename: &str) -> Result<()> {
-    // Here we only capture the first layer of frame.
-    let data = frame.data[0];
-    let linesize = frame.linesize[0] as usize;
-
-    let width = frame.width as usize;
-    let height = frame.height as usize;
-
-    let buffer = unsafe { slice::from_raw_parts(data, height * linesize * 3) };
-
+/// Save a `AVFrameWithImage` as *colorful* pgm file.
+fn pgm_save(frame: &AVFrameWithImage, filename: &str) -> Result<()> {
     // Create pgm file
     let mut pgm_file = File::create(filename)?;
 
     // Write pgm header(P6 means colorful)
-    pgm_file.write_all(&format!("P6\n{} {}\n{}\n", width, height, 255).into_bytes())?;
+    pgm_file.write_all(&format!("P6\n{} {}\n{}\n", frame.width, frame.height, 255).into_bytes())?;
 
-    // Write pgm data
-    for i in 0..height {
-        // Here the linesize is bigger than width * 3.
-        pgm_file.write_all(&buffer[i * linesize..i * linesize + width * 3])?;
-    }
+    // Write pgm data, rows are written without the linesize padding.
+    frame.write_raw_to(&mut pgm_file)?;
     Ok(())
 }
 
@@ -49,19 +36,16 @@ fn _main(file: &CStr, out_dir: &str) -> Result<()> {
     fs::create_dir_all(out_dir)?;
     let mut input_format_context = AVFormatContextInput::open(file, None, &mut None)?;
     input_format_context.dump(0, file)?;
-    let video_stream_index = input_format_context
-        .streams()
-        .into_iter()
-        .position(|stream| stream.codecpar().codec_type().is_video())
-        .context("No video stream")?;
-    let mut decode_context = {
-        let video_stream = &input_format_context.streams()[video_stream_index];
+    let (video_stream_index, mut decode_context) = {
+        let (index, video_stream) = input_format_context
+            .first_video()
+            .context("No video stream")?;
         let decoder = AVCodec::find_decoder(video_stream.codecpar().codec_id)
-            .context("Cannot find the decoder for video stream")?;
+            .context("Failed to find the decoder")?;
         let mut decode_context = AVCodecContext::new(&decoder);
         decode_context.apply_codecpar(&video_stream.codecpar())?;
         decode_context.open(None)?;
-        decode_context
+        (index, decode_context)
     };
 
     let image_buffer = AVImage::new(
@@ -81,7 +65,7 @@ fn _main(file: &CStr, out_dir: &str) -> Result<()> {
         decode_context.width,
         decode_context.height,
         ffi::AV_PIX_FMT_RGB24,
-        ffi::SWS_BILINEAR,
+        SwsFlags::BILINEAR,
         None,
         None,
         None,
@@ -89,17 +73,23 @@ fn _main(file: &CStr, out_dir: &str) -> Result<()> {
     .context("Failed to create a swscale context.")?;
 
     let mut i = 0;
-    while let Some(packet) = input_format_context.read_packet().unwrap() {
-        if packet.stream_index != video_stream_index as i32 {
-            continue;
+    loop {
+        let packet = input_format_context.read_packet()?;
+        if let Some(packet) = &packet {
+            if packet.stream_index != video_stream_index as i32 {
+                continue;
+            }
         }
-        decode_context.send_packet(Some(&packet))?;
+        // Sending `None` at the end of the input enters drain mode, the
+        // decoder then outputs the frames it still holds.
+        decode_context.send_packet(packet.as_ref())?;
+        // One packet may yield zero or several frames, receive until the
+        // decoder asks for more input.
         loop {
             let frame = match decode_context.receive_frame() {
                 Ok(frame) => frame,
-                Err(RsmpegError::DecoderDrainError) | Err(RsmpegError::DecoderFlushedError) => {
-                    break
-                }
+                Err(RsmpegError::DecoderDrainError) => break,
+                Err(RsmpegError::DecoderFlushedError) => return Ok(()),
                 Err(e) => return Err(e.into()),
             };
 
@@ -111,7 +101,6 @@ fn _main(file: &CStr, out_dir: &str) -> Result<()> {
             pgm_save(&frame_rgb, &format!("{}/frame{}.ppm", out_dir, i))?;
         }
     }
-    Ok(())
 }
 
 #[test]
@@ -0,0 +1,147 @@
+//! Write decoded frames to a muxer accepting uncoded frames with
+//! `AVFormatContextOutput::write_uncoded_frame`.
+use anyhow::{Context, Result};
+use cstr::cstr;
+use rsmpeg::{
+    avcodec::{AVCodecContext, AVCodecParameters},
+    avformat::{AVFormatContextInput, AVFormatContextOutput},
+    avutil::ra,
+    error::RsmpegError,
+    ffi, UnsafeDerefMut,
+};
+use std::{ffi::CStr, fs};
+
+fn raw_video_codecpar(dec_ctx: &AVCodecContext) -> AVCodecParameters {
+    let mut codecpar = AVCodecParameters::new();
+    let raw = unsafe { codecpar.deref_mut() };
+    raw.codec_type = ffi::AVMEDIA_TYPE_VIDEO;
+    raw.codec_id = ffi::AV_CODEC_ID_RAWVIDEO;
+    raw.width = dec_ctx.width;
+    raw.height = dec_ctx.height;
+    raw.format = dec_ctx.pix_fmt;
+    codecpar
+}
+
+/// Return the number of frames written.
+fn write_uncoded(input_path: &CStr, output_path: &CStr, format_name: &CStr) -> Result<usize> {
+    let mut ifmt_ctx = AVFormatContextInput::open(input_path, None, &mut None)?;
+    let (video_index, decoder) = ifmt_ctx
+        .find_best_stream(ffi::AVMEDIA_TYPE_VIDEO)?
+        .context("Failed to find video stream")?;
+    let mut dec_ctx = AVCodecContext::new(&decoder);
+    dec_ctx.apply_codecpar(&ifmt_ctx.streams()[video_index].codecpar())?;
+    dec_ctx.open(None)?;
+
+    let mut ofmt_ctx =
+        AVFormatContextOutput::create_with_format(output_path, Some(format_name), None)?;
+    {
+        let mut out_stream = ofmt_ctx.new_stream();
+        out_stream.set_codecpar(raw_video_codecpar(&dec_ctx));
+        out_stream.set_time_base(ra(1, 25));
+    }
+    ofmt_ctx.write_header(&mut None)?;
+    assert!(ofmt_ctx.can_write_uncoded(0));
+    assert!(!ofmt_ctx.can_write_uncoded(1));
+
+    let mut nb_frames = 0;
+    loop {
+        let packet = ifmt_ctx.read_packet()?;
+        if let Some(packet) = &packet {
+            if packet.stream_index as usize != video_index {
+                continue;
+            }
+        }
+        dec_ctx.send_packet(packet.as_ref())?;
+        loop {
+            let mut frame = match dec_ctx.receive_frame() {
+                Ok(frame) => frame,
+                Err(RsmpegError::DecoderDrainError) | Err(RsmpegError::DecoderFlushedError) => {
+                    break
+                }
+                Err(e) => return Err(e.into()),
+            };
+            frame.set_pts(nb_frames as i64);
+            ofmt_ctx.write_uncoded_frame(0, frame, true)?;
+            nb_frames += 1;
+        }
+        if packet.is_none() {
+            break;
+        }
+    }
+    ofmt_ctx.write_trailer()?;
+    Ok(nb_frames)
+}
+
+#[test]
+fn test_uncoded_frame0() {
+    let dir = "tests/output/uncoded_frame";
+    fs::create_dir_all(dir).unwrap();
+    let nb_frames = write_uncoded(
+        cstr!("tests/assets/vids/bear.mp4"),
+        cstr!("tests/output/uncoded_frame/bear.crc"),
+        cstr!("uncodedframecrc"),
+    )
+    .unwrap();
+    assert!(nb_frames > 0);
+
+    // One checksum line per frame.
+    let output = fs::read_to_string(format!("{}/bear.crc", dir)).unwrap();
+    let lines = output.lines().filter(|line| !line.starts_with('#')).count();
+    assert_eq!(lines, nb_frames);
+}
+
+#[test]
+fn test_uncoded_frame1() {
+    // `yuv4mpegpipe` takes `wrapped_avframe` packets and has no uncoded frame
+    // callback, the frames are rejected and freed by the muxer.
+    let dir = "tests/output/uncoded_frame";
+    fs::create_dir_all(dir).unwrap();
+    let mut ifmt_ctx =
+        AVFormatContextInput::open(cstr!("tests/assets/vids/bear.mp4"), None, &mut None).unwrap();
+    let (video_index, decoder) = ifmt_ctx
+        .find_best_stream(ffi::AVMEDIA_TYPE_VIDEO)
+        .unwrap()
+        .unwrap();
+    let mut dec_ctx = AVCodecContext::new(&decoder);
+    dec_ctx
+        .apply_codecpar(&ifmt_ctx.streams()[video_index].codecpar())
+        .unwrap();
+    dec_ctx.open(None).unwrap();
+    let frame = loop {
+        let packet = ifmt_ctx.read_packet().unwrap().unwrap();
+        if packet.stream_index as usize != video_index {
+            continue;
+        }
+        dec_ctx.send_packet(Some(&packet)).unwrap();
+        if let Ok(frame) = dec_ctx.receive_frame() {
+            break frame;
+        }
+    };
+
+    let output_path = cstr!("tests/output/uncoded_frame/bear.y4m");
+    let mut ofmt_ctx =
+        AVFormatContextOutput::create_with_format(output_path, Some(cstr!("yuv4mpegpipe")), None)
+            .unwrap();
+    {
+        let mut codecpar = raw_video_codecpar(&dec_ctx);
+        unsafe { codecpar.deref_mut() }.codec_id = ffi::AV_CODEC_ID_WRAPPED_AVFRAME;
+        let mut out_stream = ofmt_ctx.new_stream();
+        out_stream.set_codecpar(codecpar);
+        out_stream.set_time_base(ra(1, 25));
+    }
+    ofmt_ctx.write_header(&mut None).unwrap();
+    assert!(!ofmt_ctx.can_write_uncoded(0));
+    assert_eq!(
+        ofmt_ctx.write_uncoded_frame(0, frame, true),
+        Err(RsmpegError::AVError(ffi::AVERROR(ffi::ENOSYS)))
+    );
+    ofmt_ctx.write_trailer().unwrap();
+
+    // The header written is a valid y4m stream of the decoded size.
+    let ifmt_ctx = AVFormatContextInput::open(output_path, None, &mut None).unwrap();
+    let codecpar = ifmt_ctx.streams()[0].codecpar();
+    assert_eq!(
+        (codecpar.width, codecpar.height),
+        (dec_ctx.width, dec_ctx.height)
+    );
+}
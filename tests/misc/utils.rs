@@ -0,0 +1,21 @@
+//! Helpers shared by the tests.
+use anyhow::Result;
+use rsmpeg::avformat::{AVFormatContextInput, AVFormatContextOutput};
+
+/// Copy all the packets of `ifmt_ctx` into the same stream index of
+/// `ofmt_ctx`, whose header must have been written.
+pub fn copy_packets(
+    ifmt_ctx: &mut AVFormatContextInput,
+    ofmt_ctx: &mut AVFormatContextOutput,
+) -> Result<()> {
+    while let Some(mut packet) = ifmt_ctx.read_packet()? {
+        let index = packet.stream_index as usize;
+        packet.rescale_ts(
+            ifmt_ctx.streams()[index].time_base,
+            ofmt_ctx.streams()[index].time_base,
+        );
+        packet.set_pos(-1);
+        ofmt_ctx.interleaved_write_frame(&mut packet)?;
+    }
+    Ok(())
+}